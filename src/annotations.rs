@@ -0,0 +1,277 @@
+//! Ink drawn over pages during talks, like strokes of a pen and highlighted areas.
+//!
+//! Annotations are kept by the stable ids of pages, see [`Deck::page_id`], so they stay on their
+//! pages when other pages are added or removed, and pages with the same content keep their own.
+//! Positions are relative to the size of the page, from `0.0` to `1.0`, so annotations fit any
+//! screen.
+//!
+//! Annotations are saved alongside their deck, in a text file with a line per annotation below a
+//! line per page:
+//! ```text
+//! page 5f1d7c3b9a2e4d60
+//! stroke #e03131 0.004 0.1,0.2 0.15,0.22 0.2,0.25
+//! highlight 0.1 0.6 0.8 0.1
+//! ```
+//!
+//! ```
+//! use opmark::{annotations::{Annotation, Annotations, Point, Rect}, deck::Deck};
+//!
+//! let deck = Deck::new("# Title\n---\nDetails".to_owned());
+//! let mut annotations = Annotations::new();
+//! annotations.add(&deck, 1, Annotation::Highlight(Rect::new(0.1, 0.6, 0.8, 0.1)));
+//! annotations.add(
+//!     &deck,
+//!     1,
+//!     Annotation::Stroke {
+//!         points: vec![Point::new(0.1, 0.2), Point::new(0.2, 0.25)],
+//!         color: "#e03131".to_owned(),
+//!         width: 0.004,
+//!     },
+//! );
+//!
+//! let saved = annotations.to_string();
+//! let loaded: Annotations = saved.parse().unwrap();
+//! assert_eq!(loaded, annotations);
+//! assert_eq!(loaded.page(&deck, 1).len(), 2);
+//! assert!(loaded.page(&deck, 0).is_empty());
+//! ```
+
+use crate::{deck::Deck, id::MarkId, parser::LINE_ENDING};
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// A point on a page, relative to its size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    #[inline]
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
+/// An area of a page, relative to its size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    #[inline]
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Ink drawn over a page.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Annotation {
+    /// A stroke of a pen through `points`.
+    Stroke {
+        points: Vec<Point>,
+        /// A CSS color, like `#e03131`, without whitespace.
+        color: String,
+        /// The width of the stroke, relative to the width of the page.
+        width: f32,
+    },
+    /// A highlighted area.
+    Highlight(Rect),
+}
+
+/// The annotations of the pages of a deck.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotations {
+    pages: BTreeMap<MarkId, Vec<Annotation>>,
+}
+
+impl Annotations {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The path of the annotations saved alongside the deck at `path`, like
+    /// `talk.opmark.annotations` for `talk.opmark`.
+    pub fn sidecar_path(path: impl AsRef<Path>) -> PathBuf {
+        let mut path = path.as_ref().as_os_str().to_owned();
+        path.push(".annotations");
+        path.into()
+    }
+
+    /// Read annotations saved at `path`. No annotations if the file does not exist.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(s) => s
+                .parse()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Save the annotations at `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Add `annotation` over the page of `deck` at `page`. Returns `false` if there is no such
+    /// page.
+    pub fn add(&mut self, deck: &Deck, page: usize, annotation: Annotation) -> bool {
+        match deck.page_id(page) {
+            Some(id) => {
+                self.pages.entry(id).or_default().push(annotation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The annotations over the page of `deck` at `page`, in the order they were added.
+    ///
+    /// ```
+    /// use opmark::{annotations::{Annotation, Annotations, Rect}, deck::Deck};
+    ///
+    /// let deck = Deck::new("# Demo\n---\n# Other\n---\n# Demo".to_owned());
+    /// let mut annotations = Annotations::new();
+    /// annotations.add(&deck, 0, Annotation::Highlight(Rect::new(0.1, 0.6, 0.8, 0.1)));
+    /// assert_eq!(annotations.page(&deck, 0).len(), 1);
+    /// // a page with the same content has its own annotations
+    /// assert!(annotations.page(&deck, 2).is_empty());
+    /// ```
+    pub fn page(&self, deck: &Deck, page: usize) -> &[Annotation] {
+        deck.page_id(page)
+            .and_then(|id| self.pages.get(&id))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Remove the annotations over the page of `deck` at `page`, and return them.
+    pub fn clear_page(&mut self, deck: &Deck, page: usize) -> Vec<Annotation> {
+        deck.page_id(page)
+            .and_then(|id| self.pages.remove(&id))
+            .unwrap_or_default()
+    }
+
+    /// Remove the annotations of the pages which are not in `deck` anymore.
+    pub fn retain_deck(&mut self, deck: &Deck) {
+        let ids = deck.page_ids();
+        self.pages.retain(|id, _| ids.contains(id));
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pages.values().all(Vec::is_empty)
+    }
+}
+
+impl fmt::Display for Annotations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, annotations) in self.pages.iter().filter(|(_, a)| !a.is_empty()) {
+            write!(f, "page {}{}", id, LINE_ENDING)?;
+            for annotation in annotations {
+                match annotation {
+                    Annotation::Stroke {
+                        points,
+                        color,
+                        width,
+                    } => {
+                        write!(f, "stroke {} {}", color, width)?;
+                        for point in points {
+                            write!(f, " {},{}", point.x, point.y)?;
+                        }
+                    }
+                    Annotation::Highlight(rect) => write!(
+                        f,
+                        "highlight {} {} {} {}",
+                        rect.x, rect.y, rect.width, rect.height
+                    )?,
+                }
+                f.write_str(LINE_ENDING)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Annotations {
+    type Err = AnnotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut annotations = Self::new();
+        let mut page = None;
+        for (idx, line) in s.lines().enumerate() {
+            let invalid = || AnnotationError { line: idx + 1 };
+            let mut tokens = line.split_whitespace();
+            let number = |token: Option<&str>| -> Result<f32, AnnotationError> {
+                token.and_then(|n| n.parse().ok()).ok_or_else(invalid)
+            };
+            let annotation = match tokens.next() {
+                None => continue,
+                Some("page") => {
+                    let id = tokens
+                        .next()
+                        .and_then(|id| u64::from_str_radix(id, 16).ok())
+                        .ok_or_else(invalid)?;
+                    page = Some(MarkId(id));
+                    continue;
+                }
+                Some("stroke") => {
+                    let color = tokens.next().ok_or_else(invalid)?.to_owned();
+                    let width = number(tokens.next())?;
+                    let points = tokens
+                        .map(|point| {
+                            let (x, y) = point.split_once(',').ok_or_else(invalid)?;
+                            Ok(Point::new(number(Some(x))?, number(Some(y))?))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    Annotation::Stroke {
+                        points,
+                        color,
+                        width,
+                    }
+                }
+                Some("highlight") => Annotation::Highlight(Rect::new(
+                    number(tokens.next())?,
+                    number(tokens.next())?,
+                    number(tokens.next())?,
+                    number(tokens.next())?,
+                )),
+                Some(_) => return Err(invalid()),
+            };
+            let page = page.ok_or_else(invalid)?;
+            annotations.pages.entry(page).or_default().push(annotation);
+        }
+        Ok(annotations)
+    }
+}
+
+/// An invalid line in saved annotations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotationError {
+    /// The number of the line, starting from 1.
+    pub line: usize,
+}
+
+impl fmt::Display for AnnotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid annotation at line {}", self.line)
+    }
+}
+
+impl error::Error for AnnotationError {}
@@ -0,0 +1,168 @@
+//! Audiences of pages and blocks, so one deck yields its internal and external versions.
+//!
+//! A page is for the audiences of its `audience` option, like `---<audience=internal>`, and a
+//! block is for the audiences of the `@audience(...)` directive right before it, up to the next
+//! empty line, see [`Directive::Audience`]. Audiences are separated by commas. Pages and blocks
+//! without audience are for all audiences.
+//!
+//! [`Deck::for_audience`](crate::deck::Deck::for_audience) keeps the content for some audiences
+//! only, and [`check`] reports the content for restricted audiences which is still in a deck,
+//! like content for `confidential` and `public` in the public version.
+//!
+//! ```
+//! use opmark::{audience::check, deck::Deck};
+//!
+//! let deck = Deck::new(
+//!     "# Results\n\
+//!      @audience(internal, board)\nRevenue grew by 12%\n\n\
+//!      @audience(public, confidential)\nA new office opens in May\n\n\
+//!      Thank you\n\
+//!      ---<audience=internal>\n# Roadmap"
+//!         .to_owned(),
+//! );
+//! let public = deck.for_audience(&["public"]);
+//! assert_eq!(public.pages().len(), 1);
+//! assert_eq!(
+//!     public.pages()[0].summary(80),
+//!     "A new office opens in May Thank you"
+//! );
+//! let leaks: Vec<String> = check(&public, &["confidential"])
+//!     .iter()
+//!     .map(|leak| leak.to_string())
+//!     .collect();
+//! assert_eq!(
+//!     leaks,
+//!     ["page 1: content for confidential: A new office opens in May"]
+//! );
+//! ```
+
+use crate::{
+    deck::{Deck, Page},
+    mark::{Directive, Mark},
+    text::plain_text,
+};
+use std::fmt;
+
+/// Content for a restricted audience found in a deck, see [`check`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Leak {
+    /// The index of the page of the content.
+    pub page: usize,
+    /// The restricted audience the content is for.
+    pub audience: String,
+    /// The text of the block, or the title of the page, on a line.
+    pub text: String,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "page {}: content for {}", self.page + 1, self.audience)?;
+        if !self.text.is_empty() {
+            write!(f, ": {}", self.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// The pages and the blocks of `deck` for any of the audiences `restricted`, in order.
+pub fn check(deck: &Deck, restricted: &[&str]) -> Vec<Leak> {
+    let mut leaks = vec![];
+    for (idx, page) in deck.pages().iter().enumerate() {
+        let page_audiences = page.style.audiences.iter().map(String::as_str);
+        if let Some(audience) = restricted_audience(page_audiences, restricted) {
+            leaks.push(Leak {
+                page: idx,
+                audience,
+                text: page.title().unwrap_or_default(),
+            });
+        }
+        for transition in &page.transitions {
+            let Mark::Transition(_, marks, _) = transition else {
+                continue;
+            };
+            for (start, mark) in marks.iter().enumerate() {
+                let Mark::Directive(Directive::Audience(audiences)) = mark else {
+                    continue;
+                };
+                if let Some(audience) = restricted_audience(tags(audiences), restricted) {
+                    leaks.push(Leak {
+                        page: idx,
+                        audience,
+                        text: plain_text(&marks[start + 1..block_end(marks, start)]),
+                    });
+                }
+            }
+        }
+    }
+    leaks
+}
+
+/// The pages of `pages` for any of `audiences`, without the blocks for other audiences.
+pub(crate) fn filter(pages: &[Page], audiences: &[&str]) -> Vec<Page> {
+    pages
+        .iter()
+        .filter(|page| is_for(page.style.audiences.iter().map(String::as_str), audiences))
+        .map(|page| {
+            let transitions = page
+                .transitions
+                .iter()
+                .map(|transition| match transition {
+                    Mark::Transition(order, marks, style) => {
+                        Mark::Transition(*order, filter_marks(marks, audiences), style.clone())
+                    }
+                    mark => mark.clone(),
+                })
+                .collect();
+            Page {
+                transitions,
+                ..page.clone()
+            }
+        })
+        .collect()
+}
+
+/// The audiences of the argument of an `@audience` directive or an `audience` page option.
+pub(crate) fn tags(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+fn filter_marks(marks: &[Mark], audiences: &[&str]) -> Vec<Mark> {
+    let mut kept = vec![];
+    let mut skip_to = 0;
+    for (idx, mark) in marks.iter().enumerate() {
+        if idx < skip_to {
+            continue;
+        }
+        if let Mark::Directive(Directive::Audience(argument)) = mark {
+            if !is_for(tags(argument), audiences) {
+                skip_to = block_end(marks, idx);
+                continue;
+            }
+        }
+        kept.push(mark.clone());
+    }
+    kept
+}
+
+/// The first of `tags` which is one of the audiences `restricted`.
+fn restricted_audience<'a>(
+    mut tags: impl Iterator<Item = &'a str>,
+    restricted: &[&str],
+) -> Option<String> {
+    tags.find(|tag| restricted.contains(tag)).map(str::to_owned)
+}
+
+/// Whether content for `tags` is for any of `audiences`. Content without tags is for all.
+fn is_for<'a>(tags: impl Iterator<Item = &'a str>, audiences: &[&str]) -> bool {
+    let mut tags = tags.peekable();
+    tags.peek().is_none() || tags.any(|tag| audiences.contains(&tag))
+}
+
+/// The end of the block after the `@audience` directive at `start` in `marks`: the next empty
+/// line, or the end of the transition.
+fn block_end(marks: &[Mark], start: usize) -> usize {
+    marks[start + 1..]
+        .iter()
+        .position(|mark| matches!(mark, Mark::NewLine))
+        .map_or(marks.len(), |end| start + 1 + end)
+}
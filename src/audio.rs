@@ -0,0 +1,102 @@
+//! Probing audio files, like the narration of pages.
+
+use std::time::Duration;
+
+/// The duration of the audio file `bytes`, read from its headers without decoding it.
+///
+/// WAV, FLAC, and Ogg files with Vorbis or Opus audio are supported. `None` for other formats or
+/// malformed files.
+///
+/// ```
+/// use opmark::audio::duration;
+/// use std::time::Duration;
+///
+/// // one second of 8 kHz, 8-bit mono WAV
+/// let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0\x01\0\x01\0\x40\x1f\0\0\x40\x1f\0\0\x01\0\x08\0".to_vec();
+/// wav.extend(b"data\x40\x1f\0\0");
+/// wav.extend([128; 8000]);
+/// assert_eq!(duration(&wav), Some(Duration::from_secs(1)));
+/// ```
+pub fn duration(bytes: &[u8]) -> Option<Duration> {
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+        wav(bytes)
+    } else if bytes.starts_with(b"fLaC") {
+        flac(bytes)
+    } else if bytes.starts_with(b"OggS") {
+        ogg(bytes)
+    } else {
+        None
+    }
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The size of the `data` chunk divided by the byte rate of the `fmt ` chunk.
+fn wav(bytes: &[u8]) -> Option<Duration> {
+    let mut byte_rate = None;
+    let mut at = 12;
+    while let Some(id) = bytes.get(at..at + 4) {
+        let size = u32_le(bytes, at + 4)? as usize;
+        match id {
+            b"fmt " => byte_rate = Some(u32_le(bytes, at + 16)?),
+            b"data" => {
+                let byte_rate = byte_rate.filter(|&rate| rate > 0)?;
+                return Some(Duration::from_secs_f64(size as f64 / byte_rate as f64));
+            }
+            _ => {}
+        }
+        // chunks are padded to an even size
+        at += 8 + size + size % 2;
+    }
+    None
+}
+
+/// The total number of samples divided by the sample rate, in the `STREAMINFO` block.
+fn flac(bytes: &[u8]) -> Option<Duration> {
+    // the first metadata block is always `STREAMINFO`
+    let info = bytes.get(8..26)?;
+    let sample_rate = (info[10] as u64) << 12 | (info[11] as u64) << 4 | (info[12] as u64) >> 4;
+    let samples =
+        (info[13] as u64 & 0x0f) << 32 | u32::from_be_bytes(info[14..18].try_into().ok()?) as u64;
+    if sample_rate == 0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(samples as f64 / sample_rate as f64))
+}
+
+/// The granule position of the last page divided by the sample rate of the first stream.
+fn ogg(bytes: &[u8]) -> Option<Duration> {
+    let serial = u32_le(bytes, 14)?;
+    let segments = *bytes.get(26)? as usize;
+    let packet = bytes.get(27 + segments..)?;
+    let (rate, pre_skip) = if packet.starts_with(b"\x01vorbis") {
+        (u32_le(packet, 12)? as u64, 0)
+    } else if packet.starts_with(b"OpusHead") {
+        // Opus is always decoded at 48 kHz
+        (48_000, u16_le(packet, 10)? as u64)
+    } else {
+        return None;
+    };
+
+    let mut at = bytes.len().checked_sub(27)?;
+    loop {
+        if &bytes[at..at + 4] == b"OggS" && u32_le(bytes, at + 14) == Some(serial) {
+            let granule = u64::from_le_bytes(bytes.get(at + 6..at + 14)?.try_into().ok()?);
+            // pages where no packet ends have no granule position
+            if granule != u64::MAX {
+                if rate == 0 {
+                    return None;
+                }
+                let samples = granule.saturating_sub(pre_skip);
+                return Some(Duration::from_secs_f64(samples as f64 / rate as f64));
+            }
+        }
+        at = at.checked_sub(1)?;
+    }
+}
@@ -0,0 +1,263 @@
+//! Drive decks from stage control hardware, like lighting consoles and show controllers, with
+//! OSC or MIDI messages.
+//!
+//! A [`ControlMap`] turns the messages received by the application into [`ControlAction`]s,
+//! which move a deck. OSC messages are read from their packets, like the datagrams of a UDP
+//! socket, with their addresses under the prefix of the map:
+//! - `/opmark/next` and `/opmark/prev`: the next or the previous step.
+//! - `/opmark/next-page` and `/opmark/prev-page`: the next or the previous page.
+//! - `/opmark/goto n`: the page numbered `n`, from 1.
+//! - `/opmark/bookmark name`: the bookmark `name`.
+//!
+//! Messages whose first argument is 0 or false are ignored, like the release of a button.
+//!
+//! MIDI messages are read from their bytes, and do what the map binds to their notes and
+//! controllers, see [`ControlMap::with_midi`]. Program changes go to the page of their number,
+//! from 0, if [`ControlMap::program_change`] is set.
+//!
+//! ```
+//! use opmark::{
+//!     control::{ControlAction, ControlMap, MidiTrigger},
+//!     deck::Deck,
+//! };
+//!
+//! let map = ControlMap::new().with_midi(MidiTrigger::Note(60), ControlAction::NextStep);
+//! let mut deck = Deck::new("one\n---\ntwo\n---\nthree".to_owned());
+//!
+//! // a note on, of the key C4, on the channel 1
+//! for action in map.midi_actions(&[0x90, 60, 100]) {
+//!     action.apply(&mut deck);
+//! }
+//! assert_eq!(deck.current(), 1);
+//!
+//! // `/opmark/goto 3`
+//! let packet = b"/opmark/goto\0\0\0\0,i\0\0\0\0\0\x03";
+//! assert_eq!(map.osc_actions(packet), [ControlAction::GotoPage(2)]);
+//! ```
+
+use crate::deck::Deck;
+
+/// What a control message does to a deck.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlAction {
+    /// See [`Deck::next_step`].
+    NextStep,
+    /// See [`Deck::prev_step`].
+    PrevStep,
+    /// See [`Deck::next_page`].
+    NextPage,
+    /// See [`Deck::prev_page`].
+    PrevPage,
+    /// Go to the page at the index, see [`Deck::goto_page`].
+    GotoPage(usize),
+    /// Go to the bookmark, see [`Deck::goto_bookmark`].
+    GotoBookmark(String),
+}
+
+impl ControlAction {
+    /// Do the action on `deck`. Returns `false` if the deck did not move.
+    pub fn apply(&self, deck: &mut Deck) -> bool {
+        match self {
+            ControlAction::NextStep => deck.next_step(),
+            ControlAction::PrevStep => deck.prev_step(),
+            ControlAction::NextPage => deck.next_page(),
+            ControlAction::PrevPage => deck.prev_page(),
+            ControlAction::GotoPage(index) => deck.goto_page(*index),
+            ControlAction::GotoBookmark(name) => deck.goto_bookmark(name),
+        }
+    }
+}
+
+/// A MIDI message bound to an action, see [`ControlMap::with_midi`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MidiTrigger {
+    /// A note on of the note, with a velocity above 0.
+    Note(u8),
+    /// A control change of the controller, with a value of 64 or more, like a pedal pressed.
+    Control(u8),
+}
+
+/// How control messages map to actions.
+#[derive(Clone, Debug)]
+pub struct ControlMap {
+    /// The prefix of the OSC addresses. `/opmark` by default.
+    pub osc_prefix: String,
+    /// The actions bound to MIDI messages.
+    pub midi: Vec<(MidiTrigger, ControlAction)>,
+    /// The MIDI channel to listen to, from 1 to 16. If `None`, all channels.
+    pub midi_channel: Option<u8>,
+    /// Should MIDI program changes go to the page of their number.
+    pub program_change: bool,
+}
+
+impl Default for ControlMap {
+    fn default() -> Self {
+        Self {
+            osc_prefix: "/opmark".to_owned(),
+            midi: vec![],
+            midi_channel: None,
+            program_change: false,
+        }
+    }
+}
+
+impl ControlMap {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Bind `trigger` to `action`, replacing its previous action.
+    pub fn with_midi(mut self, trigger: MidiTrigger, action: ControlAction) -> Self {
+        match self.midi.iter_mut().find(|(t, _)| *t == trigger) {
+            Some((_, a)) => *a = action,
+            None => self.midi.push((trigger, action)),
+        }
+        self
+    }
+
+    #[inline]
+    pub fn with_midi_channel(mut self, channel: u8) -> Self {
+        self.midi_channel = Some(channel);
+        self
+    }
+
+    #[inline]
+    pub fn with_osc_prefix(mut self, prefix: String) -> Self {
+        self.osc_prefix = prefix;
+        self
+    }
+
+    #[inline]
+    pub fn with_program_change(mut self) -> Self {
+        self.program_change = true;
+        self
+    }
+
+    /// The actions of the OSC `packet`: of its message, or of the messages of its bundle, in
+    /// order. Messages which are malformed or do nothing are skipped.
+    pub fn osc_actions(&self, packet: &[u8]) -> Vec<ControlAction> {
+        let mut actions = vec![];
+        self.osc_packet(packet, &mut actions);
+        actions
+    }
+
+    /// The actions of the MIDI `message`, from its status byte.
+    pub fn midi_actions(&self, message: &[u8]) -> Vec<ControlAction> {
+        let (&status, data) = match message.split_first() {
+            Some(split) => split,
+            None => return vec![],
+        };
+        let channel = (status & 0x0f) + 1;
+        if self.midi_channel.is_some_and(|c| c != channel) {
+            return vec![];
+        }
+        let trigger = match (status & 0xf0, data) {
+            (0x90, &[note, velocity, ..]) if velocity > 0 => MidiTrigger::Note(note),
+            (0xb0, &[controller, value, ..]) if value >= 64 => MidiTrigger::Control(controller),
+            (0xc0, &[program, ..]) if self.program_change => {
+                return vec![ControlAction::GotoPage(program as usize)]
+            }
+            _ => return vec![],
+        };
+        self.midi
+            .iter()
+            .filter(|(t, _)| *t == trigger)
+            .map(|(_, action)| action.clone())
+            .collect()
+    }
+
+    fn osc_packet(&self, packet: &[u8], actions: &mut Vec<ControlAction>) {
+        if let Some(mut rest) = packet.strip_prefix(b"#bundle\0") {
+            // the time tag, then elements prefixed by their size
+            rest = rest.get(8..).unwrap_or_default();
+            while let Some((size, elements)) = rest.split_first_chunk::<4>() {
+                let size = u32::from_be_bytes(*size) as usize;
+                let Some(element) = elements.get(..size) else {
+                    return;
+                };
+                self.osc_packet(element, actions);
+                rest = &elements[size..];
+            }
+            return;
+        }
+        if let Some(action) = self.osc_message(packet) {
+            actions.push(action);
+        }
+    }
+
+    fn osc_message(&self, packet: &[u8]) -> Option<ControlAction> {
+        let (address, rest) = osc_string(packet)?;
+        let command = address.strip_prefix(self.osc_prefix.as_str())?;
+        let (tags, mut rest) = match osc_string(rest) {
+            Some((tags, rest)) => (tags.strip_prefix(',')?, rest),
+            // messages of old senders have no type tags, nor arguments
+            None => ("", rest),
+        };
+        let mut args = vec![];
+        for tag in tags.chars() {
+            let arg = match tag {
+                'i' => {
+                    let (bytes, tail) = rest.split_first_chunk::<4>()?;
+                    rest = tail;
+                    OscArg::Int(i32::from_be_bytes(*bytes))
+                }
+                'f' => {
+                    let (bytes, tail) = rest.split_first_chunk::<4>()?;
+                    rest = tail;
+                    OscArg::Float(f32::from_be_bytes(*bytes))
+                }
+                's' => {
+                    let (s, tail) = osc_string(rest)?;
+                    rest = tail;
+                    OscArg::Str(s)
+                }
+                'T' => OscArg::Int(1),
+                'F' => OscArg::Int(0),
+                _ => return None,
+            };
+            args.push(arg);
+        }
+        let is_pressed = match args.first() {
+            Some(OscArg::Int(n)) => *n != 0,
+            Some(OscArg::Float(n)) => *n != 0.0,
+            _ => true,
+        };
+        match command {
+            "/next" if is_pressed => Some(ControlAction::NextStep),
+            "/prev" if is_pressed => Some(ControlAction::PrevStep),
+            "/next-page" if is_pressed => Some(ControlAction::NextPage),
+            "/prev-page" if is_pressed => Some(ControlAction::PrevPage),
+            "/goto" => {
+                let number = match args.first()? {
+                    OscArg::Int(n) => usize::try_from(*n).ok()?,
+                    OscArg::Float(n) if *n >= 1.0 => *n as usize,
+                    OscArg::Str(s) => s.parse().ok()?,
+                    _ => return None,
+                };
+                number.checked_sub(1).map(ControlAction::GotoPage)
+            }
+            "/bookmark" => match args.first()? {
+                OscArg::Str(name) => Some(ControlAction::GotoBookmark((*name).to_owned())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// An argument of an OSC message.
+enum OscArg<'a> {
+    Int(i32),
+    Float(f32),
+    Str(&'a str),
+}
+
+/// The OSC string `bytes` starts with, ended by a null byte and padded to 4 bytes, with the
+/// bytes after it.
+fn osc_string(bytes: &[u8]) -> Option<(&str, &[u8])> {
+    let end = bytes.iter().position(|&b| b == 0)?;
+    let s = std::str::from_utf8(&bytes[..end]).ok()?;
+    let padded = (end / 4 + 1) * 4;
+    Some((s, bytes.get(padded..).unwrap_or_default()))
+}
@@ -0,0 +1,182 @@
+//! Terminal output colored with ANSI escape sequences.
+
+use super::DeckBuilder;
+use crate::mark::{Mark, StyleText};
+
+/// The names of the 8 basic colors, in the order of their codes.
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Convert captured terminal output into a console block, to paste CLI demos into decks.
+///
+/// Each line of the output becomes a paragraph of text with the class `console`. The bold,
+/// italics, underline and strikethrough styles of the output are kept as text styles, and the
+/// other attributes as classes:
+/// - `ansi-<color>` and `ansi-bg-<color>` for the basic colors, like `ansi-red` or
+///   `ansi-bg-bright-blue`.
+/// - `ansi-256-<n>` and `ansi-bg-256-<n>` for the colors of the 256-color palette.
+/// - `ansi-rgb-<rrggbb>` and `ansi-bg-rgb-<rrggbb>` for true colors.
+/// - `ansi-dim` and `ansi-inverse`.
+///
+/// Carriage returns overwrite the line, like progress bars do, and other escape sequences are
+/// dropped.
+///
+/// ```
+/// use opmark::{convert::from_ansi, serializer::to_source};
+///
+/// let marks = from_ansi("\x1b[1;32mok\x1b[0m 2 tests\n");
+/// assert_eq!(to_source(&marks), "{.console .ansi-green}*ok{.console}* 2 tests");
+/// ```
+pub fn from_ansi(text: &str) -> Vec<Mark> {
+    let mut lines: Vec<Vec<(String, Attributes)>> = vec![vec![]];
+    let mut attributes = Attributes::default();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let line = lines.last_mut().expect("there is always a line");
+        match c {
+            '\x1b' => match chars.next() {
+                // Control Sequence Introducer: parameters, intermediates and a final byte
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut end = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            end = Some(c);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    if end == Some('m') {
+                        attributes.apply(&params);
+                    }
+                }
+                // Operating System Command, ended by BEL or ST
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' => lines.push(vec![]),
+            '\r' if chars.peek() != Some(&'\n') => line.clear(),
+            '\x08' => {
+                if let Some((text, _)) = line.last_mut() {
+                    text.pop();
+                    if text.is_empty() {
+                        line.pop();
+                    }
+                }
+            }
+            c if c.is_control() && c != '\t' => {}
+            c => match line.last_mut() {
+                Some((text, run_attributes)) if *run_attributes == attributes => text.push(c),
+                _ => line.push((c.to_string(), attributes.clone())),
+            },
+        }
+    }
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let mut deck = DeckBuilder::default();
+    for (idx, line) in lines.into_iter().enumerate() {
+        if idx > 0 {
+            deck.push(Mark::NewLine);
+        }
+        for (text, attributes) in line {
+            deck.push(Mark::Text(text, attributes.style()));
+        }
+    }
+    deck.finish()
+}
+
+/// The attributes of text set by Select Graphic Rendition sequences.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Attributes {
+    bold: bool,
+    dim: bool,
+    italics: bool,
+    underline: bool,
+    inverse: bool,
+    strikethrough: bool,
+    /// The class of the foreground color, without the `ansi-` prefix.
+    foreground: Option<String>,
+    /// The class of the background color, without the `ansi-bg-` prefix.
+    background: Option<String>,
+}
+
+impl Attributes {
+    /// Apply the parameters of a Select Graphic Rendition sequence, like `1;31`.
+    fn apply(&mut self, params: &str) {
+        let mut codes = params
+            .split(';')
+            .map(|code| code.parse::<u8>().unwrap_or_default());
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Default::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italics = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strikethrough = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italics = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.foreground = Some(COLORS[code as usize - 30].to_owned()),
+                38 => self.foreground = extended_color(&mut codes),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(COLORS[code as usize - 40].to_owned()),
+                48 => self.background = extended_color(&mut codes),
+                49 => self.background = None,
+                90..=97 => self.foreground = Some(format!("bright-{}", COLORS[code as usize - 90])),
+                100..=107 => {
+                    self.background = Some(format!("bright-{}", COLORS[code as usize - 100]))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn style(&self) -> StyleText {
+        let mut style = StyleText::new().with_class("console".to_owned());
+        if let Some(foreground) = &self.foreground {
+            style = style.with_class(format!("ansi-{}", foreground));
+        }
+        if let Some(background) = &self.background {
+            style = style.with_class(format!("ansi-bg-{}", background));
+        }
+        if self.dim {
+            style = style.with_class("ansi-dim".to_owned());
+        }
+        if self.inverse {
+            style = style.with_class("ansi-inverse".to_owned());
+        }
+        style.bold = self.bold;
+        style.italics = self.italics;
+        style.underline = self.underline;
+        style.strikethrough = self.strikethrough;
+        style
+    }
+}
+
+/// The class of a color of the 256-color palette (`5;n`) or a true color (`2;r;g;b`), without
+/// prefix.
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<String> {
+    match codes.next()? {
+        5 => Some(format!("256-{}", codes.next()?)),
+        2 => Some(format!(
+            "rgb-{:02x}{:02x}{:02x}",
+            codes.next()?,
+            codes.next()?,
+            codes.next()?
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,171 @@
+//! AsciiDoc, as far as it maps to OpMark.
+
+use super::{ordered_item, DeckBuilder, InlineSyntax, Toggle};
+use crate::{
+    mark::{Heading, IndentLevel, Listing, Mark, StyleImage, StyleText},
+    parser::LINE_ENDING,
+};
+
+/// The inline syntax of AsciiDoc.
+const INLINE: InlineSyntax = InlineSyntax {
+    toggles: &[
+        ("**", Toggle::Bold),
+        ("__", Toggle::Italics),
+        ("*", Toggle::Bold),
+        ("_", Toggle::Italics),
+    ],
+    code: &['`'],
+    link,
+};
+
+/// Convert AsciiDoc text into a deck, starting a new page at each page break (`<<<`) and
+/// thematic break (`'''`).
+///
+/// Section titles, lists, listing, literal and quote blocks, block images, links and the bold,
+/// italics and monospace styles are converted. Attribute entries, block attributes and comments
+/// are dropped, other constructs are kept as text.
+///
+/// ```
+/// use opmark::{convert::from_asciidoc, serializer::to_source};
+///
+/// let marks = from_asciidoc("= Talk\n:toc:\n\n* *first* point\n<<<\n[source,rust]\n----\nfn main() {}\n----");
+/// assert_eq!(to_source(&marks), "# Talk\n\n- first point\n---\n```rust\nfn main() {}\n```");
+/// ```
+pub fn from_asciidoc(s: &str) -> Vec<Mark> {
+    let mut deck = DeckBuilder::default();
+    // the language set by a block attribute line, like `[source,rust]`
+    let mut language = None;
+    let mut numbers = [0u8; 5];
+    let mut lines = s.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            deck.blank();
+            continue;
+        }
+        if line == "<<<" || line == "'''" {
+            deck.page();
+            continue;
+        }
+        if line.starts_with("//") || is_attribute_entry(line) {
+            continue;
+        }
+        if let Some(attributes) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            let mut attributes = attributes.split(',').map(str::trim);
+            if attributes.next() == Some("source") {
+                language = attributes.next().map(str::to_owned);
+            }
+            continue;
+        }
+        match line {
+            "----" | "...." => {
+                let code: Vec<&str> = lines.by_ref().take_while(|end| *end != line).collect();
+                deck.push(Mark::CodeBlock(
+                    code.join(LINE_ENDING),
+                    language.take().filter(|_| line == "----"),
+                ));
+                continue;
+            }
+            "____" => {
+                for line in lines
+                    .by_ref()
+                    .take_while(|end| *end != "____")
+                    .filter(|line| !line.trim().is_empty())
+                {
+                    deck.push(Mark::Text(
+                        INLINE.plain(line.trim()),
+                        StyleText::new().with_quote(),
+                    ));
+                }
+                continue;
+            }
+            _ => language = None,
+        }
+
+        let (marker, text) = line.split_once(' ').unwrap_or((line, ""));
+        let text = text.trim_start();
+        let depth = marker.len();
+        if marker.chars().all(|c| c == '=') && !text.is_empty() {
+            deck.push(Mark::Text(
+                INLINE.plain(text),
+                StyleText::new()
+                    .with_heading(Heading::from(u8::try_from(depth).unwrap_or(u8::MAX))),
+            ));
+        } else if marker == "-" || (depth <= 5 && marker.chars().all(|c| c == '*')) {
+            deck.push(Mark::Text(
+                INLINE.plain(text),
+                StyleText::new()
+                    .with_listing(Listing::Unordered(IndentLevel::from(depth as u8 - 1))),
+            ));
+        } else if depth <= 5 && marker.chars().all(|c| c == '.') {
+            // number the items, restarting the deeper levels
+            numbers[depth - 1] = numbers[depth - 1].saturating_add(1);
+            numbers[depth..].fill(0);
+            deck.push(Mark::Text(
+                INLINE.plain(text),
+                StyleText::new().with_listing(Listing::Ordered(
+                    numbers[depth - 1],
+                    IndentLevel::from(depth as u8 - 1),
+                )),
+            ));
+        } else if let Some((number, text)) = ordered_item(line) {
+            deck.push(Mark::Text(
+                INLINE.plain(text),
+                StyleText::new().with_listing(Listing::Ordered(number, IndentLevel::None)),
+            ));
+        } else if let Some((alt, src, "")) = line.strip_prefix("image::").and_then(macro_target) {
+            let alt = if alt == src { "" } else { alt };
+            deck.push(Mark::Image(
+                src.to_owned(),
+                INLINE.plain(alt),
+                StyleImage::new(),
+            ));
+        } else {
+            for mark in INLINE.marks(line) {
+                deck.push(mark);
+            }
+        }
+    }
+    deck.finish()
+}
+
+/// `link:url[text]` or `https://url[text]`, returning the text, or the url without text, the url
+/// and the text after the link.
+fn link(s: &str) -> Option<(&str, &str, &str)> {
+    if let Some(target) = s.strip_prefix("link:") {
+        return macro_target(target);
+    }
+    if !(s.starts_with("https://") || s.starts_with("http://")) {
+        return None;
+    }
+    let end = s
+        .find(|c: char| c.is_whitespace() || c == '[')
+        .unwrap_or(s.len());
+    if s[end..].starts_with('[') {
+        return macro_target(s);
+    }
+    // punctuation ending a sentence
+    let end = s[..end]
+        .trim_end_matches(['.', ',', ';', ':', '!', '?', ')'])
+        .len();
+    Some((&s[..end], &s[..end], &s[end..]))
+}
+
+/// `target[text]`, returning the text, or the target without text, the target and the text after
+/// the macro.
+fn macro_target(s: &str) -> Option<(&str, &str, &str)> {
+    let (target, rest) = s.split_once('[')?;
+    let (text, rest) = rest.split_once(']')?;
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    let text = text.split(',').next().unwrap_or_default();
+    Some((if text.is_empty() { target } else { text }, target, rest))
+}
+
+/// `:name: value`
+fn is_attribute_entry(line: &str) -> bool {
+    line.strip_prefix(':')
+        .and_then(|s| s.split_once(':'))
+        .is_some_and(|(name, _)| !name.is_empty() && !name.contains(' '))
+}
@@ -0,0 +1,490 @@
+//! HTML fragments, like the rich text copied from browsers and editors.
+
+use super::DeckBuilder;
+use crate::{
+    mark::{
+        Heading, IndentLevel, Listing, Mark, SeparatorDir, SeparatorStyle, StyleImage, StyleText,
+    },
+    parser::LINE_ENDING,
+    render::{tag_end, unescape_xml},
+};
+use std::mem;
+
+/// The elements which start and end a paragraph.
+const BLOCKS: [&str; 24] = [
+    "address",
+    "article",
+    "aside",
+    "center",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "main",
+    "nav",
+    "p",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "thead",
+    "tr",
+];
+
+/// The elements whose content is not shown.
+const HIDDEN: [&str; 6] = ["head", "noscript", "script", "style", "template", "title"];
+
+/// The elements without content nor closing tags.
+const VOID: [&str; 10] = [
+    "area", "base", "br", "col", "hr", "img", "input", "link", "meta", "wbr",
+];
+
+/// Convert an HTML fragment into marks, to paste rich text into a deck with its formatting.
+///
+/// Paragraphs, headings, lists, quotes, preformatted code, images, links and the bold, italics,
+/// underline, strikethrough, small and code styles are converted, including the styles set by the
+/// `style` attribute, like the spans of online word processors. Other elements are kept as their
+/// text. Line breaks become paragraph breaks, which is the closest OpMark has, and text in list
+/// items, headings and quotes drops its styles, like in OpMark.
+///
+/// The fragment of the HTML format of the Windows clipboard, between `<!--StartFragment-->` and
+/// `<!--EndFragment-->`, is converted alone.
+///
+/// ```
+/// use opmark::{convert::from_html_fragment, serializer::to_source};
+///
+/// let html = "<p>Some <b>bold</b> and <a href=\"https://github.com/\">a link</a></p>\
+///             <ul><li>one</li><li><p>two</p></li></ul>\
+///             <span style=\"font-style:italic\">Pasted</span> text";
+/// assert_eq!(
+///     to_source(&from_html_fragment(html)),
+///     "Some *bold* and [a link](https://github.com/)\n\n- one\n- two\n\n/Pasted/ text"
+/// );
+/// ```
+pub fn from_html_fragment(html: &str) -> Vec<Mark> {
+    let html = match html.split_once("<!--StartFragment-->") {
+        Some((_, fragment)) => fragment
+            .split_once("<!--EndFragment-->")
+            .map_or(fragment, |(fragment, _)| fragment),
+        None => html,
+    };
+    let mut reader = Reader::default();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        reader.text(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.split_once("-->").map_or("", |(_, after)| after);
+            continue;
+        }
+        let Some(end) = tag_end(rest) else {
+            // a lone `<` is text
+            reader.text("<");
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if let Some(name) = tag.strip_prefix('/') {
+            reader.close(&name.trim().to_ascii_lowercase());
+        } else if !tag.starts_with(['!', '?']) {
+            let tag = tag.strip_suffix('/').unwrap_or(tag);
+            let name_end = tag
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(tag.len());
+            reader.open(
+                &tag[..name_end].to_ascii_lowercase(),
+                &attrs(&tag[name_end..]),
+            );
+        }
+    }
+    reader.text(rest);
+    reader.finish()
+}
+
+/// What the text being read becomes.
+#[derive(Clone, Debug, Default)]
+enum Block {
+    #[default]
+    Paragraph,
+    Heading(u8),
+    Item(Listing),
+    Quote,
+}
+
+#[derive(Debug, Default)]
+struct Reader {
+    deck: DeckBuilder,
+    /// The text of the current block, in runs of the same style.
+    runs: Vec<(String, StyleText)>,
+    block: Block,
+    /// The open elements, with the style of their text.
+    elements: Vec<(String, StyleText)>,
+    /// The open lists, with the number of their next item if they are ordered.
+    lists: Vec<Option<u8>>,
+    /// Whether a list item is open, in the innermost list.
+    is_item: bool,
+    quotes: usize,
+    /// The code and the language of the open `pre` element.
+    pre: Option<(String, Option<String>)>,
+    /// The depth of the hidden elements the reader is in.
+    hidden: usize,
+}
+
+impl Reader {
+    fn style(&self) -> StyleText {
+        self.elements
+            .last()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default()
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.hidden > 0 || text.is_empty() {
+            return;
+        }
+        let text = unescape_xml(text);
+        if let Some((code, _)) = &mut self.pre {
+            code.push_str(&text);
+            return;
+        }
+        // runs of whitespace show as one space
+        let mut collapsed = String::with_capacity(text.len());
+        let mut is_space = self.runs.last().is_none_or(|(text, _)| text.ends_with(' '));
+        for c in text.chars() {
+            if c.is_ascii_whitespace() {
+                if !is_space {
+                    collapsed.push(' ');
+                }
+                is_space = true;
+            } else {
+                collapsed.push(c);
+                is_space = false;
+            }
+        }
+        if collapsed.is_empty() {
+            return;
+        }
+        let style = self.style();
+        match self.runs.last_mut() {
+            Some((text, last)) if *last == style => text.push_str(&collapsed),
+            _ => self.runs.push((collapsed, style)),
+        }
+    }
+
+    fn open(&mut self, name: &str, attrs: &[(String, String)]) {
+        let attr = |name: &str| {
+            attrs
+                .iter()
+                .find(|(attr, _)| attr == name)
+                .map(|(_, value)| value.as_str())
+        };
+        if self.hidden > 0 || HIDDEN.contains(&name) {
+            if !VOID.contains(&name) {
+                self.hidden += 1;
+                self.elements.push((name.to_owned(), StyleText::new()));
+            }
+            return;
+        }
+        if let Some((code, language)) = &mut self.pre {
+            match name {
+                "br" => code.push('\n'),
+                "code" if language.is_none() => *language = attr("class").and_then(code_language),
+                _ => {}
+            }
+            if !VOID.contains(&name) {
+                self.elements.push((name.to_owned(), StyleText::new()));
+            }
+            return;
+        }
+        match name {
+            "br" => self.break_block(),
+            "hr" => {
+                self.finish_block();
+                self.deck.push(Mark::Separator(
+                    SeparatorDir::Horizontal,
+                    SeparatorStyle::new(),
+                ));
+            }
+            "img" => {
+                let Some(src) = attr("src").filter(|src| !src.is_empty()) else {
+                    return;
+                };
+                self.finish_block();
+                let mut style = StyleImage::new();
+                let hyperlink = self.style().hyperlink;
+                if !hyperlink.is_empty() {
+                    style = style.with_hyperlink(hyperlink);
+                }
+                let title = attr("alt").unwrap_or_default().trim().to_owned();
+                self.deck.push(Mark::Image(src.to_owned(), title, style));
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.finish_block();
+                self.block = Block::Heading(name.as_bytes()[1] - b'0');
+            }
+            "ul" | "ol" => {
+                self.finish_block();
+                let start = attr("start").and_then(|start| start.parse().ok());
+                self.lists.push((name == "ol").then(|| start.unwrap_or(1)));
+                self.is_item = false;
+            }
+            "li" => {
+                self.finish_block();
+                let indent = IndentLevel::from(
+                    u8::try_from(self.lists.len().saturating_sub(1)).unwrap_or(u8::MAX),
+                );
+                self.block = Block::Item(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let listing = Listing::Ordered(*number, indent);
+                        *number = number.saturating_add(1);
+                        listing
+                    }
+                    _ => Listing::Unordered(indent),
+                });
+                self.is_item = true;
+            }
+            "blockquote" => {
+                self.finish_block();
+                self.quotes += 1;
+            }
+            "pre" => {
+                self.finish_block();
+                self.pre = Some((String::new(), attr("class").and_then(code_language)));
+            }
+            "td" | "th" => self.text(" "),
+            name if BLOCKS.contains(&name) => self.break_block(),
+            _ => {}
+        }
+        if !VOID.contains(&name) {
+            let style = element_style(name, attr, self.style());
+            self.elements.push((name.to_owned(), style));
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        let Some(idx) = self.elements.iter().rposition(|(open, _)| open == name) else {
+            return;
+        };
+        // elements left open in it, like `p` and `li` whose closing tags are optional, are
+        // closed with it
+        while self.elements.len() > idx {
+            let Some((name, _)) = self.elements.pop() else {
+                break;
+            };
+            self.end(&name);
+        }
+    }
+
+    /// End the open element `name`.
+    fn end(&mut self, name: &str) {
+        if self.hidden > 0 {
+            self.hidden -= 1;
+            return;
+        }
+        match name {
+            "pre" => {
+                if let Some((code, language)) = self.pre.take() {
+                    let code = code.strip_prefix('\n').unwrap_or(&code);
+                    let code = code.strip_suffix('\n').unwrap_or(code);
+                    self.deck
+                        .push(Mark::CodeBlock(code.replace('\n', LINE_ENDING), language));
+                    self.deck.blank();
+                }
+            }
+            _ if self.pre.is_some() => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.finish_block(),
+            "li" => {
+                self.finish_block();
+                self.is_item = false;
+            }
+            "ul" | "ol" => {
+                self.finish_block();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.deck.blank();
+                }
+            }
+            "blockquote" => {
+                self.finish_block();
+                self.quotes = self.quotes.saturating_sub(1);
+                if self.quotes == 0 {
+                    self.deck.blank();
+                }
+            }
+            name if BLOCKS.contains(&name) => self.break_block(),
+            _ => {}
+        }
+    }
+
+    /// End the current paragraph, or separate the paragraphs of a list item, which takes one
+    /// line.
+    fn break_block(&mut self) {
+        if self.is_item {
+            self.text(" ");
+        } else {
+            self.finish_block();
+        }
+    }
+
+    /// Add the text of the current block to the deck.
+    fn finish_block(&mut self) {
+        let mut runs = mem::take(&mut self.runs);
+        // the text after a block, like the rest of an item after a nested list, is a paragraph
+        let block = match mem::take(&mut self.block) {
+            Block::Paragraph if self.quotes > 0 => Block::Quote,
+            block => block,
+        };
+        if let Some((text, _)) = runs.first_mut() {
+            *text = text.trim_start().to_owned();
+        }
+        if let Some((text, _)) = runs.last_mut() {
+            *text = text.trim_end().to_owned();
+        }
+        runs.retain(|(text, _)| !text.is_empty());
+        if runs.is_empty() {
+            return;
+        }
+        let plain = || {
+            runs.iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<String>()
+        };
+        match block {
+            Block::Paragraph => {
+                for (text, style) in runs {
+                    self.deck.push(Mark::Text(text, style));
+                }
+                self.deck.blank();
+            }
+            Block::Heading(level) => self.deck.push(Mark::Text(
+                plain(),
+                StyleText::new().with_heading(Heading::from(level)),
+            )),
+            Block::Item(listing) => self
+                .deck
+                .push(Mark::Text(plain(), StyleText::new().with_listing(listing))),
+            Block::Quote => self
+                .deck
+                .push(Mark::Text(plain(), StyleText::new().with_quote())),
+        }
+    }
+
+    fn finish(mut self) -> Vec<Mark> {
+        while let Some((name, _)) = self.elements.pop() {
+            self.end(&name);
+        }
+        self.finish_block();
+        self.deck.finish()
+    }
+}
+
+/// The style of the text of the element `name`, in an element whose text is in `style`.
+fn element_style<'a>(
+    name: &str,
+    attr: impl Fn(&str) -> Option<&'a str>,
+    style: StyleText,
+) -> StyleText {
+    let mut style = match name {
+        "a" => match attr("href").filter(|href| !href.is_empty()) {
+            Some(href) => {
+                let style = StyleText::new().with_hyperlink(href.to_owned());
+                match attr("title").filter(|title| !title.is_empty()) {
+                    Some(title) => style.with_link_title(title.to_owned()),
+                    None => style,
+                }
+            }
+            None => style,
+        },
+        // code and links have no other styles
+        _ if style.code || !style.hyperlink.is_empty() => style,
+        "code" | "kbd" | "samp" | "tt" => StyleText::new().with_code(),
+        "b" | "strong" => style.with_bold(),
+        "cite" | "dfn" | "em" | "i" | "var" => style.with_italics(),
+        "ins" | "u" => style.with_underline(),
+        "del" | "s" | "strike" => style.with_strikethrough(),
+        "small" => style.with_small(),
+        _ => style,
+    };
+    if style.code || !style.hyperlink.is_empty() {
+        return style;
+    }
+    // the declarations of the `style` attribute
+    for declaration in attr("style").unwrap_or_default().split(';') {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match property.trim().to_ascii_lowercase().as_str() {
+            "font-weight" => {
+                style.bold = match value.parse::<u16>() {
+                    Ok(weight) => weight >= 600,
+                    Err(_) => value.starts_with("bold"),
+                }
+            }
+            "font-style" => style.italics = value == "italic" || value == "oblique",
+            "text-decoration" | "text-decoration-line" => {
+                style.underline = value.contains("underline");
+                style.strikethrough = value.contains("line-through");
+            }
+            _ => {}
+        }
+    }
+    style
+}
+
+/// The language of code from the classes of its element, like `language-rust`.
+fn code_language(classes: &str) -> Option<String> {
+    classes
+        .split_ascii_whitespace()
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+        })
+        .filter(|language| !language.is_empty())
+        .map(str::to_owned)
+}
+
+/// The attributes of a tag, after its name, with lowercase names. Values may be quoted or not,
+/// and attributes without values have empty values.
+fn attrs(s: &str) -> Vec<(String, String)> {
+    let mut attrs = vec![];
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, after) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        after[1..].split_once(quote).unwrap_or((&after[1..], ""))
+                    }
+                    _ => after.split_at(
+                        after
+                            .find(|c: char| c.is_ascii_whitespace())
+                            .unwrap_or(after.len()),
+                    ),
+                };
+                rest = after.trim_start();
+                unescape_xml(value)
+            }
+            None => String::new(),
+        };
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+    }
+    attrs
+}
@@ -0,0 +1,152 @@
+//! Jupyter notebooks.
+
+use super::{markdown::push_markdown, ConvertError, DeckBuilder};
+use crate::{
+    mark::{Mark, StyleImage},
+    parser::LINE_ENDING,
+};
+use serde_json::Value;
+
+/// The image types of outputs, which are base64 encoded in notebooks.
+const IMAGE_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/gif"];
+
+/// Convert the JSON text of a Jupyter notebook (`.ipynb`) into a deck.
+///
+/// Each markdown cell starts a new page, and more at each thematic break, see
+/// [`from_markdown`](super::from_markdown). Each code cell starts a new page with its code, and
+/// its outputs appear in a transition below: text outputs as code blocks, and images as images
+/// with a `data:` source. Raw cells and empty cells are skipped.
+///
+/// ```
+/// use opmark::{convert::from_ipynb, serializer::to_source};
+///
+/// let notebook = r##"{
+///     "metadata": { "language_info": { "name": "python" } },
+///     "cells": [
+///         { "cell_type": "markdown", "source": ["# Results\n", "Some *data*"] },
+///         {
+///             "cell_type": "code",
+///             "source": "print(42)",
+///             "outputs": [{ "output_type": "stream", "text": ["42\n"] }]
+///         }
+///     ]
+/// }"##;
+/// let marks = from_ipynb(notebook).unwrap();
+/// assert_eq!(
+///     to_source(&marks),
+///     "# Results\nSome /data/\n---\n```python\nprint(42)\n```\n---t\n```\n42\n```"
+/// );
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ConvertError::Invalid`] if `s` is not a notebook.
+pub fn from_ipynb(s: &str) -> Result<Vec<Mark>, ConvertError> {
+    let notebook: Value =
+        serde_json::from_str(s).map_err(|err| ConvertError::Invalid(err.to_string()))?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ConvertError::Invalid("missing cells".to_owned()))?;
+    let language = notebook
+        .pointer("/metadata/language_info/name")
+        .or_else(|| notebook.pointer("/metadata/kernelspec/language"))
+        .and_then(Value::as_str);
+
+    let mut deck = DeckBuilder::default();
+    for cell in cells {
+        let source = text(cell.get("source"));
+        let outputs = cell
+            .get("outputs")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") if !source.trim().is_empty() => {
+                deck.page();
+                let start = deck.marks.len();
+                push_markdown(&mut deck, &source);
+                // images attached to the cell
+                for mark in &mut deck.marks[start..] {
+                    if let Mark::Image(src, _, _) = mark {
+                        if let Some(data) = src
+                            .strip_prefix("attachment:")
+                            .and_then(|name| cell.get("attachments")?.get(name))
+                            .and_then(image)
+                        {
+                            *src = data;
+                        }
+                    }
+                }
+            }
+            Some("code") if !source.trim().is_empty() || !outputs.is_empty() => {
+                deck.page();
+                if !source.trim().is_empty() {
+                    deck.push(Mark::CodeBlock(lines(&source), language.map(str::to_owned)));
+                }
+                if !outputs.is_empty() {
+                    deck.transition();
+                }
+                for output in outputs {
+                    if let Some(mark) = output_mark(output) {
+                        deck.push(mark);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(deck.finish())
+}
+
+/// The mark showing an output of a code cell.
+fn output_mark(output: &Value) -> Option<Mark> {
+    let text = match output.get("output_type").and_then(Value::as_str)? {
+        "stream" => text(output.get("text")),
+        "execute_result" | "display_data" => {
+            let data = output.get("data")?;
+            if let Some(src) = image(data) {
+                return Some(Mark::Image(src, String::new(), StyleImage::new()));
+            }
+            text(data.get("text/plain"))
+        }
+        "error" => format!(
+            "{}: {}",
+            output
+                .get("ename")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+            output
+                .get("evalue")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+        ),
+        _ => return None,
+    };
+    Some(Mark::CodeBlock(lines(&text), None))
+}
+
+/// The first image in a MIME bundle, as a `data:` URL.
+fn image(data: &Value) -> Option<String> {
+    IMAGE_TYPES.iter().find_map(|mime| {
+        let base64: String = text(data.get(*mime))
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        (!base64.is_empty()).then(|| format!("data:{};base64,{}", mime, base64))
+    })
+}
+
+/// Multiline text, which notebooks store as a string or an array of lines.
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// `s` with the line endings of OpMark and without the trailing line ending.
+fn lines(s: &str) -> String {
+    s.lines().collect::<Vec<&str>>().join(LINE_ENDING)
+}
@@ -0,0 +1,129 @@
+//! Markdown, as far as it maps to OpMark.
+
+use super::{ordered_item, DeckBuilder, InlineSyntax, Toggle};
+use crate::{
+    mark::{Heading, IndentLevel, Listing, Mark, StyleImage, StyleText},
+    parser::LINE_ENDING,
+};
+
+/// Convert Markdown text into a deck, starting a new page at each thematic break (`---`).
+///
+/// Headings, lists, quotes, fenced code blocks, images on their own line, links and the bold,
+/// italics, strikethrough and code styles are converted. Other constructs are kept as text.
+///
+/// ```
+/// use opmark::{convert::from_markdown, serializer::to_source};
+///
+/// let marks = from_markdown("# Title\n\nSome **bold** and _italics_\n\n---\n\n- item");
+/// assert_eq!(to_source(&marks), "# Title\n\nSome *bold* and /italics/\n---\n- item");
+/// ```
+pub fn from_markdown(s: &str) -> Vec<Mark> {
+    let mut deck = DeckBuilder::default();
+    push_markdown(&mut deck, s);
+    deck.finish()
+}
+
+/// Add the marks of the Markdown text `s` to the current page of `deck`.
+pub(super) fn push_markdown(deck: &mut DeckBuilder, s: &str) {
+    let mut lines = s.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            deck.blank();
+            continue;
+        }
+        if is_thematic_break(trimmed) {
+            deck.page();
+            continue;
+        }
+        if let Some(info) = trimmed.strip_prefix("```") {
+            let code: Vec<&str> = lines
+                .by_ref()
+                .take_while(|line| !line.trim_start().starts_with("```"))
+                .collect();
+            let language = info.trim();
+            deck.push(Mark::CodeBlock(
+                code.join(LINE_ENDING),
+                (!language.is_empty()).then(|| language.to_owned()),
+            ));
+            continue;
+        }
+        let indent =
+            IndentLevel::from(u8::try_from((line.len() - trimmed.len()) / 2).unwrap_or(u8::MAX));
+        if let Some((level, text)) = heading(trimmed) {
+            deck.push(Mark::Text(
+                plain(text),
+                StyleText::new().with_heading(Heading::from(level)),
+            ));
+        } else if let Some(text) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        {
+            deck.push(Mark::Text(
+                plain(text),
+                StyleText::new().with_listing(Listing::Unordered(indent)),
+            ));
+        } else if let Some((number, text)) = ordered_item(trimmed) {
+            deck.push(Mark::Text(
+                plain(text),
+                StyleText::new().with_listing(Listing::Ordered(number, indent)),
+            ));
+        } else if let Some(text) = trimmed.strip_prefix('>') {
+            deck.push(Mark::Text(
+                plain(text.trim_start()),
+                StyleText::new().with_quote(),
+            ));
+        } else if let Some((title, src, "")) = trimmed.strip_prefix('!').and_then(link) {
+            deck.push(Mark::Image(src.to_owned(), plain(title), StyleImage::new()));
+        } else {
+            for mark in INLINE.marks(trimmed) {
+                deck.push(mark);
+            }
+        }
+    }
+}
+
+/// The inline syntax of Markdown.
+const INLINE: InlineSyntax = InlineSyntax {
+    toggles: &[
+        ("**", Toggle::Bold),
+        ("__", Toggle::Bold),
+        ("~~", Toggle::Strikethrough),
+        ("*", Toggle::Italics),
+        ("_", Toggle::Italics),
+    ],
+    code: &['`'],
+    link: |s| link(s.strip_prefix('!').unwrap_or(s)),
+};
+
+/// The text of a line, without the styles.
+fn plain(line: &str) -> String {
+    INLINE.plain(line)
+}
+
+/// `# Heading`, returning the level and the text.
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = &line[level..];
+    if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with(' ')) {
+        return None;
+    }
+    Some((level as u8, text.trim().trim_end_matches('#').trim_end()))
+}
+
+/// `[title](url "tooltip")`, returning the title, the url and the text after the link.
+fn link(s: &str) -> Option<(&str, &str, &str)> {
+    let (title, rest) = s.strip_prefix('[')?.split_once("](")?;
+    let (target, rest) = rest.split_once(')')?;
+    let url = target.split_whitespace().next().unwrap_or_default();
+    Some((title, url, rest))
+}
+
+/// `---`, `***` or `___`, possibly with spaces between the characters.
+fn is_thematic_break(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| chars.iter().all(|&x| x == c))
+}
@@ -0,0 +1,261 @@
+//! Converters between OpMark decks and other document formats.
+//!
+//! The `from_*` converters return the flat marks the parser would yield for the converted deck,
+//! which [`to_source`](crate::serializer::to_source) turns into OpMark text.
+//!
+//! Some converters need extra dependencies and are enabled by Cargo features:
+//! - `ipynb`: [`from_ipynb`].
+//! - `pandoc`: [`from_pandoc_json`] and [`to_pandoc_json`].
+
+mod ansi;
+mod asciidoc;
+mod html;
+#[cfg(feature = "ipynb")]
+mod ipynb;
+mod markdown;
+mod opml;
+mod org;
+mod outline;
+#[cfg(feature = "pandoc")]
+mod pandoc;
+
+pub use self::ansi::from_ansi;
+pub use self::asciidoc::from_asciidoc;
+pub use self::html::from_html_fragment;
+#[cfg(feature = "ipynb")]
+pub use self::ipynb::from_ipynb;
+pub use self::markdown::from_markdown;
+pub use self::opml::{to_freemind, to_opml};
+pub use self::org::from_org;
+pub use self::outline::{from_outline, OutlineOptions};
+#[cfg(feature = "pandoc")]
+pub use self::pandoc::{from_pandoc_json, to_pandoc_json};
+
+use crate::mark::{Mark, StylePage, StyleText, StyleTransition};
+use std::{error, fmt, mem};
+
+/// An error converting a document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConvertError {
+    /// The document is not valid in its format.
+    Invalid(String),
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Invalid(reason) => write!(f, "invalid document: {}", reason),
+        }
+    }
+}
+
+impl error::Error for ConvertError {}
+
+/// Builds the flat marks of a deck, keeping the output order of the parser.
+#[derive(Debug, Default)]
+struct DeckBuilder {
+    marks: Vec<Mark>,
+    transition_order: usize,
+    /// Whether the current page has content.
+    has_content: bool,
+    /// Whether a blank line follows the content of the current page.
+    is_blank: bool,
+}
+
+impl DeckBuilder {
+    /// Start a new page.
+    fn page(&mut self) {
+        self.marks.push(Mark::Page(vec![], StylePage::new()));
+        self.marks
+            .push(Mark::Transition(0, vec![], StyleTransition::new()));
+        self.transition_order = 1;
+        self.has_content = false;
+        self.is_blank = false;
+    }
+
+    /// Start a new transition in the current page.
+    fn transition(&mut self) {
+        if self.marks.is_empty() {
+            self.page();
+        }
+        self.marks.push(Mark::Transition(
+            self.transition_order,
+            vec![],
+            StyleTransition::new(),
+        ));
+        self.transition_order += 1;
+    }
+
+    /// Add a blank line, which becomes a `Mark::NewLine` between content, but not at the start
+    /// or the end of pages.
+    fn blank(&mut self) {
+        self.is_blank = self.has_content;
+    }
+
+    /// Add `mark` to the current transition.
+    fn push(&mut self, mark: Mark) {
+        if self.marks.is_empty() {
+            self.page();
+        }
+        if mem::take(&mut self.is_blank) {
+            self.marks.push(Mark::NewLine);
+        }
+        self.marks.push(mark);
+        self.has_content = true;
+    }
+
+    fn finish(self) -> Vec<Mark> {
+        self.marks
+    }
+}
+
+/// A text style toggled by an inline marker.
+#[derive(Clone, Copy, Debug)]
+enum Toggle {
+    Bold,
+    Italics,
+    Strikethrough,
+    Underline,
+}
+
+impl Toggle {
+    fn is_on(self, style: &StyleText) -> bool {
+        match self {
+            Toggle::Bold => style.bold,
+            Toggle::Italics => style.italics,
+            Toggle::Strikethrough => style.strikethrough,
+            Toggle::Underline => style.underline,
+        }
+    }
+
+    fn flag(self, style: &mut StyleText) -> &mut bool {
+        match self {
+            Toggle::Bold => &mut style.bold,
+            Toggle::Italics => &mut style.italics,
+            Toggle::Strikethrough => &mut style.strikethrough,
+            Toggle::Underline => &mut style.underline,
+        }
+    }
+}
+
+/// How a format marks up text within a line.
+struct InlineSyntax {
+    /// The markers toggling styles, longer markers first.
+    toggles: &'static [(&'static str, Toggle)],
+    /// The chars around code spans.
+    code: &'static [char],
+    /// Parse a link at the start of the text, returning the title, the url and the text after
+    /// the link.
+    link: fn(&str) -> Option<(&str, &str, &str)>,
+}
+
+impl InlineSyntax {
+    /// Convert a line of text into text elements.
+    ///
+    /// Markers only toggle styles before or after text, and single char markers within words are
+    /// kept as text, like the punctuation chars escaped with `\`.
+    fn marks(&self, line: &str) -> Vec<Mark> {
+        let mut marks = vec![];
+        let mut style = StyleText::new();
+        let mut text = String::new();
+        let mut prev = None;
+        let mut rest = line.trim_end();
+        while let Some(c) = rest.chars().next() {
+            let next = rest[c.len_utf8()..].chars().next();
+            if c == '\\' && next.is_some_and(|next| next.is_ascii_punctuation()) {
+                text.push(next.unwrap_or_default());
+                rest = &rest[2..];
+                prev = next;
+                continue;
+            }
+            if let Some((code, after)) = self
+                .code
+                .contains(&c)
+                .then(|| rest[1..].split_once(c))
+                .flatten()
+                .filter(|(code, _)| !code.is_empty() && code.trim() == *code)
+            {
+                flush(&mut marks, &mut text, &style);
+                marks.push(Mark::Text(code.to_owned(), StyleText::new().with_code()));
+                rest = after;
+                prev = Some(c);
+                continue;
+            }
+            if let Some((title, url, after)) = (self.link)(rest) {
+                flush(&mut marks, &mut text, &style);
+                // links without a title show their url
+                let title = if title == url {
+                    url.to_owned()
+                } else {
+                    self.plain(title)
+                };
+                marks.push(Mark::Text(
+                    title,
+                    StyleText::new().with_hyperlink(url.to_owned()),
+                ));
+                rest = after;
+                prev = None;
+                continue;
+            }
+            let toggle = self
+                .toggles
+                .iter()
+                .find(|(marker, _)| rest.starts_with(marker))
+                .filter(|(marker, toggle)| {
+                    let after = rest[marker.len()..].chars().next();
+                    // opening markers come before text, closing markers after text
+                    let is_flanking = if toggle.is_on(&style) {
+                        prev.is_some_and(|c| !c.is_whitespace())
+                    } else {
+                        after.is_some_and(|c| !c.is_whitespace())
+                    };
+                    let is_intraword = marker.len() == 1
+                        && prev.is_some_and(char::is_alphanumeric)
+                        && after.is_some_and(char::is_alphanumeric);
+                    is_flanking && !is_intraword
+                });
+            match toggle {
+                Some((marker, toggle)) => {
+                    flush(&mut marks, &mut text, &style);
+                    let flag = toggle.flag(&mut style);
+                    *flag = !*flag;
+                    rest = &rest[marker.len()..];
+                }
+                None => {
+                    text.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+            prev = Some(c);
+        }
+        flush(&mut marks, &mut text, &style);
+        marks
+    }
+
+    /// The text of a line, without the styles.
+    fn plain(&self, line: &str) -> String {
+        self.marks(line)
+            .into_iter()
+            .filter_map(|mark| match mark {
+                Mark::Text(text, _) => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn flush(marks: &mut Vec<Mark>, text: &mut String, style: &StyleText) {
+    if !text.is_empty() {
+        marks.push(Mark::Text(mem::take(text), style.clone()));
+    }
+}
+
+/// `1. item` or `1) item`, returning the number and the text.
+fn ordered_item(line: &str) -> Option<(u8, &str)> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let text = line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))?;
+    let number = line[..digits].parse::<usize>().ok()?;
+    Some((number.min(u8::MAX as usize) as u8, text))
+}
@@ -0,0 +1,131 @@
+//! Outlines of decks, for outliners and mind mapping tools.
+
+use crate::{
+    deck::Deck,
+    mark::Mark,
+    render::{escape_xml, page_marks},
+};
+
+/// An entry of the outline of a deck, with the entries nested under it.
+struct Node {
+    text: String,
+    children: Vec<Node>,
+}
+
+/// Convert the outline of `deck` into OPML 2.0, to review and reorganize the deck in an
+/// outliner.
+///
+/// Headings are nested by level. Pages without headings are entries under the last heading
+/// before them, with the title of the page, see [`Page::title`](crate::deck::Page::title). The
+/// title of the outline is the `title` of the front matter.
+///
+/// ```
+/// use opmark::{convert::to_opml, deck::Deck};
+///
+/// let deck = Deck::new("# Intro\n## Why it matters\n---\nSome context\n---\n# Results".to_owned());
+/// assert_eq!(
+///     to_opml(&deck),
+///     "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+///      <opml version=\"2.0\"><head><title>OpMark</title></head><body>\
+///      <outline text=\"Intro\"><outline text=\"Why it matters\"><outline text=\"Some context\"/>\
+///      </outline></outline><outline text=\"Results\"/></body></opml>\n"
+/// );
+/// ```
+pub fn to_opml(deck: &Deck) -> String {
+    let mut body = String::new();
+    write(&outline(deck), "outline", "text", &mut body);
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <opml version=\"2.0\"><head><title>{}</title></head><body>{}</body></opml>\n",
+        escape_xml(title(deck)),
+        body
+    )
+}
+
+/// Convert the outline of `deck` into a FreeMind mind map, whose root is the title of the deck,
+/// see [`to_opml`].
+///
+/// ```
+/// use opmark::{convert::to_freemind, deck::Deck};
+///
+/// let deck = Deck::new("+++\ntitle = Q3 review\n+++\n# Sales\n## Europe".to_owned());
+/// assert_eq!(
+///     to_freemind(&deck),
+///     "<map version=\"1.0.1\"><node TEXT=\"Q3 review\"><node TEXT=\"Sales\">\
+///      <node TEXT=\"Europe\"/></node></node></map>\n"
+/// );
+/// ```
+pub fn to_freemind(deck: &Deck) -> String {
+    let mut nodes = String::new();
+    write(&outline(deck), "node", "TEXT", &mut nodes);
+    format!(
+        "<map version=\"1.0.1\"><node TEXT=\"{}\">{}</node></map>\n",
+        escape_xml(title(deck)),
+        nodes
+    )
+}
+
+fn title(deck: &Deck) -> &str {
+    deck.front_matter().title().unwrap_or("OpMark")
+}
+
+/// The entries of the outline of `deck`.
+fn outline(deck: &Deck) -> Vec<Node> {
+    let mut entries: Vec<(u8, String)> = vec![];
+    let mut last_level = 0;
+    for page in deck.pages() {
+        let headings: Vec<(u8, String)> = page_marks(page)
+            .into_iter()
+            .filter_map(|mark| match mark {
+                Mark::Text(text, style) if style.heading.to_int() > 0 => {
+                    Some((style.heading.to_int(), text.trim().to_owned()))
+                }
+                _ => None,
+            })
+            .collect();
+        match headings.last() {
+            Some(&(level, _)) => {
+                last_level = level;
+                entries.extend(headings);
+            }
+            None => {
+                if let Some(title) = page.title() {
+                    entries.push((last_level + 1, title));
+                }
+            }
+        }
+    }
+    nest(&entries)
+}
+
+/// The entries `entries` nested under the entries of lower levels before them.
+fn nest(entries: &[(u8, String)]) -> Vec<Node> {
+    let mut nodes = vec![];
+    let mut rest = entries;
+    while let Some(((level, text), after)) = rest.split_first() {
+        let end = after
+            .iter()
+            .position(|(other, _)| other <= level)
+            .unwrap_or(after.len());
+        nodes.push(Node {
+            text: text.clone(),
+            children: nest(&after[..end]),
+        });
+        rest = &after[end..];
+    }
+    nodes
+}
+
+/// Write `nodes` as the XML elements `tag`, with their text in the attribute `attr`.
+fn write(nodes: &[Node], tag: &str, attr: &str, out: &mut String) {
+    for node in nodes {
+        out.push_str(&format!("<{} {}=\"{}\"", tag, attr, escape_xml(&node.text)));
+        if node.children.is_empty() {
+            out.push_str("/>");
+        } else {
+            out.push('>');
+            write(&node.children, tag, attr, out);
+            out.push_str(&format!("</{}>", tag));
+        }
+    }
+}
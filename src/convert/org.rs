@@ -0,0 +1,169 @@
+//! Org-mode, as far as it maps to OpMark.
+
+use super::{ordered_item, DeckBuilder, InlineSyntax, Toggle};
+use crate::{
+    mark::{Heading, IndentLevel, Listing, Mark, StyleImage, StyleText},
+    parser::LINE_ENDING,
+};
+
+/// The extensions of links which are shown as images.
+const IMAGE_EXTENSIONS: [&str; 6] = [".png", ".jpg", ".jpeg", ".gif", ".svg", ".webp"];
+
+/// The inline syntax of Org-mode.
+const INLINE: InlineSyntax = InlineSyntax {
+    toggles: &[
+        ("*", Toggle::Bold),
+        ("/", Toggle::Italics),
+        ("_", Toggle::Underline),
+        ("+", Toggle::Strikethrough),
+    ],
+    code: &['=', '~'],
+    link,
+};
+
+/// Convert Org-mode text into a deck, starting a new page at each horizontal rule (`-----`).
+///
+/// The title, headings, lists, quote, source and example blocks, links to images on their own
+/// line, links and the bold, italics, underline, strikethrough, verbatim and code styles are
+/// converted. Other keywords, drawers and comments are dropped, other constructs are kept as
+/// text.
+///
+/// ```
+/// use opmark::{convert::from_org, serializer::to_source};
+///
+/// let marks = from_org("#+TITLE: Talk\n* Intro\n- /first/ point\n-----\n[[file:chart.png]]");
+/// assert_eq!(to_source(&marks), "# Talk\n# Intro\n- first point\n---\n![](chart.png)");
+/// ```
+pub fn from_org(s: &str) -> Vec<Mark> {
+    let mut deck = DeckBuilder::default();
+    let mut lines = s.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            deck.blank();
+            continue;
+        }
+        if trimmed.len() >= 5 && trimmed.trim_end().chars().all(|c| c == '-') {
+            deck.page();
+            continue;
+        }
+        if let Some(keyword) = trimmed.strip_prefix("#+") {
+            let name_end = keyword.find([' ', ':']).unwrap_or(keyword.len());
+            let (name, args) = keyword.split_at(name_end);
+            let args = args.trim_start_matches(':').trim();
+            let name = name.to_ascii_uppercase();
+            match name.as_str() {
+                "TITLE" => deck.push(Mark::Text(
+                    INLINE.plain(args),
+                    StyleText::new().with_heading(Heading::H1),
+                )),
+                "BEGIN_SRC" | "BEGIN_EXAMPLE" => {
+                    let end = format!("#+END_{}", &name[6..]);
+                    let code: Vec<&str> = lines
+                        .by_ref()
+                        .take_while(|line| !line.trim().eq_ignore_ascii_case(&end))
+                        .collect();
+                    let language = args
+                        .split_whitespace()
+                        .next()
+                        .filter(|_| name == "BEGIN_SRC");
+                    deck.push(Mark::CodeBlock(
+                        code.join(LINE_ENDING),
+                        language.map(str::to_owned),
+                    ));
+                }
+                "BEGIN_QUOTE" => {
+                    for line in lines
+                        .by_ref()
+                        .take_while(|line| !line.trim().eq_ignore_ascii_case("#+END_QUOTE"))
+                        .filter(|line| !line.trim().is_empty())
+                    {
+                        deck.push(Mark::Text(
+                            INLINE.plain(line.trim()),
+                            StyleText::new().with_quote(),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if trimmed.starts_with("# ") || trimmed == "#" {
+            continue;
+        }
+        if trimmed.starts_with(':') && trimmed.trim_end().ends_with(':') {
+            // drawers, like :PROPERTIES:, until :END:
+            if !trimmed.trim_end().eq_ignore_ascii_case(":END:") {
+                lines
+                    .by_ref()
+                    .find(|line| line.trim().eq_ignore_ascii_case(":END:"));
+            }
+            continue;
+        }
+
+        let indent =
+            IndentLevel::from(u8::try_from((line.len() - trimmed.len()) / 2).unwrap_or(u8::MAX));
+        let stars = line.chars().take_while(|&c| c == '*').count();
+        if stars > 0 && line[stars..].starts_with(' ') {
+            deck.push(Mark::Text(
+                INLINE.plain(without_tags(&line[stars..])),
+                StyleText::new()
+                    .with_heading(Heading::from(u8::try_from(stars).unwrap_or(u8::MAX))),
+            ));
+        } else if let Some(text) = ["- ", "+ ", "* "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        {
+            deck.push(Mark::Text(
+                INLINE.plain(text),
+                StyleText::new().with_listing(Listing::Unordered(indent)),
+            ));
+        } else if let Some((number, text)) = ordered_item(trimmed) {
+            deck.push(Mark::Text(
+                INLINE.plain(text),
+                StyleText::new().with_listing(Listing::Ordered(number, indent)),
+            ));
+        } else if let Some((title, src, "")) = link(trimmed.trim_end()) {
+            if IMAGE_EXTENSIONS
+                .iter()
+                .any(|ext| src.to_ascii_lowercase().ends_with(ext))
+            {
+                let title = if title == src { "" } else { title };
+                deck.push(Mark::Image(
+                    src.to_owned(),
+                    INLINE.plain(title),
+                    StyleImage::new(),
+                ));
+            } else {
+                for mark in INLINE.marks(trimmed) {
+                    deck.push(mark);
+                }
+            }
+        } else {
+            for mark in INLINE.marks(trimmed) {
+                deck.push(mark);
+            }
+        }
+    }
+    deck.finish()
+}
+
+/// `[[target][description]]` or `[[target]]`, returning the description, the target without
+/// a `file:` prefix, and the text after the link.
+fn link(s: &str) -> Option<(&str, &str, &str)> {
+    let (link, rest) = s.strip_prefix("[[")?.split_once("]]")?;
+    let (target, title) = link.split_once("][").unwrap_or((link, ""));
+    let target = target.strip_prefix("file:").unwrap_or(target);
+    Some((if title.is_empty() { target } else { title }, target, rest))
+}
+
+/// A heading without its tags, like `:work:urgent:`.
+fn without_tags(heading: &str) -> &str {
+    let heading = heading.trim();
+    match heading.rsplit_once(' ') {
+        Some((text, tags)) if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') => {
+            text.trim_end()
+        }
+        _ => heading,
+    }
+}
@@ -0,0 +1,120 @@
+//! Plain text outlines, to draft decks.
+
+use super::{ordered_item, DeckBuilder};
+use crate::mark::{Heading, IndentLevel, Listing, Mark, StyleText};
+
+/// The markers of unordered items, stripped from the items of outlines.
+const BULLETS: [&str; 4] = ["- ", "* ", "+ ", "• "];
+
+/// Options for [`from_outline`].
+#[derive(Clone, Debug)]
+pub struct OutlineOptions {
+    /// The heading level of the titles of the pages. [`Heading::H1`] by default.
+    pub heading: Heading,
+    /// Reveal the items of each page one by one, each with its children.
+    pub incremental: bool,
+    /// The number of spaces a tab indents by. 4 by default.
+    pub tab_width: usize,
+}
+
+impl Default for OutlineOptions {
+    fn default() -> Self {
+        Self {
+            heading: Heading::H1,
+            incremental: false,
+            tab_width: 4,
+        }
+    }
+}
+
+impl OutlineOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_heading(mut self, heading: Heading) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    #[inline]
+    pub fn with_incremental(mut self) -> Self {
+        self.incremental = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+}
+
+/// Convert an indented plain text outline into a deck: each item which is not indented starts a
+/// page, with the item as its title, and the items indented under it become the items of a list,
+/// as deep as they are indented.
+///
+/// Items are indented by spaces or tabs, by any width, and may start with a bullet, like `-`,
+/// `*` or `•`, which is dropped, or with a number, like `1.`, which makes an ordered item. Empty
+/// lines are ignored.
+///
+/// ```
+/// use opmark::{
+///     convert::{from_outline, OutlineOptions},
+///     serializer::to_source,
+/// };
+///
+/// let outline = "Intro\n  Who we are\n    2 teams\nResults\n\t1. Revenue\n\t2. Costs";
+/// let marks = from_outline(outline, &OutlineOptions::new().with_incremental());
+/// assert_eq!(
+///     to_source(&marks),
+///     "# Intro\n---t\n- Who we are\n  - 2 teams\n---\n# Results\n---t\n1. Revenue\n---t\n2. Costs"
+/// );
+/// ```
+pub fn from_outline(s: &str, options: &OutlineOptions) -> Vec<Mark> {
+    let mut deck = DeckBuilder::default();
+    // the indents of the items the current item is nested in
+    let mut indents: Vec<usize> = vec![];
+    for line in s.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent: usize = line[..line.len() - line.trim_start().len()]
+            .chars()
+            .map(|c| if c == '\t' { options.tab_width } else { 1 })
+            .sum();
+        let depth = indents.iter().filter(|&&outer| outer < indent).count();
+        indents.truncate(depth);
+        indents.push(indent);
+
+        let text = BULLETS
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+            .unwrap_or(trimmed)
+            .trim_start();
+        if depth == 0 {
+            deck.page();
+            deck.push(Mark::Text(
+                text.to_owned(),
+                StyleText::new().with_heading(options.heading.clone()),
+            ));
+            continue;
+        }
+        if depth == 1 && options.incremental {
+            deck.transition();
+        }
+        let level = IndentLevel::from(u8::try_from(depth - 1).unwrap_or(u8::MAX));
+        let (listing, text) = match ordered_item(text) {
+            Some((number, text)) => (Listing::Ordered(number, level), text),
+            None => (Listing::Unordered(level), text),
+        };
+        deck.push(Mark::Text(
+            text.to_owned(),
+            StyleText::new().with_listing(listing),
+        ));
+    }
+    deck.finish()
+}
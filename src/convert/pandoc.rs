@@ -0,0 +1,474 @@
+//! The JSON representation of the Pandoc AST, as read and written by `pandoc -t json`.
+
+use super::{ConvertError, DeckBuilder};
+use crate::{
+    mark::{Directive, Heading, IndentLevel, Listing, Mark, MathMode, StyleImage, StyleText},
+    parser::format_time_of_day,
+};
+use serde_json::{json, Value};
+
+/// The version of the Pandoc AST written by [`to_pandoc_json`].
+const API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// Convert a document in the JSON representation of the Pandoc AST into a deck, starting a new
+/// page at each horizontal rule.
+///
+/// Headers, paragraphs, lists, block quotes, code blocks, images alone in a paragraph or a
+/// figure, links, and the emphasis, strong, strikeout, underline, small caps and code styles
+/// are converted. The content of divs and spans is converted without them, and the other
+/// elements are dropped.
+///
+/// ```
+/// use opmark::{convert::from_pandoc_json, serializer::to_source};
+///
+/// // pandoc -t json <<< '# Title'
+/// let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+///     {"t":"Header","c":[1,["title",[],[]],[{"t":"Str","c":"Title"}]]}
+/// ]}"#;
+/// assert_eq!(to_source(&from_pandoc_json(json).unwrap()), "# Title");
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ConvertError::Invalid`] if `s` is not a Pandoc document.
+pub fn from_pandoc_json(s: &str) -> Result<Vec<Mark>, ConvertError> {
+    let document: Value =
+        serde_json::from_str(s).map_err(|err| ConvertError::Invalid(err.to_string()))?;
+    let blocks = document
+        .get("blocks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ConvertError::Invalid("missing blocks".to_owned()))?;
+    let mut deck = DeckBuilder::default();
+    read_blocks(&mut deck, blocks);
+    Ok(deck.finish())
+}
+
+/// Convert `marks` into the JSON representation of the Pandoc AST, e.g. to be converted further
+/// with `pandoc -f json`.
+///
+/// `marks` may either be flat or nested, like for [`to_source`](crate::serializer::to_source).
+/// Pages after the first start with a horizontal rule. Transitions, separators and classes are
+/// dropped, galleries become paragraphs of images and embeds become links.
+///
+/// ```
+/// use opmark::{convert::to_pandoc_json, mark::Mark, Parser};
+///
+/// let marks: Vec<Mark> = Parser::new("# Title\n- item".to_owned()).collect();
+/// assert_eq!(
+///     to_pandoc_json(&marks),
+///     concat!(
+///         r#"{"blocks":[{"c":[1,["",[],[]],[{"c":"Title","t":"Str"}]],"t":"Header"},"#,
+///         r#"{"c":[[{"c":[{"c":"item","t":"Str"}],"t":"Plain"}]],"t":"BulletList"}],"#,
+///         r#""meta":{},"pandoc-api-version":[1,23,1]}"#
+///     )
+/// );
+/// ```
+pub fn to_pandoc_json(marks: &[Mark]) -> String {
+    let mut writer = Writer::default();
+    writer.marks(marks);
+    writer.finish_paragraph();
+    writer.finish_list();
+    json!({
+        "pandoc-api-version": API_VERSION,
+        "meta": {},
+        "blocks": writer.blocks,
+    })
+    .to_string()
+}
+
+fn read_blocks(deck: &mut DeckBuilder, blocks: &[Value]) {
+    for block in blocks {
+        read_block(deck, block, &StyleText::new(), 0);
+    }
+}
+
+/// Read a block, where `style` is the style of the list or quote the block is in, and `depth`
+/// is the nesting depth of the lists.
+fn read_block(deck: &mut DeckBuilder, block: &Value, style: &StyleText, depth: u8) {
+    let content = &block["c"];
+    match block["t"].as_str().unwrap_or_default() {
+        "Header" => deck.push(Mark::Text(
+            plain(&content[2]),
+            StyleText::new().with_heading(Heading::from(
+                u8::try_from(content[0].as_u64().unwrap_or(1)).unwrap_or(u8::MAX),
+            )),
+        )),
+        "Para" | "Plain" => {
+            let inlines = content.as_array().map(Vec::as_slice).unwrap_or_default();
+            match inlines {
+                [image] if image["t"] == "Image" => read_image(deck, image),
+                _ if is_block_style(style) => deck.push(Mark::Text(plain(content), style.clone())),
+                _ => {
+                    for mark in read_inlines(content, style) {
+                        deck.push(mark);
+                    }
+                }
+            }
+            if block["t"] == "Para" && !is_block_style(style) {
+                deck.blank();
+            }
+        }
+        "CodeBlock" => deck.push(Mark::CodeBlock(
+            content[1].as_str().unwrap_or_default().to_owned(),
+            content[0][1][0].as_str().map(str::to_owned),
+        )),
+        "BulletList" | "OrderedList" => {
+            let (items, start) = match &content[0] {
+                Value::Array(attrs) if block["t"] == "OrderedList" => {
+                    (&content[1], attrs[0].as_u64().unwrap_or(1))
+                }
+                _ => (content, 0),
+            };
+            for (idx, item) in items.as_array().into_iter().flatten().enumerate() {
+                let listing = match start {
+                    0 => Listing::Unordered(IndentLevel::from(depth)),
+                    start => Listing::Ordered(
+                        (start + idx as u64).min(u8::MAX as u64) as u8,
+                        IndentLevel::from(depth),
+                    ),
+                };
+                let style = StyleText::new().with_listing(listing);
+                for block in item.as_array().into_iter().flatten() {
+                    read_block(deck, block, &style, depth.saturating_add(1));
+                }
+            }
+        }
+        "BlockQuote" => {
+            for block in content.as_array().into_iter().flatten() {
+                read_block(deck, block, &StyleText::new().with_quote(), depth);
+            }
+        }
+        "HorizontalRule" => deck.page(),
+        "Div" => {
+            for block in content[1].as_array().into_iter().flatten() {
+                read_block(deck, block, style, depth);
+            }
+        }
+        "Figure" => {
+            for block in content[2].as_array().into_iter().flatten() {
+                read_block(deck, block, style, depth);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn read_image(deck: &mut DeckBuilder, image: &Value) {
+    let content = &image["c"];
+    deck.push(Mark::Image(
+        content[2][0].as_str().unwrap_or_default().to_owned(),
+        plain(&content[1]),
+        StyleImage::new(),
+    ));
+}
+
+/// Whether text in `style` takes a whole line, so it can not have inline styles.
+fn is_block_style(style: &StyleText) -> bool {
+    style.quote || !matches!(style.listing, Listing::None)
+}
+
+/// Convert inlines into text elements, merging adjacent text with the same style.
+fn read_inlines(inlines: &Value, style: &StyleText) -> Vec<Mark> {
+    let mut marks: Vec<Mark> = vec![];
+    for inline in inlines.as_array().into_iter().flatten() {
+        let content = &inline["c"];
+        let (text, style) = match inline["t"].as_str().unwrap_or_default() {
+            "Str" => (
+                content.as_str().unwrap_or_default().to_owned(),
+                style.clone(),
+            ),
+            "Space" | "SoftBreak" | "LineBreak" => (" ".to_owned(), style.clone()),
+            "Code" => (
+                content[1].as_str().unwrap_or_default().to_owned(),
+                StyleText::new().with_code(),
+            ),
+            "Math" => {
+                let mode = match content[0]["t"].as_str() {
+                    Some("DisplayMath") => MathMode::Display,
+                    _ => MathMode::Inline,
+                };
+                let math = content[1].as_str().unwrap_or_default().to_owned();
+                marks.push(Mark::Math(math, mode));
+                continue;
+            }
+            "Link" => {
+                let style = StyleText::new()
+                    .with_hyperlink(content[2][0].as_str().unwrap_or_default().to_owned());
+                let style = match content[2][1].as_str().filter(|title| !title.is_empty()) {
+                    Some(title) => style.with_link_title(title.to_owned()),
+                    None => style,
+                };
+                (plain(&content[1]), style)
+            }
+            "Image" => (plain(&content[1]), style.clone()),
+            "Quoted" => {
+                marks.push(Mark::Text("\u{201c}".to_owned(), style.clone()));
+                marks.extend(read_inlines(&content[1], style));
+                ("\u{201d}".to_owned(), style.clone())
+            }
+            t => {
+                let (inner, inner_style) = match t {
+                    "Emph" => (content, style.clone().with_italics()),
+                    "Strong" => (content, style.clone().with_bold()),
+                    "Strikeout" => (content, style.clone().with_strikethrough()),
+                    "Underline" => (content, style.clone().with_underline()),
+                    "SmallCaps" => (content, style.clone().with_small()),
+                    "Span" => (&content[1], style.clone()),
+                    _ => continue,
+                };
+                marks.extend(read_inlines(inner, &inner_style));
+                continue;
+            }
+        };
+        marks.push(Mark::Text(text, style));
+    }
+    // merge adjacent text with the same style
+    let mut merged: Vec<Mark> = vec![];
+    for mark in marks {
+        if let (Some(Mark::Text(prev, prev_style)), Mark::Text(text, style)) =
+            (merged.last_mut(), &mark)
+        {
+            if prev_style == style {
+                prev.push_str(text);
+                continue;
+            }
+        }
+        merged.push(mark);
+    }
+    merged
+}
+
+/// The text of inlines, without the styles.
+fn plain(inlines: &Value) -> String {
+    read_inlines(inlines, &StyleText::new())
+        .into_iter()
+        .filter_map(|mark| match mark {
+            Mark::Text(text, _) | Mark::Math(text, _) => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+/// An item of a list being written.
+struct ListItem {
+    indent: u8,
+    number: Option<u8>,
+    inlines: Vec<Value>,
+}
+
+/// Writes the blocks of a Pandoc document.
+#[derive(Default)]
+struct Writer {
+    blocks: Vec<Value>,
+    /// The inlines of the current paragraph.
+    paragraph: Vec<Value>,
+    /// The items of the current list.
+    list: Vec<ListItem>,
+    has_page: bool,
+}
+
+impl Writer {
+    fn marks(&mut self, marks: &[Mark]) {
+        for mark in marks {
+            self.mark(mark);
+        }
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        if let Mark::Text(text, style) = mark {
+            if let Listing::Ordered(_, indent) | Listing::Unordered(indent) = &style.listing {
+                self.finish_paragraph();
+                let number = match style.listing {
+                    Listing::Ordered(number, _) => Some(number),
+                    _ => None,
+                };
+                self.list.push(ListItem {
+                    indent: indent.to_int(),
+                    number,
+                    inlines: inlines(text, &StyleText::new()),
+                });
+                return;
+            }
+            self.finish_list();
+            if style.heading.to_int() > 0 {
+                self.finish_paragraph();
+                self.blocks.push(json!({
+                    "t": "Header",
+                    "c": [style.heading.to_int(), attr(), inlines(text, &StyleText::new())],
+                }));
+            } else if style.quote {
+                self.finish_paragraph();
+                self.blocks.push(json!({
+                    "t": "BlockQuote",
+                    "c": [{ "t": "Para", "c": inlines(text, &StyleText::new()) }],
+                }));
+            } else {
+                self.paragraph.extend(inlines(text, style));
+            }
+            return;
+        }
+        if let Mark::Math(math, MathMode::Inline) = mark {
+            let math = json!({ "t": "Math", "c": [{ "t": "InlineMath" }, math] });
+            // within the open list item, or a paragraph
+            match self.list.last_mut() {
+                Some(item) => item.inlines.push(math),
+                None => self.paragraph.push(math),
+            }
+            return;
+        }
+
+        self.finish_paragraph();
+        self.finish_list();
+        match mark {
+            Mark::CodeBlock(code, language) | Mark::CodeWalkthrough(code, language, _) => {
+                let classes: Vec<&str> = language.iter().map(String::as_str).collect();
+                self.blocks.push(json!({
+                    "t": "CodeBlock",
+                    "c": [["", classes, []], code],
+                }));
+            }
+            Mark::Embed(url, _) => self.blocks.push(json!({
+                "t": "Para",
+                "c": [link(vec![json!({ "t": "Str", "c": url })], url, None)],
+            })),
+            Mark::HoldSlide(start, message) => {
+                let start = format_time_of_day(*start);
+                let mut content =
+                    vec![json!({ "t": "Para", "c": inlines(message, &StyleText::new()) })];
+                content.push(json!({
+                    "t": "Para",
+                    "c": inlines(&format!("Starting at {}", start), &StyleText::new()),
+                }));
+                self.blocks.push(json!({
+                    "t": "Div",
+                    "c": [["", ["holdslide"], [["start", start]]], content],
+                }));
+            }
+            Mark::Gallery(items, _) => {
+                let images: Vec<Value> =
+                    items.iter().map(|(src, title)| image(src, title)).collect();
+                self.blocks.push(json!({ "t": "Para", "c": images }));
+            }
+            Mark::Image(src, title, style) => {
+                let mut image = image(src, title);
+                if !style.hyperlink.is_empty() {
+                    image = link(vec![image], &style.hyperlink, None);
+                }
+                self.blocks.push(json!({ "t": "Para", "c": [image] }));
+            }
+            Mark::Math(math, _) => self.blocks.push(json!({
+                "t": "Para",
+                "c": [{ "t": "Math", "c": [{ "t": "DisplayMath" }, math] }],
+            })),
+            Mark::Page(marks, _) => {
+                if self.has_page {
+                    self.blocks.push(json!({ "t": "HorizontalRule" }));
+                }
+                self.has_page = true;
+                self.marks(marks);
+            }
+            Mark::Transition(_, marks, _) => self.marks(marks),
+            // the page break of LaTeX, which Pandoc writes PDFs with
+            Mark::Directive(Directive::PageBreak) => self.blocks.push(json!({
+                "t": "RawBlock",
+                "c": ["latex", "\\newpage"],
+            })),
+            Mark::Directive(_)
+            | Mark::NewLine
+            | Mark::Separator(..)
+            | Mark::Space(_)
+            | Mark::Text(..)
+            | Mark::TransitionEnd => {}
+        }
+    }
+
+    fn finish_paragraph(&mut self) {
+        if !self.paragraph.is_empty() {
+            let inlines = std::mem::take(&mut self.paragraph);
+            self.blocks.push(json!({ "t": "Para", "c": inlines }));
+        }
+    }
+
+    fn finish_list(&mut self) {
+        let items = std::mem::take(&mut self.list);
+        let mut idx = 0;
+        while idx < items.len() {
+            let indent = items[idx].indent;
+            self.blocks.push(list(&items, &mut idx, indent));
+        }
+    }
+}
+
+/// The list starting at the item `idx`, including the lists nested in its items.
+fn list(items: &[ListItem], idx: &mut usize, indent: u8) -> Value {
+    let number = items[*idx].number;
+    let mut entries: Vec<Vec<Value>> = vec![];
+    while let Some(item) = items.get(*idx) {
+        if item.indent > indent {
+            let nested = list(items, idx, item.indent);
+            match entries.last_mut() {
+                Some(blocks) => blocks.push(nested),
+                None => entries.push(vec![nested]),
+            }
+            continue;
+        }
+        if item.indent < indent || item.number.is_some() != number.is_some() {
+            break;
+        }
+        entries.push(vec![json!({ "t": "Plain", "c": item.inlines })]);
+        *idx += 1;
+    }
+    match number {
+        Some(start) => json!({
+            "t": "OrderedList",
+            "c": [[start.max(1), { "t": "Decimal" }, { "t": "Period" }], entries],
+        }),
+        None => json!({ "t": "BulletList", "c": entries }),
+    }
+}
+
+/// The inlines of text in `style`, with words and spaces as separate inlines.
+fn inlines(text: &str, style: &StyleText) -> Vec<Value> {
+    if style.code {
+        return vec![json!({ "t": "Code", "c": [attr(), text] })];
+    }
+    let mut inlines = vec![];
+    for (idx, word) in text.split(' ').enumerate() {
+        if idx > 0 {
+            inlines.push(json!({ "t": "Space" }));
+        }
+        if !word.is_empty() {
+            inlines.push(json!({ "t": "Str", "c": word }));
+        }
+    }
+    for (is_set, t) in [
+        (style.small, "SmallCaps"),
+        (style.underline, "Underline"),
+        (style.strikethrough, "Strikeout"),
+        (style.italics, "Emph"),
+        (style.bold, "Strong"),
+    ] {
+        if is_set {
+            inlines = vec![json!({ "t": t, "c": inlines })];
+        }
+    }
+    if !style.hyperlink.is_empty() {
+        inlines = vec![link(inlines, &style.hyperlink, style.link_title.as_deref())];
+    }
+    inlines
+}
+
+fn attr() -> Value {
+    json!(["", [], []])
+}
+
+fn image(src: &str, title: &str) -> Value {
+    json!({
+        "t": "Image",
+        "c": [attr(), inlines(title, &StyleText::new()), [src, ""]],
+    })
+}
+
+fn link(inlines: Vec<Value>, url: &str, title: Option<&str>) -> Value {
+    json!({ "t": "Link", "c": [attr(), inlines, [url, title.unwrap_or_default()]] })
+}
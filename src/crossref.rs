@@ -0,0 +1,397 @@
+//! Cross-references to figures, tables and code listings, numbered in the order of the deck.
+//!
+//! A label is written on its own line right after the element it labels, like `{#fig:arch}`,
+//! see [`Directive::Label`](crate::mark::Directive::Label). The prefix of the label tells the
+//! kind of the element, see [`LabelKind`], and the elements of each kind are numbered from 1.
+//!
+//! Text refers to a labeled element with `@ref(fig:arch)`. References are replaced by the
+//! caption of their label, like `Figure 1`, when parsing with
+//! [`ParserOptions::with_cross_references`](crate::ParserOptions::with_cross_references), and
+//! link to the label. References to labels which are not in the document stay as written, and
+//! are reported as [`Warning::UndefinedReference`](crate::lint::Warning::UndefinedReference).
+//!
+//! ```
+//! use opmark::{lint::Warning, mark::Mark, Parser, ParserOptions};
+//!
+//! let source = "As @ref(fig:arch) shows, @ref(code:main) starts it.\n\
+//!     ![Architecture](arch.png)\n\
+//!     {#fig:arch}\n\
+//!     ```rust\nfn main() {}\n```\n\
+//!     {#code:main}\n\
+//!     See @ref(tbl:costs).";
+//! let options = ParserOptions::new().with_cross_references();
+//! let mut parser = Parser::with_options(source.to_owned(), options);
+//! let links: Vec<(String, String)> = parser
+//!     .by_ref()
+//!     .filter_map(|mark| match mark {
+//!         Mark::Text(text, style) if !style.hyperlink.is_empty() => Some((text, style.hyperlink)),
+//!         _ => None,
+//!     })
+//!     .collect();
+//! assert_eq!(
+//!     links,
+//!     [
+//!         ("Figure 1".to_owned(), "#fig:arch".to_owned()),
+//!         ("Listing 1".to_owned(), "#code:main".to_owned()),
+//!     ]
+//! );
+//! assert_eq!(
+//!     parser.warnings(),
+//!     [Warning::UndefinedReference { label: "tbl:costs".to_owned() }]
+//! );
+//! ```
+//!
+//! # Numbering
+//!
+//! The words and the number styles of captions follow the [`Numbering`] of the parser, see
+//! [`ParserOptions::with_numbering`](crate::ParserOptions::with_numbering), which the front
+//! matter of the document may override:
+//! - `lang`: the language of the words, like `de` for `Abbildung 1`, see
+//!   [`Numbering::for_language`].
+//! - `figure-label`, `table-label` and `listing-label`: the word of each kind.
+//! - `figure-numbering`, `table-numbering` and `listing-numbering`: the number style of each
+//!   kind, see [`NumberStyle::from_name`].
+//!
+//! ```
+//! use opmark::{deck::Deck, mark::Mark, Parser, ParserOptions};
+//!
+//! let source = "+++\nlang = ja\ntable-numbering = upper-roman\n+++\n\
+//!     @ref(fig:arch), @ref(tbl:costs)\n{#fig:arch}\n{#tbl:costs}";
+//! let options = ParserOptions::new().with_cross_references();
+//! let texts: Vec<String> = Parser::with_options(source.to_owned(), options)
+//!     .filter_map(|mark| match mark {
+//!         Mark::Text(text, _) => Some(text),
+//!         _ => None,
+//!     })
+//!     .collect();
+//! assert_eq!(texts, ["図1", ", ", "表I"]);
+//! assert_eq!(Deck::new(source.to_owned()).labels()[1].caption, "表I");
+//! ```
+
+use crate::{front_matter::FrontMatter, parser::LINE_ENDING};
+use std::collections::HashMap;
+
+/// The kind of a labeled element, told by the prefix of its label.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LabelKind {
+    /// `fig:`, like an image or a gallery.
+    Figure,
+    /// `tbl:`.
+    Table,
+    /// `code:`, a code block.
+    Listing,
+}
+
+impl LabelKind {
+    /// The kind of the element labeled `label`, `None` if the label has no known prefix.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let (prefix, name) = label.split_once(':')?;
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || matches!(c, ')' | '}')) {
+            return None;
+        }
+        match prefix {
+            "fig" => Some(LabelKind::Figure),
+            "tbl" => Some(LabelKind::Table),
+            "code" => Some(LabelKind::Listing),
+            _ => None,
+        }
+    }
+
+    /// The name of the kind, written before the number in English captions, see [`Numbering`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            LabelKind::Figure => "Figure",
+            LabelKind::Table => "Table",
+            LabelKind::Listing => "Listing",
+        }
+    }
+}
+
+/// A labeled element of a deck, see [`Deck::labels`](crate::deck::Deck::labels).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    /// The label, like `fig:arch`.
+    pub name: String,
+    pub kind: LabelKind,
+    /// The number of the element among the elements of its kind, starting from 1.
+    pub number: usize,
+    /// The index of the page of the element.
+    pub page: usize,
+    /// The caption of the element, like `Figure 1`, in the numbering of the deck.
+    pub caption: String,
+}
+
+/// How the numbers of captions are written.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum NumberStyle {
+    /// `1`, `2`, `3`.
+    #[default]
+    Decimal,
+    /// `i`, `ii`, `iii`.
+    LowerRoman,
+    /// `I`, `II`, `III`.
+    UpperRoman,
+    /// `a`, `b`, …, `z`, `aa`.
+    LowerAlpha,
+    /// `A`, `B`, …, `Z`, `AA`.
+    UpperAlpha,
+}
+
+impl NumberStyle {
+    /// The style named `name`, like the list styles of CSS: `decimal`, `lower-roman`,
+    /// `upper-roman`, `lower-alpha` or `upper-alpha`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "decimal" => Some(NumberStyle::Decimal),
+            "lower-roman" => Some(NumberStyle::LowerRoman),
+            "upper-roman" => Some(NumberStyle::UpperRoman),
+            "lower-alpha" => Some(NumberStyle::LowerAlpha),
+            "upper-alpha" => Some(NumberStyle::UpperAlpha),
+            _ => None,
+        }
+    }
+
+    /// `number`, from 1, in the style. Roman numbers past 3999 are decimal.
+    ///
+    /// ```
+    /// use opmark::crossref::NumberStyle;
+    ///
+    /// assert_eq!(NumberStyle::UpperRoman.format(14), "XIV");
+    /// assert_eq!(NumberStyle::LowerAlpha.format(28), "ab");
+    /// ```
+    pub fn format(&self, number: usize) -> String {
+        match self {
+            NumberStyle::Decimal => number.to_string(),
+            NumberStyle::LowerRoman => roman(number).to_lowercase(),
+            NumberStyle::UpperRoman => roman(number),
+            NumberStyle::LowerAlpha => alpha(number).to_lowercase(),
+            NumberStyle::UpperAlpha => alpha(number),
+        }
+    }
+}
+
+fn roman(number: usize) -> String {
+    if !(1..4000).contains(&number) {
+        return number.to_string();
+    }
+    let mut rest = number;
+    let mut roman = String::new();
+    for (value, numeral) in [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ] {
+        while rest >= value {
+            roman.push_str(numeral);
+            rest -= value;
+        }
+    }
+    roman
+}
+
+/// `A` to `Z`, then `AA`, like the columns of spreadsheets.
+fn alpha(number: usize) -> String {
+    let mut letters = vec![];
+    let mut rest = number;
+    while rest > 0 {
+        rest -= 1;
+        letters.push((b'A' + (rest % 26) as u8) as char);
+        rest /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// How the captions of a kind are written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaptionStyle {
+    /// The word written before the number, like `Figure`. It is followed by a space, unless it
+    /// ends with a Chinese or Japanese character, like `図`.
+    pub word: String,
+    pub number: NumberStyle,
+}
+
+/// The words and the number styles of the captions of each kind, see
+/// [Numbering](crate::crossref#numbering). English words and decimal numbers by default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Numbering {
+    pub figure: CaptionStyle,
+    pub table: CaptionStyle,
+    pub listing: CaptionStyle,
+}
+
+impl Default for Numbering {
+    fn default() -> Self {
+        Self::for_language("en")
+    }
+}
+
+impl Numbering {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The words of the language `tag`, a BCP 47 tag like `de` or `zh-TW`, with decimal numbers.
+    /// Languages without known words are English.
+    pub fn for_language(tag: &str) -> Self {
+        let tag = tag.to_ascii_lowercase().replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+        let [figure, table, listing] = match language {
+            "de" => ["Abbildung", "Tabelle", "Listing"],
+            "es" => ["Figura", "Tabla", "Listado"],
+            "fr" => ["Figure", "Tableau", "Listing"],
+            "it" => ["Figura", "Tabella", "Listato"],
+            "ja" => ["図", "表", "リスト"],
+            "ko" => ["그림", "표", "코드"],
+            "nl" => ["Figuur", "Tabel", "Listing"],
+            "pt" => ["Figura", "Tabela", "Listagem"],
+            "ru" => ["Рисунок", "Таблица", "Листинг"],
+            "zh" if ["hant", "tw", "hk", "mo"]
+                .iter()
+                .any(|subtag| tag.split('-').skip(1).any(|s| s == *subtag)) =>
+            {
+                ["圖", "表", "程式碼"]
+            }
+            "zh" => ["图", "表", "代码"],
+            _ => ["Figure", "Table", "Listing"],
+        };
+        let style = |word: &str| CaptionStyle {
+            word: word.to_owned(),
+            number: NumberStyle::Decimal,
+        };
+        Self {
+            figure: style(figure),
+            table: style(table),
+            listing: style(listing),
+        }
+    }
+
+    /// Override the numbering with the keys of `front_matter`, see [Numbering](crate::crossref#numbering).
+    /// Number styles which are not known are ignored.
+    pub fn with_front_matter(mut self, front_matter: &FrontMatter) -> Self {
+        if let Some(lang) = front_matter.get("lang") {
+            let words = Self::for_language(lang);
+            for kind in [LabelKind::Figure, LabelKind::Table, LabelKind::Listing] {
+                self.style_mut(kind).word = words.style(kind).word.clone();
+            }
+        }
+        for (kind, prefix) in [
+            (LabelKind::Figure, "figure"),
+            (LabelKind::Table, "table"),
+            (LabelKind::Listing, "listing"),
+        ] {
+            if let Some(word) = front_matter.get(&format!("{}-label", prefix)) {
+                self.style_mut(kind).word = word.to_owned();
+            }
+            let number = front_matter
+                .get(&format!("{}-numbering", prefix))
+                .and_then(NumberStyle::from_name);
+            if let Some(number) = number {
+                self.style_mut(kind).number = number;
+            }
+        }
+        self
+    }
+
+    #[inline]
+    pub fn with_number_style(mut self, kind: LabelKind, number: NumberStyle) -> Self {
+        self.style_mut(kind).number = number;
+        self
+    }
+
+    #[inline]
+    pub fn with_word(mut self, kind: LabelKind, word: String) -> Self {
+        self.style_mut(kind).word = word;
+        self
+    }
+
+    /// The style of the captions of `kind`.
+    pub fn style(&self, kind: LabelKind) -> &CaptionStyle {
+        match kind {
+            LabelKind::Figure => &self.figure,
+            LabelKind::Table => &self.table,
+            LabelKind::Listing => &self.listing,
+        }
+    }
+
+    fn style_mut(&mut self, kind: LabelKind) -> &mut CaptionStyle {
+        match kind {
+            LabelKind::Figure => &mut self.figure,
+            LabelKind::Table => &mut self.table,
+            LabelKind::Listing => &mut self.listing,
+        }
+    }
+
+    /// The caption of the element of `kind` numbered `number`, like `Figure 1`.
+    ///
+    /// ```
+    /// use opmark::crossref::{LabelKind, NumberStyle, Numbering};
+    ///
+    /// let numbering = Numbering::for_language("de")
+    ///     .with_number_style(LabelKind::Table, NumberStyle::UpperAlpha);
+    /// assert_eq!(numbering.caption(LabelKind::Figure, 2), "Abbildung 2");
+    /// assert_eq!(numbering.caption(LabelKind::Table, 2), "Tabelle B");
+    /// ```
+    pub fn caption(&self, kind: LabelKind, number: usize) -> String {
+        let style = self.style(kind);
+        let number = style.number.format(number);
+        match style.word.chars().last() {
+            Some(c) if is_han_or_kana(c) => format!("{}{}", style.word, number),
+            Some(_) => format!("{} {}", style.word, number),
+            None => number,
+        }
+    }
+}
+
+/// Whether `c` is a Chinese character or a Japanese kana, which captions do not space from their
+/// numbers.
+fn is_han_or_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}')
+}
+
+/// The label of the line `line` with its kind, like `fig:arch` for `{#fig:arch}`.
+pub(crate) fn parse_label(line: &str) -> Option<(&str, LabelKind)> {
+    let label = line.trim_end().strip_prefix("{#")?.strip_suffix('}')?;
+    LabelKind::from_label(label).map(|kind| (label, kind))
+}
+
+/// The label of the reference `s` starts with, like `fig:arch` for `@ref(fig:arch)`, with the
+/// length of the reference.
+pub(crate) fn reference_at(s: &str) -> Option<(&str, usize)> {
+    let rest = s.strip_prefix("@ref(")?;
+    let label = &rest[..rest.find(')')?];
+    LabelKind::from_label(label).map(|_| (label, label.len() + 6))
+}
+
+/// The kinds and numbers of the labels of the document `s`. The first of several elements with
+/// the same label keeps it.
+pub(crate) fn label_numbers(s: &str) -> HashMap<String, (LabelKind, usize)> {
+    let mut numbers = HashMap::new();
+    let mut counts: HashMap<LabelKind, usize> = HashMap::new();
+    let mut is_code = false;
+    for line in s.split(LINE_ENDING) {
+        if line.starts_with("```") {
+            is_code = !is_code;
+            continue;
+        }
+        let Some((label, kind)) = parse_label(line).filter(|_| !is_code) else {
+            continue;
+        };
+        if !numbers.contains_key(label) {
+            let count = counts.entry(kind).or_default();
+            *count += 1;
+            numbers.insert(label.to_owned(), (kind, *count));
+        }
+    }
+    numbers
+}
@@ -0,0 +1,115 @@
+//! Explain how a document is read, line by line, to answer why some syntax does not work.
+//!
+//! [`explain`] parses a document with a [`ParseTrace`](crate::grammar::ParseTrace), and tells
+//! for each line of the source which rules consumed it and which marks it produced. A line which
+//! only the `text` rule consumed is read as plain text, and a line without rules nor marks was
+//! skipped, like a malformed block, see [`Parser`](crate::Parser#malformed-blocks).
+//!
+//! ```
+//! use opmark::{debug::explain, grammar::RuleId, mark::Mark};
+//!
+//! let lines = explain("# Title\n##Not a heading\n*bold* text");
+//! assert_eq!(lines[0].rules, [RuleId::Heading]);
+//! // a heading needs a space after its `#`
+//! assert_eq!(lines[1].rules, [RuleId::Text]);
+//! assert_eq!(lines[2].rules, [RuleId::Bold, RuleId::Text]);
+//! assert!(matches!(&lines[2].marks[..], [Mark::Text(bold, _), Mark::Text(text, _)]
+//!     if bold == "bold" && text == " text"));
+//! assert_eq!(lines[2].to_string(), "3: bold, text: *bold* text");
+//! ```
+
+use crate::{
+    grammar::{Rule, RuleId, RuleKind},
+    mark::Mark,
+    parser::{normalize_line_endings, LINE_ENDING},
+    Parser, ParserOptions,
+};
+use std::{fmt, ops::Range};
+
+/// How a line of a document is read, see [`explain`].
+#[derive(Clone, Debug)]
+pub struct LineExplanation {
+    /// The number of the line, from 1.
+    pub number: usize,
+    /// The line, without its line ending.
+    pub line: String,
+    /// The rules which consumed the line, in the order they first matched on it. Rules taking
+    /// several lines, like code blocks, are on each of their lines.
+    pub rules: Vec<RuleId>,
+    /// The marks produced from the line, in order. Marks of rules taking several lines are on
+    /// their first line, and the page and transition marks the parser adds are on the line the
+    /// parser was at.
+    pub marks: Vec<Mark>,
+}
+
+impl fmt::Display for LineExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<&str> = self.rules.iter().map(|&id| Rule::get(id).name).collect();
+        match rules.is_empty() {
+            true => write!(f, "{}: no rule: {}", self.number, self.line),
+            false => write!(f, "{}: {}: {}", self.number, rules.join(", "), self.line),
+        }
+    }
+}
+
+/// Explain how each line of `source` is read.
+pub fn explain(source: &str) -> Vec<LineExplanation> {
+    let normalized = normalize_line_endings(source.to_owned());
+    let mut spans: Vec<Range<usize>> = vec![];
+    let mut lines = vec![];
+    for (idx, line) in normalized.split(LINE_ENDING).enumerate() {
+        let start = spans.last().map_or(0, |span| span.end + LINE_ENDING.len());
+        spans.push(start..start + line.len());
+        lines.push(LineExplanation {
+            number: idx + 1,
+            line: line.to_owned(),
+            rules: vec![],
+            marks: vec![],
+        });
+    }
+    // the line holding the byte at `offset`, line endings belonging to the line they end
+    let line_at = |offset: usize| {
+        spans
+            .iter()
+            .position(|span| offset <= span.end)
+            .unwrap_or(spans.len() - 1)
+    };
+
+    let mut parser = Parser::with_options(source.to_owned(), ParserOptions::new().with_trace());
+    let mut seen = 0;
+    let mut last_idx = None;
+    loop {
+        let start = parser.offset();
+        let mark = parser.next();
+        let end = parser.offset();
+        let hits = parser.trace().map(|trace| trace.hits()).unwrap_or_default();
+        for hit in &hits[seen..] {
+            let last = hit.span.end.max(hit.span.start + 1) - 1;
+            for line in &mut lines[line_at(hit.span.start)..=line_at(last)] {
+                if !line.rules.contains(&hit.rule) {
+                    line.rules.push(hit.rule);
+                }
+            }
+        }
+        // the rule producing the mark, after the ones producing none, like style toggles
+        let producer = hits[seen..].iter().rev().find(|hit| {
+            let rule = Rule::get(hit.rule);
+            matches!(rule.kind, RuleKind::Block | RuleKind::Inline)
+                && rule.id != RuleId::LinkDefinition
+        });
+        let idx = match producer {
+            Some(hit) => line_at(hit.span.start),
+            // an empty line
+            None if end > start => line_at(end - 1),
+            // a mark added by the parser
+            None => last_idx.unwrap_or_else(|| line_at(start)),
+        };
+        seen = hits.len();
+        let Some(mark) = mark else {
+            break;
+        };
+        lines[idx].marks.push(mark);
+        last_idx = Some(idx);
+    }
+    lines
+}
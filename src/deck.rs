@@ -0,0 +1,1536 @@
+//! A deck of pages, with navigation through pages and transitions.
+
+#[cfg(feature = "remote-async")]
+use crate::remote::Prefetched;
+#[cfg(feature = "remote")]
+use crate::remote::UrlResolver;
+#[cfg(any(feature = "remote", feature = "remote-async"))]
+use crate::remote::{self, join_url, CachePolicy, RemoteError};
+use crate::{
+    audience, audio,
+    crossref::{Label, LabelKind, Numbering},
+    front_matter::{FrontMatter, KeyBinding},
+    id::{self, MarkId},
+    include::IncludeResolver,
+    integrity::{self, Digest},
+    mark::{
+        CodeStep, Directive, Heading, Mark, StyleImage, StylePage, StyleText, StyleTransition,
+        TransitionEffect, TransitionTarget,
+    },
+    media::MediaManifest,
+    parser::{Parser, ParserOptions},
+    render::page_marks,
+    text::{self, plain_text},
+};
+use std::{fmt, ops::Range, path::Path, sync::Arc, time::Duration};
+
+/// A page of the deck.
+#[derive(Clone, Debug)]
+pub struct Page {
+    /// The `Mark::Transition` elements of the page.
+    pub transitions: Vec<Mark>,
+    /// The configuration of the page.
+    pub style: StylePage,
+    /// The highest order of the transitions in the page.
+    pub max_step: usize,
+    /// The order of the transition which just appeared.
+    ///
+    /// Transitions with an order less than or equal to `step` are visible.
+    pub step: usize,
+}
+
+impl Page {
+    /// The stable id of the page, see [`MarkId`].
+    #[inline]
+    pub fn id(&self) -> MarkId {
+        MarkId::of_page(self)
+    }
+
+    /// A label of the page, for slide sorters and exports: the text of its first heading, or else
+    /// the first line of its text, shortened to [`TITLE_MAX_GRAPHEMES`]. `None` if the page has
+    /// no text.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let deck = Deck::new("# Intro\nHello there\n---\nA page without heading\n\nMore".to_owned());
+    /// assert_eq!(deck.pages()[0].title().as_deref(), Some("Intro"));
+    /// assert_eq!(deck.pages()[1].title().as_deref(), Some("A page without heading"));
+    /// ```
+    pub fn title(&self) -> Option<String> {
+        let marks = page_marks(self);
+        if let Some(heading) = heading(&marks) {
+            return Some(heading.to_owned());
+        }
+        let first_line: Vec<Mark> = marks
+            .into_iter()
+            .skip_while(|mark| matches!(mark, Mark::NewLine))
+            .take_while(|mark| matches!(mark, Mark::Text(..)))
+            .collect();
+        let title = plain_text(&first_line);
+        (!title.is_empty()).then(|| text::truncate(&title, TITLE_MAX_GRAPHEMES))
+    }
+
+    /// A summary of the text of the page, without its heading, shortened to at most `max_chars`
+    /// grapheme clusters at a word boundary if possible. Empty if the page has no other text.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let deck = Deck::new("# Intro\nOpMark is a markup language for presentations".to_owned());
+    /// assert_eq!(deck.pages()[0].summary(20), "OpMark is a markup…");
+    /// assert_eq!(deck.pages()[0].summary(100), "OpMark is a markup language for presentations");
+    /// ```
+    pub fn summary(&self, max_chars: usize) -> String {
+        let mut marks = page_marks(self);
+        let is_heading =
+            |mark: &Mark| matches!(mark, Mark::Text(_, style) if style.heading.to_int() > 0);
+        if let Some(idx) = marks.iter().position(is_heading) {
+            marks.remove(idx);
+        }
+        let summary = plain_text(&marks);
+        if text::grapheme_len(&summary) <= max_chars {
+            return summary;
+        }
+        let truncated = text::truncate(&summary, max_chars);
+        let kept = truncated.strip_suffix(text::ELLIPSIS).unwrap_or(&truncated);
+        if summary[kept.len()..].starts_with(' ') {
+            return format!("{}{}", kept.trim_end(), text::ELLIPSIS);
+        }
+        // drop the word which was cut, unless it is the only one
+        match kept.trim_end().rfind(' ') {
+            Some(idx) => format!("{}{}", kept[..idx].trim_end(), text::ELLIPSIS),
+            None => truncated,
+        }
+    }
+
+    /// The source of the audio narrating the page, set by the `narration=src` page option or else
+    /// the first `@narration(src)` directive of the page.
+    pub fn narration(&self) -> Option<&str> {
+        self.style.narration.as_deref().or_else(|| {
+            self.directives()
+                .find_map(|(_, directive)| match directive {
+                    Directive::Narration(src) => Some(src.as_str()),
+                    _ => None,
+                })
+        })
+    }
+
+    /// The speaker notes of the page, a line per `@note(text)` directive.
+    pub fn notes(&self) -> Vec<&str> {
+        self.directives()
+            .filter_map(|(_, directive)| match directive {
+                Directive::Note(note) => Some(note.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The presenter cues in the speaker notes of the page, in the order they are written.
+    ///
+    /// ```
+    /// use opmark::deck::{Cue, Deck};
+    /// use std::time::Duration;
+    ///
+    /// let deck = Deck::new("# Demo\n@note(Show the CLI [cue: switch to terminal])\n@note([time: 10:00])".to_owned());
+    /// let page = &deck.pages()[0];
+    /// assert_eq!(page.notes().len(), 2);
+    /// assert_eq!(
+    ///     page.cues(),
+    ///     vec![
+    ///         Cue::Action("switch to terminal".to_owned()),
+    ///         Cue::Time(Duration::from_secs(600)),
+    ///     ]
+    /// );
+    /// ```
+    pub fn cues(&self) -> Vec<Cue> {
+        self.notes().into_iter().flat_map(Cue::parse_all).collect()
+    }
+
+    /// The directives of the page, with the order of the transition they are in.
+    pub(crate) fn directives(&self) -> impl Iterator<Item = (usize, &Directive)> {
+        self.transitions
+            .iter()
+            .filter_map(|transition| match transition {
+                Mark::Transition(order, marks, _) => Some((*order, marks)),
+                _ => None,
+            })
+            .flat_map(|(order, marks)| {
+                marks.iter().filter_map(move |mark| match mark {
+                    Mark::Directive(directive) => Some((order, directive)),
+                    _ => None,
+                })
+            })
+    }
+
+    /// The step to go back to when stepping forward past the last step of the page.
+    ///
+    /// This is the step just before the first transition marked with `loop` appears. Transition
+    /// `0` is always visible, so looping it has no effect.
+    pub fn loop_step(&self) -> Option<usize> {
+        self.transitions
+            .iter()
+            .filter_map(|transition| match transition {
+                Mark::Transition(order, _, style) if style.looping && *order > 0 => Some(order - 1),
+                _ => None,
+            })
+            .min()
+    }
+
+    /// The step of a `Mark::CodeWalkthrough` with `steps`, in the transition with the given
+    /// `order`, shown at the current step. `None` before the transition appears.
+    ///
+    /// ```
+    /// use opmark::{deck::Deck, mark::{CodeStep, Mark}};
+    ///
+    /// let mut deck = Deck::new("```rust steps=[1,2-3]\nfn main() {\n}\n```".to_owned());
+    /// deck.next_step();
+    /// let page = deck.current_page().unwrap();
+    /// let Mark::Transition(order, marks, _) = &page.transitions[0] else { panic!() };
+    /// let Mark::CodeWalkthrough(_, _, steps) = &marks[0] else { panic!() };
+    /// assert_eq!(page.max_step, 1);
+    /// assert_eq!(page.code_step(*order, steps), Some(&CodeStep::Lines(2, 3)));
+    /// ```
+    pub fn code_step<'a>(&self, order: usize, steps: &'a [CodeStep]) -> Option<&'a CodeStep> {
+        let step = self.step.checked_sub(order)?;
+        steps.get(step).or_else(|| steps.last())
+    }
+
+    /// How each transition of the page is displayed at the current step, in the same order as
+    /// `transitions`.
+    pub fn visibilities(&self) -> Vec<Visibility> {
+        let styles: Vec<(usize, &StyleTransition)> = self
+            .transitions
+            .iter()
+            .filter_map(|transition| match transition {
+                Mark::Transition(order, _, style) => Some((*order, style)),
+                _ => None,
+            })
+            .collect();
+
+        // effects of transitions on themselves
+        let mut visibilities: Vec<Visibility> = styles
+            .iter()
+            .map(|(order, style)| {
+                let is_reached = *order <= self.step;
+                match (&style.target, &style.effect) {
+                    (TransitionTarget::This, TransitionEffect::Disappear) if is_reached => {
+                        Visibility::Hidden
+                    }
+                    (TransitionTarget::This, TransitionEffect::Dim) if is_reached => {
+                        Visibility::Dimmed
+                    }
+                    (
+                        TransitionTarget::This,
+                        TransitionEffect::Disappear | TransitionEffect::Dim,
+                    ) => Visibility::Visible,
+                    _ if is_reached => Visibility::Visible,
+                    _ => Visibility::Hidden,
+                }
+            })
+            .collect();
+
+        // effects on other transitions, applied in the order they are reached
+        let mut reached: Vec<usize> = (0..styles.len())
+            .filter(|&idx| {
+                styles[idx].0 <= self.step && styles[idx].1.target != TransitionTarget::This
+            })
+            .collect();
+        reached.sort_by_key(|&idx| styles[idx].0);
+        for idx in reached {
+            let style = styles[idx].1;
+            let visibility = match style.effect {
+                TransitionEffect::Appear => Visibility::Visible,
+                TransitionEffect::Disappear | TransitionEffect::Replace => Visibility::Hidden,
+                TransitionEffect::Dim => Visibility::Dimmed,
+            };
+            match style.target {
+                TransitionTarget::This => {}
+                TransitionTarget::Prev => {
+                    if idx > 0 {
+                        visibilities[idx - 1] = visibility;
+                    }
+                }
+                TransitionTarget::Order(order) => {
+                    for (target, (target_order, _)) in styles.iter().enumerate() {
+                        if *target_order == order {
+                            visibilities[target] = visibility;
+                        }
+                    }
+                }
+            }
+        }
+
+        visibilities
+    }
+}
+
+/// The maximum length of the titles of pages without a heading, in grapheme clusters, see
+/// [`Page::title`].
+pub const TITLE_MAX_GRAPHEMES: usize = 60;
+
+/// The text of the first heading in `marks`.
+pub(crate) fn heading(marks: &[Mark]) -> Option<&str> {
+    marks.iter().find_map(|mark| match mark {
+        Mark::Text(text, style) if style.heading.to_int() > 0 => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+/// The pages of the marks of `parser`.
+fn pages(parser: Parser) -> Vec<Page> {
+    Parser::into_pages(parser)
+        .into_iter()
+        .filter_map(|(mark, max_step, step)| match mark {
+            Mark::Page(transitions, style) => Some(Page {
+                transitions,
+                style,
+                max_step,
+                step,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `page` split into pages of at most `max_blocks` blocks, see [`Deck::paginate`].
+fn split_page(page: Page, max_blocks: usize) -> Vec<Page> {
+    let mut parts: Vec<Vec<Mark>> = vec![vec![]];
+    let mut blocks = 0;
+    for transition in &page.transitions {
+        let Mark::Transition(order, marks, style) = transition else {
+            parts.last_mut().unwrap().push(transition.clone());
+            continue;
+        };
+        let mut chunk = vec![];
+        // whether the last block of the chunk goes on
+        let mut is_block = false;
+        for mark in marks {
+            if matches!(mark, Mark::NewLine) {
+                is_block = false;
+            } else if !is_block {
+                if blocks == max_blocks {
+                    while matches!(chunk.last(), Some(Mark::NewLine)) {
+                        chunk.pop();
+                    }
+                    let chunk = Mark::Transition(*order, std::mem::take(&mut chunk), style.clone());
+                    parts.last_mut().unwrap().push(chunk);
+                    parts.push(vec![]);
+                    blocks = 0;
+                }
+                blocks += 1;
+                is_block = true;
+            }
+            chunk.push(mark.clone());
+        }
+        parts
+            .last_mut()
+            .unwrap()
+            .push(Mark::Transition(*order, chunk, style.clone()));
+    }
+    if parts.len() == 1 {
+        return vec![page];
+    }
+
+    let mut pages = vec![];
+    for (i, mut transitions) in parts.into_iter().enumerate() {
+        let mut style = page.style.clone();
+        if i > 0 {
+            style.narration = None;
+            style.duration = None;
+            style.at = None;
+            // the steps of the added pages start from 0
+            let base = transitions.iter().find_map(|transition| match transition {
+                Mark::Transition(order, ..) => Some(*order),
+                _ => None,
+            });
+            for transition in &mut transitions {
+                if let Mark::Transition(order, _, style) = transition {
+                    *order = order.saturating_sub(base.unwrap_or(0));
+                    if let TransitionTarget::Order(target) = &mut style.target {
+                        *target = target.saturating_sub(base.unwrap_or(0));
+                    }
+                }
+            }
+        }
+        let max_step = max_step(&transitions);
+        pages.push(Page {
+            transitions,
+            style,
+            max_step,
+            step: if i == 0 { page.step.min(max_step) } else { 0 },
+        });
+    }
+    pages
+}
+
+/// The highest step of `transitions`, counting the steps of code walkthroughs.
+fn max_step(transitions: &[Mark]) -> usize {
+    transitions
+        .iter()
+        .filter_map(|transition| match transition {
+            Mark::Transition(order, marks, _) => {
+                Some(marks.iter().fold(*order, |max, mark| match mark {
+                    Mark::CodeWalkthrough(_, _, steps) => {
+                        max.max(order + steps.len().saturating_sub(1))
+                    }
+                    _ => max,
+                }))
+            }
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// A page listing `sections`, with the section at `current` highlighted.
+fn agenda_page(
+    options: &AgendaOptions,
+    sections: &[(String, usize)],
+    current: Option<usize>,
+) -> Page {
+    let mut marks = vec![Mark::Text(
+        options.title.clone(),
+        StyleText::new().with_heading(Heading::H1),
+    )];
+    for (i, (title, idx)) in sections.iter().enumerate() {
+        let mut style = StyleText::new().with_hyperlink(format!("#page-{}", idx + 1));
+        if current == Some(i) {
+            style = style
+                .with_bold()
+                .with_class(CURRENT_SECTION_CLASS.to_owned());
+        }
+        marks.push(Mark::Text(title.clone(), style));
+        marks.push(Mark::NewLine);
+    }
+    Page {
+        transitions: vec![Mark::Transition(0, marks, StyleTransition::new())],
+        style: StylePage::new().with_class(AGENDA_CLASS.to_owned()),
+        max_step: 0,
+        step: 0,
+    }
+}
+
+/// A presenter cue, written in the speaker notes of a page.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cue {
+    /// `[cue: switch to terminal]`: something for the presenter to do.
+    Action(String),
+    /// `[time: 10:00]`: the time since the start of the talk the page should be reached at, as
+    /// `minutes:seconds` or `hours:minutes:seconds`.
+    Time(Duration),
+}
+
+impl Cue {
+    /// All cues in `note`. Cues with an invalid time are ignored.
+    fn parse_all(note: &str) -> Vec<Cue> {
+        let mut cues = vec![];
+        let mut rest = note;
+        while let Some(start) = rest.find('[') {
+            rest = &rest[start + 1..];
+            let Some(end) = rest.find(']') else { break };
+            let cue = match rest[..end].split_once(':') {
+                Some((kind, value)) => match (kind.trim(), value.trim()) {
+                    ("cue", action) if !action.is_empty() => Some(Cue::Action(action.to_owned())),
+                    ("time", time) => parse_time(time).map(Cue::Time),
+                    _ => None,
+                },
+                None => None,
+            };
+            if let Some(cue) = cue {
+                cues.push(cue);
+                rest = &rest[end + 1..];
+            }
+        }
+        cues
+    }
+}
+
+/// `minutes:seconds` or `hours:minutes:seconds`.
+fn parse_time(s: &str) -> Option<Duration> {
+    let parts = s
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let secs = match parts[..] {
+        [minutes, seconds] if seconds < 60 => minutes * 60 + seconds,
+        [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+            hours * 3600 + minutes * 60 + seconds
+        }
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
+
+/// How a transition is displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visibility {
+    Hidden,
+    Visible,
+    Dimmed,
+}
+
+/// A named point of a deck, set by a `@bookmark(name)` directive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    /// The index of the page of the bookmark.
+    pub page: usize,
+    /// The order of the transition the bookmark is in.
+    pub step: usize,
+}
+
+/// Options of the agenda of a deck, see [`Deck::generate_agenda`].
+#[derive(Clone, Debug)]
+pub struct AgendaOptions {
+    /// The heading of the agenda. `"Agenda"` by default.
+    pub title: String,
+    /// The deepest level of the headings starting a section. 1 by default.
+    pub level: u8,
+    /// The index of the page the agenda is inserted before. Pages before it, like a title page,
+    /// are not listed. 1 by default.
+    pub position: usize,
+    /// Whether the agenda is repeated before each section, with the section highlighted. `false`
+    /// by default.
+    pub repeat: bool,
+}
+
+impl Default for AgendaOptions {
+    fn default() -> Self {
+        Self {
+            title: "Agenda".to_owned(),
+            level: 1,
+            position: 1,
+            repeat: false,
+        }
+    }
+}
+
+impl AgendaOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_level(mut self, level: u8) -> Self {
+        self.level = level;
+        self
+    }
+
+    #[inline]
+    pub fn with_position(mut self, position: usize) -> Self {
+        self.position = position;
+        self
+    }
+
+    #[inline]
+    pub fn with_repeat(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
+}
+
+/// The class of the pages of the agenda, see [`Deck::generate_agenda`].
+pub const AGENDA_CLASS: &str = "agenda";
+
+/// The class of the section highlighted in a repeated agenda, see [`Deck::generate_agenda`].
+pub const CURRENT_SECTION_CLASS: &str = "current";
+
+/// Where page numbers are placed at the bottom of pages, see [`Deck::with_page_numbers`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PageNumberPosition {
+    Left,
+    Center,
+    #[default]
+    Right,
+}
+
+impl PageNumberPosition {
+    /// The class of page numbers at the position, so renderers and themes can place them.
+    pub fn class(&self) -> &'static str {
+        match self {
+            PageNumberPosition::Left => "footer-left",
+            PageNumberPosition::Center => "footer-center",
+            PageNumberPosition::Right => "footer-right",
+        }
+    }
+}
+
+/// The class of page numbers, see [`Deck::with_page_numbers`].
+pub const PAGE_NUMBER_CLASS: &str = "page-number";
+
+/// The layout of a title page built from front matter, see
+/// [`Deck::generate_title_slide_with_layout`]. Themes can define their own layout, showing other
+/// fields or in another order.
+#[derive(Clone, Debug)]
+pub struct TitleLayout {
+    /// The style of the title page. Of the class `title` by default.
+    pub page: StylePage,
+    /// The style of the image of the `logo` field, shown first. If `None`, the logo is not shown.
+    /// Of the class `logo` by default.
+    pub logo: Option<StyleImage>,
+    /// The front matter fields shown, in order, with their style. Fields the front matter does
+    /// not set are skipped. By default, `title` as a level 1 heading, `subtitle` as a level 2
+    /// heading, then `author` and `date` on a line each, all of the class named after the
+    /// field.
+    pub fields: Vec<(String, StyleText)>,
+}
+
+impl Default for TitleLayout {
+    fn default() -> Self {
+        let field = |key: &str, heading: Heading| {
+            let style = StyleText::new()
+                .with_heading(heading)
+                .with_class(key.to_owned());
+            (key.to_owned(), style)
+        };
+        Self {
+            page: StylePage::new().with_class("title".to_owned()),
+            logo: Some(StyleImage::new().with_class("logo".to_owned())),
+            fields: vec![
+                field("title", Heading::H1),
+                field("subtitle", Heading::H2),
+                field("author", Heading::None),
+                field("date", Heading::None),
+            ],
+        }
+    }
+}
+
+impl TitleLayout {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_field(mut self, key: String, style: StyleText) -> Self {
+        self.fields.push((key, style));
+        self
+    }
+
+    #[inline]
+    pub fn with_logo(mut self, logo: Option<StyleImage>) -> Self {
+        self.logo = logo;
+        self
+    }
+
+    #[inline]
+    pub fn with_page(mut self, page: StylePage) -> Self {
+        self.page = page;
+        self
+    }
+}
+
+/// A change of the current page or step of a deck, see [`DeckObserver`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DeckEvent {
+    /// The page at `page` became the current page, showing the transitions up to `step`.
+    PageEntered { page: usize, step: usize },
+    /// The transition `step` of the page at `page` appeared.
+    StepRevealed { page: usize, step: usize },
+    /// The transition after `step` of the page at `page` was hidden again, `step` being the
+    /// current step.
+    StepHidden { page: usize, step: usize },
+    /// The deck was asked to go past its last page.
+    DeckFinished,
+}
+
+/// Observes the navigation of a deck, like for analytics, scene switching in streaming software
+/// or keeping remote screens in sync, see [`Deck::with_observer`].
+pub trait DeckObserver: fmt::Debug + Send + Sync {
+    /// Called after `event` happened.
+    fn on_event(&self, event: &DeckEvent);
+}
+
+/// A deck of pages, keeping track of the current page and the current step of each page.
+///
+/// ```
+/// use opmark::deck::Deck;
+///
+/// let mut deck = Deck::new("one\n---t\ntwo\n---\nthree".to_owned());
+/// assert_eq!((deck.current(), deck.current_page().unwrap().step), (0, 0));
+/// deck.next_step();
+/// assert_eq!((deck.current(), deck.current_page().unwrap().step), (0, 1));
+/// deck.next_step();
+/// assert_eq!(deck.current(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Deck {
+    pages: Vec<Page>,
+    current: usize,
+    front_matter: FrontMatter,
+    numbering: Numbering,
+    observers: Vec<Arc<dyn DeckObserver>>,
+}
+
+impl Deck {
+    /// Create a new Deck, where `s` is the text content of the OpMark document.
+    pub fn new(s: String) -> Self {
+        Self::from_parser(Parser::new(s))
+    }
+
+    /// Create a new Deck from the marks of `parser`.
+    pub fn from_parser(parser: Parser) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("deck").entered();
+
+        let front_matter = parser.front_matter().clone();
+        let numbering = parser.numbering().clone();
+        Self {
+            pages: pages(parser),
+            current: 0,
+            front_matter,
+            numbering,
+            observers: vec![],
+        }
+    }
+
+    /// Load the deck at `url`, blocking the thread, see [`remote`](crate::remote).
+    #[cfg(feature = "remote")]
+    pub fn from_url(url: &str, policy: &CachePolicy) -> Result<Self, RemoteError> {
+        let source = remote::fetch(url, policy)?;
+        let source = String::from_utf8(source).map_err(|_| RemoteError::NotText)?;
+        let resolver = UrlResolver::new(url.to_owned(), policy.clone());
+        let parser = Parser::with_options(source, ParserOptions::new().with_resolver(resolver));
+        let mut deck = Self::from_parser(parser);
+        deck.join_sources(url);
+        Ok(deck)
+    }
+
+    /// Load the deck at `url` asynchronously, in a Tokio runtime, see [`remote`](crate::remote).
+    #[cfg(feature = "remote-async")]
+    pub async fn from_url_async(url: &str, policy: &CachePolicy) -> Result<Self, RemoteError> {
+        let client = reqwest::Client::new();
+        let source = remote::fetch_async(&client, url, policy).await?;
+        let source = String::from_utf8(source).map_err(|_| RemoteError::NotText)?;
+        // the parser reads included files synchronously, so they are downloaded first
+        let mut files = Prefetched::default();
+        for path in remote::included_paths(&source) {
+            if let Ok(body) = remote::fetch_async(&client, &join_url(url, &path), policy).await {
+                files.0.insert(path, body);
+            }
+        }
+        let parser = Parser::with_options(source, ParserOptions::new().with_resolver(files));
+        let mut deck = Self::from_parser(parser);
+        deck.join_sources(url);
+        Ok(deck)
+    }
+
+    /// Resolve the relative sources of images, galleries and narrations against `base`.
+    #[cfg(any(feature = "remote", feature = "remote-async"))]
+    fn join_sources(&mut self, base: &str) {
+        for page in &mut self.pages {
+            if let Some(narration) = &mut page.style.narration {
+                *narration = join_url(base, narration);
+            }
+            for transition in &mut page.transitions {
+                let Mark::Transition(_, marks, _) = transition else {
+                    continue;
+                };
+                for mark in marks {
+                    match mark {
+                        Mark::Image(src, ..) | Mark::Directive(Directive::Narration(src)) => {
+                            *src = join_url(base, src)
+                        }
+                        Mark::Gallery(items, _) => {
+                            for (src, _) in items {
+                                *src = join_url(base, src);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tell `observer` about the navigation of the deck from now on, see [`DeckEvent`].
+    ///
+    /// ```
+    /// use opmark::deck::{Deck, DeckEvent, DeckObserver};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Clone, Debug, Default)]
+    /// struct Log(Arc<Mutex<Vec<DeckEvent>>>);
+    ///
+    /// impl DeckObserver for Log {
+    ///     fn on_event(&self, event: &DeckEvent) {
+    ///         self.0.lock().unwrap().push(*event);
+    ///     }
+    /// }
+    ///
+    /// let log = Log::default();
+    /// let mut deck = Deck::new("one\n---t\ntwo\n---\nthree".to_owned()).with_observer(log.clone());
+    /// while deck.next_step() {}
+    /// assert_eq!(
+    ///     *log.0.lock().unwrap(),
+    ///     [
+    ///         DeckEvent::StepRevealed { page: 0, step: 1 },
+    ///         DeckEvent::PageEntered { page: 1, step: 0 },
+    ///         DeckEvent::DeckFinished,
+    ///     ]
+    /// );
+    /// ```
+    #[inline]
+    pub fn with_observer(mut self, observer: impl DeckObserver + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    fn emit(&self, event: DeckEvent) {
+        for observer in &self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    /// Parse `more` and append its pages, like pages generated by another program while
+    /// presenting. Returns the indexes of the appended pages.
+    ///
+    /// The pages of the deck keep their indexes, and the current page stays the same. `more` is
+    /// parsed on its own, starting on a new page whether or not it starts with a page mark, and
+    /// its front matter is ignored.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let mut deck = Deck::new("# Live results".to_owned());
+    /// deck.next_step();
+    /// assert_eq!(deck.extend_from_str("# Round 1\n---\n# Round 2"), 1..3);
+    /// assert_eq!(deck.extend_from_str("---\n# Round 3"), 3..4);
+    /// assert_eq!(deck.extend_from_str(""), 4..4);
+    /// assert_eq!(deck.pages()[3].title().as_deref(), Some("Round 3"));
+    /// assert_eq!(deck.current(), 0);
+    /// ```
+    pub fn extend_from_str(&mut self, more: &str) -> Range<usize> {
+        let options = ParserOptions::new().with_suppress_synthetic();
+        self.extend_from_parser(Parser::with_options(more.to_owned(), options))
+    }
+
+    /// Append the pages of the marks of `parser`, see [`Deck::extend_from_str`].
+    pub fn extend_from_parser(&mut self, parser: Parser) -> Range<usize> {
+        let start = self.pages.len();
+        self.pages.extend(pages(parser));
+        start..self.pages.len()
+    }
+
+    /// The index of the current page.
+    #[inline]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The current page. `None` if the deck has no pages.
+    #[inline]
+    pub fn current_page(&self) -> Option<&Page> {
+        self.pages.get(self.current)
+    }
+
+    /// The digest of the content of the deck, to check its integrity, see
+    /// [`integrity`](crate::integrity).
+    pub fn digest(&self) -> Digest {
+        let digests = integrity::digests(self);
+        digests[digests.len() - 1]
+    }
+
+    /// The chain of digests of the deck: the digest of its front matter, then the digest after
+    /// each page. The last one is the digest of the deck.
+    #[inline]
+    pub fn digests(&self) -> Vec<Digest> {
+        integrity::digests(self)
+    }
+
+    /// Assign a stable id to every page and mark of the deck.
+    ///
+    /// The first index of each path is the index of the page, the rest lead through the
+    /// transitions of the page, see [`id::mark_ids`].
+    pub fn mark_ids(&self) -> Vec<(Vec<usize>, MarkId)> {
+        id::page_ids(&self.pages)
+    }
+
+    /// The stable id of the page at `index`, told apart from the pages with the same content
+    /// before it, unlike [`Page::id`]. `None` if there is no such page.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let deck = Deck::new("# Demo\n---\n# Other\n---\n# Demo".to_owned());
+    /// assert_eq!(deck.pages()[0].id(), deck.pages()[2].id());
+    /// assert_ne!(deck.page_id(0), deck.page_id(2));
+    /// ```
+    pub fn page_id(&self, index: usize) -> Option<MarkId> {
+        self.page_ids().into_iter().nth(index)
+    }
+
+    /// The stable ids of the pages, see [`Deck::page_id`].
+    pub(crate) fn page_ids(&self) -> Vec<MarkId> {
+        id::page_ids(&self.pages)
+            .into_iter()
+            .filter(|(path, _)| path.len() == 1)
+            .map(|(_, id)| id)
+            .collect()
+    }
+
+    /// All pages of the deck.
+    #[inline]
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    /// The front matter of the deck, see [`front_matter`](crate::front_matter).
+    #[inline]
+    pub fn front_matter(&self) -> &FrontMatter {
+        &self.front_matter
+    }
+
+    /// The numbering of the captions of the deck, see [`crossref`](crate::crossref#numbering).
+    #[inline]
+    pub fn numbering(&self) -> &Numbering {
+        &self.numbering
+    }
+
+    /// The shortcuts of the presenter declared in the front matter, see
+    /// [`FrontMatter::key_bindings`].
+    #[inline]
+    pub fn key_bindings(&self) -> Vec<KeyBinding> {
+        self.front_matter.key_bindings()
+    }
+
+    /// The bookmarks of the deck, in the order they are written.
+    ///
+    /// Unlike headings, bookmarks are not shown, so they can mark any point of the deck, like a
+    /// step in the middle of a page.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let mut deck = Deck::new("# Intro\n---\nSetup\n---t\n@bookmark(demo)\nRun it".to_owned());
+    /// let bookmarks = deck.bookmarks();
+    /// assert_eq!(bookmarks[0].name, "demo");
+    /// assert_eq!((bookmarks[0].page, bookmarks[0].step), (1, 1));
+    ///
+    /// assert!(deck.goto_bookmark("demo"));
+    /// assert_eq!((deck.current(), deck.current_page().unwrap().step), (1, 1));
+    /// assert!(!deck.goto_bookmark("missing"));
+    /// ```
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        let mut bookmarks = vec![];
+        for (idx, page) in self.pages.iter().enumerate() {
+            for (order, directive) in page.directives() {
+                if let Directive::Bookmark(name) = directive {
+                    bookmarks.push(Bookmark {
+                        name: name.clone(),
+                        page: idx,
+                        step: order,
+                    });
+                }
+            }
+        }
+        bookmarks
+    }
+
+    /// The labeled figures, tables and code blocks of the deck, in the order they are written,
+    /// see [`crossref`](crate::crossref).
+    ///
+    /// ```
+    /// use opmark::{crossref::LabelKind, deck::Deck};
+    ///
+    /// let deck = Deck::new(
+    ///     "![Before](a.png)\n{#fig:before}\n---\n![After](b.png)\n{#fig:after}\n{#tbl:costs}".to_owned(),
+    /// );
+    /// let labels = deck.labels();
+    /// assert_eq!(labels[1].name, "fig:after");
+    /// assert_eq!((labels[1].kind, labels[1].page), (LabelKind::Figure, 1));
+    /// assert_eq!(labels[1].caption, "Figure 2");
+    /// assert_eq!(labels[2].caption, "Table 1");
+    /// ```
+    pub fn labels(&self) -> Vec<Label> {
+        let mut labels: Vec<Label> = vec![];
+        for (idx, page) in self.pages.iter().enumerate() {
+            for (_, directive) in page.directives() {
+                let Directive::Label(name) = directive else {
+                    continue;
+                };
+                let Some(kind) = LabelKind::from_label(name) else {
+                    continue;
+                };
+                // the first element with a label keeps it
+                if labels.iter().any(|label| label.name == *name) {
+                    continue;
+                }
+                let number = labels.iter().filter(|label| label.kind == kind).count() + 1;
+                labels.push(Label {
+                    name: name.clone(),
+                    kind,
+                    number,
+                    page: idx,
+                    caption: self.numbering.caption(kind, number),
+                });
+            }
+        }
+        labels
+    }
+
+    /// The text of the deck for screen readers, in reading order: each page is announced with
+    /// its number, headings with their level and list items with their kind, images are
+    /// replaced by their titles as alt text, and code blocks are delimited. Pages hidden when
+    /// presenting are left out, and so are speaker notes.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let deck = Deck::new(
+    ///     "# Intro\nSee [the docs](https://docs.rs).\n---\n- fast\n  - really\n![Chart of sales](sales.png)"
+    ///         .to_owned(),
+    /// );
+    /// assert_eq!(
+    ///     deck.to_accessible_text(),
+    ///     "Page 1 of 2\n\
+    ///      Heading level 1: Intro\n\
+    ///      See the docs (link to https://docs.rs).\n\
+    ///      \n\
+    ///      Page 2 of 2\n\
+    ///      Bullet: fast\n\
+    ///      Bullet, level 2: really\n\
+    ///      Image: Chart of sales"
+    /// );
+    /// ```
+    pub fn to_accessible_text(&self) -> String {
+        text::accessible_text(self)
+    }
+
+    /// The images, narrations and embedded resources of the deck, with their sources resolved
+    /// against `dir`, the directory of the deck, see [`media`](crate::media).
+    pub fn media_manifest(&self, dir: impl AsRef<Path>) -> MediaManifest {
+        MediaManifest::new(self, dir.as_ref())
+    }
+
+    /// The version of the deck for any of `audiences`: without the pages and the blocks for
+    /// other audiences, see [`audience`](crate::audience). The current page is the first one.
+    pub fn for_audience(&self, audiences: &[&str]) -> Deck {
+        Deck {
+            pages: audience::filter(&self.pages, audiences),
+            current: 0,
+            front_matter: self.front_matter.clone(),
+            numbering: self.numbering.clone(),
+            observers: vec![],
+        }
+    }
+
+    /// The index of the first page of the appendix, the page with an `@appendix` directive, see
+    /// [`Directive::Appendix`]. `None` if the deck has no appendix.
+    pub fn appendix(&self) -> Option<usize> {
+        self.pages.iter().position(|page| {
+            page.directives()
+                .any(|(_, directive)| matches!(directive, Directive::Appendix))
+        })
+    }
+
+    /// The indexes of the pages presented in the talk: the pages which are neither hidden nor in
+    /// the appendix.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let mut deck = Deck::new(
+    ///     "# Talk\n---<hidden>\nDraft\n---\nEnd\n---\n@appendix\n# Backup\n---\nMore".to_owned(),
+    /// );
+    /// assert_eq!(deck.appendix(), Some(3));
+    /// assert_eq!(deck.visible_pages(), [0, 2]);
+    ///
+    /// deck.goto_page(2);
+    /// assert_eq!(deck.progress(), (2, 2));
+    /// deck.next_page();
+    /// assert_eq!(deck.progress(), (2, 2));
+    /// ```
+    pub fn visible_pages(&self) -> Vec<usize> {
+        let end = self.appendix().unwrap_or(self.pages.len());
+        (0..end)
+            .filter(|&idx| !self.pages[idx].style.hidden)
+            .collect()
+    }
+
+    /// The number of the visible pages up to the current page, and the number of all visible
+    /// pages, see [`Deck::visible_pages`]. Pages of the appendix do not count, so going through
+    /// them keeps the talk complete.
+    pub fn progress(&self) -> (usize, usize) {
+        let visible = self.visible_pages();
+        let done = visible.iter().filter(|&&idx| idx <= self.current).count();
+        (done, visible.len())
+    }
+
+    /// The pages related to the page at `page` by `@see(#name)` directives, see
+    /// [`Directive::See`]: first the pages of the bookmarks the page refers to, then the pages
+    /// referring to the bookmarks of the page, each once. References to missing bookmarks are
+    /// ignored.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let deck = Deck::new(
+    ///     "Results\n@see(#raw-data)\n---\nMethod\n---\n@bookmark(raw-data)\nRaw data".to_owned(),
+    /// );
+    /// assert_eq!(deck.related(0), [2]);
+    /// assert_eq!(deck.related(2), [0]);
+    /// assert!(deck.related(1).is_empty());
+    /// ```
+    pub fn related(&self, page: usize) -> Vec<usize> {
+        let bookmarks = self.bookmarks();
+        let references = |idx: usize| -> Vec<usize> {
+            self.pages[idx]
+                .directives()
+                .filter_map(|(_, directive)| match directive {
+                    Directive::See(target) => {
+                        let name = target.strip_prefix('#').unwrap_or(target);
+                        bookmarks.iter().find(|b| b.name == name).map(|b| b.page)
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        if page >= self.pages.len() {
+            return vec![];
+        }
+
+        let mut related: Vec<usize> = vec![];
+        let referring = (0..self.pages.len()).filter(|&idx| references(idx).contains(&page));
+        for idx in references(page).into_iter().chain(referring) {
+            if idx != page && !related.contains(&idx) {
+                related.push(idx);
+            }
+        }
+        related
+    }
+
+    /// Go to the first bookmark named `name`, revealing the transitions up to it. Returns `false`
+    /// if there is no such bookmark.
+    pub fn goto_bookmark(&mut self, name: &str) -> bool {
+        let bookmark = match self.bookmarks().into_iter().find(|b| b.name == name) {
+            Some(bookmark) => bookmark,
+            None => return false,
+        };
+        self.goto_step(bookmark.page, bookmark.step)
+    }
+
+    /// The total duration of the narration of all pages, probed from the audio files read by
+    /// `resolver`, see [`audio::duration`]. `None` if the narration of a page can not be read or
+    /// probed.
+    ///
+    /// ```
+    /// use opmark::{deck::Deck, include::IncludeResolver};
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug)]
+    /// struct Files;
+    ///
+    /// impl IncludeResolver for Files {
+    ///     fn resolve(&self, _: &str) -> Option<String> {
+    ///         None
+    ///     }
+    ///
+    ///     fn resolve_bytes(&self, _: &str) -> Option<Vec<u8>> {
+    ///         // 1.5 seconds of 8 kHz, 8-bit mono WAV
+    ///         let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0\x01\0\x01\0\x40\x1f\0\0\x40\x1f\0\0\x01\0\x08\0".to_vec();
+    ///         wav.extend(b"data\xe0\x2e\0\0");
+    ///         wav.extend([128; 12000]);
+    ///         Some(wav)
+    ///     }
+    /// }
+    ///
+    /// let deck = Deck::new("Hello\n---<narration=intro.wav>\nIntro\n---\n@narration(end.wav)\nBye".to_owned());
+    /// assert_eq!(deck.pages()[0].narration(), None);
+    /// assert_eq!(deck.pages()[1].narration(), Some("intro.wav"));
+    /// assert_eq!(deck.pages()[2].narration(), Some("end.wav"));
+    /// assert_eq!(deck.total_narration_duration(&Files), Some(Duration::from_secs(3)));
+    /// ```
+    pub fn total_narration_duration(&self, resolver: &dyn IncludeResolver) -> Option<Duration> {
+        self.pages
+            .iter()
+            .filter_map(Page::narration)
+            .map(|src| audio::duration(&resolver.resolve_bytes(src)?))
+            .sum()
+    }
+
+    /// Append the number of each page to it, as a line of text of the classes
+    /// [`PAGE_NUMBER_CLASS`] and of `position`, shown from the first step of the page.
+    ///
+    /// In `format`, `{number}` is replaced by the number of the page and `{total}` by the number
+    /// of pages. Hidden pages are not numbered nor counted. If `skip_title` is set, the first
+    /// page is counted but not numbered.
+    ///
+    /// ```
+    /// use opmark::{
+    ///     deck::{Deck, PageNumberPosition},
+    ///     render::html::to_html,
+    /// };
+    ///
+    /// let deck = Deck::new("# Talk\n---\nIntro\n---<hidden>\nBackup\n---\nEnd".to_owned())
+    ///     .with_page_numbers("{number} / {total}", PageNumberPosition::Right, true);
+    /// let html: Vec<String> = deck.pages().iter().map(|page| to_html(&page.transitions)).collect();
+    /// assert_eq!(html[0], "<h1>Talk</h1>");
+    /// assert_eq!(
+    ///     html[1],
+    ///     "<p>Intro</p><p><span class=\"page-number footer-right\">2 / 3</span></p>"
+    /// );
+    /// assert_eq!(html[2], "<p>Backup</p>");
+    /// assert!(html[3].ends_with(">3 / 3</span></p>"));
+    /// ```
+    pub fn with_page_numbers(
+        mut self,
+        format: &str,
+        position: PageNumberPosition,
+        skip_title: bool,
+    ) -> Self {
+        let total = self.pages.iter().filter(|page| !page.style.hidden).count();
+        let pages = self.pages.iter_mut().filter(|page| !page.style.hidden);
+        for (idx, page) in pages.enumerate() {
+            if skip_title && idx == 0 {
+                continue;
+            }
+            let text = format
+                .replace("{number}", &(idx + 1).to_string())
+                .replace("{total}", &total.to_string());
+            let style = StyleText::new()
+                .with_class(PAGE_NUMBER_CLASS.to_owned())
+                .with_class(position.class().to_owned());
+            // the first transition of a page always has the order 0
+            if let Some(Mark::Transition(_, marks, _)) = page.transitions.first_mut() {
+                if marks
+                    .last()
+                    .is_some_and(|mark| !matches!(mark, Mark::NewLine))
+                {
+                    marks.push(Mark::NewLine);
+                }
+                marks.push(Mark::Text(text, style));
+                marks.push(Mark::NewLine);
+            }
+        }
+        self
+    }
+
+    /// Insert a title page at the start of the deck, built from the title, subtitle, author,
+    /// date and logo of its front matter with the default [`TitleLayout`]. Returns `false`,
+    /// inserting nothing, if the front matter has no title.
+    ///
+    /// ```
+    /// use opmark::{deck::Deck, render::html::to_html};
+    ///
+    /// let source = "+++\ntitle = OpMark\nauthor = \"Zoron\"\nlogo = logo.png\n+++\n# Intro";
+    /// let mut deck = Deck::new(source.to_owned());
+    /// assert!(deck.generate_title_slide());
+    /// assert_eq!(deck.pages().len(), 2);
+    /// assert_eq!(deck.pages()[0].style.classes, ["title"]);
+    /// assert_eq!(
+    ///     to_html(&deck.pages()[0].transitions),
+    ///     "<p><img src=\"logo.png\" alt=\"logo\" class=\"logo\" /></p><h1 class=\"title\">OpMark</h1>\
+    ///      <p><span class=\"author\">Zoron</span></p>"
+    /// );
+    ///
+    /// assert!(!Deck::new("# No front matter".to_owned()).generate_title_slide());
+    /// ```
+    pub fn generate_title_slide(&mut self) -> bool {
+        self.generate_title_slide_with_layout(&TitleLayout::new())
+    }
+
+    /// Insert a title page at the start of the deck, built from its front matter with `layout`.
+    /// Returns `false`, inserting nothing, if the front matter has no title.
+    pub fn generate_title_slide_with_layout(&mut self, layout: &TitleLayout) -> bool {
+        if self.front_matter.title().is_none() {
+            return false;
+        }
+        let mut marks = vec![];
+        if let (Some(style), Some(logo)) = (&layout.logo, self.front_matter.logo()) {
+            marks.push(Mark::Image(
+                logo.to_owned(),
+                "logo".to_owned(),
+                style.clone(),
+            ));
+            marks.push(Mark::NewLine);
+        }
+        for (key, style) in &layout.fields {
+            if let Some(value) = self.front_matter.get(key) {
+                marks.push(Mark::Text(value.to_owned(), style.clone()));
+                if style.heading.to_int() == 0 {
+                    marks.push(Mark::NewLine);
+                }
+            }
+        }
+        self.pages.insert(
+            0,
+            Page {
+                transitions: vec![Mark::Transition(0, marks, StyleTransition::new())],
+                style: layout.page.clone(),
+                max_step: 0,
+                step: 0,
+            },
+        );
+        self.current += 1;
+        true
+    }
+
+    /// The headings starting a section at or after the page at `options.position`, with the
+    /// index of their page.
+    fn sections(&self, options: &AgendaOptions) -> Vec<(String, usize)> {
+        let level = options.level.max(1);
+        self.pages
+            .iter()
+            .enumerate()
+            .skip(options.position)
+            .filter_map(|(idx, page)| {
+                let marks = page_marks(page);
+                let (text, style) = marks.into_iter().find_map(|mark| match mark {
+                    Mark::Text(text, style) if style.heading.to_int() > 0 => Some((text, style)),
+                    _ => None,
+                })?;
+                (style.heading.to_int() <= level).then_some((text, idx))
+            })
+            .collect()
+    }
+
+    /// The agenda of the deck, listing the sections with links to their pages, without
+    /// inserting it. See [`Deck::generate_agenda`].
+    ///
+    /// `None` if no page starts a section.
+    pub fn agenda(&self, options: &AgendaOptions) -> Option<Page> {
+        let sections = self.sections(options);
+        (!sections.is_empty()).then(|| agenda_page(options, &sections, None))
+    }
+
+    /// Insert an agenda before the page at `options.position`, listing the pages starting with a
+    /// heading of `options.level` or above as sections, a line of a link to each of them.
+    ///
+    /// Links to pages are written `#page-<number>`, the number counting from 1, like the HTML
+    /// exports name the sections of pages, see [`to_html`](crate::render::html::to_html), and
+    /// jump to the slide of the page in PowerPoint exports. If `options.repeat` is set, the
+    /// agenda is also inserted before each section, with the item of the section in bold and of
+    /// the class [`CURRENT_SECTION_CLASS`]. Agenda pages have the class [`AGENDA_CLASS`], so
+    /// themes can style them.
+    ///
+    /// Returns the indexes of the inserted pages, empty if no page starts a section.
+    ///
+    /// ```
+    /// use opmark::{
+    ///     deck::{AgendaOptions, Deck},
+    ///     render::html::to_html,
+    /// };
+    ///
+    /// let mut deck = Deck::new("# Talk\n---\n# Intro\n---\nMore\n---\n# Demo".to_owned());
+    /// let inserted = deck.generate_agenda(&AgendaOptions::new().with_repeat());
+    /// assert_eq!(inserted, [1, 2, 5]);
+    ///
+    /// let titles: Vec<_> = deck.pages().iter().map(|page| page.title().unwrap()).collect();
+    /// assert_eq!(titles, ["Talk", "Agenda", "Agenda", "Intro", "More", "Agenda", "Demo"]);
+    ///
+    /// let html = to_html(&deck.pages()[5].transitions);
+    /// assert!(html.starts_with("<h1>Agenda</h1><p><a href=\"#page-4\">Intro</a></p>"));
+    /// assert!(html.contains("<span class=\"current\"><a href=\"#page-7\"><strong>Demo</strong>"));
+    /// ```
+    pub fn generate_agenda(&mut self, options: &AgendaOptions) -> Vec<usize> {
+        let sections = self.sections(options);
+        if sections.is_empty() {
+            return vec![];
+        }
+        let position = options.position.min(self.pages.len());
+        let mut inserts: Vec<(usize, Option<usize>)> = vec![(position, None)];
+        if options.repeat {
+            inserts.extend(
+                sections
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, idx))| (*idx, Some(i))),
+            );
+        }
+        // the indexes of the sections once the agenda is inserted
+        let targets: Vec<(String, usize)> = sections
+            .iter()
+            .map(|(title, idx)| {
+                let shift = inserts.iter().filter(|(at, _)| at <= idx).count();
+                (title.clone(), idx + shift)
+            })
+            .collect();
+
+        let mut inserted = vec![];
+        for (i, (at, current)) in inserts.into_iter().enumerate() {
+            let idx = at + i;
+            self.pages
+                .insert(idx, agenda_page(options, &targets, current));
+            if self.current >= idx {
+                self.current += 1;
+            }
+            inserted.push(idx);
+        }
+        inserted
+    }
+
+    /// Split the pages with more than `max_blocks` blocks into pages of `max_blocks` blocks, like
+    /// the pages of a long document imported into a deck. Returns the indexes of the pages added.
+    ///
+    /// Blocks are separated by empty lines and transitions. A transition split across pages goes
+    /// on in the next page, where the steps start again from 0. The pages added keep the classes
+    /// and the layout options of their page, but not its narration, duration nor time of day.
+    /// Pages with the `no-split` option are kept whole.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let mut deck = Deck::new(
+    ///     "# Report\n\nOne\n\nTwo\n---t\nThree\n\nFour\n---<no-split>\nA\n\nB\n\nC".to_owned(),
+    /// );
+    /// assert_eq!(deck.paginate(2), [1, 2]);
+    /// let summaries: Vec<String> = deck.pages().iter().map(|page| page.summary(20)).collect();
+    /// assert_eq!(summaries[0], "One");
+    /// assert_eq!(summaries[2..], ["Four", "A B C"]);
+    /// // the transition of `Three` goes on in the last page added
+    /// assert_eq!((deck.pages()[1].max_step, deck.pages()[2].max_step), (1, 0));
+    /// ```
+    pub fn paginate(&mut self, max_blocks: usize) -> Vec<usize> {
+        let max_blocks = max_blocks.max(1);
+        let mut pages = vec![];
+        let mut added = vec![];
+        let mut current = 0;
+        for (idx, page) in std::mem::take(&mut self.pages).into_iter().enumerate() {
+            if idx == self.current {
+                current = pages.len();
+            }
+            let first = pages.len();
+            match page.style.no_split {
+                true => pages.push(page),
+                false => pages.extend(split_page(page, max_blocks)),
+            }
+            added.extend(first + 1..pages.len());
+        }
+        self.pages = pages;
+        self.current = current;
+        added
+    }
+
+    /// Go to the page at `index`. Returns `false` if there is no such page.
+    pub fn goto_page(&mut self, index: usize) -> bool {
+        if index >= self.pages.len() {
+            return false;
+        }
+        self.current = index;
+        let page = &mut self.pages[index];
+        if page.style.reset_on_enter {
+            page.step = 0;
+        }
+        let step = page.step;
+        self.emit(DeckEvent::PageEntered { page: index, step });
+        true
+    }
+
+    /// Go to the page at `index`, showing the transitions up to `step`, or all of them if it has
+    /// fewer. Returns `false` if there is no such page.
+    pub fn goto_step(&mut self, index: usize, step: usize) -> bool {
+        if index >= self.pages.len() {
+            return false;
+        }
+        self.current = index;
+        let page = &mut self.pages[index];
+        page.step = step.min(page.max_step);
+        let step = page.step;
+        self.emit(DeckEvent::PageEntered { page: index, step });
+        true
+    }
+
+    /// Go to the next page, skipping hidden pages. Returns `false` if there is no next page to
+    /// show, telling the observers that the deck is finished.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let mut deck = Deck::new("one\n---<hidden>\nbackup\n---\nthree".to_owned());
+    /// assert!(deck.next_page());
+    /// assert_eq!(deck.current(), 2);
+    /// assert!(deck.prev_page());
+    /// assert_eq!(deck.current(), 0);
+    /// ```
+    pub fn next_page(&mut self) -> bool {
+        match (self.current + 1..self.pages.len()).find(|&idx| !self.pages[idx].style.hidden) {
+            Some(idx) => self.goto_page(idx),
+            None => {
+                if !self.pages.is_empty() {
+                    self.emit(DeckEvent::DeckFinished);
+                }
+                false
+            }
+        }
+    }
+
+    /// Go to the previous page, skipping hidden pages. Returns `false` if there is no previous
+    /// page to show.
+    pub fn prev_page(&mut self) -> bool {
+        match (0..self.current)
+            .rev()
+            .find(|&idx| !self.pages[idx].style.hidden)
+        {
+            Some(idx) => self.goto_page(idx),
+            None => false,
+        }
+    }
+
+    /// Reveal the next transition, or go to the next page if all transitions have appeared.
+    ///
+    /// If the current page has a looping transition, the page replays from it instead of going to
+    /// the next page, see [`Page::loop_step`], as many times as it is stepped forward. Use
+    /// [`Deck::next_page`] to leave such a page. Returns `false` if the deck did not move.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let mut deck = Deck::new("one\n---t<loop>\ntwo\n---\nthree".to_owned());
+    /// let mut steps = vec![];
+    /// for _ in 0..4 {
+    ///     assert!(deck.next_step());
+    ///     steps.push(deck.current_page().unwrap().step);
+    /// }
+    /// assert_eq!(steps, [1, 0, 1, 0]);
+    /// assert!(deck.next_page());
+    /// assert_eq!(deck.current(), 1);
+    /// ```
+    pub fn next_step(&mut self) -> bool {
+        let page = match self.pages.get_mut(self.current) {
+            Some(page) => page,
+            None => return false,
+        };
+        if page.step < page.max_step {
+            page.step += 1;
+        } else if let Some(step) = page.loop_step() {
+            page.step = step;
+        } else {
+            return self.next_page();
+        }
+        let step = page.step;
+        self.emit(DeckEvent::StepRevealed {
+            page: self.current,
+            step,
+        });
+        true
+    }
+
+    /// Hide the last transition, or go to the previous page if only the first transition is
+    /// visible. Returns `false` if the deck did not move.
+    pub fn prev_step(&mut self) -> bool {
+        let page = match self.pages.get_mut(self.current) {
+            Some(page) => page,
+            None => return false,
+        };
+        if page.step > 0 {
+            page.step -= 1;
+            let step = page.step;
+            self.emit(DeckEvent::StepHidden {
+                page: self.current,
+                step,
+            });
+            return true;
+        }
+        self.prev_page()
+    }
+}
@@ -0,0 +1,436 @@
+//! draw.io diagrams, in XML.
+
+use super::{
+    svg::{Paint, Shape, Svg},
+    DiagramError,
+};
+use crate::render::{tag_end, unescape_xml};
+use std::collections::HashMap;
+
+/// Render the XML text of an uncompressed draw.io diagram to SVG.
+pub(super) fn to_svg(source: &str) -> Result<String, DiagramError> {
+    let root = Element::parse(source)?;
+    let model = if root.name == "mxGraphModel" {
+        &root
+    } else {
+        // the first page of an `mxfile`
+        let diagram = root
+            .child("diagram")
+            .ok_or_else(|| DiagramError::Invalid("missing diagram".to_owned()))?;
+        match diagram.child("mxGraphModel") {
+            Some(model) => model,
+            None if !diagram.text.trim().is_empty() => {
+                return Err(DiagramError::Unsupported(
+                    "compressed draw.io diagram".to_owned(),
+                ))
+            }
+            None => return Err(DiagramError::Invalid("missing mxGraphModel".to_owned())),
+        }
+    };
+    let cells: Vec<Cell> = model
+        .child("root")
+        .map(|root| root.children.iter().filter_map(Cell::new).collect())
+        .unwrap_or_default();
+    let by_id: HashMap<&str, &Cell> = cells.iter().map(|cell| (cell.id, cell)).collect();
+    // the position of a cell is relative to its parent vertex
+    let origin = |cell: &Cell| {
+        let mut origin = (0.0, 0.0);
+        let mut parent = cell.parent.and_then(|id| by_id.get(id));
+        let mut depth = 0;
+        while let Some(cell) = parent.filter(|_| depth < cells.len()) {
+            if cell.is_vertex {
+                origin = (origin.0 + cell.geometry.x, origin.1 + cell.geometry.y);
+            }
+            parent = cell.parent.and_then(|id| by_id.get(id));
+            depth += 1;
+        }
+        origin
+    };
+    let bounds = |cell: &Cell| {
+        let (x, y) = origin(cell);
+        let geometry = &cell.geometry;
+        (
+            x + geometry.x,
+            y + geometry.y,
+            geometry.width,
+            geometry.height,
+        )
+    };
+
+    let mut svg = Svg::default();
+    for cell in &cells {
+        let style = Style::parse(cell.style);
+        let paint = Paint {
+            stroke: style.color("strokeColor", Svg::STROKE),
+            fill: style.color(
+                "fillColor",
+                if cell.is_vertex && style.shape() != "text" {
+                    "#ffffff"
+                } else {
+                    "none"
+                },
+            ),
+            stroke_width: style.number("strokeWidth", 1.0),
+            opacity: style.number("opacity", 100.0) / 100.0,
+            rotation: style.number("rotation", 0.0),
+        };
+        let font_size = style.number("fontSize", 12.0);
+        let font_color = style.color("fontColor", Svg::STROKE);
+        let label = label(&cell.value);
+
+        if cell.is_vertex {
+            let (x, y, width, height) = bounds(cell);
+            match style.shape() {
+                "text" => {}
+                "ellipse" => svg.shape(Shape::Ellipse, x, y, width, height, &paint),
+                "rhombus" => svg.shape(Shape::Diamond, x, y, width, height, &paint),
+                _ => svg.shape(
+                    Shape::Rectangle {
+                        rounded: style.get("rounded") == Some("1"),
+                    },
+                    x,
+                    y,
+                    width,
+                    height,
+                    &paint,
+                ),
+            }
+            if !label.is_empty() {
+                let lines = label.lines().count() as f64;
+                let text_y = y + (height - font_size * lines) / 2.0;
+                svg.text(
+                    x + width / 2.0,
+                    text_y,
+                    &label,
+                    font_size,
+                    &font_color,
+                    "middle",
+                );
+            }
+        } else if cell.is_edge {
+            let (x, y) = origin(cell);
+            // the terminal cell, or the terminal point
+            let end = |id: Option<&str>, point: Option<(f64, f64)>| match id
+                .and_then(|id| by_id.get(id))
+            {
+                Some(cell) => Some(bounds(cell)),
+                None => point.map(|(px, py)| (x + px, y + py, 0.0, 0.0)),
+            };
+            let (Some(source), Some(target)) = (
+                end(cell.source, cell.geometry.source_point),
+                end(cell.target, cell.geometry.target_point),
+            ) else {
+                continue;
+            };
+            let mut points = vec![center(source)];
+            points.extend(cell.geometry.points.iter().map(|(px, py)| (x + px, y + py)));
+            points.push(center(target));
+            // end the edge at the borders of the terminal cells
+            let len = points.len();
+            points[0] = border(source, points[1]);
+            points[len - 1] = border(target, points[len - 2]);
+            svg.polyline(&points, &paint, style.get("endArrow") != Some("none"));
+            if !label.is_empty() {
+                // at the middle of the middle segment
+                let idx = (points.len() - 1) / 2;
+                let ((x1, y1), (x2, y2)) = (points[idx], points[idx + 1]);
+                svg.text(
+                    (x1 + x2) / 2.0,
+                    (y1 + y2) / 2.0 - font_size,
+                    &label,
+                    font_size,
+                    &font_color,
+                    "middle",
+                );
+            }
+        }
+    }
+    Ok(svg.finish())
+}
+
+/// The center of the rectangle `(x, y, width, height)`.
+fn center((x, y, width, height): (f64, f64, f64, f64)) -> (f64, f64) {
+    (x + width / 2.0, y + height / 2.0)
+}
+
+/// The point where the line from the center of the rectangle `rect` to `toward` leaves it.
+fn border(rect: (f64, f64, f64, f64), toward: (f64, f64)) -> (f64, f64) {
+    let (cx, cy) = center(rect);
+    let (dx, dy) = (toward.0 - cx, toward.1 - cy);
+    let scale = (rect.2 / 2.0 / dx.abs())
+        .min(rect.3 / 2.0 / dy.abs())
+        .min(1.0);
+    if !scale.is_finite() {
+        return (cx, cy);
+    }
+    (cx + dx * scale, cy + dy * scale)
+}
+
+/// The text of a label, which may be HTML.
+fn label(value: &str) -> String {
+    let mut text = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+        let tag = rest[start..end]
+            .trim_start_matches(['<', '/'])
+            .to_ascii_lowercase();
+        if ["br", "div", "p"]
+            .iter()
+            .any(|name| tag.starts_with(name) && !text.is_empty() && !text.ends_with('\n'))
+        {
+            text.push('\n');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    unescape_xml(&text).trim().to_owned()
+}
+
+/// A cell of a draw.io graph.
+#[derive(Debug)]
+struct Cell<'a> {
+    id: &'a str,
+    parent: Option<&'a str>,
+    value: String,
+    style: &'a str,
+    is_vertex: bool,
+    is_edge: bool,
+    source: Option<&'a str>,
+    target: Option<&'a str>,
+    geometry: Geometry,
+}
+
+impl<'a> Cell<'a> {
+    /// The cell of `element`, an `mxCell` or an object wrapping one.
+    fn new(element: &'a Element) -> Option<Self> {
+        let (wrapper, cell) = match element.name.as_str() {
+            "mxCell" => (None, element),
+            "UserObject" | "object" => (Some(element), element.child("mxCell")?),
+            _ => return None,
+        };
+        let attr = |name: &str| {
+            wrapper
+                .and_then(|e| e.attr(name))
+                .or_else(|| cell.attr(name))
+        };
+        Some(Self {
+            id: attr("id")?,
+            parent: cell.attr("parent"),
+            value: wrapper
+                .and_then(|e| e.attr("label"))
+                .or_else(|| cell.attr("value"))
+                .unwrap_or_default()
+                .to_owned(),
+            style: cell.attr("style").unwrap_or_default(),
+            is_vertex: cell.attr("vertex") == Some("1"),
+            is_edge: cell.attr("edge") == Some("1"),
+            source: cell.attr("source"),
+            target: cell.attr("target"),
+            geometry: cell
+                .child("mxGeometry")
+                .map(Geometry::new)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// The geometry of a cell.
+#[derive(Debug, Default)]
+struct Geometry {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    source_point: Option<(f64, f64)>,
+    target_point: Option<(f64, f64)>,
+    /// The waypoints of an edge.
+    points: Vec<(f64, f64)>,
+}
+
+impl Geometry {
+    fn new(element: &Element) -> Self {
+        let point = |element: &Element| (element.number("x"), element.number("y"));
+        let mut geometry = Self {
+            x: element.number("x"),
+            y: element.number("y"),
+            width: element.number("width"),
+            height: element.number("height"),
+            ..Default::default()
+        };
+        for child in &element.children {
+            match (child.name.as_str(), child.attr("as")) {
+                ("mxPoint", Some("sourcePoint")) => geometry.source_point = Some(point(child)),
+                ("mxPoint", Some("targetPoint")) => geometry.target_point = Some(point(child)),
+                ("Array", Some("points")) => {
+                    geometry.points = child.children.iter().map(point).collect()
+                }
+                _ => {}
+            }
+        }
+        geometry
+    }
+}
+
+/// The style of a cell, like `ellipse;fillColor=#dae8fc;`.
+struct Style<'a> {
+    /// The first entry without value, like `ellipse`.
+    name: &'a str,
+    values: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Style<'a> {
+    fn parse(style: &'a str) -> Self {
+        let mut name = "";
+        let mut values = HashMap::new();
+        for entry in style.split(';').filter(|entry| !entry.is_empty()) {
+            match entry.split_once('=') {
+                Some((key, value)) => {
+                    values.insert(key, value);
+                }
+                None if name.is_empty() => name = entry,
+                None => {}
+            }
+        }
+        Self { name, values }
+    }
+
+    fn get(&self, key: &str) -> Option<&'a str> {
+        self.values.get(key).copied()
+    }
+
+    fn shape(&self) -> &'a str {
+        self.get("shape").unwrap_or(self.name)
+    }
+
+    fn number(&self, key: &str, default: f64) -> f64 {
+        self.get(key)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn color(&self, key: &str, default: &str) -> String {
+        match self.get(key) {
+            Some("none") => "none".to_owned(),
+            Some(color) if color.starts_with('#') => color.to_owned(),
+            _ => default.to_owned(),
+        }
+    }
+}
+
+/// An XML element, as far as diagrams need: without namespaces and with the text of its
+/// children merged.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// The root element of the XML text `s`.
+    fn parse(s: &str) -> Result<Self, DiagramError> {
+        let invalid = |reason: &str| DiagramError::Invalid(reason.to_owned());
+        // the open elements, the root first
+        let mut stack: Vec<Element> = vec![];
+        let mut root = None;
+        let mut rest = s;
+        while let Some(start) = rest.find('<') {
+            if let Some(element) = stack.last_mut() {
+                element.text.push_str(&unescape_xml(&rest[..start]));
+            }
+            rest = &rest[start..];
+            let skip = [
+                ("<?", "?>"),
+                ("<!--", "-->"),
+                ("<![CDATA[", "]]>"),
+                ("<!", ">"),
+            ]
+            .iter()
+            .find(|(open, _)| rest.starts_with(open));
+            if let Some((_, close)) = skip {
+                let end = rest.find(close).ok_or_else(|| invalid("unclosed markup"))?;
+                rest = &rest[end + close.len()..];
+                continue;
+            }
+            let end = tag_end(rest).ok_or_else(|| invalid("unclosed tag"))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| invalid("unexpected closing tag"))?;
+                if element.name != name.trim() {
+                    return Err(invalid("mismatched closing tag"));
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = root.or(Some(element)),
+                }
+                continue;
+            }
+            let (tag, is_empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = Self::open(tag)?;
+            if is_empty {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = root.or(Some(element)),
+                }
+            } else {
+                stack.push(element);
+            }
+        }
+        if !stack.is_empty() {
+            return Err(invalid("unclosed element"));
+        }
+        root.ok_or_else(|| invalid("missing root element"))
+    }
+
+    /// The element of the opening tag `tag`, without its angle brackets.
+    fn open(tag: &str) -> Result<Self, DiagramError> {
+        let invalid = || DiagramError::Invalid("invalid attribute".to_owned());
+        let tag = tag.trim();
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let mut element = Self {
+            name: tag[..name_end].to_owned(),
+            ..Default::default()
+        };
+        let mut rest = tag[name_end..].trim_start();
+        while !rest.is_empty() {
+            let (name, after) = rest.split_once('=').ok_or_else(invalid)?;
+            let after = after.trim_start();
+            let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'');
+            let quote = quote.ok_or_else(invalid)?;
+            let value_end = after[1..].find(quote).ok_or_else(invalid)? + 1;
+            element
+                .attrs
+                .push((name.trim().to_owned(), unescape_xml(&after[1..value_end])));
+            rest = after[value_end + 1..].trim_start();
+        }
+        Ok(element)
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number(&self, name: &str) -> f64 {
+        self.attr(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
@@ -0,0 +1,78 @@
+//! Excalidraw scenes, in JSON.
+
+use super::{
+    svg::{Paint, Shape, Svg},
+    DiagramError,
+};
+use serde_json::Value;
+
+/// Render the JSON text of an Excalidraw scene to SVG.
+pub(super) fn to_svg(source: &str) -> Result<String, DiagramError> {
+    let scene: Value =
+        serde_json::from_str(source).map_err(|err| DiagramError::Invalid(err.to_string()))?;
+    let elements = scene
+        .get("elements")
+        .and_then(Value::as_array)
+        .ok_or_else(|| DiagramError::Invalid("missing elements".to_owned()))?;
+
+    let mut svg = Svg::default();
+    for element in elements {
+        if element["isDeleted"].as_bool() == Some(true) {
+            continue;
+        }
+        let number = |key: &str| element[key].as_f64().unwrap_or_default();
+        let (x, y, width, height) = (number("x"), number("y"), number("width"), number("height"));
+        let color = |key: &str, default: &str| {
+            element[key]
+                .as_str()
+                .filter(|color| *color != "transparent")
+                .unwrap_or(default)
+                .to_owned()
+        };
+        let paint = Paint {
+            stroke: color("strokeColor", Svg::STROKE),
+            fill: color("backgroundColor", "none"),
+            stroke_width: element["strokeWidth"].as_f64().unwrap_or(1.0),
+            opacity: element["opacity"].as_f64().unwrap_or(100.0) / 100.0,
+            rotation: number("angle").to_degrees(),
+        };
+        match element["type"].as_str().unwrap_or_default() {
+            "rectangle" => svg.shape(
+                Shape::Rectangle {
+                    rounded: !element["roundness"].is_null(),
+                },
+                x,
+                y,
+                width,
+                height,
+                &paint,
+            ),
+            "ellipse" => svg.shape(Shape::Ellipse, x, y, width, height, &paint),
+            "diamond" => svg.shape(Shape::Diamond, x, y, width, height, &paint),
+            kind @ ("line" | "arrow" | "freedraw") => {
+                // the points are relative to the position of the element
+                let points: Vec<(f64, f64)> = element["points"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|point| Some((x + point[0].as_f64()?, y + point[1].as_f64()?)))
+                    .collect();
+                let has_arrow = kind == "arrow" && element["endArrowhead"].as_str().is_some();
+                svg.polyline(&points, &paint, has_arrow);
+            }
+            "text" => {
+                let size = element["fontSize"].as_f64().unwrap_or(20.0);
+                let (x, anchor) = match element["textAlign"].as_str() {
+                    Some("center") => (x + width / 2.0, "middle"),
+                    Some("right") => (x + width, "end"),
+                    _ => (x, "start"),
+                };
+                let text = element["text"].as_str().unwrap_or_default();
+                svg.text(x, y, text, size, &paint.stroke, anchor);
+            }
+            _ => {}
+        }
+    }
+    Ok(svg.finish())
+}
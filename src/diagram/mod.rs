@@ -0,0 +1,191 @@
+//! Diagrams drawn with [draw.io](https://www.drawio.com/) or
+//! [Excalidraw](https://excalidraw.com/), shown as images.
+//!
+//! Images with a `.drawio` or `.excalidraw` source are diagrams, which stay editable in their
+//! native tools:
+//! ```text
+//! ![Architecture](diagrams/architecture.excalidraw)
+//! ```
+//! With the `diagrams` feature, the parser renders them to SVG when both a
+//! `DiagramCache` and an [`IncludeResolver`](crate::include::IncludeResolver) are set in its
+//! options, replacing their source with a `data:` URI. The cache keeps the SVG of each diagram
+//! until its content changes, so decks can be parsed again cheaply while editing. Diagrams which
+//! can not be rendered keep their source, and are reported as
+//! [`Warning`](crate::lint::Warning)s.
+//!
+//! Only the shapes, lines, arrows and labels of diagrams are rendered, and only the first page of
+//! draw.io files. Compressed draw.io files are not supported: they can be saved uncompressed with
+//! _File > Properties > Compressed_.
+
+#[cfg(feature = "diagrams")]
+mod drawio;
+#[cfg(feature = "diagrams")]
+mod excalidraw;
+#[cfg(feature = "diagrams")]
+mod svg;
+
+#[cfg(feature = "diagrams")]
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    error, fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+/// The format of a diagram file.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DiagramFormat {
+    /// `.drawio` files, in XML.
+    DrawIo,
+    /// `.excalidraw` files, in JSON.
+    Excalidraw,
+}
+
+impl DiagramFormat {
+    /// The format of the diagram at `src`, recognized by its extension.
+    ///
+    /// ```
+    /// use opmark::diagram::DiagramFormat;
+    ///
+    /// assert_eq!(DiagramFormat::from_src("flow.drawio"), Some(DiagramFormat::DrawIo));
+    /// assert_eq!(DiagramFormat::from_src("photo.png"), None);
+    /// ```
+    pub fn from_src(src: &str) -> Option<Self> {
+        let path = src.split(['?', '#']).next().unwrap_or_default();
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "drawio" => Some(DiagramFormat::DrawIo),
+            "excalidraw" => Some(DiagramFormat::Excalidraw),
+            _ => None,
+        }
+    }
+}
+
+/// An error rendering a diagram.
+#[cfg(feature = "diagrams")]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiagramError {
+    /// The file is not valid in its format.
+    Invalid(String),
+    /// The file uses a feature of its format which is not supported.
+    Unsupported(String),
+}
+
+#[cfg(feature = "diagrams")]
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagramError::Invalid(reason) => write!(f, "invalid diagram: {}", reason),
+            DiagramError::Unsupported(feature) => write!(f, "unsupported diagram: {}", feature),
+        }
+    }
+}
+
+#[cfg(feature = "diagrams")]
+impl error::Error for DiagramError {}
+
+/// Render the diagram `source` in `format` to SVG.
+#[cfg(feature = "diagrams")]
+pub fn to_svg(format: DiagramFormat, source: &str) -> Result<String, DiagramError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("diagram", ?format, len = source.len()).entered();
+
+    match format {
+        DiagramFormat::DrawIo => drawio::to_svg(source),
+        DiagramFormat::Excalidraw => excalidraw::to_svg(source),
+    }
+}
+
+/// The SVG of rendered diagrams, by their content.
+///
+/// The cache can be shared between parsers and threads.
+///
+/// ```
+/// use opmark::{diagram::DiagramCache, include::IncludeResolver, mark::Mark, Parser, ParserOptions};
+/// use std::sync::Arc;
+///
+/// #[derive(Debug)]
+/// struct Files;
+///
+/// impl IncludeResolver for Files {
+///     fn resolve(&self, path: &str) -> Option<String> {
+///         (path == "box.excalidraw").then(|| {
+///             r#"{"type": "excalidraw", "elements": [
+///                 {"type": "rectangle", "x": 0, "y": 0, "width": 100, "height": 50}
+///             ]}"#
+///             .to_owned()
+///         })
+///     }
+/// }
+///
+/// let cache = Arc::new(DiagramCache::new());
+/// let options = ParserOptions::new()
+///     .with_resolver(Files)
+///     .with_diagrams(cache.clone());
+/// let s = "![A box](box.excalidraw)".to_owned();
+/// let src = Parser::with_options(s, options).find_map(|mark| match mark {
+///     Mark::Image(src, ..) => Some(src),
+///     _ => None,
+/// });
+/// assert!(src.unwrap().starts_with("data:image/svg+xml,%3Csvg"));
+/// assert_eq!(cache.len(), 1);
+/// ```
+#[cfg(feature = "diagrams")]
+#[derive(Debug, Default)]
+pub struct DiagramCache {
+    svgs: Mutex<HashMap<(DiagramFormat, u64), Arc<str>>>,
+}
+
+#[cfg(feature = "diagrams")]
+impl DiagramCache {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The SVG of the diagram `source` in `format`, rendered unless it is cached.
+    pub fn to_svg(&self, format: DiagramFormat, source: &str) -> Result<Arc<str>, DiagramError> {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        let key = (format, hasher.finish());
+        if let Some(svg) = self.svgs().get(&key) {
+            return Ok(svg.clone());
+        }
+        let svg: Arc<str> = to_svg(format, source)?.into();
+        self.svgs().insert(key, svg.clone());
+        Ok(svg)
+    }
+
+    /// The number of cached diagrams.
+    pub fn len(&self) -> usize {
+        self.svgs().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.svgs().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.svgs().clear();
+    }
+
+    fn svgs(&self) -> std::sync::MutexGuard<'_, HashMap<(DiagramFormat, u64), Arc<str>>> {
+        // the map stays consistent even if a thread panicked while holding the lock
+        self.svgs.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// `svg` as a `data:` URI, which can be the source of an image.
+#[cfg(feature = "diagrams")]
+pub fn svg_data_uri(svg: &str) -> String {
+    let mut uri = String::from("data:image/svg+xml,");
+    for byte in svg.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => uri.push(byte as char),
+            b'-' | b'_' | b'.' | b'~' | b'!' | b'$' | b'&' | b'*' | b'+' | b',' | b';' | b'='
+            | b':' | b'@' | b'/' | b'?' | b'\'' => uri.push(byte as char),
+            byte => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
@@ -0,0 +1,213 @@
+//! Building SVG documents.
+
+use crate::render::escape_xml;
+
+/// An SVG document, sized to fit its content.
+#[derive(Debug, Default)]
+pub(super) struct Svg {
+    body: String,
+    /// The bounds of the content: left, top, right and bottom.
+    bounds: Option<(f64, f64, f64, f64)>,
+    /// Whether an arrow head marker is used.
+    has_arrow: bool,
+}
+
+impl Svg {
+    /// The stroke color of shapes, when the diagram sets none.
+    pub(super) const STROKE: &'static str = "#1e1e1e";
+    /// The space around the content.
+    pub(super) const PADDING: f64 = 10.0;
+
+    /// Extend the bounds to the point (`x`, `y`).
+    pub(super) fn extend(&mut self, x: f64, y: f64) {
+        self.bounds = Some(match self.bounds {
+            Some((left, top, right, bottom)) => {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }
+            None => (x, y, x, y),
+        });
+    }
+
+    /// Extend the bounds to the rectangle at (`x`, `y`).
+    pub(super) fn extend_rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.extend(x, y);
+        self.extend(x + width, y + height);
+    }
+
+    /// Add a line through `points`, ending with an arrow head if `has_arrow`.
+    pub(super) fn polyline(&mut self, points: &[(f64, f64)], paint: &Paint, has_arrow: bool) {
+        for (x, y) in points {
+            self.extend(*x, *y);
+        }
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        self.body.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\"{}",
+            points.join(" "),
+            paint.stroke_attrs()
+        ));
+        if has_arrow {
+            self.has_arrow = true;
+            self.body.push_str(" marker-end=\"url(#arrow)\"");
+        }
+        self.body.push_str(" />");
+    }
+
+    /// Add the lines of `text`, centered at `x` or starting from it, below `y`.
+    pub(super) fn text(
+        &mut self,
+        x: f64,
+        y: f64,
+        text: &str,
+        size: f64,
+        color: &str,
+        anchor: &str,
+    ) {
+        for (idx, line) in text.lines().enumerate() {
+            let line_y = y + size * (idx as f64 + 1.0);
+            self.extend(x, line_y - size);
+            self.extend(x, line_y);
+            self.body.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\" text-anchor=\"{}\">{}</text>",
+                x,
+                line_y,
+                size,
+                escape_xml(color),
+                anchor,
+                escape_xml(line)
+            ));
+        }
+    }
+
+    pub(super) fn finish(self) -> String {
+        let (left, top, right, bottom) = self.bounds.unwrap_or_default();
+        let (x, y) = (left - Self::PADDING, top - Self::PADDING);
+        let (width, height) = (
+            right - left + 2.0 * Self::PADDING,
+            bottom - top + 2.0 * Self::PADDING,
+        );
+        let defs = if self.has_arrow {
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\"><path d=\"M0,0L10,5L0,10z\" fill=\"context-stroke\" /></marker></defs>"
+        } else {
+            ""
+        };
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">{}{}</svg>",
+            x, y, width, height, width, height, defs, self.body
+        )
+    }
+}
+
+/// How a shape is painted.
+#[derive(Clone, Debug)]
+pub(super) struct Paint {
+    pub(super) stroke: String,
+    pub(super) fill: String,
+    pub(super) stroke_width: f64,
+    /// The opacity, from 0 to 1.
+    pub(super) opacity: f64,
+    /// The rotation around the center of the shape, in degrees.
+    pub(super) rotation: f64,
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Self {
+            stroke: Svg::STROKE.to_owned(),
+            fill: "none".to_owned(),
+            stroke_width: 1.0,
+            opacity: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Paint {
+    pub(super) fn stroke_attrs(&self) -> String {
+        let mut attrs = format!(
+            " stroke=\"{}\" stroke-width=\"{}\"",
+            escape_xml(&self.stroke),
+            self.stroke_width
+        );
+        if self.opacity < 1.0 {
+            attrs.push_str(&format!(" opacity=\"{}\"", self.opacity));
+        }
+        attrs
+    }
+
+    /// The attributes of a closed shape in the rectangle at (`x`, `y`).
+    pub(super) fn shape_attrs(&self, x: f64, y: f64, width: f64, height: f64) -> String {
+        let mut attrs = format!(
+            " fill=\"{}\"{}",
+            escape_xml(&self.fill),
+            self.stroke_attrs()
+        );
+        if self.rotation != 0.0 {
+            attrs.push_str(&format!(
+                " transform=\"rotate({} {} {})\"",
+                self.rotation,
+                x + width / 2.0,
+                y + height / 2.0
+            ));
+        }
+        attrs
+    }
+}
+
+/// The shapes of diagrams.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Shape {
+    Rectangle { rounded: bool },
+    Ellipse,
+    Diamond,
+}
+
+impl Svg {
+    /// Add `shape` in the rectangle at (`x`, `y`).
+    pub(super) fn shape(
+        &mut self,
+        shape: Shape,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        paint: &Paint,
+    ) {
+        self.extend_rect(x, y, width, height);
+        let attrs = paint.shape_attrs(x, y, width, height);
+        self.body.push_str(&match shape {
+            Shape::Rectangle { rounded } => format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{} />",
+                x,
+                y,
+                width,
+                height,
+                if rounded {
+                    format!(" rx=\"{}\"", (width.min(height) * 0.15).min(16.0))
+                } else {
+                    String::new()
+                },
+                attrs
+            ),
+            Shape::Ellipse => format!(
+                "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"{} />",
+                x + width / 2.0,
+                y + height / 2.0,
+                width / 2.0,
+                height / 2.0,
+                attrs
+            ),
+            Shape::Diamond => format!(
+                "<polygon points=\"{},{} {},{} {},{} {},{}\"{} />",
+                x + width / 2.0,
+                y,
+                x + width,
+                y + height / 2.0,
+                x + width / 2.0,
+                y + height,
+                x,
+                y + height / 2.0,
+                attrs
+            ),
+        });
+    }
+}
@@ -0,0 +1,255 @@
+//! An editable OpMark document which keeps the original text of everything not edited.
+
+use crate::{
+    deck::Deck,
+    mark::Mark,
+    parser::{normalize_line_endings, page_style, LINE_ENDING},
+    serializer::{escape, to_source},
+    FragmentContext, Parser,
+};
+
+/// A block of a document: a line of text, or all lines of the front matter, a code block or a
+/// gallery.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Block {
+    source: String,
+}
+
+impl Block {
+    /// Create a new block from OpMark text.
+    pub fn new(source: String) -> Self {
+        Self { source }
+    }
+
+    /// Create a new block from marks, see [`to_source`].
+    pub fn from_marks(marks: &[Mark]) -> Self {
+        Self::new(to_source(marks))
+    }
+
+    /// Parse the block on its own.
+    ///
+    /// Ordered lists are numbered as if the block was the start of the document.
+    pub fn marks(&self) -> Vec<Mark> {
+        Parser::parse_fragment(&self.source, FragmentContext::new())
+    }
+
+    /// The OpMark text of the block.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// A page of a document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocumentPage {
+    /// The page mark line. `None` for the first page, unless the document starts with a page mark.
+    pub marker: Option<String>,
+    /// The blocks of the page.
+    pub blocks: Vec<Block>,
+}
+
+impl DocumentPage {
+    /// Create a new page starting with a plain page mark.
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Self {
+            marker: Some("---".to_owned()),
+            blocks,
+        }
+    }
+}
+
+/// An editable OpMark document.
+///
+/// The document is split into pages and blocks, keeping their original text. Edits only replace
+/// the text of the blocks they touch, so serializing the document changes as little as possible.
+/// Every method which removes or replaces content returns it, so edits can be undone.
+///
+/// ```
+/// use opmark::document::{Block, Document};
+///
+/// let mut document = Document::new("## Title\n*keep*   this\n---\nsecond".to_owned());
+/// document.set_text(0, 0, "New title".to_owned());
+/// document.insert_block(1, 1, Block::new("- added".to_owned()));
+/// assert_eq!(
+///     document.to_source(),
+///     "## New title\n*keep*   this\n---\nsecond\n- added"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    pages: Vec<DocumentPage>,
+}
+
+impl Document {
+    /// Create a new Document, where `s` is the text content of the OpMark document.
+    pub fn new(s: String) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("document", len = s.len()).entered();
+
+        let s = normalize_line_endings(s);
+        let lines: Vec<&str> = s.split(LINE_ENDING).collect();
+        let mut pages = vec![DocumentPage::default()];
+        let mut idx = 0;
+        while idx < lines.len() {
+            let line = lines[idx];
+            // the parser only accepts a page mark followed by a line ending
+            if idx + 1 < lines.len() && page_style(line).is_some() {
+                pages.push(DocumentPage {
+                    marker: Some(line.to_owned()),
+                    blocks: vec![],
+                });
+                idx += 1;
+                continue;
+            }
+            let mut end = idx + 1;
+            // the front matter, code blocks and galleries take the lines up to their closing
+            // fence
+            let is_front_matter = line.trim_end() == "+++"
+                && (idx == 0 || idx == 1 && lines[0].starts_with("%opmark "));
+            let fence = if is_front_matter {
+                Some("+++")
+            } else {
+                ["```", "!!!"]
+                    .into_iter()
+                    .find(|fence| line.starts_with(fence))
+            };
+            if let Some(fence) = fence {
+                if let Some(close) = lines[idx + 1..]
+                    .iter()
+                    .position(|line| line.starts_with(fence))
+                {
+                    end = idx + 1 + close + 1;
+                }
+            }
+            let block = Block::new(lines[idx..end].join(LINE_ENDING));
+            if let Some(page) = pages.last_mut() {
+                page.blocks.push(block);
+            }
+            idx = end;
+        }
+        Self { pages }
+    }
+
+    /// Parse the document into a deck.
+    pub fn deck(&self) -> Deck {
+        Deck::new(self.to_source())
+    }
+
+    /// Insert `block` at `index` in the page at `page`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such page, or `index` is greater than the count of its blocks.
+    pub fn insert_block(&mut self, page: usize, index: usize, block: Block) {
+        self.pages[page].blocks.insert(index, block);
+    }
+
+    /// Insert `document_page` at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the count of pages.
+    pub fn insert_page(&mut self, index: usize, mut document_page: DocumentPage) {
+        if index == 0 {
+            if let Some(first) = self.pages.first_mut() {
+                first.marker.get_or_insert_with(|| "---".to_owned());
+            }
+            if document_page.marker.as_deref() == Some("---") {
+                document_page.marker = None;
+            }
+        } else if document_page.marker.is_none() {
+            document_page.marker = Some("---".to_owned());
+        }
+        self.pages.insert(index, document_page);
+    }
+
+    /// The pages of the document.
+    #[inline]
+    pub fn pages(&self) -> &[DocumentPage] {
+        &self.pages
+    }
+
+    /// Remove the block at `index` in the page at `page` and return it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such page or block.
+    pub fn remove_block(&mut self, page: usize, index: usize) -> Block {
+        self.pages[page].blocks.remove(index)
+    }
+
+    /// Remove the page at `index` and return it.
+    ///
+    /// If the first page is removed, the plain page mark of the page which becomes first is
+    /// dropped, so the document does not start with an empty page.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such page.
+    pub fn remove_page(&mut self, index: usize) -> DocumentPage {
+        let removed = self.pages.remove(index);
+        if index == 0 {
+            if let Some(first) = self.pages.first_mut() {
+                if first.marker.as_deref() == Some("---") {
+                    first.marker = None;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Replace the text of the block at `index` in the page at `page` with `text`, and return the
+    /// previous block.
+    ///
+    /// Heading, list and quote marks of the block are kept, and `text` is escaped so it shows up
+    /// as is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such page or block.
+    pub fn set_text(&mut self, page: usize, index: usize, text: String) -> Block {
+        let block = &mut self.pages[page].blocks[index];
+        let prefix_len = block_prefix_len(&block.source);
+        let text = if prefix_len > 0 { text } else { escape(&text) };
+        let source = format!("{}{}", &block.source[..prefix_len], text);
+        std::mem::replace(block, Block::new(source))
+    }
+
+    /// Serialize the document into OpMark text.
+    pub fn to_source(&self) -> String {
+        let mut lines: Vec<&str> = vec![];
+        for page in &self.pages {
+            if let Some(marker) = &page.marker {
+                lines.push(marker);
+            }
+            lines.extend(page.blocks.iter().map(Block::source));
+        }
+        lines.join(LINE_ENDING)
+    }
+}
+
+/// The length of the heading, list or quote mark at the start of `line`, including the indent
+/// and the space after it. Text after such a mark is taken as is by the parser.
+fn block_prefix_len(line: &str) -> usize {
+    let trimmed = line.trim_start_matches([' ', '\t']);
+    let indent = line.len() - trimmed.len();
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if hashes > 0 && line[hashes..].starts_with(' ') {
+        return hashes + 1;
+    }
+    if line.starts_with("> ") {
+        return 2;
+    }
+    if trimmed.starts_with("- ") {
+        return indent + 2;
+    }
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if trimmed[digits..].starts_with(". ") {
+        return indent + digits + 2;
+    }
+    0
+}
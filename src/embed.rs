@@ -0,0 +1,63 @@
+//! Recognize links to online services which can be embedded, like videos.
+//!
+//! The parser uses this to turn bare links on their own line into `Mark::Embed` elements, when
+//! enabled with [`ParserOptions::with_embeds`](crate::ParserOptions::with_embeds).
+//!
+//! ```
+//! use opmark::{embed::recognize, mark::EmbedProvider};
+//!
+//! assert_eq!(
+//!     recognize("https://youtu.be/dQw4w9WgXcQ"),
+//!     Some(EmbedProvider::YouTube { id: "dQw4w9WgXcQ".to_owned() })
+//! );
+//! assert_eq!(recognize("https://github.com/fralonra/opmark"), None);
+//! ```
+
+use crate::mark::EmbedProvider;
+
+/// The provider of the resource `url` links to, if it can be embedded.
+pub fn recognize(url: &str) -> Option<EmbedProvider> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(host);
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let path = path.split('#').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let provider = match (host, &segments[..]) {
+        ("youtube.com", ["watch"]) => EmbedProvider::YouTube {
+            id: query_value(query, "v")?.to_owned(),
+        },
+        ("youtube.com", ["embed" | "shorts", id]) | ("youtu.be", [id]) => {
+            EmbedProvider::YouTube { id: id.to_string() }
+        }
+        ("vimeo.com", [id]) if id.chars().all(|c| c.is_ascii_digit()) => {
+            EmbedProvider::Vimeo { id: id.to_string() }
+        }
+        ("gist.github.com", [user, id]) => EmbedProvider::Gist {
+            user: user.to_string(),
+            id: id.to_string(),
+        },
+        ("twitter.com" | "x.com", [user, "status", id]) => EmbedProvider::Tweet {
+            user: user.to_string(),
+            id: id.to_string(),
+        },
+        _ => return None,
+    };
+    Some(provider)
+}
+
+/// The value of `key` in the query string `query`.
+fn query_value<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,453 @@
+//! Fonts of decks, so exports use the same typography as the presentation.
+//!
+//! A [`FontRegistry`] loads TrueType and OpenType fonts, reading their family, weight and style
+//! from the files, and keeps a chain of font families for each [`FontRole`]: the font declared
+//! for the role, like `fonts.heading = "Inter-Bold.ttf"` in a theme, then its fallbacks. Renderers
+//! take the first family of the chain they have, see
+//! [`HtmlTheme::with_fonts`](crate::render::html::HtmlTheme::with_fonts).
+//!
+//! ```
+//! use opmark::{font::{FontRegistry, FontRole}, include::IncludeResolver};
+//! # fn font_file(family: &str) -> Vec<u8> {
+//! #     let name: Vec<u8> = family.encode_utf16().flat_map(u16::to_be_bytes).collect();
+//! #     let mut bytes = b"\0\x01\0\0\0\x01\0\0\0\0\0\0name\0\0\0\0\0\0\0\x1c".to_vec();
+//! #     bytes.extend((18 + name.len() as u32).to_be_bytes());
+//! #     bytes.extend(b"\0\0\0\x01\0\x12\0\x03\0\x01\x04\x09\0\x01");
+//! #     bytes.extend((name.len() as u16).to_be_bytes());
+//! #     bytes.extend(b"\0\0");
+//! #     bytes.extend(name);
+//! #     bytes
+//! # }
+//!
+//! #[derive(Debug)]
+//! struct Fonts;
+//!
+//! impl IncludeResolver for Fonts {
+//!     fn resolve(&self, _: &str) -> Option<String> {
+//!         None
+//!     }
+//!
+//!     fn resolve_bytes(&self, path: &str) -> Option<Vec<u8>> {
+//!         (path == "Inter-Bold.ttf").then(|| font_file("Inter"))
+//!     }
+//! }
+//!
+//! let mut fonts = FontRegistry::new();
+//! fonts.declare("heading", "Inter-Bold.ttf", &Fonts).unwrap();
+//! fonts.add_fallback(FontRole::Heading, "Helvetica".to_owned());
+//! assert_eq!(fonts.families(FontRole::Heading), ["Inter", "Helvetica"]);
+//! assert_eq!(fonts.css_font_family(FontRole::Heading), "\"Inter\", \"Helvetica\", sans-serif");
+//! assert!(fonts.families(FontRole::Body).is_empty());
+//! ```
+//!
+//! Renderers laying out pages measure text with a [`FontMeasure`]. [`ApproxMeasure`] needs no
+//! fonts; with the `shaping` feature, `ShapingMeasure` shapes text with the fonts of a registry
+//! and the fonts installed on the system, falling back along the chains for the characters a
+//! font lacks, which non-Latin scripts and emoji need.
+
+#[cfg(feature = "shaping")]
+mod shaping;
+
+#[cfg(feature = "shaping")]
+pub use shaping::ShapingMeasure;
+
+use crate::{include::IncludeResolver, mark::StyleText};
+use std::{error, fmt, str::FromStr, sync::Arc};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// What text a font is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FontRole {
+    /// The text of pages.
+    Body,
+    /// Headings.
+    Heading,
+    /// Code and code blocks.
+    Code,
+}
+
+impl FontRole {
+    pub const ALL: [FontRole; 3] = [FontRole::Body, FontRole::Heading, FontRole::Code];
+
+    /// The role of text styled by `style`.
+    pub fn of(style: &StyleText) -> Self {
+        if style.code {
+            FontRole::Code
+        } else if style.heading.to_int() > 0 {
+            FontRole::Heading
+        } else {
+            FontRole::Body
+        }
+    }
+
+    /// The name of the role, as declared in themes.
+    pub fn name(self) -> &'static str {
+        match self {
+            FontRole::Body => "body",
+            FontRole::Heading => "heading",
+            FontRole::Code => "code",
+        }
+    }
+
+    /// The generic CSS family ending the fallback chain of the role.
+    pub fn generic_family(self) -> &'static str {
+        match self {
+            FontRole::Body | FontRole::Heading => "sans-serif",
+            FontRole::Code => "monospace",
+        }
+    }
+}
+
+impl FromStr for FontRole {
+    type Err = FontError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FontRole::ALL
+            .into_iter()
+            .find(|role| role.name() == s)
+            .ok_or_else(|| FontError::UnknownRole(s.to_owned()))
+    }
+}
+
+/// The face of a font family text is set in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FontStyle {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl FontStyle {
+    /// The face of text styled by `style`.
+    pub fn of(style: &StyleText) -> Self {
+        Self {
+            bold: style.bold,
+            italic: style.italics,
+        }
+    }
+}
+
+/// Measures the width of text, for renderers laying out pages.
+pub trait FontMeasure {
+    /// The width of `text` set on one line in the font of `role` and `style`, at `size`, in the
+    /// unit of `size`.
+    fn text_width(&self, text: &str, role: FontRole, style: FontStyle, size: f32) -> f32;
+}
+
+/// Approximates the width of text from the kind of its characters, without fonts.
+///
+/// Each grapheme cluster of code is 0.6 times the size wide. Otherwise, wide characters of East
+/// Asian scripts and emoji are as wide as the size, spaces a quarter of it and other characters
+/// half of it.
+///
+/// ```
+/// use opmark::font::{ApproxMeasure, FontMeasure, FontRole, FontStyle};
+///
+/// let width = |text| ApproxMeasure.text_width(text, FontRole::Body, FontStyle::default(), 10.0);
+/// assert_eq!(width("ab c"), 17.5);
+/// assert_eq!(width("日本"), 20.0);
+/// // combining marks add no width
+/// assert_eq!(width("e\u{301}"), 5.0);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ApproxMeasure;
+
+impl ApproxMeasure {
+    /// The width of the grapheme cluster `grapheme`, in ems.
+    fn grapheme_width(grapheme: &str, role: FontRole) -> f32 {
+        if role == FontRole::Code {
+            return 0.6;
+        }
+        match grapheme.chars().next() {
+            Some(' ') => 0.25,
+            Some(c) if is_wide(c) => 1.0,
+            Some(_) => 0.5,
+            None => 0.0,
+        }
+    }
+}
+
+impl FontMeasure for ApproxMeasure {
+    fn text_width(&self, text: &str, role: FontRole, _style: FontStyle, size: f32) -> f32 {
+        text.graphemes(true)
+            .map(|grapheme| Self::grapheme_width(grapheme, role))
+            .sum::<f32>()
+            * size
+    }
+}
+
+/// Whether `c` takes the width of two narrow characters, like CJK ideographs and emoji.
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115f
+            | 0x2e80..=0xa4cf
+            | 0xac00..=0xd7a3
+            | 0xf900..=0xfaff
+            | 0xfe30..=0xfe4f
+            | 0xff00..=0xff60
+            | 0xffe0..=0xffe6
+            | 0x1f300..=0x1faff
+            | 0x20000..=0x3fffd
+    )
+}
+
+/// A TrueType or OpenType font file.
+#[derive(Clone)]
+pub struct Font {
+    /// The source of the file, as declared.
+    pub src: String,
+    /// The family of the font, read from its `name` table.
+    pub family: String,
+    /// The weight of the font, from 100 to 900, 400 being regular.
+    pub weight: u16,
+    pub italic: bool,
+    /// The content of the file.
+    pub data: Arc<[u8]>,
+}
+
+impl Font {
+    /// Read the font file `data` from `src`.
+    pub fn parse(src: String, data: Vec<u8>) -> Result<Self, FontError> {
+        let malformed = || FontError::Malformed(src.clone());
+        if !matches!(data.get(..4), Some(b"\0\x01\0\0" | b"OTTO" | b"true")) {
+            return Err(malformed());
+        }
+        let name = table(&data, b"name").ok_or_else(malformed)?;
+        let family = family_name(name).ok_or_else(malformed)?;
+        // `usWeightClass` and the italic bit of `fsSelection`
+        let os2 = table(&data, b"OS/2");
+        let weight = os2
+            .and_then(|os2| u16_be(os2, 4))
+            .filter(|weight| (1..=1000).contains(weight))
+            .unwrap_or(400);
+        let italic = os2
+            .and_then(|os2| u16_be(os2, 62))
+            .is_some_and(|selection| selection & 1 != 0);
+        Ok(Self {
+            src,
+            family,
+            weight,
+            italic,
+            data: data.into(),
+        })
+    }
+
+    /// The format of the file for the `format()` hint of CSS `@font-face` rules.
+    pub fn css_format(&self) -> &'static str {
+        if self.data.starts_with(b"OTTO") {
+            "opentype"
+        } else {
+            "truetype"
+        }
+    }
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Font")
+            .field("src", &self.src)
+            .field("family", &self.family)
+            .field("weight", &self.weight)
+            .field("italic", &self.italic)
+            .field("data", &format_args!("<{} bytes>", self.data.len()))
+            .finish()
+    }
+}
+
+/// The fonts loaded for a deck, and the chains of font families of each [`FontRole`].
+#[derive(Clone, Debug, Default)]
+pub struct FontRegistry {
+    fonts: Vec<Font>,
+    /// The families of each role, in the order of [`FontRole::ALL`].
+    chains: [Vec<String>; 3],
+}
+
+impl FontRegistry {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add the font file `data` from `src`, keeping the font loaded before from `src` if any.
+    pub fn register(&mut self, src: &str, data: Vec<u8>) -> Result<&Font, FontError> {
+        let idx = match self.fonts.iter().position(|font| font.src == src) {
+            Some(idx) => idx,
+            None => {
+                self.fonts.push(Font::parse(src.to_owned(), data)?);
+                self.fonts.len() - 1
+            }
+        };
+        Ok(&self.fonts[idx])
+    }
+
+    /// Read the font file at `src` with `resolver` and add it.
+    pub fn load(&mut self, src: &str, resolver: &dyn IncludeResolver) -> Result<&Font, FontError> {
+        if let Some(idx) = self.fonts.iter().position(|font| font.src == src) {
+            return Ok(&self.fonts[idx]);
+        }
+        let data = resolver
+            .resolve_bytes(src)
+            .ok_or_else(|| FontError::NotFound(src.to_owned()))?;
+        self.register(src, data)
+    }
+
+    /// Load the font at `src` and put its family first in the chain of the role named `role`,
+    /// like the `fonts.heading = "Inter-Bold.ttf"` declaration of a theme.
+    pub fn declare(
+        &mut self,
+        role: &str,
+        src: &str,
+        resolver: &dyn IncludeResolver,
+    ) -> Result<FontRole, FontError> {
+        let role: FontRole = role.parse()?;
+        let family = self.load(src, resolver)?.family.clone();
+        let chain = &mut self.chains[role as usize];
+        chain.retain(|f| *f != family);
+        chain.insert(0, family);
+        Ok(role)
+    }
+
+    /// Append `family` to the chain of `role`, to be used when the fonts before are missing.
+    ///
+    /// The family may be a loaded font, or a font installed where the deck is shown.
+    pub fn add_fallback(&mut self, role: FontRole, family: String) {
+        let chain = &mut self.chains[role as usize];
+        if !chain.contains(&family) {
+            chain.push(family);
+        }
+    }
+
+    /// The chain of font families of `role`, without its generic family.
+    pub fn families(&self, role: FontRole) -> &[String] {
+        &self.chains[role as usize]
+    }
+
+    /// The loaded fonts.
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// The loaded fonts of `family`.
+    pub fn fonts_of<'a>(&'a self, family: &'a str) -> impl Iterator<Item = &'a Font> + 'a {
+        self.fonts.iter().filter(move |font| font.family == family)
+    }
+
+    /// The chain of `role` as the value of a CSS `font-family` property, ending with the generic
+    /// family of the role.
+    pub fn css_font_family(&self, role: FontRole) -> String {
+        let mut families: Vec<String> = self
+            .families(role)
+            .iter()
+            .map(|family| format!("\"{}\"", family.replace(['"', '\\'], "")))
+            .collect();
+        families.push(role.generic_family().to_owned());
+        families.join(", ")
+    }
+
+    /// The CSS `@font-face` rules of the loaded fonts, fetched from their sources.
+    pub fn to_css(&self) -> String {
+        let mut css = String::new();
+        for font in &self.fonts {
+            css.push_str(&format!(
+                "@font-face {{ font-family: \"{}\"; src: url(\"{}\") format(\"{}\"); font-weight: {}; font-style: {}; }}\n",
+                font.family.replace(['"', '\\'], ""),
+                font.src.replace(['"', '\\'], ""),
+                font.css_format(),
+                font.weight,
+                if font.italic { "italic" } else { "normal" },
+            ));
+        }
+        css
+    }
+}
+
+/// An error of loading fonts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FontError {
+    /// The font file could not be read.
+    NotFound(String),
+    /// The file is not a TrueType or OpenType font with a family name.
+    Malformed(String),
+    /// No role has this name.
+    UnknownRole(String),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::NotFound(src) => write!(f, "font {} not found", src),
+            FontError::Malformed(src) => {
+                write!(f, "font {} is not a TrueType or OpenType font", src)
+            }
+            FontError::UnknownRole(role) => write!(f, "unknown font role {}", role),
+        }
+    }
+}
+
+impl error::Error for FontError {}
+
+fn u16_be(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The table `tag` of the font file `data`, from its table directory.
+fn table<'a>(data: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = u16_be(data, 4)? as usize;
+    (0..count).find_map(|idx| {
+        let record = 12 + idx * 16;
+        if data.get(record..record + 4)? != tag {
+            return None;
+        }
+        let offset = u32_be(data, record + 8)? as usize;
+        let len = u32_be(data, record + 12)? as usize;
+        data.get(offset..offset.checked_add(len)?)
+    })
+}
+
+/// The family of the `name` table `name`: the typographic family if any, or else the family.
+fn family_name(name: &[u8]) -> Option<String> {
+    let count = u16_be(name, 2)? as usize;
+    let storage = u16_be(name, 4)? as usize;
+    let mut family = None;
+    for idx in 0..count {
+        let record = 6 + idx * 12;
+        let platform = u16_be(name, record)?;
+        let name_id = u16_be(name, record + 6)?;
+        let len = u16_be(name, record + 8)? as usize;
+        let offset = storage + u16_be(name, record + 10)? as usize;
+        if !matches!(name_id, 1 | 16) {
+            continue;
+        }
+        let Some(bytes) = name.get(offset..offset + len) else {
+            continue;
+        };
+        let text = match platform {
+            // Unicode and Windows names are UTF-16BE
+            0 | 3 => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            // Macintosh names are Mac Roman, read as ASCII
+            1 => bytes
+                .iter()
+                .map(|&b| if b.is_ascii() { b as char } else { '?' })
+                .collect(),
+            _ => continue,
+        };
+        if text.is_empty() {
+            continue;
+        }
+        if name_id == 16 {
+            return Some(text);
+        }
+        family.get_or_insert(text);
+    }
+    family
+}
@@ -0,0 +1,154 @@
+use super::{ApproxMeasure, FontMeasure, FontRegistry, FontRole, FontStyle};
+use fontdb::{Database, Family, Query, Source, Style, Weight, ID};
+use rustybuzz::{Face, UnicodeBuffer};
+use std::{collections::HashMap, fmt, sync::Arc};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Measures text by shaping it with [rustybuzz](https://docs.rs/rustybuzz), with the fonts of a
+/// [`FontRegistry`] and, optionally, the fonts installed on the system.
+///
+/// Each grapheme cluster is set in the first font of the chain of its role having glyphs for
+/// all its characters, then in the font of the generic family of the role. The runs of clusters
+/// set in the same font are shaped together, so ligatures, kerning and complex scripts are
+/// measured as they are drawn. Clusters no font has glyphs for are measured by
+/// [`ApproxMeasure`].
+///
+/// ```
+/// use opmark::font::{ApproxMeasure, FontMeasure, FontRegistry, FontRole, FontStyle, ShapingMeasure};
+///
+/// // without fonts, text is approximated
+/// let measure = ShapingMeasure::new(&FontRegistry::new());
+/// let style = FontStyle::default();
+/// assert_eq!(
+///     measure.text_width("Hello", FontRole::Body, style, 16.0),
+///     ApproxMeasure.text_width("Hello", FontRole::Body, style, 16.0),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ShapingMeasure {
+    /// The data of the fonts used, with the index of the face in the file.
+    fonts: Vec<(Arc<[u8]>, u32)>,
+    /// The fonts of each role and style, in order of preference.
+    chains: HashMap<(FontRole, FontStyle), Vec<usize>>,
+}
+
+impl ShapingMeasure {
+    /// Measure with the fonts of `registry` only.
+    pub fn new(registry: &FontRegistry) -> Self {
+        Self::with_database(registry, Database::new())
+    }
+
+    /// Measure with the fonts of `registry` and the fonts installed on the system, which are
+    /// also used for the generic family of each role.
+    pub fn with_system_fonts(registry: &FontRegistry) -> Self {
+        let mut database = Database::new();
+        database.load_system_fonts();
+        Self::with_database(registry, database)
+    }
+
+    fn with_database(registry: &FontRegistry, mut database: Database) -> Self {
+        for font in registry.fonts() {
+            database.load_font_source(Source::Binary(Arc::new(font.data.clone())));
+        }
+
+        let mut fonts = vec![];
+        let mut ids: HashMap<ID, Option<usize>> = HashMap::new();
+        let mut chains = HashMap::new();
+        for role in FontRole::ALL {
+            let generic = match role {
+                FontRole::Code => Family::Monospace,
+                FontRole::Body | FontRole::Heading => Family::SansSerif,
+            };
+            let families = registry
+                .families(role)
+                .iter()
+                .map(|family| Family::Name(family))
+                .chain([generic]);
+            for bold in [false, true] {
+                for italic in [false, true] {
+                    let mut chain = vec![];
+                    for family in families.clone() {
+                        let query = Query {
+                            families: &[family],
+                            weight: if bold { Weight::BOLD } else { Weight::NORMAL },
+                            style: if italic { Style::Italic } else { Style::Normal },
+                            ..Default::default()
+                        };
+                        let Some(id) = database.query(&query) else {
+                            continue;
+                        };
+                        let idx = *ids.entry(id).or_insert_with(|| {
+                            let font = database
+                                .with_face_data(id, |data, index| (Arc::from(data), index))?;
+                            fonts.push(font);
+                            Some(fonts.len() - 1)
+                        });
+                        chain.extend(idx.filter(|idx| !chain.contains(idx)));
+                    }
+                    chains.insert((role, FontStyle { bold, italic }), chain);
+                }
+            }
+        }
+        Self { fonts, chains }
+    }
+}
+
+impl FontMeasure for ShapingMeasure {
+    fn text_width(&self, text: &str, role: FontRole, style: FontStyle, size: f32) -> f32 {
+        let faces: Vec<Face> = self
+            .chains
+            .get(&(role, style))
+            .into_iter()
+            .flatten()
+            .filter_map(|&idx| {
+                let (data, index) = &self.fonts[idx];
+                Face::from_slice(data, *index)
+            })
+            .collect();
+        let has_glyphs = |face: &Face, grapheme: &str| {
+            grapheme
+                .chars()
+                // joiners and variation selectors have no glyphs of their own
+                .filter(|c| !matches!(c, '\u{200c}' | '\u{200d}' | '\u{fe00}'..='\u{fe0f}'))
+                .all(|c| face.glyph_index(c).is_some())
+        };
+
+        let mut width = 0.0;
+        // the runs of graphemes set in the same face, `None` for graphemes without face
+        let mut runs: Vec<(Option<usize>, String)> = vec![];
+        for grapheme in text.graphemes(true) {
+            let face = faces.iter().position(|face| has_glyphs(face, grapheme));
+            match runs.last_mut() {
+                Some((run_face, run)) if *run_face == face => run.push_str(grapheme),
+                _ => runs.push((face, grapheme.to_owned())),
+            }
+        }
+        for (face, run) in runs {
+            width += match face {
+                Some(face) => {
+                    let face = &faces[face];
+                    let mut buffer = UnicodeBuffer::new();
+                    buffer.push_str(&run);
+                    let glyphs = rustybuzz::shape(face, &[], buffer);
+                    let advance: i32 = glyphs
+                        .glyph_positions()
+                        .iter()
+                        .map(|position| position.x_advance)
+                        .sum();
+                    advance as f32 * size / face.units_per_em() as f32
+                }
+                None => ApproxMeasure.text_width(&run, role, style, size),
+            };
+        }
+        width
+    }
+}
+
+impl fmt::Debug for ShapingMeasure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShapingMeasure")
+            .field("fonts", &self.fonts.len())
+            .field("chains", &self.chains)
+            .finish()
+    }
+}
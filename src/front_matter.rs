@@ -0,0 +1,237 @@
+//! The metadata of a document, like its title and author.
+//!
+//! A document may start with front matter, after its dialect declaration if any, between two
+//! `+++` lines:
+//! ```text
+//! +++
+//! title = "OpMark in production"
+//! author = Zoron
+//! +++
+//! ```
+//! Each line sets a key to a value, which may be quoted. Blank lines and lines starting with `#`
+//! are ignored. Front matter adds no marks, see
+//! [`Parser::front_matter`](crate::Parser::front_matter).
+//!
+//! ```
+//! use opmark::Parser;
+//!
+//! let parser = Parser::new("+++\ntitle = \"OpMark\"\ndate = 2024-05-01\n+++\n# Intro".to_owned());
+//! let front_matter = parser.front_matter();
+//! assert_eq!(front_matter.title(), Some("OpMark"));
+//! assert_eq!(front_matter.get("date"), Some("2024-05-01"));
+//! assert_eq!(front_matter.author(), None);
+//! ```
+
+use crate::parser::parse_duration;
+use std::time::Duration;
+
+/// The keys and values of the front matter of a document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrontMatter {
+    fields: Vec<(String, String)>,
+}
+
+impl FrontMatter {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set `key` to `value`, replacing its previous value.
+    pub fn with_field(mut self, key: String, value: String) -> Self {
+        match self.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((key, value)),
+        }
+        self
+    }
+
+    /// The value of `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All keys and values, in the order they are written.
+    #[inline]
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The `title` of the document.
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    /// The `subtitle` of the document.
+    #[inline]
+    pub fn subtitle(&self) -> Option<&str> {
+        self.get("subtitle")
+    }
+
+    /// The `author` of the document.
+    #[inline]
+    pub fn author(&self) -> Option<&str> {
+        self.get("author")
+    }
+
+    /// The `date` of the document, as written.
+    #[inline]
+    pub fn date(&self) -> Option<&str> {
+        self.get("date")
+    }
+
+    /// The source of the `logo` image of the document.
+    #[inline]
+    pub fn logo(&self) -> Option<&str> {
+        self.get("logo")
+    }
+
+    /// The length of the talk, from its `duration` written in hours, minutes and seconds, like
+    /// `20m`. `None` if it is not set or is not a duration.
+    pub fn duration(&self) -> Option<Duration> {
+        self.get("duration").and_then(parse_duration)
+    }
+
+    /// The shortcuts of the presenter, from `keys`, so all frontends honor them. `keys` lists
+    /// bindings separated by commas, each a key, `:` and an action, see [`KeyAction`], and may
+    /// be written between braces. Bindings without a key or an action are ignored.
+    ///
+    /// ```
+    /// use opmark::{
+    ///     front_matter::{KeyAction, KeyBinding},
+    ///     Parser,
+    /// };
+    ///
+    /// let parser = Parser::new(
+    ///     "+++\nkeys = { d: goto #demo, 1: goto 1, b: blank-screen, n: toggle-notes }\n+++".to_owned(),
+    /// );
+    /// assert_eq!(
+    ///     parser.front_matter().key_bindings(),
+    ///     [
+    ///         KeyBinding { key: "d".to_owned(), action: KeyAction::GotoBookmark("demo".to_owned()) },
+    ///         KeyBinding { key: "1".to_owned(), action: KeyAction::GotoPage(0) },
+    ///         KeyBinding { key: "b".to_owned(), action: KeyAction::BlankScreen },
+    ///         KeyBinding { key: "n".to_owned(), action: KeyAction::Other("toggle-notes".to_owned()) },
+    ///     ]
+    /// );
+    /// ```
+    pub fn key_bindings(&self) -> Vec<KeyBinding> {
+        let Some(keys) = self.get("keys") else {
+            return vec![];
+        };
+        let keys = keys.trim();
+        let keys = keys
+            .strip_prefix('{')
+            .and_then(|keys| keys.strip_suffix('}'))
+            .unwrap_or(keys);
+        keys.split(',')
+            .filter_map(|binding| {
+                let (key, action) = binding.split_once(':')?;
+                let (key, action) = (key.trim(), KeyAction::parse(action.trim())?);
+                (!key.is_empty()).then(|| KeyBinding {
+                    key: key.to_owned(),
+                    action,
+                })
+            })
+            .collect()
+    }
+
+    /// Parse the lines between the `+++` lines, returning the front matter and the lines which
+    /// set no key.
+    pub(crate) fn parse<'a>(lines: impl Iterator<Item = &'a str>) -> (Self, Vec<&'a str>) {
+        let mut front_matter = Self::new();
+        let mut invalid = vec![];
+        for line in lines {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            match parse_field(trimmed) {
+                Some((key, value)) => front_matter = front_matter.with_field(key, value),
+                None => invalid.push(line),
+            }
+        }
+        (front_matter, invalid)
+    }
+}
+
+/// A shortcut of the presenter, see [`FrontMatter::key_bindings`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBinding {
+    /// The key, as written, like `d` or `F5`. Frontends map it to their own key names.
+    pub key: String,
+    pub action: KeyAction,
+}
+
+/// What a shortcut of the presenter does.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyAction {
+    /// `goto #name`: go to the bookmark `name`, see `Deck::goto_bookmark`.
+    GotoBookmark(String),
+    /// `goto n`: go to the page numbered `n`, from 1. The field is the index of the page.
+    GotoPage(usize),
+    /// `blank-screen`: hide the page, until a key is pressed.
+    BlankScreen,
+    /// Any other action, like `toggle-notes`, done by the frontends which know it.
+    Other(String),
+}
+
+impl KeyAction {
+    fn parse(s: &str) -> Option<Self> {
+        if let Some(target) = s.strip_prefix("goto ") {
+            let target = target.trim();
+            if let Some(name) = target.strip_prefix('#') {
+                return (!name.is_empty()).then(|| KeyAction::GotoBookmark(name.to_owned()));
+            }
+            let number: usize = target.parse().ok()?;
+            return number.checked_sub(1).map(KeyAction::GotoPage);
+        }
+        match s {
+            "" => None,
+            "blank-screen" => Some(KeyAction::BlankScreen),
+            _ => Some(KeyAction::Other(s.to_owned())),
+        }
+    }
+}
+
+/// `key = value` or `key = "value"`, where `\"` and `\\` escape in quoted values.
+fn parse_field(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once('=')?;
+    let key = key.trim();
+    let is_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'));
+    if !is_key {
+        return None;
+    }
+    let value = value.trim();
+    let value = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let mut unquoted = String::new();
+            let mut chars = quoted.chars();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => unquoted.push(chars.next()?),
+                    c => unquoted.push(c),
+                }
+            }
+            if !chars.as_str().trim().is_empty() {
+                return None;
+            }
+            unquoted
+        }
+        None => value.to_owned(),
+    };
+    Some((key.to_owned(), value))
+}
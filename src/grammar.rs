@@ -0,0 +1,684 @@
+//! The syntax of OpMark as data.
+//!
+//! Editor plugins and documentation can read the rules here to stay in sync with the parser,
+//! which tries them in the order of [`RULES`]: at the start of a line the block rules are tried
+//! first, then the inline rules, ending with plain text.
+//!
+//! ```
+//! use opmark::grammar::{RuleKind, RULES};
+//!
+//! let blocks: Vec<&str> = RULES
+//!     .iter()
+//!     .filter(|rule| rule.kind == RuleKind::Block)
+//!     .map(|rule| rule.name)
+//!     .collect();
+//! assert_eq!(blocks[..2], ["page", "transition"]);
+//! ```
+//!
+//! Which rules match while parsing a document can be recorded in a [`ParseTrace`].
+
+use std::ops::Range;
+
+/// Where a rule applies.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RuleKind {
+    /// At the start of the document only.
+    Declaration,
+    /// At the start of a line. The rule takes the rest of the line, or several lines.
+    Block,
+    /// Anywhere within a line.
+    Inline,
+    /// Anywhere within a line, turning a text style on or off.
+    Toggle,
+}
+
+/// The constructs of OpMark.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RuleId {
+    Declaration,
+    FrontMatter,
+    Page,
+    Transition,
+    TransitionEnd,
+    CodeBlock,
+    DisplayMath,
+    Gallery,
+    Heading,
+    Image,
+    OrderedList,
+    Quote,
+    Separator,
+    Space,
+    HoldSlide,
+    UnorderedList,
+    Embed,
+    Directive,
+    LinkDefinition,
+    Bold,
+    Code,
+    InlineMath,
+    Hyperlink,
+    Italics,
+    Small,
+    Strikethrough,
+    Underline,
+    Reference,
+    Placeholder,
+    Classes,
+    Escape,
+    Text,
+}
+
+/// A rule of the syntax.
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    pub id: RuleId,
+    /// The name of the rule, in snake case.
+    pub name: &'static str,
+    pub kind: RuleKind,
+    /// The literal texts the rule starts with. Empty if the rule starts with a variable part,
+    /// like the indent of a list.
+    pub markers: &'static [&'static str],
+    /// A regular expression matching the rule at the cursor, where `\n` stands for the line
+    /// ending.
+    pub pattern: &'static str,
+    /// An example of the rule.
+    pub example: &'static str,
+}
+
+impl Rule {
+    /// The precedence of the rule: rules with a lower precedence are tried first.
+    pub fn precedence(&self) -> usize {
+        RULES
+            .iter()
+            .position(|rule| rule.id == self.id)
+            .unwrap_or(RULES.len())
+    }
+
+    /// The rule with the given id.
+    pub fn get(id: RuleId) -> &'static Rule {
+        RULES
+            .iter()
+            .find(|rule| rule.id == id)
+            .expect("every rule id has a rule")
+    }
+}
+
+/// The markers of the toggle rules, which can be remapped to write documents in a familiar
+/// dialect, see [`ParserOptions::with_markers`](crate::ParserOptions::with_markers) and
+/// [`to_source_with_markers`](crate::serializer::to_source_with_markers).
+///
+/// When several markers match, the longest one wins, so `_` can mark italics while `__` marks
+/// underlined text. Empty markers never match.
+///
+/// Bold, strikethrough and underlined text marked with a single character can also be marked
+/// with it doubled, like `**bold**`, which toggles the same style. The single character is text
+/// between two letters or digits, like in `file_names`, while the doubled one toggles anywhere,
+/// like in `**bold**ness`. A marker of another rule wins over a doubled marker of the same
+/// length.
+///
+/// ```
+/// use opmark::{mark::Mark, Parser};
+///
+/// let bold: Vec<String> = Parser::new("file_names **bold**ness *a* b*c*d".to_owned())
+///     .filter_map(|mark| match mark {
+///         Mark::Text(text, style) if style.bold => Some(text),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(bold, ["bold", "a"]);
+/// ```
+///
+/// ```
+/// use opmark::{grammar::Markers, mark::Mark, Parser, ParserOptions};
+///
+/// let options = ParserOptions::new().with_markers(Markers::markdown());
+/// let marks: Vec<Mark> = Parser::with_options("**bold** _italics_ a/b".to_owned(), options)
+///     .filter(|mark| matches!(mark, Mark::Text(..)))
+///     .collect();
+/// assert!(matches!(&marks[0], Mark::Text(text, style) if text == "bold" && style.bold));
+/// assert!(matches!(&marks[2], Mark::Text(text, style) if text == "italics" && style.italics));
+/// assert!(marks[3..].iter().all(|mark| matches!(mark, Mark::Text(_, style) if !style.italics)));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Markers {
+    pub bold: String,
+    pub italics: String,
+    pub small: String,
+    pub strikethrough: String,
+    pub underline: String,
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        let marker = |id| Rule::get(id).markers[0].to_owned();
+        Self {
+            bold: marker(RuleId::Bold),
+            italics: marker(RuleId::Italics),
+            small: marker(RuleId::Small),
+            strikethrough: marker(RuleId::Strikethrough),
+            underline: marker(RuleId::Underline),
+        }
+    }
+}
+
+impl Markers {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The markers closest to Markdown: `**bold**`, `_italics_`, `~~strikethrough~~` and
+    /// `__underline__`. Small text keeps `$small$`.
+    pub fn markdown() -> Self {
+        Self {
+            bold: "**".to_owned(),
+            italics: "_".to_owned(),
+            small: "$".to_owned(),
+            strikethrough: "~~".to_owned(),
+            underline: "__".to_owned(),
+        }
+    }
+
+    #[inline]
+    pub fn with_bold(mut self, marker: String) -> Self {
+        self.bold = marker;
+        self
+    }
+
+    #[inline]
+    pub fn with_italics(mut self, marker: String) -> Self {
+        self.italics = marker;
+        self
+    }
+
+    #[inline]
+    pub fn with_small(mut self, marker: String) -> Self {
+        self.small = marker;
+        self
+    }
+
+    #[inline]
+    pub fn with_strikethrough(mut self, marker: String) -> Self {
+        self.strikethrough = marker;
+        self
+    }
+
+    #[inline]
+    pub fn with_underline(mut self, marker: String) -> Self {
+        self.underline = marker;
+        self
+    }
+
+    /// The marker of the toggle rule `id`, `None` if it is not a toggle rule.
+    pub fn get(&self, id: RuleId) -> Option<&str> {
+        self.toggles()
+            .into_iter()
+            .find(|(toggle, _)| *toggle == id)
+            .map(|(_, marker)| marker)
+    }
+
+    /// The doubled marker of the toggle rule `id`, `None` if its marker is not a single
+    /// character or the rule is not bold, strikethrough nor underline.
+    ///
+    /// ```
+    /// use opmark::grammar::{Markers, RuleId};
+    ///
+    /// assert_eq!(Markers::new().doubled(RuleId::Bold).as_deref(), Some("**"));
+    /// assert_eq!(Markers::new().doubled(RuleId::Italics), None);
+    /// assert_eq!(Markers::markdown().doubled(RuleId::Bold), None);
+    /// ```
+    pub fn doubled(&self, id: RuleId) -> Option<String> {
+        if !matches!(id, RuleId::Bold | RuleId::Strikethrough | RuleId::Underline) {
+            return None;
+        }
+        let marker = self.get(id)?;
+        let mut chars = marker.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(format!("{}{}", c, c)),
+            _ => None,
+        }
+    }
+
+    /// The toggle rule whose marker starts `s`, the one with the longest marker if several do.
+    pub fn toggle_at(&self, s: &str) -> Option<RuleId> {
+        self.marker_at(s, true).map(|(id, _)| id)
+    }
+
+    /// The toggle rule whose marker starts `s` and the length of the marker, considering the
+    /// doubled markers if `doubled` is set.
+    pub(crate) fn marker_at(&self, s: &str, doubled: bool) -> Option<(RuleId, usize)> {
+        let mut found: Option<(RuleId, usize)> = None;
+        let mut consider = |id: RuleId, marker: &str| {
+            let is_longer = found.is_none_or(|(_, len)| marker.len() > len);
+            if !marker.is_empty() && s.starts_with(marker) && is_longer {
+                found = Some((id, marker.len()));
+            }
+        };
+        for (id, marker) in self.toggles() {
+            consider(id, marker);
+        }
+        if doubled {
+            for (id, _) in self.toggles() {
+                if let Some(marker) = self.doubled(id) {
+                    consider(id, &marker);
+                }
+            }
+        }
+        found
+    }
+
+    /// Whether a marker starts with `c`, so plain text has to end before it.
+    pub(crate) fn is_marker_start(&self, c: char) -> bool {
+        self.toggles()
+            .iter()
+            .any(|(_, marker)| marker.starts_with(c))
+    }
+
+    /// The toggle rules and their markers, in the order of [`RULES`].
+    fn toggles(&self) -> [(RuleId, &str); 5] {
+        [
+            (RuleId::Bold, &self.bold),
+            (RuleId::Italics, &self.italics),
+            (RuleId::Small, &self.small),
+            (RuleId::Strikethrough, &self.strikethrough),
+            (RuleId::Underline, &self.underline),
+        ]
+    }
+}
+
+/// Characters which end plain text, since they may start an inline rule.
+pub const SPECIAL_CHARS: [char; 11] = ['*', '`', '~', '_', '/', '$', '^', '\\', '<', '[', '{'];
+
+/// All rules, in the order the parser tries them.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: RuleId::Declaration,
+        name: "declaration",
+        kind: RuleKind::Declaration,
+        markers: &["%opmark "],
+        pattern: r"%opmark [0-9]+\.[0-9]+[ \t]*\n?",
+        example: "%opmark 0.3",
+    },
+    Rule {
+        id: RuleId::FrontMatter,
+        name: "front_matter",
+        kind: RuleKind::Declaration,
+        markers: &["+++"],
+        pattern: r"\+\+\+[ \t]*\n(\+{0,2}\n|\+{0,2}[^+\n][^\n]*\n)*\+\+\+[ \t]*\n?",
+        example: "+++\ntitle = \"OpMark\"\n+++",
+    },
+    Rule {
+        id: RuleId::Page,
+        name: "page",
+        kind: RuleKind::Block,
+        markers: &["---"],
+        pattern: r"---[ \t]*(<[^\n]*>)?\n",
+        example: "--- <class=title|reset-on-enter>",
+    },
+    Rule {
+        id: RuleId::Transition,
+        name: "transition",
+        kind: RuleKind::Block,
+        markers: &["---t"],
+        pattern: r"---t[0-9]*(<[^\n]*>)?",
+        example: "---t2<dim:prev|loop>",
+    },
+    Rule {
+        id: RuleId::TransitionEnd,
+        name: "transition_end",
+        kind: RuleKind::Block,
+        markers: &["t---"],
+        pattern: r"t---\n",
+        example: "t---",
+    },
+    Rule {
+        id: RuleId::CodeBlock,
+        name: "code_block",
+        kind: RuleKind::Block,
+        markers: &["```"],
+        pattern: r"```[^\n]*\n(`{0,2}\n|`{0,2}[^`\n][^\n]*\n)*```",
+        example: "```rust\nfn main() {}\n```",
+    },
+    Rule {
+        id: RuleId::DisplayMath,
+        name: "display_math",
+        kind: RuleKind::Block,
+        markers: &["$$"],
+        pattern: r"\$\$([^\n]+\$\$|[ \t]*\n(\$?\n|\$?[^$\n][^\n]*\n)*\$\$)",
+        example: "$$\nx^2 + y^2 = z^2\n$$",
+    },
+    Rule {
+        id: RuleId::Gallery,
+        name: "gallery",
+        kind: RuleKind::Block,
+        markers: &["!!!"],
+        pattern: r"!!![ \t]*(<[^\n]*>)?\n(!{0,2}\n|!{0,2}[^!\n][^\n]*\n)*!!!",
+        example: "!!!<columns=2>\n![one](one.png)\n![two](two.png)\n!!!",
+    },
+    Rule {
+        id: RuleId::Heading,
+        name: "heading",
+        kind: RuleKind::Block,
+        markers: &["#"],
+        pattern: r"#+ [^\n]*",
+        example: "## Heading",
+    },
+    Rule {
+        id: RuleId::Image,
+        name: "image",
+        kind: RuleKind::Block,
+        markers: &["!["],
+        pattern: r"!\[[^\]\n]*\]\([^)\n]*\)(<[^>\n]*>)?",
+        example: "![title](src.png)<w50|center>",
+    },
+    Rule {
+        id: RuleId::OrderedList,
+        name: "ordered_list",
+        kind: RuleKind::Block,
+        markers: &[],
+        pattern: r"([ \t]*)[0-9]*\. [^\n]*",
+        example: "1. ordered list",
+    },
+    Rule {
+        id: RuleId::Quote,
+        name: "quote",
+        kind: RuleKind::Block,
+        markers: &["> "],
+        pattern: r"> [^\n]*",
+        example: "> quote",
+    },
+    Rule {
+        id: RuleId::Separator,
+        name: "separator",
+        kind: RuleKind::Block,
+        markers: &["----"],
+        pattern: r"----v?(<[^>\n]*>)?\n",
+        example: "----v",
+    },
+    Rule {
+        id: RuleId::Space,
+        name: "space",
+        kind: RuleKind::Block,
+        markers: &["@space("],
+        pattern: r"@space\([0-9]+(\.[0-9]+)?\)[ \t]*",
+        example: "@space(2)",
+    },
+    Rule {
+        id: RuleId::HoldSlide,
+        name: "hold_slide",
+        kind: RuleKind::Block,
+        markers: &["@holdslide("],
+        pattern: r"@holdslide\([^\n]*\)[ \t]*",
+        example: "@holdslide(start=14:00, message=\"Starting soon\")",
+    },
+    Rule {
+        id: RuleId::UnorderedList,
+        name: "unordered_list",
+        kind: RuleKind::Block,
+        markers: &[],
+        pattern: r"([ \t]*)- [^\n]*",
+        example: "- unordered list",
+    },
+    Rule {
+        id: RuleId::Embed,
+        name: "embed",
+        kind: RuleKind::Block,
+        markers: &["<", "http"],
+        pattern: r"<?https?://[^\s>]+>?[ \t]*\n",
+        example: "https://youtu.be/dQw4w9WgXcQ",
+    },
+    Rule {
+        id: RuleId::Directive,
+        name: "directive",
+        kind: RuleKind::Block,
+        markers: &["@", "{#"],
+        pattern: r"(@[a-z]+(\([^)\n]*\)(:[^\n]*)?)?|\{#[a-z]+:[^\s}]+\})[ \t]*",
+        example: "@bookmark(demo)",
+    },
+    Rule {
+        id: RuleId::LinkDefinition,
+        name: "link_definition",
+        kind: RuleKind::Block,
+        markers: &["["],
+        pattern: r"\[[^\]\n]+\]:[ \t]*\S+[ \t]*",
+        example: "[github]: https://github.com/",
+    },
+    Rule {
+        id: RuleId::Bold,
+        name: "bold",
+        kind: RuleKind::Toggle,
+        markers: &["*", "**"],
+        pattern: r"\*\*?",
+        example: "*bold*",
+    },
+    Rule {
+        id: RuleId::Code,
+        name: "code",
+        kind: RuleKind::Inline,
+        markers: &["`"],
+        pattern: r"`[^`\n]*`",
+        example: "`code`",
+    },
+    Rule {
+        id: RuleId::InlineMath,
+        name: "inline_math",
+        kind: RuleKind::Inline,
+        markers: &["\\("],
+        pattern: r"\\\(([^\\\n]|\\[^)\n])+\\\)",
+        example: r"\(\pi r^2\)",
+    },
+    Rule {
+        id: RuleId::Hyperlink,
+        name: "hyperlink",
+        kind: RuleKind::Inline,
+        markers: &["<", "[", "http://", "https://"],
+        pattern: r"<[^>\n]*>|\[[^\]\n]*\](\([^)\n]*\)|\[[^\]\n]*\])|https?://[^\s<>]+",
+        example: "[title](https://github.com/ \"tooltip\")",
+    },
+    Rule {
+        id: RuleId::Italics,
+        name: "italics",
+        kind: RuleKind::Toggle,
+        markers: &["/"],
+        pattern: r"/",
+        example: "/italics/",
+    },
+    Rule {
+        id: RuleId::Small,
+        name: "small",
+        kind: RuleKind::Toggle,
+        markers: &["$"],
+        pattern: r"\$",
+        example: "$small$",
+    },
+    Rule {
+        id: RuleId::Strikethrough,
+        name: "strikethrough",
+        kind: RuleKind::Toggle,
+        markers: &["~", "~~"],
+        pattern: r"~~?",
+        example: "~strikethrough~",
+    },
+    Rule {
+        id: RuleId::Underline,
+        name: "underline",
+        kind: RuleKind::Toggle,
+        markers: &["_", "__"],
+        pattern: r"__?",
+        example: "_underline_",
+    },
+    Rule {
+        id: RuleId::Reference,
+        name: "reference",
+        kind: RuleKind::Inline,
+        markers: &["@ref("],
+        pattern: r"@ref\([a-z]+:[^\s)]+\)",
+        example: "@ref(fig:arch)",
+    },
+    Rule {
+        id: RuleId::Placeholder,
+        name: "placeholder",
+        kind: RuleKind::Inline,
+        markers: &["{{"],
+        pattern: r"\{\{[^{}\n]+\}\}",
+        example: "{{today}}",
+    },
+    Rule {
+        id: RuleId::Classes,
+        name: "classes",
+        kind: RuleKind::Inline,
+        markers: &["{."],
+        pattern: r"\{\.[^\s}]+(\s+\.[^\s}]+)*\s*\}",
+        example: "{.class-a .class-b}",
+    },
+    Rule {
+        id: RuleId::Escape,
+        name: "escape",
+        kind: RuleKind::Inline,
+        markers: &["\\"],
+        pattern: r"\\[\s\S]",
+        example: r"\*",
+    },
+    Rule {
+        id: RuleId::Text,
+        name: "text",
+        kind: RuleKind::Inline,
+        markers: &[],
+        pattern: r"[\s\S][^*`~_/$^\\<\[{\n]*",
+        example: "plain text",
+    },
+];
+
+/// Generate a [Tree-sitter](https://tree-sitter.github.io/) grammar (`grammar.js`) from
+/// [`RULES`].
+///
+/// Each rule becomes a token with the precedence of the rule. Tree-sitter lexes without knowing
+/// whether it is at the start of a line, so block rules are recognized anywhere a token may
+/// start; this only matters for invalid highlighting of text which looks like a block mark.
+///
+/// ```
+/// let grammar = opmark::grammar::tree_sitter_grammar();
+/// assert!(grammar.contains("  name: 'opmark',"));
+/// assert!(grammar.contains("heading: $ => token(prec("));
+/// // Tree-sitter rejects lazy quantifiers
+/// assert!(!grammar.contains(")*?"));
+/// ```
+pub fn tree_sitter_grammar() -> String {
+    let names = |kinds: &[RuleKind]| {
+        RULES
+            .iter()
+            .filter(|rule| kinds.contains(&rule.kind))
+            .map(|rule| format!("$.{}", rule.name))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let mut grammar = String::new();
+    grammar.push_str("// Generated by opmark::grammar::tree_sitter_grammar, do not edit.\n");
+    grammar.push_str("module.exports = grammar({\n");
+    grammar.push_str("  name: 'opmark',\n\n");
+    grammar.push_str("  extras: $ => [],\n\n");
+    grammar.push_str("  rules: {\n");
+    grammar.push_str("    document: $ => seq(\n");
+    grammar.push_str("      optional($.declaration),\n");
+    grammar.push_str("      optional($.front_matter),\n");
+    grammar.push_str("      repeat(choice($._block, $._inline, /\\n/)),\n");
+    grammar.push_str("    ),\n\n");
+    grammar.push_str(&format!(
+        "    _block: $ => choice({}),\n\n",
+        names(&[RuleKind::Block])
+    ));
+    grammar.push_str(&format!(
+        "    _inline: $ => choice({}),\n",
+        names(&[RuleKind::Inline, RuleKind::Toggle])
+    ));
+    for rule in RULES {
+        grammar.push_str(&format!(
+            "\n    {}: $ => token(prec({}, /{}/)),\n",
+            rule.name,
+            RULES.len() - rule.precedence(),
+            rule.pattern.replace('/', "\\/")
+        ));
+    }
+    grammar.push_str("  },\n");
+    grammar.push_str("});\n");
+    grammar
+}
+
+/// A rule which matched while parsing, see [`ParseTrace`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RuleHit {
+    pub rule: RuleId,
+    /// The bytes of the document the rule consumed.
+    pub span: Range<usize>,
+}
+
+/// The rules which matched while parsing a document, in the order they matched, recorded when
+/// parsing with [`ParserOptions::with_trace`](crate::ParserOptions::with_trace).
+///
+/// A trace shows how a document is read, like which of two overlapping rules wins, and which
+/// rules a set of documents covers. Text which the author meant as markup but which only the
+/// `text` rule matched hints at a construct the parser does not know.
+///
+/// ```
+/// use opmark::{grammar::RuleId, Parser, ParserOptions};
+///
+/// let mut parser = Parser::with_options(
+///     "# Title\n*bold* text".to_owned(),
+///     ParserOptions::new().with_trace(),
+/// );
+/// parser.by_ref().for_each(drop);
+/// let trace = parser.trace().unwrap();
+/// let hits: Vec<(RuleId, &str)> = trace
+///     .hits()
+///     .iter()
+///     .map(|hit| (hit.rule, &"# Title\n*bold* text"[hit.span.clone()]))
+///     .collect();
+/// assert_eq!(
+///     hits,
+///     [
+///         (RuleId::Heading, "# Title"),
+///         (RuleId::Bold, "*"),
+///         (RuleId::Text, "bold"),
+///         (RuleId::Bold, "*"),
+///         (RuleId::Text, " text"),
+///     ]
+/// );
+/// assert_eq!(trace.count(RuleId::Bold), 2);
+/// assert!(trace.unmatched().any(|rule| rule.id == RuleId::Page));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParseTrace {
+    hits: Vec<RuleHit>,
+}
+
+impl ParseTrace {
+    /// The rules which matched, in order.
+    #[inline]
+    pub fn hits(&self) -> &[RuleHit] {
+        &self.hits
+    }
+
+    /// How many times the rule `id` matched.
+    pub fn count(&self, id: RuleId) -> usize {
+        self.hits.iter().filter(|hit| hit.rule == id).count()
+    }
+
+    /// How many times each rule matched, in the order of [`RULES`].
+    pub fn counts(&self) -> Vec<(RuleId, usize)> {
+        RULES
+            .iter()
+            .map(|rule| (rule.id, self.count(rule.id)))
+            .collect()
+    }
+
+    /// The rules which never matched, in the order of [`RULES`].
+    pub fn unmatched(&self) -> impl Iterator<Item = &'static Rule> + '_ {
+        RULES.iter().filter(|rule| self.count(rule.id) == 0)
+    }
+
+    pub(crate) fn record(&mut self, rule: RuleId, span: Range<usize>) {
+        self.hits.push(RuleHit { rule, span });
+    }
+}
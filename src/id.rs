@@ -0,0 +1,416 @@
+//! Stable identifiers for marks and pages.
+//!
+//! An id is a hash of the content of a mark, so it stays the same across edits elsewhere in the
+//! document. Identical marks are told apart by the order they appear in.
+
+use crate::{
+    deck::Page,
+    mark::{
+        AlignHorizontal, CodeStep, Directive, EmbedProvider, Listing, Mark, MathMode, SeparatorDir,
+        SeparatorLine, SeparatorStyle, SeparatorThickness, StyleGallery, StyleImage, StylePage,
+        StyleText, StyleTransition, TransitionEffect, TransitionTarget,
+    },
+};
+use std::{collections::HashMap, fmt};
+
+/// A stable identifier of a mark.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MarkId(pub u64);
+
+impl fmt::Display for MarkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl MarkId {
+    /// The id of `mark` based on its content only, including the content of its children.
+    pub fn of(mark: &Mark) -> Self {
+        let mut hasher = Fnv::new();
+        hasher.mark(mark);
+        MarkId(hasher.0)
+    }
+
+    /// The id of a page of a deck, which is the id of its `Mark::Page` element.
+    ///
+    /// ```
+    /// use opmark::deck::Deck;
+    ///
+    /// let deck = Deck::new("---<hidden>\nx\n---<no-split>\nx".to_owned());
+    /// assert_ne!(deck.pages()[0].id(), deck.pages()[1].id());
+    ///
+    /// let deck = Deck::new("---<duration=30s>\nx\n---<at=00:00:30>\nx".to_owned());
+    /// assert_ne!(deck.pages()[0].id(), deck.pages()[1].id());
+    /// ```
+    pub fn of_page(page: &Page) -> Self {
+        let mut hasher = Fnv::new();
+        hasher.page(&page.transitions, &page.style);
+        MarkId(hasher.0)
+    }
+
+    /// Derive the id of the `occurrence`-th mark which has the same content as this one.
+    fn nth(self, occurrence: usize) -> Self {
+        if occurrence == 0 {
+            return self;
+        }
+        let mut hasher = Fnv(self.0);
+        hasher.int(occurrence as u64);
+        MarkId(hasher.0)
+    }
+}
+
+/// Assign an id to every mark in `marks` and their children.
+///
+/// Each id is paired with the path of the mark: the indices leading from `marks` through the
+/// children of `Mark::Page` and `Mark::Transition` elements. Marks are listed in document order,
+/// parents before their children.
+///
+/// ```
+/// use opmark::{id::mark_ids, mark::Mark, Parser};
+///
+/// let before: Vec<Mark> = Parser::new("a\nb".to_owned()).collect();
+/// let after: Vec<Mark> = Parser::new("new\na\nb".to_owned()).collect();
+/// // the text `a` is the third mark before the edit and the fourth after
+/// assert_eq!(mark_ids(&before)[2].1, mark_ids(&after)[3].1);
+/// ```
+pub fn mark_ids(marks: &[Mark]) -> Vec<(Vec<usize>, MarkId)> {
+    let mut ids = Ids::default();
+    ids.marks(marks, &mut vec![]);
+    ids.ids
+}
+
+/// Assign an id to every page of a deck and their marks, like [`mark_ids`].
+pub(crate) fn page_ids(pages: &[Page]) -> Vec<(Vec<usize>, MarkId)> {
+    let mut ids = Ids::default();
+    for (idx, page) in pages.iter().enumerate() {
+        let mut path = vec![idx];
+        ids.push(&path, MarkId::of_page(page));
+        ids.marks(&page.transitions, &mut path);
+    }
+    ids.ids
+}
+
+#[derive(Default)]
+struct Ids {
+    ids: Vec<(Vec<usize>, MarkId)>,
+    occurrences: HashMap<MarkId, usize>,
+}
+
+impl Ids {
+    fn push(&mut self, path: &[usize], id: MarkId) {
+        let occurrence = self.occurrences.entry(id).or_insert(0);
+        self.ids.push((path.to_vec(), id.nth(*occurrence)));
+        *occurrence += 1;
+    }
+
+    fn marks(&mut self, marks: &[Mark], path: &mut Vec<usize>) {
+        for (idx, mark) in marks.iter().enumerate() {
+            path.push(idx);
+            self.push(path, MarkId::of(mark));
+            if let Mark::Page(children, _) | Mark::Transition(_, children, _) = mark {
+                self.marks(children, path);
+            }
+            path.pop();
+        }
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the hasher of the standard library is stable across releases.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn int(&mut self, n: u64) {
+        self.bytes(&n.to_le_bytes());
+    }
+
+    fn flag(&mut self, b: bool) {
+        self.bytes(&[b as u8]);
+    }
+
+    /// Mark that the optional field `tag` is set, before its value if any.
+    ///
+    /// Optional fields are only hashed when set, so the ids of marks which do not use them stay
+    /// the same as before they were added. Each field has its own tag, so two fields set to the
+    /// same value give different ids.
+    fn tag(&mut self, tag: u8) {
+        self.bytes(&[tag]);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.int(s.len() as u64);
+        self.bytes(s.as_bytes());
+    }
+
+    fn strs(&mut self, strs: &[String]) {
+        self.int(strs.len() as u64);
+        for s in strs {
+            self.str(s);
+        }
+    }
+
+    fn float(&mut self, n: Option<f32>) {
+        match n {
+            Some(n) => self.int(1 << 32 | n.to_bits() as u64),
+            None => self.int(0),
+        }
+    }
+
+    fn marks(&mut self, marks: &[Mark]) {
+        self.int(marks.len() as u64);
+        for mark in marks {
+            self.mark(mark);
+        }
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        match mark {
+            Mark::CodeBlock(code, language) => {
+                self.int(0);
+                self.str(code);
+                self.flag(language.is_some());
+                self.str(language.as_deref().unwrap_or_default());
+            }
+            Mark::CodeWalkthrough(code, language, steps) => {
+                self.int(10);
+                self.str(code);
+                self.flag(language.is_some());
+                self.str(language.as_deref().unwrap_or_default());
+                self.int(steps.len() as u64);
+                for step in steps {
+                    match step {
+                        CodeStep::All => self.int(0),
+                        CodeStep::Lines(first, last) => {
+                            self.int(1);
+                            self.int(*first as u64);
+                            self.int(*last as u64);
+                        }
+                    }
+                }
+            }
+            Mark::Directive(directive) => {
+                self.int(11);
+                self.str(directive.name());
+                self.str(directive.argument());
+                if let Directive::Comment(_, text) = directive {
+                    self.str(text);
+                }
+            }
+            Mark::Embed(url, provider) => {
+                self.int(9);
+                self.str(url);
+                self.embed_provider(provider);
+            }
+            Mark::Gallery(items, style) => {
+                self.int(8);
+                self.int(items.len() as u64);
+                for (src, title) in items {
+                    self.str(src);
+                    self.str(title);
+                }
+                self.style_gallery(style);
+            }
+            Mark::Image(src, title, style) => {
+                self.int(1);
+                self.str(src);
+                self.str(title);
+                self.style_image(style);
+            }
+            Mark::Math(math, mode) => {
+                self.int(14);
+                self.flag(*mode == MathMode::Display);
+                self.str(math);
+            }
+            Mark::NewLine => self.int(2),
+            Mark::Transition(order, marks, style) => {
+                self.int(3);
+                self.int(*order as u64);
+                self.marks(marks);
+                self.style_transition(style);
+            }
+            Mark::TransitionEnd => self.int(4),
+            Mark::Page(marks, style) => self.page(marks, style),
+            Mark::Separator(dir, style) => {
+                self.int(6);
+                self.int(match dir {
+                    SeparatorDir::Horizontal => 0,
+                    SeparatorDir::Vertical => 1,
+                });
+                if *style != SeparatorStyle::default() {
+                    self.tag(b's');
+                    self.style_separator(style);
+                }
+            }
+            Mark::Space(lines) => {
+                self.int(12);
+                self.float(Some(*lines));
+            }
+            Mark::HoldSlide(start, message) => {
+                self.int(13);
+                self.int(start.as_secs());
+                self.str(message);
+            }
+            Mark::Text(text, style) => {
+                self.int(7);
+                self.str(text);
+                self.style_text(style);
+            }
+        }
+    }
+
+    fn page(&mut self, marks: &[Mark], style: &StylePage) {
+        self.int(5);
+        self.marks(marks);
+        self.style_page(style);
+    }
+
+    fn embed_provider(&mut self, provider: &EmbedProvider) {
+        match provider {
+            EmbedProvider::Gist { user, id } => {
+                self.int(0);
+                self.str(user);
+                self.str(id);
+            }
+            EmbedProvider::Tweet { user, id } => {
+                self.int(1);
+                self.str(user);
+                self.str(id);
+            }
+            EmbedProvider::Vimeo { id } => {
+                self.int(2);
+                self.str(id);
+            }
+            EmbedProvider::YouTube { id } => {
+                self.int(3);
+                self.str(id);
+            }
+        }
+    }
+
+    fn style_gallery(&mut self, style: &StyleGallery) {
+        self.strs(&style.classes);
+        self.int(style.columns.map_or(0, |columns| columns as u64 + 1));
+        self.int(style.rows.map_or(0, |rows| rows as u64 + 1));
+    }
+
+    fn style_image(&mut self, style: &StyleImage) {
+        self.int(match style.align_h {
+            AlignHorizontal::Auto => 0,
+            AlignHorizontal::Left => 1,
+            AlignHorizontal::Right => 2,
+            AlignHorizontal::Center => 3,
+        });
+        self.flag(style.autoplay);
+        self.strs(&style.classes);
+        self.float(style.fps);
+        self.str(&style.hyperlink);
+        self.flag(style.looping);
+        self.int(style.pause_at.map_or(0, |frame| frame as u64 + 1));
+        self.float(style.width);
+        self.float(style.height);
+    }
+
+    fn style_separator(&mut self, style: &SeparatorStyle) {
+        self.int(match style.line {
+            SeparatorLine::Solid => 0,
+            SeparatorLine::Dotted => 1,
+            SeparatorLine::Dashed => 2,
+        });
+        self.float(style.length);
+        self.int(match style.thickness {
+            SeparatorThickness::Thin => 0,
+            SeparatorThickness::Normal => 1,
+            SeparatorThickness::Thick => 2,
+        });
+    }
+
+    fn style_page(&mut self, style: &StylePage) {
+        self.strs(&style.classes);
+        self.flag(style.reset_on_enter);
+        if let Some(src) = &style.narration {
+            self.tag(b'n');
+            self.str(src);
+        }
+        if style.justify {
+            self.tag(b'j');
+        }
+        if style.hyphenate {
+            self.tag(b'y');
+        }
+        if style.hidden {
+            self.tag(b'h');
+        }
+        if let Some(duration) = style.duration {
+            self.tag(b'd');
+            self.int(duration.as_secs());
+        }
+        if !style.audiences.is_empty() {
+            self.tag(b'a');
+            self.strs(&style.audiences);
+        }
+        if let Some(at) = style.at {
+            self.tag(b't');
+            self.int(at.as_secs());
+        }
+        if style.no_split {
+            self.tag(b's');
+        }
+    }
+
+    fn style_text(&mut self, style: &StyleText) {
+        self.flag(style.bold);
+        self.strs(&style.classes);
+        self.flag(style.code);
+        self.int(style.heading.to_int() as u64);
+        self.str(&style.hyperlink);
+        if let Some(title) = &style.link_title {
+            self.tag(b't');
+            self.str(title);
+        }
+        self.flag(style.italics);
+        match &style.listing {
+            Listing::None => self.int(0),
+            Listing::Ordered(number, indent) => {
+                self.int(1);
+                self.int(*number as u64);
+                self.int(indent.to_int() as u64);
+            }
+            Listing::Unordered(indent) => {
+                self.int(2);
+                self.int(indent.to_int() as u64);
+            }
+        }
+        self.flag(style.quote);
+        self.flag(style.small);
+        self.flag(style.strikethrough);
+        self.flag(style.underline);
+    }
+
+    fn style_transition(&mut self, style: &StyleTransition) {
+        self.int(match style.effect {
+            TransitionEffect::Appear => 0,
+            TransitionEffect::Disappear => 1,
+            TransitionEffect::Dim => 2,
+            TransitionEffect::Replace => 3,
+        });
+        self.flag(style.looping);
+        match style.target {
+            TransitionTarget::This => self.int(0),
+            TransitionTarget::Prev => self.int(1),
+            TransitionTarget::Order(order) => {
+                self.int(2);
+                self.int(order as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Include the code of code blocks from files, so slides show the current code.
+//!
+//! A code block with a `file=path` attribute takes its code from the file, or from a range of
+//! its lines with `lines=first-last`, starting from 1:
+//! ````text
+//! ```rust file=src/main.rs lines=10-42
+//! ```
+//! ````
+//! The files are read by the [`IncludeResolver`] set with
+//! [`ParserOptions::with_resolver`](crate::ParserOptions::with_resolver). When the file can not
+//! be resolved, the code block keeps its own content. Missing files and lines are reported as
+//! [`Warning`](crate::lint::Warning)s.
+//!
+//! ```
+//! use opmark::{include::IncludeResolver, lint::Warning, mark::Mark, Parser, ParserOptions};
+//!
+//! #[derive(Debug)]
+//! struct Main;
+//!
+//! impl IncludeResolver for Main {
+//!     fn resolve(&self, path: &str) -> Option<String> {
+//!         (path == "main.rs").then(|| "fn main() {\n    run();\n}\n".to_owned())
+//!     }
+//! }
+//!
+//! let s = "```rust file=main.rs lines=2-4\n```".to_owned();
+//! let mut parser = Parser::with_options(s, ParserOptions::new().with_resolver(Main));
+//! let code = parser.by_ref().find_map(|mark| match mark {
+//!     Mark::CodeBlock(code, _) => Some(code),
+//!     _ => None,
+//! });
+//! assert_eq!(code.as_deref(), Some("    run();\n}"));
+//! assert!(matches!(parser.warnings(), [Warning::IncludeOutOfRange { len: 3, .. }]));
+//! ```
+
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Reads the files included by a document.
+pub trait IncludeResolver: fmt::Debug + Send + Sync {
+    /// The content of the file at `path`, as written in the document, or `None` if it can not be
+    /// read.
+    fn resolve(&self, path: &str) -> Option<String>;
+
+    /// The bytes of the file at `path`, like images embedded by renderers.
+    fn resolve_bytes(&self, path: &str) -> Option<Vec<u8>> {
+        self.resolve(path).map(String::into_bytes)
+    }
+}
+
+/// Reads included files from the file system, relative to a root directory.
+///
+/// Paths which are absolute or lead out of the root directory are not resolved.
+#[derive(Clone, Debug)]
+pub struct FsResolver {
+    root: PathBuf,
+}
+
+impl FsResolver {
+    #[inline]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The path of the file at `path` under the root, if it stays under it.
+    fn path(&self, path: &str) -> Option<PathBuf> {
+        let path = Path::new(path);
+        path.components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            .then(|| self.root.join(path))
+    }
+}
+
+impl IncludeResolver for FsResolver {
+    fn resolve(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.path(path)?).ok()
+    }
+
+    fn resolve_bytes(&self, path: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(path)?).ok()
+    }
+}
@@ -0,0 +1,191 @@
+//! Digests of decks, to check that a deck is the one its author published before presenting it.
+//!
+//! The digest of a deck is the last of a chain of SHA-256 hashes: the first one hashes the front
+//! matter, and each next one hashes the previous one with the canonical text of a page, as
+//! written by the serializer with `\n` line endings. So the digest changes with the content of
+//! the deck but not with the way it is written, nor with the platform, and comparing the chains
+//! of two decks finds the first page which differs, see [`Deck::digests`].
+//!
+//! ```
+//! use opmark::deck::Deck;
+//!
+//! let deck = Deck::new("# Intro\n![logo](logo.png)<w50|center>\n---\n# Plan".to_owned());
+//! let reformatted =
+//!     Deck::new("# Intro\n![logo](logo.png)<center|w50>\n---\n# Plan".to_owned());
+//! assert_eq!(deck.digest(), reformatted.digest());
+//!
+//! let edited = Deck::new("# Intro\n![logo](logo.png)<w50|center>\n---\n# Plans".to_owned());
+//! let first_change = deck
+//!     .digests()
+//!     .iter()
+//!     .zip(edited.digests())
+//!     .position(|(a, b)| *a != b);
+//! // after the front matter and the first page
+//! assert_eq!(first_change, Some(2));
+//!
+//! let deck = Deck::new("+++\ntitle = Talk\n+++\n# Intro\n```\nfn main() {\n}\n```".to_owned());
+//! assert_eq!(
+//!     deck.digest().to_string(),
+//!     "6bc30362c21ac1b8d404c2939a47772fbd328ccd061d38e5f3cc1771be1d48ce"
+//! );
+//! ```
+//!
+//! With the `signing` feature, digests can be signed with Ed25519 keys, and the signatures kept
+//! apart from the decks, to check who published a deck.
+
+use crate::{
+    deck::Deck,
+    mark::Mark,
+    parser::LINE_ENDING,
+    serializer::{front_matter, to_source},
+};
+use sha2::{Digest as _, Sha256};
+use std::{error, fmt, str::FromStr};
+
+/// A SHA-256 digest of a deck, written in hexadecimal.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    /// Sign the digest with the Ed25519 secret key `secret_key`.
+    #[cfg(feature = "signing")]
+    pub fn sign(&self, secret_key: &[u8; 32]) -> Signature {
+        use ed25519_dalek::Signer;
+
+        let key = ed25519_dalek::SigningKey::from_bytes(secret_key);
+        Signature(key.sign(&self.0).to_bytes())
+    }
+
+    /// Check that `signature` is the signature of the digest by the secret key of `public_key`.
+    ///
+    /// ```
+    /// use opmark::{
+    ///     deck::Deck,
+    ///     integrity::{public_key, IntegrityError, Signature},
+    /// };
+    ///
+    /// let secret_key = [7; 32];
+    /// let digest = Deck::new("# Quarterly results".to_owned()).digest();
+    /// // the signature is distributed alongside the deck, as text
+    /// let signature = digest.sign(&secret_key).to_string();
+    ///
+    /// let signature: Signature = signature.parse().unwrap();
+    /// assert_eq!(digest.verify(&signature, &public_key(&secret_key)), Ok(()));
+    ///
+    /// let tampered = Deck::new("# Quarterly results!".to_owned()).digest();
+    /// assert_eq!(
+    ///     tampered.verify(&signature, &public_key(&secret_key)),
+    ///     Err(IntegrityError::BadSignature)
+    /// );
+    /// ```
+    #[cfg(feature = "signing")]
+    pub fn verify(
+        &self,
+        signature: &Signature,
+        public_key: &[u8; 32],
+    ) -> Result<(), IntegrityError> {
+        let key = ed25519_dalek::VerifyingKey::from_bytes(public_key)
+            .map_err(|_| IntegrityError::InvalidKey)?;
+        key.verify_strict(&self.0, &ed25519_dalek::Signature::from_bytes(&signature.0))
+            .map_err(|_| IntegrityError::BadSignature)
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, &self.0)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = IntegrityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_hex(s).map(Self).ok_or(IntegrityError::InvalidHex)
+    }
+}
+
+/// A detached Ed25519 signature of a digest, written in hexadecimal.
+#[cfg(feature = "signing")]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Signature(pub [u8; 64]);
+
+#[cfg(feature = "signing")]
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, &self.0)
+    }
+}
+
+#[cfg(feature = "signing")]
+impl FromStr for Signature {
+    type Err = IntegrityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_hex(s).map(Self).ok_or(IntegrityError::InvalidHex)
+    }
+}
+
+/// The Ed25519 public key of the secret key `secret_key`.
+#[cfg(feature = "signing")]
+pub fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    ed25519_dalek::SigningKey::from_bytes(secret_key)
+        .verifying_key()
+        .to_bytes()
+}
+
+/// An error checking the integrity of a deck.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntegrityError {
+    /// A digest or a signature is not written as hexadecimal bytes of the right length.
+    InvalidHex,
+    /// The public key is not a valid Ed25519 key.
+    InvalidKey,
+    /// The signature is not the signature of the digest by the key.
+    BadSignature,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::InvalidHex => write!(f, "invalid hexadecimal digest or signature"),
+            IntegrityError::InvalidKey => write!(f, "invalid public key"),
+            IntegrityError::BadSignature => write!(f, "the signature does not match the deck"),
+        }
+    }
+}
+
+impl error::Error for IntegrityError {}
+
+/// The chain of digests of `deck`: of its front matter, then after each page.
+pub(crate) fn digests(deck: &Deck) -> Vec<Digest> {
+    let front_matter = front_matter(deck.front_matter()).replace(LINE_ENDING, "\n");
+    let mut digest = Digest(Sha256::digest(front_matter).into());
+    let mut digests = vec![digest];
+    for page in deck.pages() {
+        let page = Mark::Page(page.transitions.clone(), page.style.clone());
+        let mut hasher = Sha256::new();
+        hasher.update(digest.0);
+        // line endings, between marks and in their text, are hashed the same on every platform
+        hasher.update(to_source(&[page]).replace(LINE_ENDING, "\n"));
+        digest = Digest(hasher.finalize().into());
+        digests.push(digest);
+    }
+    digests
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+}
+
+fn from_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    let s = s.trim();
+    if s.len() != N * 2 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (idx, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[idx * 2..idx * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
@@ -0,0 +1,545 @@
+//! Laying out text into lines, for renderers drawing pages.
+//!
+//! A paragraph is a list of [`Span`]s, runs of text set in the same font, which [`layout`] breaks
+//! into lines no wider than the width of the [`LayoutOptions`]. Lines are broken between words,
+//! and also within words when they are hyphenated: at soft hyphens (`U+00AD`), and with a
+//! [`Hyphenator`] when the `hyphenate` option is set, like by the `hyphenate` page option. With
+//! the `hyphenation` feature, `Dictionary` hyphenates the words of many languages.
+//!
+//! Justified lines, like with the `justify` page option, spread their words to fill the width,
+//! except the last line of the paragraph.
+//!
+//! Laying out the same text again, like on each edit of a live preview, measures the same words
+//! again: a [`MeasureCache`] keeps their widths.
+//!
+//! ```
+//! use opmark::{
+//!     font::{ApproxMeasure, FontRole, FontStyle},
+//!     layout::{layout, LayoutOptions, Span},
+//! };
+//!
+//! // with ApproxMeasure at size 10, letters are 5 wide and spaces 2.5
+//! let spans = [Span::new("aaaa bb\u{ad}bb cc".to_owned(), FontRole::Body, FontStyle::default())];
+//! let options = LayoutOptions::new().with_width(40.0).with_size(10.0).with_justify(true);
+//! let lines = layout(&spans, &ApproxMeasure, &options);
+//! let text: Vec<Vec<(&str, f32)>> = lines
+//!     .iter()
+//!     .map(|line| line.fragments.iter().map(|f| (f.text.as_str(), f.x)).collect())
+//!     .collect();
+//! assert_eq!(text, [vec![("aaaa", 0.0), ("bb-", 25.0)], vec![("bb", 0.0), ("cc", 12.5)]]);
+//! ```
+
+use crate::{
+    font::{FontMeasure, FontRole, FontStyle},
+    mark::{StylePage, StyleText},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// The soft hyphen, where words may be broken.
+const SOFT_HYPHEN: char = '\u{ad}';
+
+/// Finds where words can be broken with a hyphen.
+pub trait Hyphenator: fmt::Debug + Send + Sync {
+    /// The byte offsets in `word` where it can be broken, in increasing order. `word` has no
+    /// whitespace nor punctuation around it.
+    fn breaks(&self, word: &str) -> Vec<usize>;
+}
+
+/// Hyphenates words with the patterns of a language, from
+/// [hypher](https://docs.rs/hypher).
+///
+/// ```
+/// use opmark::layout::{Dictionary, Hyphenator};
+///
+/// let english = Dictionary::from_iso("en").unwrap();
+/// assert_eq!(english.breaks("extensive"), [2, 5]);
+/// ```
+#[cfg(feature = "hyphenation")]
+#[derive(Clone, Copy, Debug)]
+pub struct Dictionary(hypher::Lang);
+
+#[cfg(feature = "hyphenation")]
+impl Dictionary {
+    /// The dictionary of the language with the ISO 639-1 code `code`, like `en`.
+    pub fn from_iso(code: &str) -> Option<Self> {
+        let code: [u8; 2] = code.to_ascii_lowercase().as_bytes().try_into().ok()?;
+        hypher::Lang::from_iso(code).map(Self)
+    }
+}
+
+#[cfg(feature = "hyphenation")]
+impl Hyphenator for Dictionary {
+    fn breaks(&self, word: &str) -> Vec<usize> {
+        let mut breaks = vec![];
+        let mut at = 0;
+        for syllable in hypher::hyphenate(word, self.0) {
+            if at > 0 {
+                breaks.push(at);
+            }
+            at += syllable.len();
+        }
+        breaks
+    }
+}
+
+/// Options for laying out text.
+#[derive(Clone, Debug)]
+pub struct LayoutOptions {
+    /// The width of lines. Infinite by default, so lines are not broken.
+    pub width: f32,
+    /// The font size of the text. 16 by default.
+    pub size: f32,
+    /// Whether lines are justified.
+    pub justify: bool,
+    /// Whether words are hyphenated with the hyphenator. Soft hyphens are always breaks.
+    pub hyphenate: bool,
+    pub hyphenator: Option<Arc<dyn Hyphenator>>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            width: f32::INFINITY,
+            size: 16.0,
+            justify: false,
+            hyphenate: false,
+            hyphenator: None,
+        }
+    }
+}
+
+impl LayoutOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_hyphenate(mut self, hyphenate: bool) -> Self {
+        self.hyphenate = hyphenate;
+        self
+    }
+
+    #[inline]
+    pub fn with_hyphenator(mut self, hyphenator: impl Hyphenator + 'static) -> Self {
+        self.hyphenator = Some(Arc::new(hyphenator));
+        self
+    }
+
+    #[inline]
+    pub fn with_justify(mut self, justify: bool) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    #[inline]
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    #[inline]
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// The options of a page styled by `style`: justified and hyphenated if the options of the
+    /// theme or the page say so.
+    pub fn for_page(&self, style: &StylePage) -> Self {
+        let mut options = self.clone();
+        options.justify |= style.justify;
+        options.hyphenate |= style.hyphenate;
+        options
+    }
+}
+
+/// Keeps the widths of the texts measured by a [`FontMeasure`], evicting the least recently used
+/// ones beyond its capacity.
+///
+/// Widths are kept by font, style, size and text, so the kerning and shaping of whole words is
+/// kept too.
+///
+/// ```
+/// use opmark::{
+///     font::{ApproxMeasure, FontMeasure, FontRole, FontStyle},
+///     layout::MeasureCache,
+/// };
+///
+/// let cache = MeasureCache::new(ApproxMeasure, 2);
+/// let width = |text| cache.text_width(text, FontRole::Body, FontStyle::default(), 10.0);
+/// assert_eq!(width("one"), 15.0);
+/// assert_eq!(width("two"), 15.0);
+/// assert_eq!(width("one"), 15.0);
+/// // evicts "two", used least recently
+/// assert_eq!(width("three"), 25.0);
+/// assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 3, 2));
+/// ```
+pub struct MeasureCache<M> {
+    measure: M,
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+/// A font, style, size and text measured.
+type CacheKey = (FontRole, FontStyle, u32, String);
+
+#[derive(Default)]
+struct CacheState {
+    /// The widths, with when they were last used.
+    widths: HashMap<CacheKey, (f32, u64)>,
+    /// The keys by when they were last used.
+    uses: BTreeMap<u64, CacheKey>,
+    /// Increased by every use.
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<M: FontMeasure> MeasureCache<M> {
+    /// Keep the widths of at most `capacity` texts measured by `measure`.
+    #[inline]
+    pub fn new(measure: M, capacity: usize) -> Self {
+        Self {
+            measure,
+            capacity,
+            state: Default::default(),
+        }
+    }
+
+    /// The number of widths kept.
+    pub fn len(&self) -> usize {
+        self.state().widths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of widths which were kept when measured.
+    pub fn hits(&self) -> u64 {
+        self.state().hits
+    }
+
+    /// The number of widths which were measured by the inner measure.
+    pub fn misses(&self) -> u64 {
+        self.state().misses
+    }
+
+    /// Forget the widths, like when the fonts change.
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.widths.clear();
+        state.uses.clear();
+    }
+
+    /// The measure the widths are measured by.
+    pub fn inner(&self) -> &M {
+        &self.measure
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // the state stays consistent when a measure panics, as it is not locked while measuring
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<M: FontMeasure> FontMeasure for MeasureCache<M> {
+    fn text_width(&self, text: &str, role: FontRole, style: FontStyle, size: f32) -> f32 {
+        let key = (role, style, size.to_bits(), text.to_owned());
+        {
+            let mut state = self.state();
+            state.clock += 1;
+            let clock = state.clock;
+            if let Some((width, used)) = state.widths.get_mut(&key) {
+                let (width, last_used) = (*width, std::mem::replace(used, clock));
+                let key = state.uses.remove(&last_used).unwrap_or(key);
+                state.uses.insert(clock, key);
+                state.hits += 1;
+                return width;
+            }
+        }
+
+        let width = self.measure.text_width(text, role, style, size);
+        let mut state = self.state();
+        state.misses += 1;
+        if self.capacity == 0 {
+            return width;
+        }
+        state.clock += 1;
+        let clock = state.clock;
+        if let Some((_, used)) = state.widths.insert(key.clone(), (width, clock)) {
+            // measured meanwhile by another thread
+            state.uses.remove(&used);
+        }
+        state.uses.insert(clock, key);
+        while state.widths.len() > self.capacity {
+            let Some((_, oldest)) = state.uses.pop_first() else {
+                break;
+            };
+            state.widths.remove(&oldest);
+        }
+        width
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for MeasureCache<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeasureCache")
+            .field("measure", &self.measure)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A run of text of a paragraph set in the same font.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub role: FontRole,
+    pub style: FontStyle,
+}
+
+impl Span {
+    #[inline]
+    pub fn new(text: String, role: FontRole, style: FontStyle) -> Self {
+        Self { text, role, style }
+    }
+
+    /// A span of `text` in the font of `style`.
+    pub fn of(text: String, style: &StyleText) -> Self {
+        Self::new(text, FontRole::of(style), FontStyle::of(style))
+    }
+}
+
+/// A piece of a span on a line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fragment {
+    /// The index of the span.
+    pub span: usize,
+    /// The text of the fragment, ending with a hyphen if a word is broken after it.
+    pub text: String,
+    /// Where the fragment starts on the line.
+    pub x: f32,
+    pub width: f32,
+    /// Whether a space separates the fragment from the one before.
+    pub space_before: bool,
+}
+
+/// A line of a paragraph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Line {
+    pub fragments: Vec<Fragment>,
+    /// The width of the content of the line, or of the line if it is justified.
+    pub width: f32,
+}
+
+/// A word, or the part of a word of a span.
+#[derive(Clone)]
+struct Piece {
+    span: usize,
+    /// The text, without soft hyphens.
+    text: String,
+    /// Where the text can be broken with a hyphen.
+    breaks: Vec<usize>,
+}
+
+impl Piece {
+    fn new(span: usize, text: &str, options: &LayoutOptions) -> Self {
+        let mut breaks = vec![];
+        let mut clean = String::with_capacity(text.len());
+        for c in text.chars() {
+            if c == SOFT_HYPHEN {
+                breaks.push(clean.len());
+            } else {
+                clean.push(c);
+            }
+        }
+        if let Some(hyphenator) = options.hyphenator.as_ref().filter(|_| options.hyphenate) {
+            let start = clean.find(char::is_alphabetic).unwrap_or(clean.len());
+            let end = clean.rfind(char::is_alphabetic).map_or(start, |end| {
+                end + clean[end..].chars().next().map_or(0, char::len_utf8)
+            });
+            if start < end {
+                breaks.extend(
+                    hyphenator
+                        .breaks(&clean[start..end])
+                        .into_iter()
+                        .map(|at| start + at),
+                );
+            }
+        }
+        breaks.sort_unstable();
+        breaks.dedup();
+        breaks.retain(|&at| at > 0 && at < clean.len() && clean.is_char_boundary(at));
+        Self {
+            span,
+            text: clean,
+            breaks,
+        }
+    }
+}
+
+/// Break `spans` into lines, measured by `measure`.
+///
+/// A word wider than a line which can not be hyphenated overflows its line.
+pub fn layout(spans: &[Span], measure: &dyn FontMeasure, options: &LayoutOptions) -> Vec<Line> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("layout", spans = spans.len()).entered();
+
+    let width = |span: usize, text: &str| {
+        let span = &spans[span];
+        measure.text_width(text, span.role, span.style, options.size)
+    };
+
+    let mut lines = vec![];
+    let mut line = Line::default();
+    for mut word in words(spans, options) {
+        loop {
+            let space = if line.fragments.is_empty() {
+                0.0
+            } else {
+                width(word[0].span, " ")
+            };
+            let available = options.width - line.width - space;
+            let word_width: f32 = word
+                .iter()
+                .map(|piece| width(piece.span, &piece.text))
+                .sum();
+            if word_width <= available {
+                push_word(&mut line, word, space, width);
+                break;
+            }
+            if let Some((head, tail)) = hyphenate(&word, available, width) {
+                push_word(&mut line, head, space, width);
+                lines.push(justify(std::mem::take(&mut line), options));
+                word = tail;
+                continue;
+            }
+            if line.fragments.is_empty() {
+                push_word(&mut line, word, space, width);
+                break;
+            }
+            lines.push(justify(std::mem::take(&mut line), options));
+        }
+    }
+    if !line.fragments.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Whether a line can be broken at `c`. Non-breaking spaces are not breaks.
+fn is_break(c: char) -> bool {
+    c.is_whitespace() && c != '\u{a0}'
+}
+
+/// The words of `spans`, as the pieces of the spans they are made of.
+fn words(spans: &[Span], options: &LayoutOptions) -> Vec<Vec<Piece>> {
+    let mut words: Vec<Vec<Piece>> = vec![];
+    // whether the next piece continues the last word, when no space is between spans
+    let mut glued = false;
+    for (idx, span) in spans.iter().enumerate() {
+        let mut rest = span.text.as_str();
+        loop {
+            let trimmed = rest.trim_start_matches(is_break);
+            if trimmed.len() != rest.len() {
+                glued = false;
+            }
+            if trimmed.is_empty() {
+                break;
+            }
+            let end = trimmed.find(is_break).unwrap_or(trimmed.len());
+            let piece = Piece::new(idx, &trimmed[..end], options);
+            match words.last_mut() {
+                Some(word) if glued => word.push(piece),
+                _ => words.push(vec![piece]),
+            }
+            glued = true;
+            rest = &trimmed[end..];
+        }
+    }
+    words
+}
+
+/// Append the pieces of `word` to `line`, after a space of `space`.
+fn push_word(line: &mut Line, word: Vec<Piece>, space: f32, width: impl Fn(usize, &str) -> f32) {
+    let mut space_before = !line.fragments.is_empty();
+    line.width += space;
+    for piece in word {
+        let piece_width = width(piece.span, &piece.text);
+        line.fragments.push(Fragment {
+            span: piece.span,
+            text: piece.text,
+            x: line.width,
+            width: piece_width,
+            space_before,
+        });
+        line.width += piece_width;
+        space_before = false;
+    }
+}
+
+/// Split `word` at the last break where the part before it, with a hyphen, is at most
+/// `available` wide.
+fn hyphenate(
+    word: &[Piece],
+    available: f32,
+    width: impl Fn(usize, &str) -> f32,
+) -> Option<(Vec<Piece>, Vec<Piece>)> {
+    let mut before = 0.0;
+    let mut best = None;
+    for (idx, piece) in word.iter().enumerate() {
+        for &at in &piece.breaks {
+            let head = before + width(piece.span, &piece.text[..at]) + width(piece.span, "-");
+            if head <= available {
+                best = Some((idx, at));
+            }
+        }
+        before += width(piece.span, &piece.text);
+    }
+
+    let (idx, at) = best?;
+    let piece = &word[idx];
+    let mut head = word[..idx].to_vec();
+    head.push(Piece {
+        span: piece.span,
+        text: format!("{}-", &piece.text[..at]),
+        breaks: vec![],
+    });
+    let mut tail = vec![Piece {
+        span: piece.span,
+        text: piece.text[at..].to_owned(),
+        breaks: piece
+            .breaks
+            .iter()
+            .filter(|&&b| b > at)
+            .map(|b| b - at)
+            .collect(),
+    }];
+    tail.extend_from_slice(&word[idx + 1..]);
+    Some((head, tail))
+}
+
+/// Spread the words of `line`, which is not the last line of its paragraph, to fill the width
+/// if the options justify lines.
+fn justify(mut line: Line, options: &LayoutOptions) -> Line {
+    let gaps = line.fragments.iter().filter(|f| f.space_before).count();
+    if !options.justify || gaps == 0 || line.width >= options.width {
+        return line;
+    }
+    let extra = (options.width - line.width) / gaps as f32;
+    let mut shift = 0.0;
+    for fragment in &mut line.fragments {
+        if fragment.space_before {
+            shift += extra;
+        }
+        fragment.x += shift;
+    }
+    line.width = options.width;
+    line
+}
@@ -39,10 +39,39 @@
 //!
 //! ### Hyperlinks
 //! [Github](https://github.com/)
+//! [OpMark][repo]
+//!
+//! [repo]: https://github.com/fralonra/opmark
 //! ```
 //!
+//! ## Cargo features
+//! - `control`: drive decks from stage control hardware with OSC or MIDI messages, see
+//!   `control`.
+//! - `diagrams`: render draw.io and Excalidraw diagrams shown by images to SVG, see `diagram`.
+//! - `epub`: export decks to e-books, see `render::epub`.
+//! - `gif`: export single pages to animated GIFs cycling through their steps, see
+//!   `render::gif`.
+//! - `hyphenation`: hyphenate the words of many languages when laying out text, see
+//!   `layout::Dictionary`.
+//! - `ipynb`: convert Jupyter notebooks into decks, see `convert::from_ipynb`.
+//! - `pandoc`: convert decks from and into the JSON representation of the Pandoc AST, see
+//!   `convert::from_pandoc_json` and `convert::to_pandoc_json`.
+//! - `policy`: validate decks against rules of organizations written in TOML, see `policy`.
+//! - `pptx`: export decks to PowerPoint slides, see `render::pptx`.
+//! - `remote`: load decks over HTTP, caching them by ETag, see `remote`.
+//! - `remote-async`: load decks over HTTP asynchronously, in a Tokio runtime, see `remote`.
+//! - `shaping`: measure text by shaping it with fonts and falling back along font chains, see
+//!   `font::ShapingMeasure`.
+//! - `signing`: sign the digests of decks and verify their detached signatures, see
+//!   `integrity`.
+//! - `test-util`: helpers to snapshot the marks of fixture decks in regression tests, see
+//!   `test_util`.
+//! - `tracing`: emit [`tracing`](https://docs.rs/tracing) spans and events while parsing and
+//!   serializing, to profile slow decks and debug parsing.
+//! - `video`: export decks to videos with their narration, see `render::video`.
+//!
 //! ## Using the parser
-//! ```
+//! ```no_run
 //! use opmark::Parser;
 //! use std::{
 //!     fs::read_to_string,
@@ -58,7 +87,50 @@
 //!     }
 //! }
 //! ```
+pub mod annotations;
+pub mod audience;
+pub mod audio;
+#[cfg(feature = "control")]
+pub mod control;
+pub mod convert;
+pub mod crossref;
+pub mod debug;
+pub mod deck;
+pub mod diagram;
+pub mod document;
+pub mod embed;
+pub mod font;
+pub mod front_matter;
+pub mod grammar;
+pub mod id;
+pub mod include;
+pub mod integrity;
+pub mod layout;
+pub mod link;
+pub mod lint;
 pub mod mark;
+pub mod media;
+pub mod merge;
 mod parser;
+pub mod placeholder;
+pub mod playlist;
+#[cfg(feature = "policy")]
+pub mod policy;
+pub mod readability;
+#[cfg(any(feature = "remote", feature = "remote-async"))]
+pub mod remote;
+pub mod render;
+pub mod review;
+pub mod scanner;
+pub mod schedule;
+pub mod serializer;
+pub mod stats;
+pub mod sync;
+pub mod teleprompter;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod text;
 
-pub use crate::parser::Parser;
+pub use crate::parser::{
+    Dialect, FragmentContext, HeadingOverflow, LineEnding, Parser, ParserOptions,
+};
@@ -0,0 +1,267 @@
+//! Classify the targets of links, so exporters and policies can treat each kind of link its own
+//! way.
+//!
+//! ```
+//! use opmark::link::LinkTarget;
+//!
+//! assert_eq!(
+//!     LinkTarget::parse("mailto:team@example.com?subject=Slides"),
+//!     LinkTarget::Mail("team@example.com".to_owned())
+//! );
+//! assert_eq!(LinkTarget::parse("tel:+1-555-0100").scheme(), Some("tel"));
+//! assert_eq!(
+//!     LinkTarget::parse("file:///home/me/notes.txt"),
+//!     LinkTarget::File("/home/me/notes.txt".to_owned())
+//! );
+//! assert_eq!(LinkTarget::parse("#intro").scheme(), None);
+//! ```
+//!
+//! Exporters write links through [`LinkHooks`], which decide per kind of target what a link
+//! points to, or that its text is written without a link. A [`LinkPolicy`] then has the last
+//! word on every link exporters write and renderers follow, so applications can enforce how
+//! links are handled in one place.
+
+use std::fmt;
+
+/// What a link points to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum LinkTarget {
+    /// A `http:` or `https:` link, with the URL.
+    Web(String),
+    /// A `mailto:` link, with the address.
+    Mail(String),
+    /// A `tel:` link, with the phone number.
+    Phone(String),
+    /// A `file:` link or a Windows path, with the path.
+    File(String),
+    /// A link without a scheme, relative to the document, like `#intro` or `other.html`.
+    Relative(String),
+    /// A link with another scheme, with the scheme in lowercase.
+    Other(String),
+}
+
+impl LinkTarget {
+    /// Classify the link `url`.
+    pub fn parse(url: &str) -> Self {
+        let Some((scheme, rest)) = split_scheme(url) else {
+            return Self::Relative(url.to_owned());
+        };
+        // a single letter is the drive of a Windows path, like `C:\`
+        if scheme.len() == 1 {
+            return Self::File(url.to_owned());
+        }
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" | "https" => Self::Web(url.to_owned()),
+            "mailto" => Self::Mail(rest.split('?').next().unwrap_or_default().to_owned()),
+            "tel" => Self::Phone(rest.to_owned()),
+            "file" => Self::File(file_path(rest)),
+            scheme => Self::Other(scheme.to_owned()),
+        }
+    }
+
+    /// The scheme of the link, in lowercase, `None` for relative links.
+    pub fn scheme(&self) -> Option<&str> {
+        match self {
+            Self::Web(url) => match url.as_bytes().get(4) {
+                Some(b's' | b'S') => Some("https"),
+                _ => Some("http"),
+            },
+            Self::Mail(_) => Some("mailto"),
+            Self::Phone(_) => Some("tel"),
+            Self::File(_) => Some("file"),
+            Self::Relative(_) => None,
+            Self::Other(scheme) => Some(scheme),
+        }
+    }
+}
+
+/// How exporters write the links of each kind of target.
+///
+/// Each hook receives the parts of the target and the URL of the link, and returns the URL to
+/// link to, or `None` to write the text of the link without a link. All hooks keep links as
+/// they are by default.
+///
+/// ```
+/// use opmark::{
+///     link::LinkHooks,
+///     mark::Mark,
+///     render::html::to_html_with_links,
+///     Parser,
+/// };
+///
+/// // a shared deck does not link to the files of its author, and dials with a call service
+/// #[derive(Debug)]
+/// struct Shared;
+///
+/// impl LinkHooks for Shared {
+///     fn phone(&self, number: &str, _url: &str) -> Option<String> {
+///         Some(format!("https://call.example.com/{}", number))
+///     }
+///
+///     fn file(&self, _path: &str, _url: &str) -> Option<String> {
+///         None
+///     }
+/// }
+///
+/// let marks: Vec<Mark> =
+///     Parser::new("[Call](tel:5550100) [notes](file:///notes.txt)".to_owned()).collect();
+/// assert!(to_html_with_links(&marks, &Shared).contains(
+///     "<a href=\"https://call.example.com/5550100\">Call</a> notes"
+/// ));
+/// ```
+pub trait LinkHooks: fmt::Debug + Send + Sync {
+    fn web(&self, url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    fn mail(&self, _address: &str, url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    fn phone(&self, _number: &str, url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    fn file(&self, _path: &str, url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    fn relative(&self, url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    fn other(&self, _scheme: &str, url: &str) -> Option<String> {
+        Some(url.to_owned())
+    }
+
+    /// The URL to link to for a link to `url`, calling the hook of its target.
+    fn href(&self, url: &str) -> Option<String> {
+        match LinkTarget::parse(url) {
+            LinkTarget::Web(_) => self.web(url),
+            LinkTarget::Mail(address) => self.mail(&address, url),
+            LinkTarget::Phone(number) => self.phone(&number, url),
+            LinkTarget::File(path) => self.file(&path, url),
+            LinkTarget::Relative(_) => self.relative(url),
+            LinkTarget::Other(scheme) => self.other(&scheme, url),
+        }
+    }
+}
+
+/// The hooks keeping all links as they are.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeepLinks;
+
+impl LinkHooks for KeepLinks {}
+
+/// What a [`LinkPolicy`] decides for a link.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LinkDecision {
+    /// The link is kept.
+    Allow,
+    /// The link is dropped: exporters write its text without a link, and renderers do not follow
+    /// it.
+    Deny,
+    /// The link points to another URL instead.
+    Transform(String),
+}
+
+/// Decides which links exporters write and renderers follow, like to block some hosts or to
+/// rewrite links through a proxy.
+///
+/// ```
+/// use opmark::{
+///     link::{LinkDecision, LinkPolicy, LinkTarget},
+///     mark::Mark,
+///     render::html::{to_html_document, HtmlOptions},
+///     Parser,
+/// };
+///
+/// // web links go through the proxy of the company, other links are dropped
+/// #[derive(Debug)]
+/// struct Proxy;
+///
+/// impl LinkPolicy for Proxy {
+///     fn decide(&self, url: &str, target: &LinkTarget) -> LinkDecision {
+///         match target {
+///             LinkTarget::Web(_) => {
+///                 LinkDecision::Transform(format!("https://proxy.example.com/?url={}", url))
+///             }
+///             LinkTarget::Relative(_) => LinkDecision::Allow,
+///             _ => LinkDecision::Deny,
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Proxy.resolve("mailto:me@example.com"), None);
+/// let marks: Vec<Mark> = Parser::new("<https://a.io> [me](mailto:me@example.com)".to_owned())
+///     .collect();
+/// let html = to_html_document(&marks, &HtmlOptions::new().with_link_policy(Proxy));
+/// assert!(html.contains(
+///     "<a href=\"https://proxy.example.com/?url=https://a.io\">https://a.io</a> me</p>"
+/// ));
+/// ```
+pub trait LinkPolicy: fmt::Debug + Send + Sync {
+    /// Decide what happens to the link to `url`, classified as `target`.
+    fn decide(&self, url: &str, target: &LinkTarget) -> LinkDecision;
+
+    /// The URL to link to, or to follow, for a link to `url`, `None` if it is denied.
+    fn resolve(&self, url: &str) -> Option<String> {
+        match self.decide(url, &LinkTarget::parse(url)) {
+            LinkDecision::Allow => Some(url.to_owned()),
+            LinkDecision::Deny => None,
+            LinkDecision::Transform(url) => Some(url),
+        }
+    }
+}
+
+/// The policy allowing all links.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllowLinks;
+
+impl LinkPolicy for AllowLinks {
+    fn decide(&self, _url: &str, _target: &LinkTarget) -> LinkDecision {
+        LinkDecision::Allow
+    }
+}
+
+/// Link hooks whose links are then decided by a policy.
+#[derive(Clone, Copy, Debug)]
+pub struct Guarded<'a> {
+    pub hooks: &'a dyn LinkHooks,
+    pub policy: &'a dyn LinkPolicy,
+}
+
+impl LinkHooks for Guarded<'_> {
+    fn href(&self, url: &str) -> Option<String> {
+        self.hooks
+            .href(url)
+            .and_then(|href| self.policy.resolve(&href))
+    }
+}
+
+/// The scheme of `url` and the rest of it, if it has a scheme.
+fn split_scheme(url: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let is_scheme = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    is_scheme.then_some((scheme, rest))
+}
+
+/// The path of a `file:` URL, after the scheme.
+fn file_path(rest: &str) -> String {
+    let Some(rest) = rest.strip_prefix("//") else {
+        return rest.to_owned();
+    };
+    let path = match rest.strip_prefix("localhost") {
+        Some(path) if path.starts_with('/') => path,
+        // the path of a network share keeps its host
+        _ if !rest.starts_with('/') => return format!("//{}", rest),
+        _ => rest,
+    };
+    // `/C:/dir` is the Windows path `C:/dir`
+    match path.strip_prefix('/') {
+        Some(windows) if windows.as_bytes().get(1) == Some(&b':') => windows.to_owned(),
+        _ => path.to_owned(),
+    }
+}
@@ -0,0 +1,111 @@
+//! Warnings about documents which parse, but likely not as their authors intended.
+
+use crate::{
+    grammar::{Rule, RuleId},
+    parser::{format_duration, Dialect},
+};
+use std::{fmt, time::Duration};
+
+/// A warning about a document, collected by the parser, see
+/// [`Parser::warnings`](crate::Parser::warnings).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// The file included by a code block could not be resolved.
+    IncludeNotFound { path: String },
+    /// The range of lines included by a code block goes past the end of the file, which has
+    /// `len` lines.
+    IncludeOutOfRange {
+        path: String,
+        lines: (usize, usize),
+        len: usize,
+    },
+    /// The diagram shown by an image could not be rendered, for the given reason.
+    DiagramNotRendered { path: String, reason: String },
+    /// A reference link, like `[title][label]`, uses a label without definition.
+    UndefinedLinkLabel { label: String },
+    /// A line of the front matter sets no key, see [`front_matter`](crate::front_matter).
+    InvalidFrontMatter { line: String },
+    /// The durations planned for the shown pages add up to `budget`, more than the `duration` of
+    /// the talk set in the front matter.
+    OverBudget {
+        budget: Duration,
+        duration: Duration,
+    },
+    /// A placeholder has no value, see [`placeholder`](crate::placeholder).
+    UnknownPlaceholder { name: String },
+    /// A placeholder reads an environment variable which is not allowed, see
+    /// [`ParserOptions::env_vars`](crate::ParserOptions::env_vars).
+    EnvVarNotAllowed { name: String },
+    /// A cross-reference, like `@ref(fig:arch)`, uses a label which is not in the document, see
+    /// [`crossref`](crate::crossref).
+    UndefinedReference { label: String },
+    /// A heading is deeper than the maximum level `max`, see
+    /// [`ParserOptions::max_heading_level`](crate::ParserOptions::max_heading_level).
+    HeadingTooDeep { level: u8, max: u8 },
+    /// A block starting with the given `line` does not parse, so it was skipped up to the next
+    /// empty line or page, see [`Parser`](crate::Parser#malformed-blocks).
+    MalformedBlock { rule: RuleId, line: String },
+    /// The document declares a dialect newer than the parser supports, so it is parsed with
+    /// [`Dialect::CURRENT`].
+    UnsupportedDialect { dialect: Dialect },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::IncludeNotFound { path } => write!(f, "included file {} not found", path),
+            Warning::IncludeOutOfRange { path, lines, len } => write!(
+                f,
+                "lines {}-{} of {} do not exist, it has {} lines",
+                lines.0, lines.1, path, len
+            ),
+            Warning::DiagramNotRendered { path, reason } => {
+                write!(f, "diagram {} not rendered: {}", path, reason)
+            }
+            Warning::UndefinedLinkLabel { label } => {
+                write!(f, "link label [{}] is not defined", label)
+            }
+            Warning::InvalidFrontMatter { line } => {
+                write!(f, "front matter line `{}` sets no key", line)
+            }
+            Warning::OverBudget { budget, duration } => write!(
+                f,
+                "pages are planned to take {}, more than the {} of the talk",
+                format_duration(*budget),
+                format_duration(*duration)
+            ),
+            Warning::UnknownPlaceholder { name } => {
+                write!(f, "placeholder {{{{{}}}}} has no value", name)
+            }
+            Warning::EnvVarNotAllowed { name } => {
+                write!(
+                    f,
+                    "environment variable {} is not allowed in placeholders",
+                    name
+                )
+            }
+            Warning::UndefinedReference { label } => {
+                write!(f, "reference to undefined label {}", label)
+            }
+            Warning::HeadingTooDeep { level, max } => {
+                write!(
+                    f,
+                    "heading level {} is deeper than the maximum {}",
+                    level, max
+                )
+            }
+            Warning::MalformedBlock { rule, line } => write!(
+                f,
+                "malformed {} `{}` skipped up to the next empty line",
+                Rule::get(*rule).name,
+                line
+            ),
+            Warning::UnsupportedDialect { dialect } => write!(
+                f,
+                "dialect {} is not supported, parsed as {}",
+                dialect,
+                Dialect::CURRENT
+            ),
+        }
+    }
+}
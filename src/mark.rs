@@ -1,35 +1,40 @@
 //! Defines the marks used in OpMark.
 
+use std::time::Duration;
+
 /// How the element aligns. Currently work for `Image` only.
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 pub enum AlignHorizontal {
+    #[default]
     Auto,
     Left,
     Right,
     Center,
 }
 
-impl Default for AlignHorizontal {
-    fn default() -> Self {
-        AlignHorizontal::Auto
-    }
-}
-
 /// The heading level of the text element.
-#[derive(Clone, Debug)]
+///
+/// Levels deeper than 6 are `Deeper`, with the level. Create levels with `Heading::from`, which
+/// gives the named variant of the levels up to 6, so equal levels compare equal:
+/// ```
+/// use opmark::mark::Heading;
+///
+/// assert_eq!(Heading::from(6), Heading::H6);
+/// assert_eq!(Heading::from(8), Heading::Deeper(8));
+/// assert_eq!(Heading::from(8).to_int(), 8);
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Default)]
 pub enum Heading {
+    #[default]
     None,
     H1,
     H2,
     H3,
     H4,
     H5,
-}
-
-impl Default for Heading {
-    fn default() -> Self {
-        Heading::None
-    }
+    H6,
+    /// A level deeper than 6.
+    Deeper(u8),
 }
 
 impl From<u8> for Heading {
@@ -41,7 +46,9 @@ impl From<u8> for Heading {
             2 => Heading::H2,
             3 => Heading::H3,
             4 => Heading::H4,
-            _ => Heading::H5,
+            5 => Heading::H5,
+            6 => Heading::H6,
+            n => Heading::Deeper(n),
         }
     }
 }
@@ -56,25 +63,34 @@ impl Heading {
             Heading::H3 => 3,
             Heading::H4 => 4,
             Heading::H5 => 5,
+            Heading::H6 => 6,
+            Heading::Deeper(n) => n,
         }
     }
 }
 
 /// The intent level of the text element.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+///
+/// Levels deeper than 5 are `Deeper`, with the level. Create levels with `IndentLevel::from`,
+/// which gives the named variant of the levels up to 5, so equal levels compare equal:
+/// ```
+/// use opmark::mark::IndentLevel;
+///
+/// assert_eq!(IndentLevel::from(3), IndentLevel::I3);
+/// assert_eq!(IndentLevel::from(7), IndentLevel::Deeper(7));
+/// assert_eq!(IndentLevel::from(7).to_int(), 7);
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Default)]
 pub enum IndentLevel {
+    #[default]
     None,
     I1,
     I2,
     I3,
     I4,
     I5,
-}
-
-impl Default for IndentLevel {
-    fn default() -> Self {
-        IndentLevel::None
-    }
+    /// A level deeper than 5.
+    Deeper(u8),
 }
 
 impl From<u8> for IndentLevel {
@@ -86,7 +102,8 @@ impl From<u8> for IndentLevel {
             2 => IndentLevel::I2,
             3 => IndentLevel::I3,
             4 => IndentLevel::I4,
-            _ => IndentLevel::I5,
+            5 => IndentLevel::I5,
+            n => IndentLevel::Deeper(n),
         }
     }
 }
@@ -101,14 +118,16 @@ impl IndentLevel {
             IndentLevel::I3 => 3,
             IndentLevel::I4 => 4,
             IndentLevel::I5 => 5,
+            IndentLevel::Deeper(n) => n,
         }
     }
 }
 
 /// Whether the text element is within a list.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum Listing {
     /// Text is not in a list.
+    #[default]
     None,
     /// Text is in an ordered list.
     Ordered(u8, IndentLevel),
@@ -116,42 +135,125 @@ pub enum Listing {
     Unordered(IndentLevel),
 }
 
-impl Default for Listing {
-    fn default() -> Self {
-        Listing::None
-    }
-}
-
 /// The marks used in OpMark.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Mark {
     /// A code block element:
     /// ````text
     /// ```language
     /// code
     /// ```
+    /// ````
+    /// The code may be included from a file instead, see [`include`](crate::include).
     CodeBlock(String, Option<String>),
-    /// An image element:
+    /// A code block element whose highlighted lines change step by step:
+    /// ````text
+    /// ```rust steps=[1-3,4-6,all]
+    /// code
+    /// ```
+    /// ````
+    /// Each step highlights a line or a range of lines, starting from 1, or `all` lines. The
+    /// first step shows up with the transition the block is in, and each following step takes
+    /// one more interaction. Transitions after the block without an explicit order show up
+    /// after its last step.
+    ///
+    /// The fields are the code, the language and the steps.
+    CodeWalkthrough(String, Option<String>, Vec<CodeStep>),
+    /// A directive, configuring the deck rather than showing content:
+    /// ```text
+    /// @bookmark(demo)
+    /// ```
+    /// A directive takes its own line, see [`Directive`] for the directives.
+    Directive(Directive),
+    /// An element embedding a resource of an online service, like a video:
+    /// ```text
+    /// https://www.youtube.com/watch?v=dQw4w9WgXcQ
+    /// <https://gist.github.com/user/0123456789abcdef>
+    /// ```
+    /// A link on its own line is embedded if the service is recognized, see `EmbedProvider`. This
+    /// is opt-in, see `ParserOptions::with_embeds`. Otherwise the link is a hyperlink.
+    ///
+    /// The first field is the link.
+    Embed(String, EmbedProvider),
+    /// A gallery element, showing images in a grid:
+    /// ```text
+    /// !!!<options>
+    /// ![title](src)
+    /// ![title](src)
+    /// !!!
+    /// ```
+    /// Each item is the source and the title of an image. Lines which are not images are
+    /// ignored.
+    ///
+    /// You can specify the layout of the grid in options:
+    /// ```text
+    /// // A grid with 3 columns.
+    /// !!!<columns=3>
+    /// // A grid with 2 rows.
+    /// !!!<rows=2>
+    /// // A gallery with classes, separated by spaces.
+    /// !!!<class=photos wide>
     /// ```
+    /// You can combine options together, and each option is separated by `|`.
+    ///
+    /// `options` is optional.
+    Gallery(Vec<(String, String)>, StyleGallery),
+    /// A hold page element, counting down to the start of a talk:
+    /// ```text
+    /// @holdslide(start=14:00, message="Starting soon")
+    /// ```
+    /// Renderers show the message with the time left until the start, a time of day in local
+    /// time, like the `at` page option. The message is optional, and is quoted to hold commas,
+    /// with `\"` and `\\` as escapes.
+    ///
+    /// The fields are the start, since midnight, and the message.
+    HoldSlide(Duration, String),
+    /// An image element:
+    /// ```text
     /// ![title](src)<options>
     /// ```
     /// You can specify the size and the alignment of the image in options:
-    /// ```
+    /// ```text
     /// // Image with width of 50.
     /// ![test](test.png)<w50>
     /// // Image with height of 50.
     /// ![test](test.png)<h50>
     /// // Image with center alignment. Available values: auto, left, right, center.
     /// ![test](test.png)<center>
+    /// // Image with classes, separated by spaces.
+    /// ![test](test.png)<class=framed wide>
+    /// ```
+    /// Animated images, like GIF or APNG, can be controlled with:
+    /// ```text
+    /// // Start playing as soon as the image shows up, and play again from the start.
+    /// ![demo](demo.gif)<autoplay|loop>
+    /// // Play at 12 frames per second.
+    /// ![demo](demo.gif)<fps=12>
+    /// // Stop playing at the frame 30, counting from 0.
+    /// ![demo](demo.gif)<pause-at=30>
     /// ```
     /// You can combine options together, and each option is separated by `|`.
     ///
     /// `options` is optional.
     Image(String, String, StyleImage),
+    /// A math element, in TeX, for renderers like KaTeX or MathJax. Inline math is within a line:
+    /// ```text
+    /// The area is \(\pi r^2\).
+    /// ```
+    /// Display math is a block, on its own line or on the lines between two `$$` lines:
+    /// ```text
+    /// $$e^{i\pi} + 1 = 0$$
+    /// $$
+    /// \sum_{k=1}^n k = \frac{n(n+1)}{2}
+    /// $$
+    /// ```
+    /// The math is kept as written. HTML output wraps it in the delimiters the auto-render of
+    /// KaTeX and MathJax look for by default, `\(..\)` and `\[..\]`.
+    Math(String, MathMode),
     /// A new line element.
     NewLine,
     /// A transition element:
-    /// ```
+    /// ```text
     /// ---t
     /// ```
     /// A transition is a group of elements which show up together after interaction (usually mouse click or keyboard input).
@@ -159,7 +261,7 @@ pub enum Mark {
     /// A transition starts at a transition mark (`---t`), and ends at either the next transition mark, next page mark, or a transition end mark.
     ///
     /// Number can be appended to a transition mark, indicates that the order of the appearence of this transition group (noted that the index starts from 0), otherwise the transitions show up from top to bottom:
-    /// ```
+    /// ```text
     /// ---t
     /// This line will show up after the first interaction.
     /// ---t3
@@ -167,33 +269,73 @@ pub enum Mark {
     /// ---t1
     /// This line will show up after the second interaction.
     /// ```
-    Transition(usize, Vec<Mark>),
-    /// An element marks where the previous transition ends:
+    ///
+    /// You can specify options for the transition, separated by `|`:
+    /// ```text
+    /// // Replay from this transition, again and again, when stepping forward past the end of the
+    /// // page.
+    /// ---t<loop>
+    /// // Hide this transition, instead of showing it, after the third interaction.
+    /// ---t3<disappear>
+    /// // Show this transition and dim the previous one after the second interaction.
+    /// ---t2<dim:prev>
     /// ```
+    /// Available effects are `appear`, `disappear`, `dim` and `replace`, see `TransitionEffect`.
+    /// An effect can be applied to another transition by appending `:prev` or `:<order>`.
+    Transition(usize, Vec<Mark>, StyleTransition),
+    /// An element marks where the previous transition ends:
+    /// ```text
     /// t----
     /// ```
     TransitionEnd,
     /// A page mark:
-    /// ```
+    /// ```text
     /// ---
     /// ```
     ///
     /// A page is a group of transitions. Transitions from different pages would never appear in the window at the same time.
-    Page(Vec<Mark>),
-    /// A separator element:
+    ///
+    /// You can specify options for the page, separated by `|`:
+    /// ```text
+    /// // Hide all transitions again whenever the page is entered.
+    /// ---<reset-on-enter>
+    /// // Add classes to the page, separated by spaces.
+    /// --- <class=title-slide dark>
+    /// // Justify the text of the page, and hyphenate its words, when it is laid out.
+    /// ---<justify|hyphenate>
+    /// // Skip the page when presenting, and leave it out of the page numbers.
+    /// ---<hidden>
+    /// // Plan to present the page in 2 minutes, written in hours, minutes and seconds.
+    /// ---<duration=2m>
+    /// // Keep the page whole when long pages are split.
+    /// ---<no-split>
     /// ```
+    Page(Vec<Mark>, StylePage),
+    /// A separator element:
+    /// ```text
     /// ---- // A horizontal separator.
     /// ----v // A vertical separator.
+    /// // A thick dotted separator, half as long as the page is wide.
+    /// ----<dotted|50%|thick>
+    /// ```
+    Separator(SeparatorDir, SeparatorStyle),
+    /// A vertical space, as high as the given number of lines of text:
+    /// ```text
+    /// @space(2)
     /// ```
-    Separator(SeparatorDir),
+    /// A space takes its own line, and keeps the same height whatever the renderer does with
+    /// blank lines.
+    Space(f32),
     /// A text element:
     /// ```text
     /// normal text
-    /// ## heading 1
-    /// ### heading 2
-    /// #### heading 3
-    /// ##### heading 4
-    /// ###### heading 5
+    /// # heading 1
+    /// ## heading 2
+    /// ### heading 3
+    /// #### heading 4
+    /// ##### heading 5
+    /// ###### heading 6
+    /// ####### deeper heading
     ///
     /// *bold*
     /// `code`
@@ -205,26 +347,315 @@ pub enum Mark {
     /// <hyperlink>
     /// [hyperlink title](hyperlink)
     ///
+    /// {.class-a .class-b} text with classes
+    ///
     /// - unordered list
     ///
     /// 1. ordered list
     Text(String, StyleText),
 }
 
+/// How a math element is shown, see [`Mark::Math`].
+///
+/// ```
+/// use opmark::{
+///     mark::{Mark, MathMode},
+///     Parser,
+/// };
+///
+/// let math: Vec<_> = Parser::new("Area: \\(\\pi r^2\\)\n$$\nE = mc^2\n$$".to_owned())
+///     .filter_map(|mark| match mark {
+///         Mark::Math(math, mode) => Some((math, mode)),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(
+///     math,
+///     [
+///         ("\\pi r^2".to_owned(), MathMode::Inline),
+///         ("E = mc^2".to_owned(), MathMode::Display)
+///     ]
+/// );
+///
+/// // other lines starting with `$$` are text, where `$` toggles small text
+/// let marks: Vec<_> = Parser::new("$$x$$ tail".to_owned()).collect();
+/// assert!(matches!(&marks[2..], [Mark::Text(x, _), Mark::Text(tail, _)]
+///     if x == "x" && tail == " tail"));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MathMode {
+    /// Within a line of text, like `\(x^2\)`.
+    Inline,
+    /// On its own line, like `$$x^2$$`.
+    Display,
+}
+
 /// The direction of the seperator element.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum SeparatorDir {
     Horizontal,
     Vertical,
 }
 
+/// The configuration of the separator element, set with options like `----<dotted|50%|thick>`.
+///
+/// ```
+/// use opmark::{
+///     mark::{Mark, SeparatorLine, SeparatorStyle, SeparatorThickness},
+///     Parser,
+/// };
+///
+/// let style = Parser::new("----<dotted|50%|thick>\n".to_owned()).find_map(|mark| match mark {
+///     Mark::Separator(_, style) => Some(style),
+///     _ => None,
+/// });
+/// assert_eq!(
+///     style,
+///     Some(
+///         SeparatorStyle::new()
+///             .with_line(SeparatorLine::Dotted)
+///             .with_length(50.0)
+///             .with_thickness(SeparatorThickness::Thick)
+///     )
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SeparatorStyle {
+    /// How the line of the separator is drawn.
+    pub line: SeparatorLine,
+    /// The length of the separator, in percent of the width of the page, or of its height for a
+    /// vertical separator. If `None`, the separator spans the page.
+    pub length: Option<f32>,
+    /// How thick the line of the separator is.
+    pub thickness: SeparatorThickness,
+}
+
+impl SeparatorStyle {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_length(mut self, percent: f32) -> Self {
+        self.length = Some(percent);
+        self
+    }
+
+    #[inline]
+    pub fn with_line(mut self, line: SeparatorLine) -> Self {
+        self.line = line;
+        self
+    }
+
+    #[inline]
+    pub fn with_thickness(mut self, thickness: SeparatorThickness) -> Self {
+        self.thickness = thickness;
+        self
+    }
+}
+
+/// How the line of a separator is drawn.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SeparatorLine {
+    #[default]
+    Solid,
+    /// `dotted`.
+    Dotted,
+    /// `dashed`.
+    Dashed,
+}
+
+/// How thick the line of a separator is.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SeparatorThickness {
+    /// `thin`.
+    Thin,
+    #[default]
+    Normal,
+    /// `thick`.
+    Thick,
+}
+
+/// The lines highlighted by a step of a code walkthrough.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CodeStep {
+    /// All lines.
+    All,
+    /// The lines from the first to the last, both included and starting from 1.
+    Lines(usize, usize),
+}
+
+impl CodeStep {
+    /// Whether the step highlights `line`, starting from 1.
+    #[inline]
+    pub fn contains(&self, line: usize) -> bool {
+        match *self {
+            CodeStep::All => true,
+            CodeStep::Lines(first, last) => first <= line && line <= last,
+        }
+    }
+}
+
+/// A directive of a deck, see `Mark::Directive`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Directive {
+    /// `@bookmark(name)`: a named point of the deck, at the transition the directive is in, to
+    /// jump to, see `Deck::bookmarks`.
+    Bookmark(String),
+    /// `@note(text)`: a line of the speaker notes of the page, not shown on the page. Notes can
+    /// hold presenter cues, see `Page::cues`.
+    Note(String),
+    /// `@narration(src)`: the audio narrating the page, like the `narration=src` page option,
+    /// see `Page::narration`.
+    Narration(String),
+    /// `@see(#name)`: a reference from the page to the page of the bookmark `name`, like to a
+    /// backup page relevant to it, see `Deck::related`. The argument keeps the `#`.
+    See(String),
+    /// `@appendix`, without argument: the page and the pages after it are backup pages, kept
+    /// out of the talk, see `Deck::appendix`.
+    Appendix,
+    /// `@pagebreak`, without argument: a page break for the exports read as a document, like
+    /// EPUB, or HTML when printed. Slides ignore it.
+    PageBreak,
+    /// `@comment(author): text`: a review comment on the nearest mark of the page, not shown on
+    /// the page, see `review::Review`. The fields are the author and the text.
+    Comment(String, String),
+    /// `@conflict(ours)`, `@conflict(theirs)` and `@conflict(end)`: the markers of a conflict
+    /// left by a merge, around both versions of the conflicting part, see `merge::merge`.
+    Conflict(ConflictMarker),
+    /// `@audience(internal, partners)`: the block after the directive, up to the next empty line,
+    /// is only for the audiences listed, see `audience`.
+    Audience(String),
+    /// `{#fig:arch}`: the label of the figure, table or code block right before the directive,
+    /// to refer to it with `@ref(fig:arch)`, see `crossref`. Unlike other directives, it is
+    /// written without `@`.
+    Label(String),
+}
+
+impl Directive {
+    /// The name of the directive, as written after `@`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Directive::Bookmark(_) => "bookmark",
+            Directive::Note(_) => "note",
+            Directive::Narration(_) => "narration",
+            Directive::See(_) => "see",
+            Directive::Appendix => "appendix",
+            Directive::PageBreak => "pagebreak",
+            Directive::Comment(..) => "comment",
+            Directive::Conflict(_) => "conflict",
+            Directive::Audience(_) => "audience",
+            Directive::Label(_) => "label",
+        }
+    }
+
+    /// The argument of the directive, as written between the parentheses. Empty for directives
+    /// without argument.
+    pub fn argument(&self) -> &str {
+        match self {
+            Directive::Bookmark(argument)
+            | Directive::Note(argument)
+            | Directive::Narration(argument)
+            | Directive::See(argument)
+            | Directive::Comment(argument, _)
+            | Directive::Audience(argument)
+            | Directive::Label(argument) => argument,
+            Directive::Appendix | Directive::PageBreak => "",
+            Directive::Conflict(marker) => marker.name(),
+        }
+    }
+}
+
+/// A marker of a merge conflict, see `Directive::Conflict`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictMarker {
+    /// The start of our version.
+    Ours,
+    /// The end of our version, and the start of theirs.
+    Theirs,
+    /// The end of their version.
+    End,
+}
+
+impl ConflictMarker {
+    /// The name of the marker, as written between the parentheses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConflictMarker::Ours => "ours",
+            ConflictMarker::Theirs => "theirs",
+            ConflictMarker::End => "end",
+        }
+    }
+}
+
+/// An online service whose resources can be embedded, with the metadata needed to embed one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EmbedProvider {
+    /// A GitHub Gist.
+    Gist { user: String, id: String },
+    /// A post on Twitter/X.
+    Tweet { user: String, id: String },
+    /// A Vimeo video.
+    Vimeo { id: String },
+    /// A YouTube video.
+    YouTube { id: String },
+}
+
+/// The configuration of the gallery element.
+#[derive(Clone, Debug, Default)]
+pub struct StyleGallery {
+    /// The classes of the gallery, used by renderers for themed overrides.
+    pub classes: Vec<String>,
+    /// The count of columns of the grid. If `None`, the renderer decides.
+    pub columns: Option<usize>,
+    /// The count of rows of the grid. If `None`, the renderer decides.
+    pub rows: Option<usize>,
+}
+
+impl StyleGallery {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_class(mut self, class: String) -> Self {
+        self.classes.push(class);
+        self
+    }
+
+    #[inline]
+    pub fn with_columns(mut self, columns: usize) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    #[inline]
+    pub fn with_rows(mut self, rows: usize) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+}
+
 /// The configuration of the image element.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct StyleImage {
     /// How the image should be aligned horizontally.
     pub align_h: AlignHorizontal,
+    /// Should an animated image start playing as soon as it shows up.
+    pub autoplay: bool,
+    /// The classes of the image, used by renderers for themed overrides.
+    pub classes: Vec<String>,
+    /// The frames per second of an animated image. If `None`, the timing of the image will be
+    /// used.
+    pub fps: Option<f32>,
     /// A string defines the url where the image should link to.
     pub hyperlink: String,
+    /// Should an animated image play again from the start when it ends.
+    pub looping: bool,
+    /// The frame, starting from 0, where an animated image stops playing.
+    pub pause_at: Option<usize>,
     /// The width of the image. If `None`, the ordinary width of the image will be used.
     pub width: Option<f32>,
     /// The height of the image. If `None`, the ordinary height of the image will be used.
@@ -243,6 +674,24 @@ impl StyleImage {
         self
     }
 
+    #[inline]
+    pub fn with_autoplay(mut self) -> Self {
+        self.autoplay = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_class(mut self, class: String) -> Self {
+        self.classes.push(class);
+        self
+    }
+
+    #[inline]
+    pub fn with_fps(mut self, fps: f32) -> Self {
+        self.fps = Some(fps);
+        self
+    }
+
     #[inline]
     pub fn with_height(mut self, height: f32) -> Self {
         self.height = Some(height);
@@ -255,6 +704,18 @@ impl StyleImage {
         self
     }
 
+    #[inline]
+    pub fn with_loop(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_pause_at(mut self, frame: usize) -> Self {
+        self.pause_at = Some(frame);
+        self
+    }
+
     #[inline]
     pub fn with_width(mut self, width: f32) -> Self {
         self.width = Some(width);
@@ -262,11 +723,106 @@ impl StyleImage {
     }
 }
 
+/// The configuration of the page element.
+#[derive(Clone, Debug, Default)]
+pub struct StylePage {
+    /// The classes of the page, used by renderers for themed overrides.
+    pub classes: Vec<String>,
+    /// Should the transitions of the page be hidden again whenever the page is entered.
+    pub reset_on_enter: bool,
+    /// The source of the audio narrating the page, played when the page is entered.
+    pub narration: Option<String>,
+    /// Should the lines of text of the page be justified, see `layout::LayoutOptions`.
+    pub justify: bool,
+    /// Should the words of the page be hyphenated, see `layout::LayoutOptions`.
+    pub hyphenate: bool,
+    /// Should the page be skipped when going to the next or previous page, see
+    /// `Deck::next_page`.
+    pub hidden: bool,
+    /// The time planned to present the page, see `stats::DeckStats`.
+    pub duration: Option<Duration>,
+    /// The audiences the page is for, all audiences if empty, see `audience`.
+    pub audiences: Vec<String>,
+    /// The time of day to show the page at, since midnight, see `schedule`.
+    pub at: Option<Duration>,
+    /// Should the page be kept whole when long pages are split, see `Deck::paginate`.
+    pub no_split: bool,
+}
+
+impl StylePage {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_class(mut self, class: String) -> Self {
+        self.classes.push(class);
+        self
+    }
+
+    #[inline]
+    pub fn with_reset_on_enter(mut self) -> Self {
+        self.reset_on_enter = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_narration(mut self, src: String) -> Self {
+        self.narration = Some(src);
+        self
+    }
+
+    #[inline]
+    pub fn with_justify(mut self) -> Self {
+        self.justify = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_hyphenate(mut self) -> Self {
+        self.hyphenate = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    #[inline]
+    pub fn with_audience(mut self, audience: String) -> Self {
+        self.audiences.push(audience);
+        self
+    }
+
+    #[inline]
+    pub fn with_at(mut self, time_of_day: Duration) -> Self {
+        self.at = Some(time_of_day);
+        self
+    }
+
+    #[inline]
+    pub fn with_no_split(mut self) -> Self {
+        self.no_split = true;
+        self
+    }
+}
+
 /// The configuration of the text element.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct StyleText {
     /// Should the text be bold.
     pub bold: bool,
+    /// The classes of the text, used by renderers for themed overrides.
+    pub classes: Vec<String>,
     /// Should the text be code-style.
     pub code: bool,
     /// The heading level of the text.
@@ -275,6 +831,8 @@ pub struct StyleText {
     pub hyperlink: String,
     /// Should the text be italics.
     pub italics: bool,
+    /// The title of the hyperlink, shown as a tooltip when hovering it.
+    pub link_title: Option<String>,
     /// Whether the text is within an ordered/unordered list.
     pub listing: Listing,
     /// Should the text be quote-style.
@@ -291,10 +849,12 @@ impl Clone for StyleText {
     fn clone(&self) -> Self {
         StyleText {
             bold: self.bold,
+            classes: self.classes.clone(),
             code: self.code,
             heading: self.heading.clone(),
             hyperlink: self.hyperlink.clone(),
             italics: self.italics,
+            link_title: self.link_title.clone(),
             listing: self.listing.clone(),
             quote: self.quote,
             small: self.small,
@@ -316,6 +876,12 @@ impl StyleText {
         self
     }
 
+    #[inline]
+    pub fn with_class(mut self, class: String) -> Self {
+        self.classes.push(class);
+        self
+    }
+
     #[inline]
     pub fn with_code(mut self) -> Self {
         self.code = true;
@@ -340,6 +906,12 @@ impl StyleText {
         self
     }
 
+    #[inline]
+    pub fn with_link_title(mut self, link_title: String) -> Self {
+        self.link_title = Some(link_title);
+        self
+    }
+
     #[inline]
     pub fn with_listing(mut self, listing: Listing) -> Self {
         self.listing = listing;
@@ -370,3 +942,66 @@ impl StyleText {
         self
     }
 }
+
+/// The configuration of the transition element.
+#[derive(Clone, Debug, Default)]
+pub struct StyleTransition {
+    /// What happens when the step of the transition is reached.
+    pub effect: TransitionEffect,
+    /// Should stepping forward past the end of the page replay the page from this transition.
+    ///
+    /// The page replays every time it is stepped forward past its end, so it loops until it is
+    /// left by going to another page, see [`Deck::next_step`](crate::deck::Deck::next_step).
+    pub looping: bool,
+    /// Which transition `effect` applies to.
+    pub target: TransitionTarget,
+}
+
+impl StyleTransition {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_effect(mut self, effect: TransitionEffect, target: TransitionTarget) -> Self {
+        self.effect = effect;
+        self.target = target;
+        self
+    }
+
+    #[inline]
+    pub fn with_loop(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+}
+
+/// What happens to the target of a transition when the step of the transition is reached.
+///
+/// If the target is another transition, the transition itself appears as usual.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TransitionEffect {
+    /// The target appears.
+    #[default]
+    Appear,
+    /// The target disappears.
+    Disappear,
+    /// The target is dimmed.
+    Dim,
+    /// The target disappears, and the transition takes its place. Targets the previous transition
+    /// if no target is given.
+    Replace,
+}
+
+/// The transition a `TransitionEffect` applies to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TransitionTarget {
+    /// The transition itself.
+    #[default]
+    This,
+    /// The transition right before it in the page.
+    Prev,
+    /// All transitions with the given order.
+    Order(usize),
+}
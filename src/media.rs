@@ -0,0 +1,162 @@
+//! The media a deck refers to, to review their licenses or feed them to asset pipelines.
+//!
+//! [`Deck::media_manifest`](crate::deck::Deck::media_manifest) lists the images, galleries,
+//! narrations and embedded resources of a deck, page by page, with their sources resolved
+//! against the directory of the deck. A manifest can be written as JSON, see
+//! [`MediaManifest::to_json`].
+//!
+//! ```
+//! use opmark::{deck::Deck, media::MediaKind};
+//!
+//! let deck = Deck::new(
+//!     "![Logo](img/logo.png)<w120>\n---<narration=intro.ogg>\n![Chart](https://example.com/chart.svg)"
+//!         .to_owned(),
+//! );
+//! let manifest = deck.media_manifest("talks/q3");
+//! let logo = &manifest.items[0];
+//! assert_eq!((logo.page, logo.kind, logo.width), (0, MediaKind::Image, Some(120.0)));
+//! assert_eq!(logo.resolved, "talks/q3/img/logo.png");
+//!
+//! let sources: Vec<&str> = manifest.items.iter().map(|item| item.resolved.as_str()).collect();
+//! assert_eq!(sources[1..], ["talks/q3/intro.ogg", "https://example.com/chart.svg"]);
+//! assert!(manifest.to_json().starts_with("[\n  {\"page\":0,\"kind\":\"image\","));
+//! ```
+
+use crate::{
+    deck::Deck,
+    link::LinkTarget,
+    mark::{Mark, StyleImage},
+    parser::LINE_ENDING,
+    render::site::escape_json,
+};
+use std::path::Path;
+
+/// The kind of a media.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MediaKind {
+    /// An image, on its own or in a gallery.
+    Image,
+    /// The audio narrating a page.
+    Audio,
+    /// A resource of an online service, like a video, see `Mark::Embed`.
+    Embed,
+}
+
+impl MediaKind {
+    /// The name of the kind, as written in JSON.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MediaKind::Image => "image",
+            MediaKind::Audio => "audio",
+            MediaKind::Embed => "embed",
+        }
+    }
+}
+
+/// A reference to a media from a deck.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaItem {
+    /// The index of the page referring to the media.
+    pub page: usize,
+    pub kind: MediaKind,
+    /// The source of the media, as written in the deck.
+    pub src: String,
+    /// The source resolved against the directory of the deck. Sources with a scheme, like URLs,
+    /// are kept.
+    pub resolved: String,
+    /// The title of an image, empty for other media.
+    pub title: String,
+    /// The width of an image, if set in its options.
+    pub width: Option<f32>,
+    /// The height of an image, if set in its options.
+    pub height: Option<f32>,
+}
+
+/// The media a deck refers to, in the order of the deck.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaManifest {
+    pub items: Vec<MediaItem>,
+}
+
+impl MediaManifest {
+    /// The media of `deck`, with their sources resolved against `dir`.
+    pub(crate) fn new(deck: &Deck, dir: &Path) -> Self {
+        let mut manifest = Self::default();
+        for (idx, page) in deck.pages().iter().enumerate() {
+            let mut push = |kind: MediaKind, src: &str, title: &str, style: Option<&StyleImage>| {
+                manifest.items.push(MediaItem {
+                    page: idx,
+                    kind,
+                    src: src.to_owned(),
+                    resolved: resolve(dir, src),
+                    title: title.to_owned(),
+                    width: style.and_then(|style| style.width),
+                    height: style.and_then(|style| style.height),
+                })
+            };
+            if let Some(src) = page.narration() {
+                push(MediaKind::Audio, src, "", None);
+            }
+            for transition in &page.transitions {
+                let Mark::Transition(_, marks, _) = transition else {
+                    continue;
+                };
+                for mark in marks {
+                    match mark {
+                        Mark::Image(src, title, style) => {
+                            push(MediaKind::Image, src, title, Some(style))
+                        }
+                        Mark::Gallery(items, _) => {
+                            for (src, title) in items {
+                                push(MediaKind::Image, src, title, None);
+                            }
+                        }
+                        Mark::Embed(url, _) => push(MediaKind::Embed, url, "", None),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        manifest
+    }
+
+    /// Write the manifest as a JSON array, with an object per media.
+    pub fn to_json(&self) -> String {
+        let items: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| {
+                format!(
+                    "  {{\"page\":{},\"kind\":{},\"src\":{},\"resolved\":{},\"title\":{},\"width\":{},\"height\":{}}}",
+                    item.page,
+                    escape_json(item.kind.name()),
+                    escape_json(&item.src),
+                    escape_json(&item.resolved),
+                    escape_json(&item.title),
+                    json_number(item.width),
+                    json_number(item.height)
+                )
+            })
+            .collect();
+        if items.is_empty() {
+            return "[]".to_owned();
+        }
+        let separator = format!(",{}", LINE_ENDING);
+        format!("[{}{}{}]", LINE_ENDING, items.join(&separator), LINE_ENDING)
+    }
+}
+
+/// `src` resolved against the directory `dir`, if it is relative.
+fn resolve(dir: &Path, src: &str) -> String {
+    match LinkTarget::parse(src) {
+        LinkTarget::Relative(_) if !src.starts_with('/') => dir.join(src).display().to_string(),
+        _ => src.to_owned(),
+    }
+}
+
+fn json_number(n: Option<f32>) -> String {
+    match n.filter(|n| n.is_finite()) {
+        Some(n) => n.to_string(),
+        None => "null".to_owned(),
+    }
+}
@@ -0,0 +1,302 @@
+//! Three-way merge of documents, to merge the changes made to a deck on two branches.
+//!
+//! Documents are merged page by page, and the pages changed on both sides block by block, see
+//! [`Block`]. Changes to different pages, or to different blocks of a page, merge cleanly, even
+//! when they are on neighbouring lines. Changes which conflict are kept from both sides, between
+//! conflict markers, see [`Directive::Conflict`](crate::mark::Directive::Conflict):
+//! ```text
+//! @conflict(ours)
+//! # Our title
+//! @conflict(theirs)
+//! # Their title
+//! @conflict(end)
+//! ```
+//! Conflict markers are directives, so a merged document with conflicts still parses, and tools
+//! find the conflicts among its marks.
+//!
+//! ```
+//! use opmark::{
+//!     mark::{ConflictMarker, Directive, Mark},
+//!     merge::merge,
+//!     Parser,
+//! };
+//!
+//! let base = "# Intro\nHello\n---\n# Plan\nOne";
+//! let ours = "# Intro\nHello, world\n---\n# Plan\nOne";
+//! let theirs = "# Intro\nHello\n---\n# Plan\nOne\nTwo";
+//! let result = merge(base, ours, theirs);
+//! assert!(result.is_clean());
+//! assert_eq!(result.to_source(), "# Intro\nHello, world\n---\n# Plan\nOne\nTwo");
+//!
+//! let theirs = "# Welcome\nHello\n---\n# Plan\nOne";
+//! let result = merge(base, "# Hi\nHello\n---\n# Plan\nOne", theirs);
+//! assert_eq!(result.conflicts[0].ours, "# Hi");
+//! assert_eq!(
+//!     result.to_source(),
+//!     "@conflict(ours)\n# Hi\n@conflict(theirs)\n# Welcome\n@conflict(end)\nHello\n---\n# Plan\nOne"
+//! );
+//! let marks: Vec<Mark> = Parser::new(result.to_source()).collect();
+//! assert!(marks.iter().any(|mark| matches!(
+//!     mark,
+//!     Mark::Directive(Directive::Conflict(ConflictMarker::Theirs))
+//! )));
+//! ```
+
+use crate::{
+    document::{Block, Document, DocumentPage},
+    mark::{ConflictMarker, Directive, Mark},
+    parser::LINE_ENDING,
+};
+
+/// The result of a merge.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeResult {
+    /// The merged document, with conflict markers around the conflicting parts.
+    pub document: Document,
+    /// The conflicts, in the order of the document.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    /// Whether the merge has no conflict.
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Serialize the merged document into OpMark text.
+    #[inline]
+    pub fn to_source(&self) -> String {
+        self.document.to_source()
+    }
+}
+
+/// A part changed on both sides in different ways.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Conflict {
+    /// The index of the page of the merged document the conflict starts in.
+    pub page: usize,
+    /// The text of the part in the base document.
+    pub base: String,
+    /// The text of the part on our side.
+    pub ours: String,
+    /// The text of the part on their side.
+    pub theirs: String,
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`, where all are the text
+/// content of OpMark documents.
+///
+/// Pages are matched by their text, and so are the blocks of the pages changed on both sides.
+/// When both sides change the same pages, each page is merged with the pages at the same place
+/// on the other sides, if both sides kept the count of pages. Otherwise the pages conflict as a
+/// whole.
+pub fn merge(base: &str, ours: &str, theirs: &str) -> MergeResult {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("merge").entered();
+
+    let base = Document::new(base.to_owned());
+    let ours = Document::new(ours.to_owned());
+    let theirs = Document::new(theirs.to_owned());
+    let mut merger = Merger::default();
+    for chunk in diff3(base.pages(), ours.pages(), theirs.pages()) {
+        match chunk {
+            Chunk::Stable(pages) => merger.push_pages(pages),
+            Chunk::Changed(base, ours, theirs) => match resolve(base, ours, theirs) {
+                Some(pages) => merger.push_pages(pages),
+                None if base.len() == ours.len() && ours.len() == theirs.len() => {
+                    for ((base, ours), theirs) in base.iter().zip(ours).zip(theirs) {
+                        merger.merge_page(base, ours, theirs);
+                    }
+                }
+                None => merger.conflict_pages(base, ours, theirs),
+            },
+        }
+    }
+    merger.result
+}
+
+/// Builds the merged document.
+#[derive(Default)]
+struct Merger {
+    result: MergeResult,
+}
+
+impl Merger {
+    fn push_pages(&mut self, pages: &[DocumentPage]) {
+        for page in pages {
+            let index = self.result.document.pages().len();
+            self.result.document.insert_page(index, page.clone());
+        }
+    }
+
+    /// Merge a page changed on both sides.
+    fn merge_page(&mut self, base: &DocumentPage, ours: &DocumentPage, theirs: &DocumentPage) {
+        let Some(marker) = resolve(&base.marker, &ours.marker, &theirs.marker) else {
+            return self.conflict_pages(
+                std::slice::from_ref(base),
+                std::slice::from_ref(ours),
+                std::slice::from_ref(theirs),
+            );
+        };
+        let mut page = DocumentPage {
+            marker: marker.clone(),
+            blocks: vec![],
+        };
+        for chunk in diff3(&base.blocks, &ours.blocks, &theirs.blocks) {
+            match chunk {
+                Chunk::Stable(blocks) => page.blocks.extend_from_slice(blocks),
+                Chunk::Changed(base, ours, theirs) => match resolve(base, ours, theirs) {
+                    Some(blocks) => page.blocks.extend_from_slice(blocks),
+                    None => {
+                        self.result.conflicts.push(Conflict {
+                            page: self.result.document.pages().len(),
+                            base: blocks_source(base),
+                            ours: blocks_source(ours),
+                            theirs: blocks_source(theirs),
+                        });
+                        page.blocks.push(conflict_marker(ConflictMarker::Ours));
+                        page.blocks.extend_from_slice(ours);
+                        page.blocks.push(conflict_marker(ConflictMarker::Theirs));
+                        page.blocks.extend_from_slice(theirs);
+                        page.blocks.push(conflict_marker(ConflictMarker::End));
+                    }
+                },
+            }
+        }
+        self.push_pages(&[page]);
+    }
+
+    /// Keep the pages of both sides, our pages first, between conflict markers.
+    fn conflict_pages(
+        &mut self,
+        base: &[DocumentPage],
+        ours: &[DocumentPage],
+        theirs: &[DocumentPage],
+    ) {
+        self.result.conflicts.push(Conflict {
+            page: self.result.document.pages().len(),
+            base: pages_source(base),
+            ours: pages_source(ours),
+            theirs: pages_source(theirs),
+        });
+        let mut ours = ours.to_vec();
+        let mut theirs = theirs.to_vec();
+        // a side without pages has its markers in the pages of the other side
+        let mut markers = vec![conflict_marker(ConflictMarker::Ours)];
+        if let Some(first) = ours.first_mut() {
+            first.blocks.splice(0..0, markers.drain(..));
+        }
+        markers.push(conflict_marker(ConflictMarker::Theirs));
+        match theirs.first_mut() {
+            Some(first) => {
+                first.blocks.splice(0..0, markers.drain(..));
+            }
+            None => {
+                if let Some(last) = ours.last_mut() {
+                    last.blocks.append(&mut markers);
+                }
+            }
+        }
+        markers.push(conflict_marker(ConflictMarker::End));
+        if let Some(last) = theirs.last_mut().or(ours.last_mut()) {
+            last.blocks.append(&mut markers);
+        }
+        self.push_pages(&ours);
+        self.push_pages(&theirs);
+    }
+}
+
+/// A part of the items of three versions.
+enum Chunk<'a, T> {
+    /// Items kept by both sides.
+    Stable(&'a [T]),
+    /// Items changed by either side, with the items of the base, ours and theirs.
+    Changed(&'a [T], &'a [T], &'a [T]),
+}
+
+/// Split the items of three versions into the parts kept and changed by each side.
+fn diff3<'a, T: PartialEq>(base: &'a [T], ours: &'a [T], theirs: &'a [T]) -> Vec<Chunk<'a, T>> {
+    let in_ours = matches(base, ours);
+    let in_theirs = matches(base, theirs);
+    let mut chunks = vec![];
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // the next item of the base kept by both sides
+        let (b, o, t) = (i..base.len())
+            .find_map(|b| Some((b, in_ours[b]?, in_theirs[b]?)))
+            .unwrap_or((base.len(), ours.len(), theirs.len()));
+        if (b, o, t) == (i, j, k) {
+            if b == base.len() {
+                break;
+            }
+            chunks.push(Chunk::Stable(&base[b..b + 1]));
+            (i, j, k) = (b + 1, o + 1, t + 1);
+        } else {
+            chunks.push(Chunk::Changed(&base[i..b], &ours[j..o], &theirs[k..t]));
+            (i, j, k) = (b, o, t);
+        }
+    }
+    chunks
+}
+
+/// For each item of `a`, the index of the same item in `b`, along a longest common subsequence.
+fn matches<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Option<usize>> {
+    // the lengths of the longest common subsequences of `a[i..]` and `b[j..]`
+    let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut matched = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            matched[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matched
+}
+
+/// The merged version of a part, `None` if both sides changed it in different ways.
+fn resolve<'a, T: PartialEq + ?Sized>(base: &T, ours: &'a T, theirs: &'a T) -> Option<&'a T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn conflict_marker(marker: ConflictMarker) -> Block {
+    Block::from_marks(&[Mark::Directive(Directive::Conflict(marker))])
+}
+
+fn blocks_source(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(Block::source)
+        .collect::<Vec<&str>>()
+        .join(LINE_ENDING)
+}
+
+fn pages_source(pages: &[DocumentPage]) -> String {
+    let mut lines: Vec<&str> = vec![];
+    for page in pages {
+        lines.extend(page.marker.as_deref());
+        lines.extend(page.blocks.iter().map(Block::source));
+    }
+    lines.join(LINE_ENDING)
+}
@@ -1,48 +1,347 @@
 //! Parser for OpMark.
 
-use crate::mark::{
-    AlignHorizontal, Heading, IndentLevel, Listing, Mark, SeparatorDir, StyleImage, StyleText,
+#[cfg(feature = "diagrams")]
+use crate::diagram::{svg_data_uri, DiagramCache, DiagramFormat};
+use crate::{
+    audience,
+    crossref::{label_numbers, parse_label, reference_at, LabelKind, Numbering},
+    embed,
+    front_matter::FrontMatter,
+    grammar::{Markers, ParseTrace, Rule, RuleId, RuleKind, RULES, SPECIAL_CHARS},
+    include::IncludeResolver,
+    lint::Warning,
+    mark::{
+        AlignHorizontal, CodeStep, ConflictMarker, Directive, Heading, IndentLevel, Listing, Mark,
+        MathMode, SeparatorDir, SeparatorLine, SeparatorStyle, SeparatorThickness, StyleGallery,
+        StyleImage, StylePage, StyleText, StyleTransition, TransitionEffect, TransitionTarget,
+    },
+    placeholder::{placeholder_at, Placeholders},
+    scanner::Cursor,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, env, fmt, sync::Arc, time::Duration};
 
 #[cfg(windows)]
-const LINE_ENDING: &'static str = "\r\n";
+pub(crate) const LINE_ENDING: &str = "\r\n";
 #[cfg(not(windows))]
-const LINE_ENDING: &'static str = "\n";
+pub(crate) const LINE_ENDING: &str = "\n";
+
+/// The UTF-8 byte order mark.
+const BOM: char = '\u{feff}';
+
+/// The block rules whose markers start nothing else, so a line starting with one of them which
+/// does not parse is a malformed block, see [`Parser`].
+const RECOVERED_BLOCKS: [RuleId; 4] = [
+    RuleId::CodeBlock,
+    RuleId::Gallery,
+    RuleId::Space,
+    RuleId::HoldSlide,
+];
 
 /// Parser for OpMark.
+///
+/// # Output order
+///
+/// The parser yields marks in the order they appear in the document, with the following
+/// guarantees:
+///
+/// 1. The first mark is a `Mark::Page`.
+/// 2. Every `Mark::Page` is immediately followed by a `Mark::Transition` with order `0`.
+/// 3. Any other mark belongs to the latest `Mark::Page` and the latest `Mark::Transition`.
+///
+/// To uphold 1 and 2, the parser emits a synthetic page at the start of the document and a
+/// synthetic transition at the start of every page. Both appear even when they stay empty, e.g.
+/// for an empty document or a document starting with a page mark:
+///
+/// ```
+/// use opmark::{mark::Mark, Parser};
+///
+/// let marks: Vec<Mark> = Parser::new("---\n---t\ntext".to_owned()).collect();
+/// assert!(matches!(
+///     marks[..],
+///     [
+///         Mark::Page(..),
+///         Mark::Transition(0, ..),
+///         Mark::Page(..),
+///         Mark::Transition(0, ..),
+///         Mark::Transition(1, ..),
+///         Mark::Text(..),
+///     ]
+/// ));
+/// ```
+///
+/// With [`ParserOptions::with_suppress_synthetic`], synthetic marks which would stay empty are
+/// omitted, so 1 and 2 only hold for pages and transitions that have content. Guarantee 3 always
+/// holds:
+///
+/// ```
+/// use opmark::{mark::Mark, Parser, ParserOptions};
+///
+/// let options = ParserOptions::new().with_suppress_synthetic();
+/// let marks: Vec<Mark> = Parser::with_options("---\n---t\ntext".to_owned(), options).collect();
+/// assert!(matches!(
+///     marks[..],
+///     [Mark::Page(..), Mark::Transition(0, ..), Mark::Text(..)]
+/// ));
+///
+/// let options = ParserOptions::new().with_suppress_synthetic();
+/// assert_eq!(Parser::with_options(String::new(), options).count(), 0);
+/// ```
+///
+/// # Malformed blocks
+///
+/// A block which starts like a code block, a gallery, a space or a hold slide, but does not
+/// parse, like a code block without its closing fence, is skipped up to the next empty line or
+/// page, and reported as [`Warning::MalformedBlock`]. Its lines are not read as text, whose
+/// styles would run over the rest of the page:
+///
+/// ```
+/// use opmark::{grammar::RuleId, lint::Warning, mark::Mark, Parser};
+///
+/// let source = "```rust\nlet a = `b`;\n\n*Next*\n---\n@space(-1)\nSkipped\n---\nEnd";
+/// let mut parser = Parser::new(source.to_owned());
+/// let texts: Vec<String> = parser
+///     .by_ref()
+///     .filter_map(|mark| match mark {
+///         Mark::Text(text, style) => Some(format!("{} {}", text, style.bold)),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(texts, ["Next true", "End false"]);
+/// assert_eq!(
+///     parser.warnings(),
+///     [
+///         Warning::MalformedBlock {
+///             rule: RuleId::CodeBlock,
+///             line: "```rust".to_owned()
+///         },
+///         Warning::MalformedBlock {
+///             rule: RuleId::Space,
+///             line: "@space(-1)".to_owned()
+///         },
+///     ]
+/// );
+/// ```
+///
+/// # Links
+///
+/// Links like `[title][label]`, or `[title][]` when the title is the label, link to the URL of
+/// the label, defined on a line of its own anywhere in the document, like `[label]: url`.
+/// Labels are compared regardless of case, and definition lines add no marks. Links and
+/// definitions may have a title after their URL, shown as a tooltip, like
+/// `[title](url "tooltip")`:
+///
+/// ```
+/// use opmark::{lint::Warning, mark::Mark, Parser};
+///
+/// let source = "[Docs][API] and [code][]\n\n[api]: https://docs.rs/ \"API reference\"";
+/// let mut parser = Parser::new(source.to_owned());
+/// let links: Vec<(String, Option<String>)> = parser
+///     .by_ref()
+///     .filter_map(|mark| match mark {
+///         Mark::Text(_, style) if !style.hyperlink.is_empty() => {
+///             Some((style.hyperlink, style.link_title))
+///         }
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(
+///     links,
+///     [("https://docs.rs/".to_owned(), Some("API reference".to_owned()))]
+/// );
+/// assert_eq!(parser.warnings(), [Warning::UndefinedLinkLabel { label: "code".to_owned() }]);
+/// ```
+///
+/// # Text
+///
+/// The text of a list item starts after its marker and the space that follows it, and the text
+/// after inline code starts right after its closing backtick. A `\` escapes any character,
+/// which is yielded as text in the style around it:
+///
+/// ```
+/// use opmark::{mark::Mark, Parser};
+///
+/// let texts: Vec<String> = Parser::new("- item\n`a`b *\\é*".to_owned())
+///     .filter_map(|mark| match mark {
+///         Mark::Text(text, style) if style.bold => Some(format!("*{}*", text)),
+///         Mark::Text(text, _) => Some(text),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(texts, ["item", "a", "b ", "*é*"]);
+/// ```
 #[derive(Debug, Default)]
 pub struct Parser {
     s: String,
+    options: ParserOptions,
+    base_style_text: StyleText,
+    dialect: Option<Dialect>,
     first_page_return: bool,
+    front_matter: FrontMatter,
     indent_level: u8,
     indent_orderer_number_map: HashMap<IndentLevel, u8>,
     is_line_start: bool,
+    /// Whether the source before the cursor ends with a letter or digit of plain text.
+    is_after_word: bool,
     is_ordered: bool,
     is_unordered: bool,
+    /// The URLs and titles of the link labels defined in the document, by normalized label.
+    link_definitions: HashMap<String, (String, Option<String>)>,
+    /// The kinds and numbers of the labels, when cross-references are resolved.
+    labels: HashMap<String, (LabelKind, usize)>,
+    /// The numbering of the captions, from the options and the front matter.
+    numbering: Numbering,
     style_text: StyleText,
     transition_order: usize,
     ordered_list_current_indent_level_int: u8,
+    warnings: Vec<Warning>,
+    /// The line ending of the document, before line endings are normalized.
+    line_ending: Option<LineEnding>,
+    /// Whether the document starts with a byte order mark, which is stripped.
+    bom: bool,
+    /// The length of the document, to locate the rest of it.
+    source_len: usize,
+    trace: Option<ParseTrace>,
 }
 
 impl Parser {
     /// Create a new Parser for OpMark, where `s` is the text content of the OpMark document.
     pub fn new(s: String) -> Self {
-        let mut indent_orderer_number_map = HashMap::new();
-        indent_orderer_number_map.insert(IndentLevel::None, 0);
-        indent_orderer_number_map.insert(IndentLevel::I1, 0);
-        indent_orderer_number_map.insert(IndentLevel::I2, 0);
-        indent_orderer_number_map.insert(IndentLevel::I3, 0);
-        indent_orderer_number_map.insert(IndentLevel::I4, 0);
-        indent_orderer_number_map.insert(IndentLevel::I5, 0);
+        Self::with_options(s, ParserOptions::new())
+    }
+
+    /// Create a new Parser for OpMark with the given options.
+    ///
+    /// If the document starts with a dialect declaration, the declaration is consumed, see
+    /// [`Dialect`], and so is the front matter after it, see
+    /// [`front_matter`](crate::front_matter).
+    pub fn with_options(s: String, options: ParserOptions) -> Self {
+        let mut parser = Self::init(s, options);
+        parser.declaration();
+        parser.front_matter_block();
+        parser.numbering = parser
+            .options
+            .numbering
+            .clone()
+            .with_front_matter(&parser.front_matter);
+        parser
+    }
+
+    fn init(s: String, options: ParserOptions) -> Self {
+        let line_ending = LineEnding::detect(&s);
+        let bom = s.starts_with(BOM);
+        let s = normalize_line_endings(s);
         Self {
+            source_len: s.len(),
+            trace: options.trace.then(ParseTrace::default),
+            link_definitions: link_definitions(&s),
+            labels: match options.cross_references {
+                true => label_numbers(&s),
+                false => HashMap::new(),
+            },
+            numbering: options.numbering.clone(),
             s,
-            indent_orderer_number_map,
+            options,
             is_line_start: true,
+            line_ending,
+            bom,
             ..Default::default()
         }
     }
 
+    /// Parse a snippet of OpMark text, e.g. pasted into an editor, where `context` describes where
+    /// the snippet is inserted.
+    ///
+    /// Unlike iterating over a parser, no page or transition marks are synthesized around the
+    /// marks of the snippet.
+    ///
+    /// ```
+    /// use opmark::{mark::{Mark, StyleText}, FragmentContext, Parser};
+    ///
+    /// let context = FragmentContext::new()
+    ///     .with_inline()
+    ///     .with_style(StyleText::new().with_bold());
+    /// let marks = Parser::parse_fragment("# not a heading", context);
+    /// assert!(matches!(&marks[..], [Mark::Text(text, style)] if text == "# not a heading" && style.bold));
+    /// ```
+    pub fn parse_fragment(s: &str, context: FragmentContext) -> Vec<Mark> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse_fragment", len = s.len()).entered();
+
+        let mut parser = Self::init(s.to_owned(), ParserOptions::new());
+        parser.first_page_return = true;
+        parser.transition_order = 1;
+        parser.is_line_start = !context.inline;
+        parser.style_text = context.style.clone();
+        parser.base_style_text = context.style;
+        parser.collect()
+    }
+
+    /// The dialect declared by the document, if any.
+    #[inline]
+    pub fn dialect(&self) -> Option<Dialect> {
+        self.dialect
+    }
+
+    /// The line ending of the document, as written before it is parsed. `None` if the document
+    /// is a single line.
+    ///
+    /// Documents may end their lines with `\r\n` or a lone `\r`, like files written on
+    /// Windows or classic Mac OS, and start with a UTF-8 byte order mark. The parser reads them
+    /// like documents ending their lines with `\n`, without the byte order mark, see
+    /// [`FormatOptions::preserve_line_endings`](crate::serializer::FormatOptions::preserve_line_endings)
+    /// to write them back the way they were.
+    ///
+    /// ```
+    /// use opmark::{mark::Mark, LineEnding, Parser};
+    ///
+    /// let mut parser = Parser::new("\u{feff}# Title\r\n- one\r\n- two".to_owned());
+    /// assert_eq!(parser.line_ending(), Some(LineEnding::CrLf));
+    /// assert!(parser.has_bom());
+    /// let texts: Vec<String> = parser
+    ///     .filter_map(|mark| match mark {
+    ///         Mark::Text(text, _) => Some(text),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(texts, ["Title", "one", "two"]);
+    /// ```
+    #[inline]
+    pub fn line_ending(&self) -> Option<LineEnding> {
+        self.line_ending
+    }
+
+    /// Whether the document starts with a UTF-8 byte order mark, see [`Parser::line_ending`].
+    #[inline]
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// The front matter of the document, empty if it has none.
+    #[inline]
+    pub fn front_matter(&self) -> &FrontMatter {
+        &self.front_matter
+    }
+
+    /// The numbering of the captions of cross-references, the one of the options overridden by
+    /// the front matter, see [`crossref`](crate::crossref#numbering).
+    #[inline]
+    pub fn numbering(&self) -> &Numbering {
+        &self.numbering
+    }
+
+    /// The warnings about the part of the document parsed so far, see [`Warning`].
+    #[inline]
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The rules which matched in the part of the document parsed so far, if recorded with
+    /// [`ParserOptions::with_trace`].
+    #[inline]
+    pub fn trace(&self) -> Option<&ParseTrace> {
+        self.trace.as_ref()
+    }
+
     /// Convert the OpMark text content into vector of pages.
     ///
     /// A page would contain three fields:
@@ -51,30 +350,37 @@ impl Parser {
     /// 2. Count of Mark::Transition element in this page.
     /// 3. The index of the Mark::Transition element which just appeared.
     pub fn into_pages(iter: Self) -> Vec<(Mark, usize, usize)> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("into_pages").entered();
+        #[cfg(feature = "tracing")]
+        let mut _page_span = None;
+
         let mut pages: Vec<(Mark, usize, usize)> = vec![];
         for mark in iter {
             match mark {
                 Mark::Page(..) => {
+                    #[cfg(feature = "tracing")]
+                    {
+                        _page_span = None;
+                        _page_span =
+                            Some(tracing::trace_span!("page", index = pages.len()).entered());
+                    }
                     pages.push((mark, 0, 0));
                 }
                 _ => {
                     let mut pages_len = pages.len();
                     // push empty page
-                    if pages_len <= 0 {
-                        pages.push((Mark::Page(vec![]), 0, 0));
+                    if pages_len == 0 {
+                        pages.push((Mark::Page(vec![], StylePage::new()), 0, 0));
                         pages_len = 1;
                     }
-                    if let (Mark::Page(transitions), max_transition_idx, _) =
+                    if let (Mark::Page(transitions, _), max_transition_idx, _) =
                         &mut pages[pages_len - 1]
                     {
                         let transitions_len = transitions.len();
-                        let is_transition_end = if let Mark::TransitionEnd = mark {
-                            true
-                        } else {
-                            false
-                        };
+                        let is_transition_end = matches!(mark, Mark::TransitionEnd);
                         // push transition
-                        if let Mark::Transition(order, _) = mark {
+                        if let Mark::Transition(order, ..) = mark {
                             transitions.push(mark);
                             if order > *max_transition_idx {
                                 *max_transition_idx = order;
@@ -82,14 +388,18 @@ impl Parser {
                             continue;
                         }
                         // push empty transition
-                        if transitions_len <= 0 || is_transition_end {
-                            transitions.push(Mark::Transition(0, vec![]));
+                        if transitions_len == 0 || is_transition_end {
+                            transitions.push(Mark::Transition(0, vec![], StyleTransition::new()));
                             if is_transition_end {
                                 continue;
                             }
                         }
                         // push mark to current transition
-                        if let Mark::Transition(_, marks) = &mut transitions[transitions_len - 1] {
+                        if let Some(Mark::Transition(order, marks, _)) = transitions.last_mut() {
+                            if let Mark::CodeWalkthrough(_, _, steps) = &mark {
+                                let last_step = *order + steps.len().saturating_sub(1);
+                                *max_transition_idx = (*max_transition_idx).max(last_step);
+                            }
                             marks.push(mark);
                         }
                     }
@@ -103,72 +413,366 @@ impl Parser {
     /// ``code``
     fn code(&mut self) -> Option<Mark> {
         if self.s.starts_with('`') {
-            let this_line = &self.s[..self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len())];
+            let this_line = Cursor::new(&self.s).line();
             if let Some(end) = this_line[1..].find('`') {
                 let text = this_line[1..end + 1].to_owned();
-                self.s = self.s[end + 3..].to_owned();
+                self.s = self.s[end + 2..].to_owned();
+                self.is_line_start = false;
                 return Some(Mark::Text(text, StyleText::new().with_code()));
             }
         }
         None
     }
 
+    /// `$$x^2$$` alone on its line, or `x^2` on the lines between two `$$` lines. Other lines
+    /// starting with `$$` are text, where `$` toggles small text.
+    fn display_math(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let rest = this_line.strip_prefix("$$")?;
+        let (math, end) = match rest.trim_end().strip_suffix("$$") {
+            Some(math) if !math.contains("$$") => (math.to_owned(), this_line.len()),
+            Some(_) => return None,
+            None if rest.trim().is_empty() => {
+                let mut end = this_line.len();
+                let mut lines = vec![];
+                loop {
+                    let next = self.s[end..].strip_prefix(LINE_ENDING)?;
+                    let line = Cursor::new(next).line();
+                    end += LINE_ENDING.len() + line.len();
+                    if line.trim_end() == "$$" {
+                        break;
+                    }
+                    lines.push(line);
+                }
+                (lines.join(LINE_ENDING), end)
+            }
+            None => return None,
+        };
+        if math.trim().is_empty() {
+            return None;
+        }
+        self.s = self.s[end..].to_owned();
+        Some(Mark::Math(math, MathMode::Display))
+    }
+
+    /// `\(x^2\)`
+    fn inline_math(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let rest = this_line.strip_prefix("\\(")?;
+        let end = rest.find("\\)")?;
+        if rest[..end].trim().is_empty() {
+            return None;
+        }
+        let math = rest[..end].to_owned();
+        self.s = self.s[end + 4..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::Math(math, MathMode::Inline))
+    }
+
     /// ````language
     /// code
     /// ````
     fn code_block(&mut self) -> Option<Mark> {
         if self.s.starts_with("```") {
             if let Some(cb_end) = self.s.find(&format!("{}```", LINE_ENDING)) {
-                let first_line_end = self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len());
-                let first_line = self.s[3..first_line_end].to_owned();
-                let language = if first_line.len() > 0 {
-                    Some(first_line)
+                let first_line_end = self.s.find(LINE_ENDING).unwrap_or(self.s.len());
+                let info_line = self.s[3..first_line_end].to_owned();
+                let info = code_info(&info_line);
+                let language = if !info.language.is_empty() {
+                    Some(info.language.to_owned())
                 } else {
                     None
                 };
-                let code = self.s[first_line_end + 1..cb_end].to_owned();
+                let mut code = self
+                    .s
+                    .get(first_line_end + 1..cb_end)
+                    .unwrap_or_default()
+                    .to_owned();
+                if let (Some(path), Some(resolver)) = (info.file, self.options.resolver.clone()) {
+                    match resolver.resolve(path) {
+                        Some(content) => code = self.include(path, &content, info.lines),
+                        None => self.warn(Warning::IncludeNotFound {
+                            path: path.to_owned(),
+                        }),
+                    }
+                }
                 self.s = self.s[cb_end + 4..].to_owned();
+                if let Some(steps) = info.steps {
+                    // the steps after the first take the orders of the following transitions
+                    self.transition_order += steps.len() - 1;
+                    return Some(Mark::CodeWalkthrough(code, language, steps));
+                }
                 return Some(Mark::CodeBlock(code, language));
             }
         }
         None
     }
 
-    /// `# Heading`
-    fn heading(&mut self) -> Option<Mark> {
-        if self.s.starts_with('#') {
-            let line_end = self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len());
-            let this_line = &self.s[..line_end];
-            if this_line.len() > 2 {
-                let mut idx = 1;
-                let mut c = this_line.chars().nth(idx).unwrap();
-                let mut heading_level = 1;
-                while c == '#' && idx < this_line.len() - 1 {
-                    heading_level += 1;
-                    idx += 1;
-                    c = this_line.chars().nth(idx).unwrap();
+    /// The `lines` of the included file `path` with the given `content`, or all of them.
+    fn include(&mut self, path: &str, content: &str, lines: Option<(usize, usize)>) -> String {
+        let content: Vec<&str> = content.lines().collect();
+        let (first, last) = lines.unwrap_or((1, content.len()));
+        if last > content.len() {
+            self.warn(Warning::IncludeOutOfRange {
+                path: path.to_owned(),
+                lines: (first, last),
+                len: content.len(),
+            });
+        }
+        content
+            .get(first.saturating_sub(1)..last.min(content.len()))
+            .unwrap_or_default()
+            .join(LINE_ENDING)
+    }
+
+    /// The `data:` URI of the diagram at `src` rendered to SVG, or `src` if it is not a
+    /// diagram or can not be rendered.
+    #[cfg(feature = "diagrams")]
+    fn diagram(&mut self, src: String) -> String {
+        let (Some(format), Some(cache), Some(resolver)) = (
+            DiagramFormat::from_src(&src),
+            self.options.diagrams.clone(),
+            self.options.resolver.clone(),
+        ) else {
+            return src;
+        };
+        let svg = resolver
+            .resolve(&src)
+            .ok_or_else(|| "file not found".to_owned())
+            .and_then(|source| cache.to_svg(format, &source).map_err(|err| err.to_string()));
+        match svg {
+            Ok(svg) => svg_data_uri(&svg),
+            Err(reason) => {
+                self.warn(Warning::DiagramNotRendered {
+                    path: src.clone(),
+                    reason,
+                });
+                src
+            }
+        }
+    }
+
+    /// Where the rest of the document starts in the document.
+    pub(crate) fn offset(&self) -> usize {
+        self.source_len - self.s.len()
+    }
+
+    /// Record that `rule` matched from `start` to the rest of the document, if tracing.
+    fn record(&mut self, rule: RuleId, start: usize) {
+        let end = self.offset();
+        if let Some(trace) = &mut self.trace {
+            trace.record(rule, start..end);
+        }
+    }
+
+    fn warn(&mut self, warning: Warning) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(%warning, "lint");
+
+        self.warnings.push(warning);
+    }
+
+    /// `https://youtu.be/id`, `<https://youtu.be/id>`
+    fn embed(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let url = this_line.trim_end();
+        let url = url
+            .strip_prefix('<')
+            .and_then(|url| url.strip_suffix('>'))
+            .unwrap_or(url);
+        if url.contains(char::is_whitespace) {
+            return None;
+        }
+        let provider = embed::recognize(url)?;
+        let url = url.to_owned();
+        self.s = self.s[this_line.len()..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::Embed(url, provider))
+    }
+
+    /// `[label]: url`, a line defining the URL of reference links, which adds no mark
+    fn link_definition(&mut self) -> bool {
+        let this_line = Cursor::new(&self.s).line();
+        if parse_link_definition(this_line).is_none() {
+            return false;
+        }
+        self.s = self.s[this_line.len()..].to_owned();
+        true
+    }
+
+    /// `@bookmark(name)`
+    fn directive(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let directive = parse_directive(this_line)?;
+        self.s = self.s[this_line.len()..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::Directive(directive))
+    }
+
+    /// `!!!<options>`, followed by lines of images and a closing `!!!`
+    fn gallery(&mut self) -> Option<Mark> {
+        let mut cursor = Cursor::new(&self.s);
+        if !cursor.eat("!!!") {
+            return None;
+        }
+        let (rest, options) = split_options(cursor.eat_line());
+        if !rest.trim().is_empty() {
+            return None;
+        }
+        let mut items = vec![];
+        while !cursor.eat("!!!") {
+            if cursor.is_eof() {
+                return None;
+            }
+            if let Some(item) = gallery_item(cursor.eat_line()) {
+                items.push(item);
+            }
+        }
+        let mut style = StyleGallery::new();
+        for option in options.unwrap_or_default().split('|') {
+            style = match option.split_once('=') {
+                Some(("class", names)) => {
+                    classes(names).fold(style, |style, class| style.with_class(class))
                 }
+                Some(("columns", n)) => match n.parse::<usize>() {
+                    Ok(n) => style.with_columns(n),
+                    _ => style,
+                },
+                Some(("rows", n)) => match n.parse::<usize>() {
+                    Ok(n) => style.with_rows(n),
+                    _ => style,
+                },
+                _ => style,
+            };
+        }
+        self.s = self.s[cursor.pos()..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::Gallery(items, style))
+    }
 
-                let hash_end = idx - 1;
-                if let Some(text) = this_line[hash_end + 1..].strip_prefix(' ') {
-                    let text = text.to_owned();
-                    let heading = Heading::from(heading_level);
-                    let style = StyleText::new().with_heading(heading);
-                    self.s = self.s[line_end..].to_owned();
-                    self.is_line_start = false;
+    /// `%opmark 0.3`
+    fn declaration(&mut self) {
+        let marker = Rule::get(RuleId::Declaration).markers[0];
+        if let Some(rest) = self.s.strip_prefix(marker) {
+            let line_end = rest.find(LINE_ENDING).unwrap_or(rest.len());
+            if let Some(dialect) = Dialect::parse(rest[..line_end].trim()) {
+                let rest = rest[line_end..]
+                    .strip_prefix(LINE_ENDING)
+                    .unwrap_or_default();
+                self.s = rest.to_owned();
+                self.dialect = Some(dialect);
+                self.record(RuleId::Declaration, 0);
+                if !dialect.is_supported() {
+                    self.warn(Warning::UnsupportedDialect { dialect });
+                }
+            }
+        }
+    }
 
-                    return Some(Mark::Text(text, style));
+    fn front_matter_block(&mut self) {
+        let marker = Rule::get(RuleId::FrontMatter).markers[0];
+        let start = self.offset();
+        let mut lines = self.s.split_inclusive(LINE_ENDING);
+        let Some(first) = lines.next().filter(|line| line.trim_end() == marker) else {
+            return;
+        };
+        let mut end = first.len();
+        let mut body = vec![];
+        // without a closing line, the lines are not front matter
+        let is_closed = lines.any(|line| {
+            end += line.len();
+            let line = line.trim_end();
+            body.push(line);
+            line == marker
+        });
+        if !is_closed {
+            return;
+        }
+        body.pop();
+
+        let (front_matter, invalid) = FrontMatter::parse(body.into_iter());
+        let invalid: Vec<String> = invalid.into_iter().map(str::to_owned).collect();
+        self.front_matter = front_matter;
+        self.s = self.s[end..].to_owned();
+        for line in invalid {
+            self.warn(Warning::InvalidFrontMatter { line });
+        }
+        self.record(RuleId::FrontMatter, start);
+        self.check_budget();
+    }
+
+    /// Warn if the durations planned for the pages exceed the duration of the talk.
+    fn check_budget(&mut self) {
+        let Some(duration) = self.front_matter.duration() else {
+            return;
+        };
+        let mut budget = Duration::ZERO;
+        let mut is_code = false;
+        for line in self.s.lines() {
+            if line.starts_with("```") {
+                is_code = !is_code;
+            } else if let Some(style) = page_style(line).filter(|_| !is_code) {
+                budget += style.duration.filter(|_| !style.hidden).unwrap_or_default();
+            }
+        }
+        if budget > duration {
+            self.warn(Warning::OverBudget { budget, duration });
+        }
+    }
+
+    /// `# Heading`
+    fn heading(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        if this_line.len() <= 2 {
+            return None;
+        }
+        let mut cursor = Cursor::new(this_line);
+        let hashes = cursor.eat_while(|c| c == '#').len();
+        if hashes == 0 || !cursor.eat(" ") {
+            return None;
+        }
+        let mut heading_level = hashes.min(u8::MAX as usize) as u8;
+        // older dialects start headings at `##`
+        if self
+            .dialect
+            .is_some_and(|dialect| dialect < Dialect::SINGLE_HASH_HEADING)
+        {
+            heading_level -= 1;
+            if heading_level == 0 {
+                return None;
+            }
+        }
+        if let Some(max) = self
+            .options
+            .max_heading_level
+            .filter(|&max| heading_level > max)
+        {
+            match self.options.heading_overflow {
+                HeadingOverflow::Clamp => heading_level = max,
+                HeadingOverflow::Error => {
+                    self.warn(Warning::HeadingTooDeep {
+                        level: heading_level,
+                        max,
+                    });
+                    return None;
                 }
             }
         }
-        None
+        let text = cursor.rest().to_owned();
+        let style = StyleText::new().with_heading(Heading::from(heading_level));
+        self.s = self.s[this_line.len()..].to_owned();
+        self.is_line_start = false;
+        let text = self.replace_placeholders(text);
+        let text = self.replace_references(text);
+        Some(Mark::Text(text, style))
     }
 
     /// `<url>`, `[title](url)`
     fn hyperlink(&mut self) -> Option<Mark> {
         if self.s.starts_with('<') {
-            let this_line = &self.s[..self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len())];
-            if let Some(angle_end) = this_line.find('>') {
+            let this_line = Cursor::new(&self.s).line();
+            // `<>` links to nothing
+            if let Some(angle_end) = this_line.find('>').filter(|&end| end > 1) {
                 let url = this_line[1..angle_end].to_owned();
                 self.s = self.s[angle_end + 1..].to_owned();
                 self.is_line_start = false;
@@ -179,27 +783,83 @@ impl Parser {
             }
         }
         if self.s.starts_with('[') {
-            let this_line = &self.s[..self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len())];
+            let this_line = Cursor::new(&self.s).line();
             if let Some(bracket_end) = this_line.find(']') {
                 if this_line[bracket_end + 1..].starts_with('(') {
-                    if let Some(parens_end) = this_line[bracket_end + 2..].find(')') {
+                    let destination = &this_line[bracket_end + 2..];
+                    if let Some((parens_end, url, link_title)) = link_destination(destination) {
                         let parens_end = bracket_end + 2 + parens_end;
                         let title = this_line[1..bracket_end].to_owned();
-                        let url = this_line[bracket_end + 2..parens_end].to_owned();
+                        let style = link_style(url, link_title);
                         self.s = self.s[parens_end + 1..].to_owned();
                         self.is_line_start = false;
-                        return Some(Mark::Text(title, StyleText::new().with_hyperlink(url)));
+                        return Some(Mark::Text(title, style));
                     }
                 }
+                // `[title][label]`, or `[title][]` when the title is the label
+                if this_line[bracket_end + 1..].starts_with('[') {
+                    if let Some(label_end) = this_line[bracket_end + 2..].find(']') {
+                        let label_end = bracket_end + 2 + label_end;
+                        let title = this_line[1..bracket_end].to_owned();
+                        let label = match &this_line[bracket_end + 2..label_end] {
+                            "" => title.as_str(),
+                            label => label,
+                        };
+                        match self.link_definitions.get(&normalize_label(label)) {
+                            Some((url, link_title)) => {
+                                let style = link_style(url, link_title.as_deref());
+                                self.s = self.s[label_end + 1..].to_owned();
+                                self.is_line_start = false;
+                                return Some(Mark::Text(title, style));
+                            }
+                            None => {
+                                let label = label.to_owned();
+                                self.warn(Warning::UndefinedLinkLabel { label });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        // bare URLs, not within a word
+        if self.options.autolinks && !self.is_after_word {
+            if let Some(url) = autolink(&self.s) {
+                let url = url.to_owned();
+                self.s = self.s[url.len()..].to_owned();
+                self.is_line_start = false;
+                return Some(Mark::Text(
+                    url.clone(),
+                    StyleText::new().with_hyperlink(url),
+                ));
             }
         }
         None
     }
 
+    /// `{.class-a .class-b}`
+    fn inline_classes(&mut self) -> Option<Vec<String>> {
+        if self.s.starts_with("{.") {
+            let this_line = Cursor::new(&self.s).line();
+            let brace_end = this_line.find('}')?;
+            let classes = this_line[1..brace_end]
+                .split_whitespace()
+                .map(|class| {
+                    class
+                        .strip_prefix('.')
+                        .filter(|class| !class.is_empty())
+                        .map(str::to_owned)
+                })
+                .collect::<Option<Vec<String>>>()?;
+            self.s = self.s[brace_end + 1..].to_owned();
+            return Some(classes);
+        }
+        None
+    }
+
     /// `![title](src)<options>`
     fn image(&mut self) -> Option<Mark> {
         if self.s.starts_with("![") {
-            let this_line = &self.s[..self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len())];
+            let this_line = Cursor::new(&self.s).line();
             if let Some(bracket_end) = this_line.find(']') {
                 if this_line[bracket_end + 1..].starts_with('(') {
                     if let Some(parens_end) = this_line[bracket_end + 2..].find(')') {
@@ -220,14 +880,32 @@ impl Parser {
                                         "left" => style.with_align_h(AlignHorizontal::Left),
                                         "right" => style.with_align_h(AlignHorizontal::Right),
                                         "center" => style.with_align_h(AlignHorizontal::Center),
+                                        "autoplay" => style.with_autoplay(),
+                                        "loop" => style.with_loop(),
+                                        _ if option.starts_with("fps=") => {
+                                            match option["fps=".len()..].parse::<f32>() {
+                                                Ok(fps) => style.with_fps(fps),
+                                                _ => style,
+                                            }
+                                        }
+                                        _ if option.starts_with("pause-at=") => {
+                                            match option["pause-at=".len()..].parse::<usize>() {
+                                                Ok(frame) => style.with_pause_at(frame),
+                                                _ => style,
+                                            }
+                                        }
+                                        _ if option.starts_with("class=") => {
+                                            classes(&option["class=".len()..])
+                                                .fold(style, |style, class| style.with_class(class))
+                                        }
                                         _ => {
-                                            if option.starts_with('w') {
-                                                match option[1..].parse::<f32>() {
+                                            if let Some(width) = option.strip_prefix('w') {
+                                                match width.parse::<f32>() {
                                                     Ok(n) => style.with_width(n),
                                                     _ => style.with_hyperlink(option.to_owned()),
                                                 }
-                                            } else if option.starts_with('h') {
-                                                match option[1..].parse::<f32>() {
+                                            } else if let Some(height) = option.strip_prefix('h') {
+                                                match height.parse::<f32>() {
                                                     Ok(n) => style.with_height(n),
                                                     _ => style.with_hyperlink(option.to_owned()),
                                                 }
@@ -241,6 +919,8 @@ impl Parser {
                         }
                         self.s = self.s[image_end + 1..].to_owned();
                         self.is_line_start = false;
+                        #[cfg(feature = "diagrams")]
+                        let url = self.diagram(url);
 
                         return Some(Mark::Image(url, title, style));
                     }
@@ -250,21 +930,271 @@ impl Parser {
         None
     }
 
-    /// `1. ordered list`
-    fn ordered_list(&mut self) -> Option<Mark> {
-        let line_end = self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len());
-        let this_line = &self.s[..line_end];
-        let indent_level = indent(this_line);
-        let indent = (indent_level.to_int() * 2) as usize;
+    /// Inline elements: the next text element, or `None` if a style was toggled.
+    fn inline(&mut self) -> Option<Mark> {
+        let start = self.offset();
+        let inlines = RULES
+            .iter()
+            .filter(|rule| matches!(rule.kind, RuleKind::Inline | RuleKind::Toggle));
+        for rule in inlines {
+            let mark = match rule.id {
+                RuleId::Bold
+                | RuleId::Italics
+                | RuleId::Small
+                | RuleId::Strikethrough
+                | RuleId::Underline => {
+                    if self.toggle(rule) {
+                        self.record(rule.id, start);
+                        return None;
+                    }
+                    continue;
+                }
+                RuleId::Code => self.code(),
+                RuleId::InlineMath => self.inline_math(),
+                RuleId::Hyperlink => self.hyperlink(),
+                // `{.class}`
+                RuleId::Classes => {
+                    if let Some(classes) = self.inline_classes() {
+                        self.is_line_start = false;
+                        self.is_after_word = false;
+                        self.style_text.classes = classes;
+                        self.record(rule.id, start);
+                        return None;
+                    }
+                    continue;
+                }
+                RuleId::Reference => self.reference(),
+                RuleId::Placeholder => self.placeholder(),
+                RuleId::Escape => self.escape(),
+                RuleId::Text => Some(self.text()),
+                _ => None,
+            };
+            if mark.is_some() {
+                self.is_after_word = matches!(rule.id, RuleId::Escape | RuleId::Text)
+                    && mark.as_ref().is_some_and(ends_with_word);
+                self.record(rule.id, start);
+                return mark;
+            }
+        }
+        let text = self.text();
+        self.is_after_word = ends_with_word(&text);
+        self.record(RuleId::Text, start);
+        Some(text)
+    }
+
+    /// `\` escape
+    fn escape(&mut self) -> Option<Mark> {
+        let c = self
+            .s
+            .strip_prefix('\\')
+            .and_then(|rest| rest.chars().next())?;
+        self.s = self.s[1 + c.len_utf8()..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::Text(c.to_string(), self.style_text.clone()))
+    }
 
-        let mut idx = indent;
-        let mut b = this_line.as_bytes()[idx];
-        while b.is_ascii_digit() && idx < this_line.len() - 1 {
-            idx += 1;
-            b = this_line.as_bytes()[idx];
+    /// `@ref(label)`
+    fn reference(&mut self) -> Option<Mark> {
+        if !self.options.cross_references {
+            return None;
+        }
+        let (label, len) = reference_at(Cursor::new(&self.s).line())?;
+        match self.labels.get(label) {
+            Some(&(kind, number)) => {
+                let style = self
+                    .style_text
+                    .clone()
+                    .with_hyperlink(format!("#{}", label));
+                self.s = self.s[len..].to_owned();
+                self.is_line_start = false;
+                Some(Mark::Text(self.numbering.caption(kind, number), style))
+            }
+            None => {
+                let label = label.to_owned();
+                self.warn(Warning::UndefinedReference { label });
+                None
+            }
         }
+    }
 
-        if this_line[idx..].starts_with(". ") {
+    /// Replace the references of `text`, which is taken as is, by the captions of their labels.
+    fn replace_references(&mut self, text: String) -> String {
+        if !self.options.cross_references {
+            return text;
+        }
+        let mut replaced = String::with_capacity(text.len());
+        let mut undefined = vec![];
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find("@ref(") {
+            replaced.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let Some((label, len)) = reference_at(rest) else {
+                replaced.push('@');
+                rest = &rest[1..];
+                continue;
+            };
+            match self.labels.get(label) {
+                Some(&(kind, number)) => replaced.push_str(&self.numbering.caption(kind, number)),
+                None => {
+                    undefined.push(label.to_owned());
+                    replaced.push_str(&rest[..len]);
+                }
+            }
+            rest = &rest[len..];
+        }
+        replaced.push_str(rest);
+        for label in undefined {
+            self.warn(Warning::UndefinedReference { label });
+        }
+        replaced
+    }
+
+    /// `{{name}}`
+    fn placeholder(&mut self) -> Option<Mark> {
+        if !self.options.has_placeholders() {
+            return None;
+        }
+        let (name, len) = placeholder_at(Cursor::new(&self.s).line())?;
+        match self.placeholder_value(name) {
+            Ok(value) => {
+                self.s = self.s[len..].to_owned();
+                self.is_line_start = false;
+                Some(Mark::Text(value, self.style_text.clone()))
+            }
+            Err(warning) => {
+                self.warn(warning);
+                None
+            }
+        }
+    }
+
+    /// Replace the placeholders of `text`, which is taken as is, like the text of a heading.
+    fn replace_placeholders(&mut self, text: String) -> String {
+        if !self.options.has_placeholders() {
+            return text;
+        }
+        let mut replaced = String::with_capacity(text.len());
+        let mut warnings = vec![];
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find("{{") {
+            replaced.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let Some((name, len)) = placeholder_at(rest) else {
+                replaced.push_str("{{");
+                rest = &rest[2..];
+                continue;
+            };
+            match self.placeholder_value(name) {
+                Ok(value) => replaced.push_str(&value),
+                Err(warning) => {
+                    warnings.push(warning);
+                    replaced.push_str(&rest[..len]);
+                }
+            }
+            rest = &rest[len..];
+        }
+        replaced.push_str(rest);
+        for warning in warnings {
+            self.warn(warning);
+        }
+        replaced
+    }
+
+    /// The value of the placeholder `name`, or the warning telling why it has none.
+    fn placeholder_value(&self, name: &str) -> Result<String, Warning> {
+        let value = match name.strip_prefix("env:") {
+            Some(var) => {
+                if !self.options.env_vars.iter().any(|allowed| allowed == var) {
+                    let name = var.to_owned();
+                    return Err(Warning::EnvVarNotAllowed { name });
+                }
+                env::var(var).ok()
+            }
+            None => self
+                .options
+                .placeholders
+                .as_ref()
+                .and_then(|placeholders| placeholders.resolve(name)),
+        };
+        value.ok_or_else(|| Warning::UnknownPlaceholder {
+            name: name.to_owned(),
+        })
+    }
+
+    /// Plain text until the next special character, start of a marker or line ending.
+    fn text(&mut self) -> Mark {
+        let markers = &self.options.markers;
+        let end = self
+            .s
+            .find(|c: char| SPECIAL_CHARS.contains(&c) || markers.is_marker_start(c))
+            .map_or_else(|| self.s.len(), |special| special.max(1));
+        let line_end = self
+            .s
+            .find(LINE_ENDING)
+            .map_or_else(|| self.s.len(), |special| special.max(1));
+        let end = if end < line_end { end } else { line_end };
+        let end = match self.options.autolinks {
+            true => autolink_start(&self.s[..line_end]).map_or(end, |start| end.min(start.max(1))),
+            false => end,
+        };
+        let end = match self.options.cross_references {
+            true => self.s[..line_end]
+                .find("@ref(")
+                .map_or(end, |start| end.min(start.max(1))),
+            false => end,
+        };
+        let text = Mark::Text(self.s[..end].to_owned(), self.style_text.clone());
+        self.s = self.s[end..].to_owned();
+        self.is_line_start = false;
+        text
+    }
+
+    /// `*bold*`, `/italics/`, `$small$`, `~strikethrough~`, `_underline_`, `**bold**`,
+    /// `~~strikethrough~~`, `__underline__`, or the markers of the options
+    fn toggle(&mut self, rule: &Rule) -> bool {
+        let markers = &self.options.markers;
+        let doubled = self
+            .dialect
+            .is_none_or(|dialect| dialect >= Dialect::DOUBLED_MARKERS);
+        // a longer marker of another rule wins
+        let len = match markers.marker_at(&self.s, doubled) {
+            Some((id, len)) if id == rule.id => len,
+            _ => return false,
+        };
+        // a single marker within a word is text, when the style has a doubled marker
+        let is_intraword = doubled
+            && self.is_after_word
+            && markers.doubled(rule.id).is_some()
+            && self.s[..len].chars().count() == 1
+            && self.s[len..].starts_with(char::is_alphanumeric);
+        if is_intraword {
+            return false;
+        }
+        let rest = self.s[len..].to_owned();
+        let style = &mut self.style_text;
+        let toggled = match rule.id {
+            RuleId::Bold => &mut style.bold,
+            RuleId::Italics => &mut style.italics,
+            RuleId::Small => &mut style.small,
+            RuleId::Strikethrough => &mut style.strikethrough,
+            RuleId::Underline => &mut style.underline,
+            _ => return false,
+        };
+        *toggled = !*toggled;
+        self.s = rest;
+        self.is_line_start = false;
+        self.is_after_word = false;
+        true
+    }
+
+    /// `1. ordered list`
+    fn ordered_list(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let (indent_level, indent) = indent(this_line, self.options.tab_width)?;
+
+        let mut cursor = Cursor::new(&this_line[indent..]);
+        cursor.eat_while(|c| c.is_ascii_digit());
+        if cursor.eat(". ") {
             let ordered_number = if self.is_ordered
                 && self.ordered_list_current_indent_level_int >= indent_level.to_int()
             {
@@ -276,14 +1206,15 @@ impl Parser {
             } else {
                 1
             };
-            if let Some(number) = self.indent_orderer_number_map.get_mut(&indent_level) {
-                *number = ordered_number;
-            }
-            let text = this_line[idx + 2..].to_owned();
-            self.s = self.s[line_end..].to_owned();
+            self.indent_orderer_number_map
+                .insert(indent_level.clone(), ordered_number);
+            let text = cursor.rest().to_owned();
+            self.s = self.s[this_line.len()..].to_owned();
             self.is_line_start = false;
             self.is_ordered = true;
             self.ordered_list_current_indent_level_int = indent_level.to_int();
+            let text = self.replace_placeholders(text);
+            let text = self.replace_references(text);
             return Some(Mark::Text(
                 text,
                 StyleText::new().with_listing(Listing::Ordered(ordered_number, indent_level)),
@@ -292,81 +1223,232 @@ impl Parser {
         None
     }
 
+    /// Whether the next mark of the document starts a new page, or there is none.
+    fn is_page_pending(&self) -> bool {
+        let s = self.s.strip_prefix(LINE_ENDING).unwrap_or(&self.s);
+        match s.find(LINE_ENDING) {
+            Some(line_end) => page_style(&s[..line_end]).is_some(),
+            None => s.is_empty(),
+        }
+    }
+
+    /// Whether the next mark of the document starts a new transition or page, or there is none.
+    fn is_transition_pending(&self) -> bool {
+        let s = self.s.strip_prefix(LINE_ENDING).unwrap_or(&self.s);
+        self.is_page_pending() || s.starts_with("---t")
+    }
+
     fn reset_indent_orderer_number_map(&mut self) {
-        self.indent_orderer_number_map.insert(IndentLevel::None, 0);
-        self.indent_orderer_number_map.insert(IndentLevel::I1, 0);
-        self.indent_orderer_number_map.insert(IndentLevel::I2, 0);
-        self.indent_orderer_number_map.insert(IndentLevel::I3, 0);
-        self.indent_orderer_number_map.insert(IndentLevel::I4, 0);
-        self.indent_orderer_number_map.insert(IndentLevel::I5, 0);
+        self.indent_orderer_number_map.clear();
+    }
+
+    /// `---`, `---<options>`
+    fn page(&mut self) -> Option<Mark> {
+        let line_end = self.s.find(LINE_ENDING)?;
+        let style = page_style(&self.s[..line_end])?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(?style, "page mark");
+        self.s = self.s[line_end + LINE_ENDING.len()..].to_owned();
+        self.transition_order = 0;
+        Some(Mark::Page(vec![], style))
     }
 
     /// `> quote`
     fn quote(&mut self) -> Option<Mark> {
         if self.s.starts_with("> ") {
-            let line_end = self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len());
+            let line_end = self.s.find(LINE_ENDING).unwrap_or(self.s.len());
             let this_line = &self.s[..line_end];
             let text = this_line[2..].to_owned();
             self.s = self.s[line_end..].to_owned();
             self.is_line_start = false;
+            let text = self.replace_placeholders(text);
+            let text = self.replace_references(text);
             return Some(Mark::Text(text, StyleText::new().with_quote()));
         }
         None
     }
 
+    /// `----`, `----v`, `----<options>`
     fn separator(&mut self) -> Option<Mark> {
-        if let Some(rest) = self.s.strip_prefix(&format!("----{}", LINE_ENDING)) {
-            self.s = rest.to_owned();
-            return Some(Mark::Separator(SeparatorDir::Horizontal));
+        let this_line = Cursor::new(&self.s).line();
+        let rest = this_line.strip_prefix("----")?;
+        let (dir, rest) = match rest.strip_prefix('v') {
+            Some(rest) => (SeparatorDir::Vertical, rest),
+            None => (SeparatorDir::Horizontal, rest),
+        };
+        let (rest, options) = split_options(rest);
+        if !rest.is_empty() || !self.s[this_line.len()..].starts_with(LINE_ENDING) {
+            return None;
         }
-        if let Some(rest) = self.s.strip_prefix(&format!("----v{}", LINE_ENDING)) {
-            self.s = rest.to_owned();
-            return Some(Mark::Separator(SeparatorDir::Vertical));
+        let mut style = SeparatorStyle::new();
+        for option in options.unwrap_or_default().split('|') {
+            style = match option {
+                "solid" => style.with_line(SeparatorLine::Solid),
+                "dotted" => style.with_line(SeparatorLine::Dotted),
+                "dashed" => style.with_line(SeparatorLine::Dashed),
+                "thin" => style.with_thickness(SeparatorThickness::Thin),
+                "thick" => style.with_thickness(SeparatorThickness::Thick),
+                _ => match option.strip_suffix('%').map(str::parse::<f32>) {
+                    Some(Ok(percent)) => style.with_length(percent),
+                    _ => style,
+                },
+            };
         }
-        None
+        self.s = self.s[this_line.len() + LINE_ENDING.len()..].to_owned();
+        Some(Mark::Separator(dir, style))
     }
 
-    /// `---t`, `---t1`
-    fn transition(&mut self) -> Option<Mark> {
-        if self.s.starts_with("---t") {
-            let this_line = &self.s[..self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len())];
-            let order = if this_line.len() > 4 {
-                let mut idx = 4;
-                let mut b = this_line.as_bytes()[idx];
-                while b.is_ascii_digit() && idx < this_line.len() - 1 {
-                    idx += 1;
-                    b = this_line.as_bytes()[idx];
+    /// `@space(2)`
+    fn space(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let lines = this_line
+            .trim_end()
+            .strip_prefix("@space(")?
+            .strip_suffix(')')?
+            .parse::<f32>()
+            .ok()
+            .filter(|lines| lines.is_finite() && *lines >= 0.0)?;
+        self.s = self.s[this_line.len()..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::Space(lines))
+    }
+
+    /// Skip the malformed block at the start of the line up to the next empty line or page,
+    /// warning about it. Returns `false` if the line does not start a block.
+    fn skip_malformed_block(&mut self) -> bool {
+        let this_line = Cursor::new(&self.s).line();
+        let Some(rule) = RECOVERED_BLOCKS
+            .iter()
+            .map(|&id| Rule::get(id))
+            .find(|rule| rule.markers.iter().any(|m| this_line.starts_with(m)))
+        else {
+            return false;
+        };
+        let mut end = this_line.len();
+        self.warn(Warning::MalformedBlock {
+            rule: rule.id,
+            line: this_line.trim_end().to_owned(),
+        });
+        let page_marker = Rule::get(RuleId::Page).markers[0];
+        while let Some(rest) = self.s[end..].strip_prefix(LINE_ENDING) {
+            if rest.starts_with(LINE_ENDING) || rest.starts_with(page_marker) {
+                break;
+            }
+            end += LINE_ENDING.len() + Cursor::new(rest).line().len();
+        }
+        self.s = self.s[end..].to_owned();
+        self.is_line_start = false;
+        true
+    }
+
+    /// `@holdslide(start=14:00, message="Starting soon")`
+    fn hold_slide(&mut self) -> Option<Mark> {
+        let this_line = Cursor::new(&self.s).line();
+        let mut rest = this_line
+            .trim_end()
+            .strip_prefix("@holdslide(")?
+            .strip_suffix(')')?;
+        let (mut start, mut message) = (None, String::new());
+        while !rest.trim().is_empty() {
+            let (key, value) = rest.split_once('=')?;
+            let value = value.trim_start();
+            let (value, after) = match value.strip_prefix('"') {
+                Some(quoted) => {
+                    let mut unquoted = String::new();
+                    let mut chars = quoted.char_indices();
+                    let end = loop {
+                        match chars.next()? {
+                            (idx, '"') => break idx + 1,
+                            (_, '\\') => unquoted.push(chars.next()?.1),
+                            (_, c) => unquoted.push(c),
+                        }
+                    };
+                    (unquoted, &quoted[end..])
                 }
-                idx += 1;
-                if idx == this_line.len() {
-                    match this_line[4..idx].parse::<usize>() {
-                        Ok(x) => x,
-                        _ => self.transition_order,
-                    }
-                } else {
-                    self.transition_order
+                None => {
+                    let end = value.find(',').unwrap_or(value.len());
+                    (value[..end].trim_end().to_owned(), &value[end..])
                 }
-            } else {
-                self.transition_order
             };
+            match key.trim() {
+                "start" => start = Some(parse_time_of_day(&value)?),
+                "message" => message = value,
+                _ => return None,
+            }
+            let after = after.trim_start();
+            rest = match after.strip_prefix(',') {
+                Some(after) => after,
+                None if after.is_empty() => after,
+                None => return None,
+            };
+        }
+        self.s = self.s[this_line.len()..].to_owned();
+        self.is_line_start = false;
+        Some(Mark::HoldSlide(start?, message))
+    }
+
+    /// `---t`, `---t1`, `---t<options>`
+    fn transition(&mut self) -> Option<Mark> {
+        if self.s.starts_with("---t") {
+            let this_line = Cursor::new(&self.s).line();
+            let (order, options) = split_options(&this_line[4..]);
+            let order = order.parse::<usize>().unwrap_or(self.transition_order);
+            let mut style = StyleTransition::new();
+            for option in options.unwrap_or_default().split('|') {
+                if option == "loop" {
+                    style = style.with_loop();
+                    continue;
+                }
+                let (effect, target) = match option.split_once(':') {
+                    Some((effect, target)) => (effect, Some(target)),
+                    None => (option, None),
+                };
+                let effect = match effect {
+                    "appear" => TransitionEffect::Appear,
+                    "disappear" => TransitionEffect::Disappear,
+                    "dim" => TransitionEffect::Dim,
+                    "replace" => TransitionEffect::Replace,
+                    _ => continue,
+                };
+                let target = match target {
+                    None if effect == TransitionEffect::Replace => TransitionTarget::Prev,
+                    None => TransitionTarget::This,
+                    Some("prev") => TransitionTarget::Prev,
+                    Some(order) => match order.parse::<usize>() {
+                        Ok(order) => TransitionTarget::Order(order),
+                        _ => continue,
+                    },
+                };
+                style = style.with_effect(effect, target);
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(order, ?style, "transition mark");
             self.transition_order = order + 1;
             self.s = self.s[this_line.len()..].to_owned();
-            return Some(Mark::Transition(order, vec![]));
+            return Some(Mark::Transition(order, vec![], style));
         }
         None
     }
 
+    /// `t---`
+    fn transition_end(&mut self) -> Option<Mark> {
+        let rest = self.s.strip_prefix(&format!("t---{}", LINE_ENDING))?;
+        self.s = rest.to_owned();
+        Some(Mark::TransitionEnd)
+    }
+
     /// `- unordered list`
     fn unordered_list(&mut self) -> Option<Mark> {
-        let line_end = self.s.find(LINE_ENDING).unwrap_or_else(|| self.s.len());
+        let line_end = self.s.find(LINE_ENDING).unwrap_or(self.s.len());
         let this_line = &self.s[..line_end];
-        let indent_level = indent(this_line);
-        let indent = (indent_level.to_int() * 2) as usize;
+        let (indent_level, indent) = indent(this_line, self.options.tab_width)?;
         if self.s[indent..].starts_with("- ") {
-            let text = this_line[indent + 1..].to_owned();
+            let text = this_line[indent + 2..].to_owned();
             self.s = self.s[line_end..].to_owned();
             self.is_line_start = false;
             self.is_unordered = true;
+            let text = self.replace_placeholders(text);
+            let text = self.replace_references(text);
             return Some(Mark::Text(
                 text,
                 StyleText::new().with_listing(Listing::Unordered(indent_level)),
@@ -382,15 +1464,19 @@ impl Iterator for Parser {
     fn next(&mut self) -> Option<Self::Item> {
         if !self.first_page_return {
             self.first_page_return = true;
-            return Some(Mark::Page(vec![]));
+            if !(self.options.suppress_synthetic && self.is_page_pending()) {
+                return Some(Mark::Page(vec![], StylePage::new()));
+            }
         }
 
-        if self.transition_order == 0 {
+        if self.transition_order == 0
+            && !(self.options.suppress_synthetic && self.is_transition_pending())
+        {
             self.transition_order = 1;
-            return Some(Mark::Transition(0, vec![]));
+            return Some(Mark::Transition(0, vec![], StyleTransition::new()));
         }
 
-        loop {
+        'lines: loop {
             if self.s.is_empty() {
                 return None;
             }
@@ -399,7 +1485,8 @@ impl Iterator for Parser {
                 self.s = rest.to_owned();
                 self.indent_level = 0;
                 self.is_line_start = true;
-                self.style_text = StyleText::new();
+                self.is_after_word = false;
+                self.style_text = self.base_style_text.clone();
                 let is_empty = self.s.is_empty();
                 if self.s.starts_with(LINE_ENDING) || is_empty {
                     if !is_empty {
@@ -414,133 +1501,824 @@ impl Iterator for Parser {
             }
 
             if self.is_line_start {
-                if let Some(rest) = self.s.strip_prefix(&format!("---{}", LINE_ENDING)) {
-                    self.s = rest.to_owned();
-                    self.transition_order = 0;
-                    return Some(Mark::Page(vec![]));
+                let start = self.offset();
+                let blocks = RULES.iter().filter(|rule| rule.kind == RuleKind::Block);
+                for rule in blocks {
+                    let mark = match rule.id {
+                        RuleId::Page => self.page(),
+                        RuleId::Transition => self.transition(),
+                        RuleId::TransitionEnd => self.transition_end(),
+                        RuleId::CodeBlock => self.code_block(),
+                        RuleId::DisplayMath => self.display_math(),
+                        RuleId::Gallery => self.gallery(),
+                        RuleId::Heading => self.heading(),
+                        RuleId::Image => self.image(),
+                        RuleId::OrderedList => self.ordered_list(),
+                        RuleId::Quote => self.quote(),
+                        RuleId::Separator => self.separator(),
+                        RuleId::Space => self.space(),
+                        RuleId::HoldSlide => self.hold_slide(),
+                        RuleId::UnorderedList => self.unordered_list(),
+                        RuleId::Embed if self.options.embeds => self.embed(),
+                        RuleId::Directive => self.directive(),
+                        RuleId::LinkDefinition => {
+                            if self.link_definition() {
+                                self.record(rule.id, start);
+                                continue 'lines;
+                            }
+                            None
+                        }
+                        _ => None,
+                    };
+                    if mark.is_some() {
+                        self.record(rule.id, start);
+                        return mark;
+                    }
                 }
-
-                if let Some(mark) = self.transition() {
-                    return Some(mark);
+                if self.skip_malformed_block() {
+                    continue 'lines;
                 }
+            }
 
-                if let Some(rest) = self.s.strip_prefix(&format!("t---{}", LINE_ENDING)) {
-                    self.s = rest.to_owned();
-                    return Some(Mark::TransitionEnd);
-                }
+            // `None` if a style was toggled
+            if let Some(mark) = self.inline() {
+                return Some(mark);
+            }
+        }
+    }
+}
 
-                if let Some(mark) = self.code_block() {
-                    return Some(mark);
-                }
+/// Where a snippet of OpMark text is inserted, see [`Parser::parse_fragment`].
+#[derive(Clone, Debug, Default)]
+pub struct FragmentContext {
+    /// Whether the snippet is inserted in the middle of a line, where block elements like
+    /// headings and lists are not recognized.
+    pub inline: bool,
+    /// The style of the text the snippet is inserted into, inherited by the inline text of the
+    /// snippet.
+    pub style: StyleText,
+}
 
-                if let Some(mark) = self.heading() {
-                    return Some(mark);
-                }
+impl FragmentContext {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
 
-                if let Some(mark) = self.image() {
-                    return Some(mark);
-                }
+    #[inline]
+    pub fn with_inline(mut self) -> Self {
+        self.inline = true;
+        self
+    }
 
-                if let Some(mark) = self.ordered_list() {
-                    return Some(mark);
-                }
+    #[inline]
+    pub fn with_style(mut self, style: StyleText) -> Self {
+        self.style = style;
+        self
+    }
+}
 
-                if let Some(mark) = self.quote() {
-                    return Some(mark);
-                }
+/// Options for the parser.
+#[derive(Clone, Debug)]
+pub struct ParserOptions {
+    /// Turn bare `http://` and `https://` URLs in text into hyperlinks, like `<https://…>`.
+    /// Punctuation ending a URL, like the period of a sentence, is not part of it.
+    pub autolinks: bool,
+    /// Replace cross-references, like `@ref(fig:arch)`, by the captions of their labels, see
+    /// [`crossref`](crate::crossref).
+    pub cross_references: bool,
+    /// Turn links to recognized online services on their own line into `Mark::Embed` elements.
+    pub embeds: bool,
+    /// The environment variables `{{env:NAME}}` placeholders may read, like the ones a CI job
+    /// sets. Placeholders reading other variables stay as written, so documents cannot read
+    /// secrets from the environment.
+    pub env_vars: Vec<String>,
+    /// What happens to headings deeper than [`ParserOptions::max_heading_level`].
+    pub heading_overflow: HeadingOverflow,
+    /// The markers of bold, italics, small, strikethrough and underlined text, see [`Markers`].
+    pub markers: Markers,
+    /// The deepest heading level, like 6 for the levels of HTML. Without a maximum, headings
+    /// keep the level of their `#`.
+    pub max_heading_level: Option<u8>,
+    /// The words and the number styles of captions, like the ones of the locale of the
+    /// application. The front matter of the document may override them, see
+    /// [`crossref`](crate::crossref#numbering).
+    pub numbering: Numbering,
+    /// Replace placeholders, like `{{today}}`, by their values, see
+    /// [`placeholder`](crate::placeholder). Without placeholders, they stay as written.
+    pub placeholders: Option<Placeholders>,
+    /// Resolve the files included by code blocks with a `file=path` attribute. Without a resolver,
+    /// code blocks keep their own content.
+    pub resolver: Option<Arc<dyn IncludeResolver>>,
+    /// Omit the synthetic page and transition marks which would stay empty.
+    pub suppress_synthetic: bool,
+    /// The width of a tab in the indentation of list items, in spaces. A tab indents up to the
+    /// next multiple of the width, and each two spaces of indentation nest a level, so with the
+    /// default width of 2, a tab nests a level.
+    pub tab_width: usize,
+    /// Record the rules which match in a [`ParseTrace`], see [`Parser::trace`].
+    pub trace: bool,
+    /// Render the diagrams shown by images to SVG, reading them with the resolver, see
+    /// [`diagram`](crate::diagram).
+    #[cfg(feature = "diagrams")]
+    pub diagrams: Option<Arc<DiagramCache>>,
+}
 
-                if let Some(mark) = self.separator() {
-                    return Some(mark);
-                }
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            autolinks: false,
+            cross_references: false,
+            embeds: false,
+            env_vars: vec![],
+            heading_overflow: HeadingOverflow::default(),
+            markers: Markers::default(),
+            max_heading_level: None,
+            numbering: Numbering::default(),
+            placeholders: None,
+            resolver: None,
+            suppress_synthetic: false,
+            tab_width: 2,
+            trace: false,
+            #[cfg(feature = "diagrams")]
+            diagrams: None,
+        }
+    }
+}
 
-                if let Some(mark) = self.unordered_list() {
-                    return Some(mark);
-                }
-            }
+impl ParserOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
 
-            // `*bold*`
-            if let Some(rest) = self.s.strip_prefix('*') {
-                self.s = rest.to_owned();
-                self.is_line_start = false;
-                self.style_text.bold = !self.style_text.bold;
-                continue;
-            }
+    /// ```
+    /// use opmark::{mark::Mark, Parser, ParserOptions};
+    ///
+    /// let text = "See https://github.com/fralonra/opmark. (or http://a.io/b_(c))";
+    /// let options = ParserOptions::new().with_autolinks();
+    /// let links: Vec<String> = Parser::with_options(text.to_owned(), options)
+    ///     .filter_map(|mark| match mark {
+    ///         Mark::Text(_, style) if !style.hyperlink.is_empty() => Some(style.hyperlink),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(links, ["https://github.com/fralonra/opmark", "http://a.io/b_(c)"]);
+    /// ```
+    #[inline]
+    pub fn with_autolinks(mut self) -> Self {
+        self.autolinks = true;
+        self
+    }
 
-            if let Some(mark) = self.code() {
-                return Some(mark);
-            }
+    #[inline]
+    pub fn with_cross_references(mut self) -> Self {
+        self.cross_references = true;
+        self
+    }
 
-            if let Some(mark) = self.hyperlink() {
-                return Some(mark);
-            }
+    #[cfg(feature = "diagrams")]
+    #[inline]
+    pub fn with_diagrams(mut self, cache: Arc<DiagramCache>) -> Self {
+        self.diagrams = Some(cache);
+        self
+    }
 
-            // `/italics/`
-            if let Some(rest) = self.s.strip_prefix('/') {
-                self.s = rest.to_owned();
-                self.is_line_start = false;
-                self.style_text.italics = !self.style_text.italics;
-                continue;
-            }
+    #[inline]
+    pub fn with_embeds(mut self) -> Self {
+        self.embeds = true;
+        self
+    }
 
-            // `$small$`
-            if let Some(rest) = self.s.strip_prefix('$') {
-                self.s = rest.to_owned();
-                self.is_line_start = false;
-                self.style_text.small = !self.style_text.small;
-                continue;
-            }
+    /// Allow `{{env:NAME}}` placeholders to read the environment variable `name`.
+    ///
+    /// ```
+    /// use opmark::{lint::Warning, mark::Mark, Parser, ParserOptions};
+    ///
+    /// std::env::set_var("CI_COMMIT_TAG", "v2.1.0");
+    /// let options = ParserOptions::new().with_env_var("CI_COMMIT_TAG".to_owned());
+    /// let mut parser = Parser::with_options(
+    ///     "# Release {{env:CI_COMMIT_TAG}}\n{{env:AWS_SECRET_ACCESS_KEY}}".to_owned(),
+    ///     options,
+    /// );
+    /// let texts: Vec<String> = parser
+    ///     .by_ref()
+    ///     .filter_map(|mark| match mark {
+    ///         Mark::Text(text, _) => Some(text),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(texts[0], "Release v2.1.0");
+    /// assert_eq!(texts[1..].concat(), "{{env:AWS_SECRET_ACCESS_KEY}}");
+    /// assert_eq!(
+    ///     parser.warnings(),
+    ///     [Warning::EnvVarNotAllowed { name: "AWS_SECRET_ACCESS_KEY".to_owned() }]
+    /// );
+    /// ```
+    pub fn with_env_var(mut self, name: String) -> Self {
+        if !self.env_vars.contains(&name) {
+            self.env_vars.push(name);
+        }
+        self
+    }
 
-            // `~strikethrough~`
-            if let Some(rest) = self.s.strip_prefix('~') {
-                self.s = rest.to_owned();
-                self.is_line_start = false;
-                self.style_text.strikethrough = !self.style_text.strikethrough;
-                continue;
-            }
+    #[inline]
+    pub fn with_heading_overflow(mut self, overflow: HeadingOverflow) -> Self {
+        self.heading_overflow = overflow;
+        self
+    }
 
-            // `_underline_`
-            if let Some(rest) = self.s.strip_prefix('_') {
-                self.s = rest.to_owned();
-                self.is_line_start = false;
-                self.style_text.underline = !self.style_text.underline;
-                continue;
-            }
+    #[inline]
+    pub fn with_markers(mut self, markers: Markers) -> Self {
+        self.markers = markers;
+        self
+    }
 
-            // \ escape
-            if self.s.starts_with('\\') && self.s.len() >= 2 {
-                let text = self.s[1..2].to_owned();
-                self.s = self.s[2..].to_owned();
-                self.is_line_start = false;
-                return Some(Mark::Text(text, StyleText::new()));
-            }
+    /// ```
+    /// use opmark::{lint::Warning, mark::Mark, HeadingOverflow, Parser, ParserOptions};
+    ///
+    /// let source = "###### Details\n######## Fine print";
+    /// let headings = |parser: &mut Parser| -> Vec<(String, u8)> {
+    ///     parser
+    ///         .filter_map(|mark| match mark {
+    ///             Mark::Text(text, style) => Some((text, style.heading.to_int())),
+    ///             _ => None,
+    ///         })
+    ///         .collect()
+    /// };
+    /// let mut parser = Parser::new(source.to_owned());
+    /// assert_eq!(headings(&mut parser)[1], ("Fine print".to_owned(), 8));
+    ///
+    /// let options = ParserOptions::new().with_max_heading_level(6);
+    /// let mut parser = Parser::with_options(source.to_owned(), options.clone());
+    /// assert_eq!(headings(&mut parser)[1], ("Fine print".to_owned(), 6));
+    ///
+    /// let options = options.with_heading_overflow(HeadingOverflow::Error);
+    /// let mut parser = Parser::with_options(source.to_owned(), options);
+    /// assert_eq!(headings(&mut parser)[1], ("######## Fine print".to_owned(), 0));
+    /// assert_eq!(parser.warnings(), [Warning::HeadingTooDeep { level: 8, max: 6 }]);
+    /// ```
+    #[inline]
+    pub fn with_max_heading_level(mut self, level: u8) -> Self {
+        self.max_heading_level = Some(level);
+        self
+    }
 
-            let end = self
-                .s
-                .find(&['*', '`', '~', '_', '/', '$', '^', '\\', '<', '['][..])
-                .map_or_else(|| self.s.len(), |special| special.max(1));
-            let line_end = self
-                .s
-                .find(LINE_ENDING)
-                .map_or_else(|| self.s.len(), |special| special.max(1));
-            let end = if end < line_end { end } else { line_end };
-            let text = Mark::Text(self.s[..end].to_owned(), self.style_text.clone());
-            self.s = self.s[end..].to_owned();
-            self.is_line_start = false;
-            return Some(text);
+    #[inline]
+    pub fn with_numbering(mut self, numbering: Numbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    #[inline]
+    pub fn with_placeholders(mut self, placeholders: Placeholders) -> Self {
+        self.placeholders = Some(placeholders);
+        self
+    }
+
+    #[inline]
+    pub fn with_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    #[inline]
+    pub fn with_suppress_synthetic(mut self) -> Self {
+        self.suppress_synthetic = true;
+        self
+    }
+
+    /// ```
+    /// use opmark::{
+    ///     mark::{IndentLevel, Listing, Mark},
+    ///     Parser, ParserOptions,
+    /// };
+    ///
+    /// let source = "- fruits\n\t- apples\n\t\t- green\n\t- pears";
+    /// let levels = |options: ParserOptions| -> Vec<u8> {
+    ///     Parser::with_options(source.to_owned(), options)
+    ///         .filter_map(|mark| match mark {
+    ///             Mark::Text(_, style) => match style.listing {
+    ///                 Listing::Unordered(level) => Some(level.to_int()),
+    ///                 _ => None,
+    ///             },
+    ///             _ => None,
+    ///         })
+    ///         .collect()
+    /// };
+    /// assert_eq!(levels(ParserOptions::new()), [0, 1, 2, 1]);
+    /// assert_eq!(levels(ParserOptions::new().with_tab_width(4)), [0, 2, 4, 2]);
+    /// ```
+    #[inline]
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    #[inline]
+    pub fn with_trace(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    /// Whether placeholders are replaced.
+    fn has_placeholders(&self) -> bool {
+        self.placeholders.is_some() || !self.env_vars.is_empty()
+    }
+}
+
+/// What happens to headings deeper than [`ParserOptions::max_heading_level`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum HeadingOverflow {
+    /// The heading takes the maximum level.
+    #[default]
+    Clamp,
+    /// The line is not a heading, and is reported as [`Warning::HeadingTooDeep`].
+    Error,
+}
+
+/// A version of the OpMark syntax.
+///
+/// A document may declare the dialect it is written in on its first line, so it keeps being
+/// parsed the same way when the syntax changes:
+/// ```text
+/// %opmark 0.3
+/// ```
+/// Documents without a declaration, or declaring a newer dialect than [`Dialect::CURRENT`],
+/// reported as [`Warning::UnsupportedDialect`], are parsed with the current dialect. Documents
+/// declaring an older dialect are parsed with its behaviors:
+/// - Before `0.3`, `##` marks a level 1 heading and `#` marks no heading.
+/// - Before `0.4`, `**`, `~~` and `__` toggle their style twice, and `*`, `~` and `_` toggle
+///   their style within words too.
+///
+/// ```
+/// use opmark::{lint::Warning, mark::Mark, Dialect, Parser};
+///
+/// let mut parser = Parser::new("%opmark 0.2\n## Title".to_owned());
+/// assert_eq!(parser.dialect(), Some(Dialect::new(0, 2)));
+/// let heading = parser.find_map(|mark| match mark {
+///     Mark::Text(_, style) => Some(style.heading.to_int()),
+///     _ => None,
+/// });
+/// assert_eq!(heading, Some(1));
+///
+/// let parser = Parser::new("%opmark 9.9\n# Title".to_owned());
+/// assert_eq!(
+///     parser.warnings(),
+///     [Warning::UnsupportedDialect {
+///         dialect: Dialect::new(9, 9)
+///     }]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Dialect {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Dialect {
+    /// The latest dialect supported by the parser.
+    pub const CURRENT: Dialect = Dialect::new(0, 4);
+    /// The first dialect where `#` marks a level 1 heading.
+    const SINGLE_HASH_HEADING: Dialect = Dialect::new(0, 3);
+    /// The first dialect with doubled markers, see [`Markers`].
+    const DOUBLED_MARKERS: Dialect = Dialect::new(0, 4);
+
+    #[inline]
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Whether the parser knows the syntax of the dialect. Newer dialects are parsed with the
+    /// current one.
+    #[inline]
+    pub fn is_supported(&self) -> bool {
+        *self <= Self::CURRENT
+    }
+
+    /// Parse a version like `0.3`.
+    fn parse(version: &str) -> Option<Self> {
+        let (major, minor) = version.split_once('.')?;
+        Some(Self::new(major.parse().ok()?, minor.parse().ok()?))
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A line ending, see [`Parser::line_ending`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LineEnding {
+    /// `\n`, as on Unix.
+    Lf,
+    /// `\r\n`, as on Windows.
+    CrLf,
+    /// A lone `\r`, as on classic Mac OS.
+    Cr,
+}
+
+impl LineEnding {
+    /// The first line ending of `s`, `None` if `s` is a single line.
+    pub fn detect(s: &str) -> Option<Self> {
+        let idx = s.find(['\r', '\n'])?;
+        Some(match &s[idx..] {
+            rest if rest.starts_with("\r\n") => LineEnding::CrLf,
+            rest if rest.starts_with('\r') => LineEnding::Cr,
+            _ => LineEnding::Lf,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+/// `s` without a leading byte order mark, and with its line endings replaced by
+/// [`LINE_ENDING`].
+pub(crate) fn normalize_line_endings(mut s: String) -> String {
+    if s.starts_with(BOM) {
+        s.drain(..BOM.len_utf8());
+    }
+    if !s.contains('\r') && LINE_ENDING == "\n" {
+        return s;
+    }
+    let s = s.replace("\r\n", "\n").replace('\r', "\n");
+    match LINE_ENDING {
+        "\n" => s,
+        _ => s.replace('\n', LINE_ENDING),
+    }
+}
+
+/// The directive on `line`, like `@bookmark(demo)`. Lines of unknown directives are text.
+pub(crate) fn parse_directive(line: &str) -> Option<Directive> {
+    if let Some((label, _)) = parse_label(line) {
+        return Some(Directive::Label(label.to_owned()));
+    }
+    if line.trim_end() == "@appendix" {
+        return Some(Directive::Appendix);
+    }
+    if line.trim_end() == "@pagebreak" {
+        return Some(Directive::PageBreak);
+    }
+    let (name, rest) = line.trim_end().strip_prefix('@')?.split_once('(')?;
+    if name == "comment" {
+        let (author, text) = rest.split_once("):")?;
+        let (author, text) = (author.trim(), text.trim());
+        if author.is_empty() || author.contains('(') || text.is_empty() {
+            return None;
+        }
+        return Some(Directive::Comment(author.to_owned(), text.to_owned()));
+    }
+    let argument = rest.strip_suffix(')')?.trim();
+    if argument.is_empty() || argument.contains([')', '\n']) {
+        return None;
+    }
+    match name {
+        "bookmark" => Some(Directive::Bookmark(argument.to_owned())),
+        "note" => Some(Directive::Note(argument.to_owned())),
+        "narration" => Some(Directive::Narration(argument.to_owned())),
+        "see" if argument.len() > 1 && argument.starts_with('#') => {
+            Some(Directive::See(argument.to_owned()))
+        }
+        "audience" if audience::tags(argument).next().is_some() => {
+            Some(Directive::Audience(argument.to_owned()))
+        }
+        "conflict" => [
+            ConflictMarker::Ours,
+            ConflictMarker::Theirs,
+            ConflictMarker::End,
+        ]
+        .into_iter()
+        .find(|marker| marker.name() == argument)
+        .map(Directive::Conflict),
+        _ => None,
+    }
+}
+
+/// The bare URL `s` starts with, without the punctuation ending it.
+fn autolink(s: &str) -> Option<&str> {
+    let rest = s
+        .strip_prefix("https://")
+        .or_else(|| s.strip_prefix("http://"))?;
+    let end = s.len() - rest.len()
+        + rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>'))
+            .unwrap_or(rest.len());
+    let mut url = &s[..end];
+    loop {
+        let trimmed =
+            url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"', '*', '_', '~']);
+        // closing parentheses belong to the URL only if it opens them
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(inner) if inner.matches('(').count() < trimmed.matches(')').count() => inner,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    (url.len() > s.len() - rest.len()).then_some(url)
+}
+
+/// Where the first bare URL in `s` starts, not within a word.
+fn autolink_start(s: &str) -> Option<usize> {
+    s.match_indices("http")
+        .map(|(idx, _)| idx)
+        .find(|&idx| !s[..idx].ends_with(char::is_alphanumeric) && autolink(&s[idx..]).is_some())
+}
+
+/// Whether the text of `mark` ends with a letter or digit.
+fn ends_with_word(mark: &Mark) -> bool {
+    matches!(mark, Mark::Text(text, _) if text.ends_with(char::is_alphanumeric))
+}
+
+/// The label, the URL and the title defined by the line `line`, like
+/// `[github]: https://github.com/ "GitHub"`.
+pub(crate) fn parse_link_definition(line: &str) -> Option<(&str, &str, Option<&str>)> {
+    let (label, rest) = line.trim_end().strip_prefix('[')?.split_once("]:")?;
+    let (url, title) = split_link_title(rest.trim_start());
+    let url = url
+        .strip_prefix('<')
+        .and_then(|url| url.strip_suffix('>'))
+        .unwrap_or(url);
+    let is_valid = !label.trim().is_empty()
+        && !label.contains([']', '\n'])
+        && !url.is_empty()
+        && !url.contains(char::is_whitespace);
+    is_valid.then_some((label, url, title))
+}
+
+/// The end of the destination of a link, like `url "title")`, at the closing parenthesis, with
+/// the URL and the title.
+fn link_destination(destination: &str) -> Option<(usize, &str, Option<&str>)> {
+    let parens_end = destination.find(')');
+    // the title may contain parentheses
+    let title_end = destination.find(" \"").and_then(|start| {
+        let end = start + 2 + destination[start + 2..].find("\")")? + 1;
+        parens_end
+            .is_none_or(|parens_end| start < parens_end)
+            .then_some(end)
+    });
+    let end = title_end.or(parens_end)?;
+    let (url, title) = split_link_title(&destination[..end]);
+    Some((end, url, title))
+}
+
+/// Split the destination of a link into the URL and the title after it, like `url "title"`.
+fn split_link_title(destination: &str) -> (&str, Option<&str>) {
+    let destination = destination.trim_end();
+    destination
+        .strip_suffix('"')
+        .and_then(|rest| rest.split_once(" \""))
+        .map_or((destination, None), |(url, title)| {
+            (url.trim_end(), Some(title))
+        })
+}
+
+/// The style of a link to `url` titled `title`.
+fn link_style(url: &str, title: Option<&str>) -> StyleText {
+    let style = StyleText::new().with_hyperlink(url.to_owned());
+    match title {
+        Some(title) => style.with_link_title(title.to_owned()),
+        None => style,
+    }
+}
+
+/// `label` compared regardless of case and of the whitespace within it.
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The URLs of the link labels defined in `s`, outside code blocks. The first definition of a
+/// label wins.
+fn link_definitions(s: &str) -> HashMap<String, (String, Option<String>)> {
+    let mut definitions = HashMap::new();
+    let mut is_code = false;
+    for line in s.lines() {
+        if line.starts_with("```") {
+            is_code = !is_code;
+        } else if let Some((label, url, title)) = parse_link_definition(line).filter(|_| !is_code) {
+            definitions
+                .entry(normalize_label(label))
+                .or_insert_with(|| (url.to_owned(), title.map(str::to_owned)));
         }
     }
+    definitions
+}
+
+/// Split the value of a `class=` option into class names.
+fn classes(names: &str) -> impl Iterator<Item = String> + '_ {
+    names.split_whitespace().map(str::to_owned)
+}
+
+/// Parse a duration written in hours, minutes and seconds, like `1h`, `2m` or `1m30s`.
+pub(crate) fn parse_duration(s: &str) -> Option<Duration> {
+    let mut cursor = Cursor::new(s);
+    let mut secs = 0;
+    let mut units = "hms";
+    while !cursor.is_eof() {
+        let digits = cursor.eat_while(|c| c.is_ascii_digit());
+        let value: u64 = digits.parse().ok()?;
+        let unit = cursor.eat_while(|c| c.is_ascii_alphabetic());
+        // each unit at most once, from the largest
+        let idx = units.find(unit).filter(|_| unit.len() == 1)?;
+        units = &units[idx + 1..];
+        secs += value
+            * match unit {
+                "h" => 3600,
+                "m" => 60,
+                _ => 1,
+            };
+    }
+    (!s.is_empty()).then(|| Duration::from_secs(secs))
+}
+
+/// Parse a time of day, like `09:30` or `21:05:30`, into the time since midnight.
+pub(crate) fn parse_time_of_day(s: &str) -> Option<Duration> {
+    let mut parts = s.split(':');
+    let mut part = |max: u64| {
+        parts
+            .next()
+            .filter(|part| part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|part| part.parse::<u64>().ok())
+            .filter(|&n| n < max)
+    };
+    let (hours, minutes) = (part(24)?, part(60)?);
+    let seconds = match parts.next() {
+        Some(seconds) => seconds
+            .parse::<u64>()
+            .ok()
+            .filter(|&n| seconds.len() == 2 && n < 60)?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Write the time of day `time` as `hh:mm`, or `hh:mm:ss` if it has seconds, see
+/// [`parse_time_of_day`].
+pub(crate) fn format_time_of_day(time: Duration) -> String {
+    let secs = time.as_secs() % 86400;
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    match seconds {
+        0 => format!("{:02}:{:02}", hours, minutes),
+        _ => format!("{:02}:{:02}:{:02}", hours, minutes, seconds),
+    }
+}
+
+/// Write `duration` in hours, minutes and whole seconds, see [`parse_duration`].
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let mut s = String::new();
+    for (value, unit) in [(secs / 3600, "h"), (secs / 60 % 60, "m"), (secs % 60, "s")] {
+        if value > 0 {
+            s.push_str(&format!("{}{}", value, unit));
+        }
+    }
+    if s.is_empty() {
+        s.push_str("0s");
+    }
+    s
+}
+
+/// Parse a page mark line into the style of the page.
+pub(crate) fn page_style(line: &str) -> Option<StylePage> {
+    let rest = line.strip_prefix("---")?.trim_start();
+    let (rest, options) = split_options(rest);
+    if !rest.is_empty() {
+        return None;
+    }
+    let mut style = StylePage::new();
+    for option in options.unwrap_or_default().split('|') {
+        if option == "reset-on-enter" {
+            style = style.with_reset_on_enter();
+        } else if let Some(names) = option.strip_prefix("class=") {
+            style = classes(names).fold(style, |style, class| style.with_class(class));
+        } else if let Some(src) = option.strip_prefix("narration=") {
+            style = style.with_narration(src.to_owned());
+        } else if option == "justify" {
+            style = style.with_justify();
+        } else if option == "hyphenate" {
+            style = style.with_hyphenate();
+        } else if option == "hidden" {
+            style = style.with_hidden();
+        } else if option == "no-split" {
+            style = style.with_no_split();
+        } else if let Some(duration) = option.strip_prefix("duration=").and_then(parse_duration) {
+            style = style.with_duration(duration);
+        } else if let Some(at) = option.strip_prefix("at=").and_then(parse_time_of_day) {
+            style = style.with_at(at);
+        } else if let Some(tags) = option.strip_prefix("audience=") {
+            style =
+                audience::tags(tags).fold(style, |style, tag| style.with_audience(tag.to_owned()));
+        }
+    }
+    Some(style)
+}
+
+/// Split `s` into the part before the trailing `<options>` and the options.
+fn split_options(s: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = s.strip_suffix('>') {
+        if let Some(start) = rest.find('<') {
+            return (&rest[..start], Some(&rest[start + 1..]));
+        }
+    }
+    (s, None)
+}
+
+/// The info string of a code block, like `rust file=src/main.rs lines=10-42 steps=[1-3,all]`.
+#[derive(Default)]
+struct CodeInfo<'a> {
+    language: &'a str,
+    file: Option<&'a str>,
+    lines: Option<(usize, usize)>,
+    steps: Option<Vec<CodeStep>>,
+}
+
+/// Split the info string of a code block into the language and the attributes after it.
+fn code_info(info: &str) -> CodeInfo<'_> {
+    let mut code_info = CodeInfo::default();
+    let mut rest = info.trim_end();
+    while let Some((key, value)) = rest
+        .rsplit(' ')
+        .next()
+        .and_then(|attr| attr.split_once('='))
+    {
+        match key {
+            "file" if code_info.file.is_none() && !value.is_empty() => code_info.file = Some(value),
+            "lines" if code_info.lines.is_none() => match line_range(value) {
+                Some(lines) => code_info.lines = Some(lines),
+                None => break,
+            },
+            "steps" if code_info.steps.is_none() => match code_steps(value) {
+                Some(steps) => code_info.steps = Some(steps),
+                None => break,
+            },
+            _ => break,
+        }
+        rest = rest[..rest.len() - key.len() - value.len() - 1].trim_end();
+    }
+    code_info.language = rest;
+    code_info
+}
+
+/// Parse the steps of a walkthrough, like `[1-3,4-6,all]`.
+fn code_steps(steps: &str) -> Option<Vec<CodeStep>> {
+    let steps = steps.strip_prefix('[')?.strip_suffix(']')?;
+    steps
+        .split(',')
+        .map(|step| match step {
+            "all" => Some(CodeStep::All),
+            _ => line_range(step).map(|(first, last)| CodeStep::Lines(first, last)),
+        })
+        .collect()
+}
+
+/// Parse a line, like `10`, or a range of lines, like `10-42`, starting from 1.
+fn line_range(lines: &str) -> Option<(usize, usize)> {
+    let (first, last) = lines.split_once('-').unwrap_or((lines, lines));
+    let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+    (0 < first && first <= last).then_some((first, last))
+}
+
+/// Parse a line of a gallery into the source and the title of the image.
+fn gallery_item(line: &str) -> Option<(String, String)> {
+    let mut cursor = Cursor::new(line);
+    if !cursor.eat("![") {
+        return None;
+    }
+    let title = cursor.eat_until("](")?;
+    cursor.eat("](");
+    let src = cursor.eat_until(")")?;
+    Some((src.to_owned(), title.to_owned()))
 }
 
-/// find indent level
-fn indent(s: &str) -> IndentLevel {
-    let mut idx = 0;
-    let mut c = s.chars().nth(idx).unwrap();
-    let mut indent_level = 0;
-    while c == ' ' && idx < s.len() - 1 {
-        indent_level += 1;
-        idx += 1;
-        c = s.chars().nth(idx).unwrap();
+/// The indent level of the line `s`, with the length of its indentation. `None` if the
+/// indentation is not a whole count of levels.
+fn indent(s: &str, tab_width: usize) -> Option<(IndentLevel, usize)> {
+    let len = s.len() - s.trim_start_matches([' ', '\t']).len();
+    let mut columns = 0;
+    for c in s[..len].chars() {
+        columns = match c {
+            '\t' if tab_width > 0 => (columns / tab_width + 1) * tab_width,
+            '\t' => columns,
+            _ => columns + 1,
+        };
     }
-    indent_level = indent_level / 2;
-    IndentLevel::from(indent_level)
+    let level = u8::try_from(columns / 2).unwrap_or(u8::MAX);
+    (columns % 2 == 0).then(|| (IndentLevel::from(level), len))
 }
@@ -0,0 +1,249 @@
+//! Placeholders replaced by their values when parsing, so footers like "last updated" stay
+//! correct.
+//!
+//! A placeholder is a name between double braces, like `{{today}}`. Placeholders are replaced
+//! when parsing with [`Placeholders`] set in
+//! [`ParserOptions::with_placeholders`](crate::ParserOptions::with_placeholders), in text,
+//! headings, list items and quotes, but not in code. The built-in placeholders are:
+//! - `{{today}}`: the date of the day, written with [`Placeholders::date_format`].
+//! - `{{now}}`: the date and time, written with [`Placeholders::time_format`].
+//! - `{{env:NAME}}`: the environment variable `NAME`, if it is allowed with
+//!   [`ParserOptions::with_env_var`](crate::ParserOptions::with_env_var).
+//!
+//! Applications provide the other ones, like `{{git-sha}}` for the commit a deck is built from,
+//! as values or with a [`PlaceholderResolver`]. Placeholders without a value stay as written,
+//! and are reported as [`Warning::UnknownPlaceholder`](crate::lint::Warning::UnknownPlaceholder).
+//!
+//! ```
+//! use opmark::{lint::Warning, mark::Mark, placeholder::Placeholders, Parser, ParserOptions};
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let placeholders = Placeholders::new()
+//!     .with_now(UNIX_EPOCH + Duration::from_secs(1_717_243_200))
+//!     .with_date_format("%B %e, %Y".to_owned())
+//!     .with_value("git-sha".to_owned(), "4f2a9c1".to_owned());
+//! let options = ParserOptions::new().with_placeholders(placeholders);
+//! let mut parser = Parser::with_options(
+//!     "# Built from {{git-sha}}\nUpdated on *{{today}}* at {{now}} by {{author}}".to_owned(),
+//!     options,
+//! );
+//! let texts: Vec<String> = parser
+//!     .by_ref()
+//!     .filter_map(|mark| match mark {
+//!         Mark::Text(text, _) => Some(text),
+//!         _ => None,
+//!     })
+//!     .collect();
+//! assert_eq!(texts[0], "Built from 4f2a9c1");
+//! assert_eq!(texts[1..3], ["Updated on ", "June 1, 2024"]);
+//! assert_eq!(texts[3..5], [" at ", "2024-06-01 12:00"]);
+//! assert_eq!(
+//!     parser.warnings(),
+//!     [Warning::UnknownPlaceholder { name: "author".to_owned() }]
+//! );
+//! ```
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Gives the values of placeholders which are not built in.
+pub trait PlaceholderResolver: fmt::Debug + Send + Sync {
+    /// The value of the placeholder `name`, or `None` if it has none.
+    fn resolve(&self, name: &str) -> Option<String>;
+}
+
+/// The values of placeholders.
+#[derive(Clone, Debug)]
+pub struct Placeholders {
+    /// The format of `{{today}}`. `%Y`, `%m`, `%d`, `%e`, `%H`, `%M`, `%S`, `%B`, `%b` and `%%`
+    /// stand for the year, the month, the day, the day without padding, the hours, the minutes,
+    /// the seconds, the name of the month, its first three letters, and `%`.
+    pub date_format: String,
+    /// The format of `{{now}}`, see [`Placeholders::date_format`].
+    pub time_format: String,
+    /// The time of `{{today}}` and `{{now}}`. If `None`, the time when they are replaced.
+    pub now: Option<SystemTime>,
+    /// The offset from UTC of `{{today}}` and `{{now}}`, in minutes.
+    pub utc_offset: i32,
+    /// The values of placeholders, by name. They win over the built-in placeholders.
+    pub values: Vec<(String, String)>,
+    /// Gives the values of the other placeholders.
+    pub resolver: Option<Arc<dyn PlaceholderResolver>>,
+}
+
+impl Default for Placeholders {
+    fn default() -> Self {
+        Self {
+            date_format: "%Y-%m-%d".to_owned(),
+            time_format: "%Y-%m-%d %H:%M".to_owned(),
+            now: None,
+            utc_offset: 0,
+            values: vec![],
+            resolver: None,
+        }
+    }
+}
+
+impl Placeholders {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_date_format(mut self, format: String) -> Self {
+        self.date_format = format;
+        self
+    }
+
+    #[inline]
+    pub fn with_now(mut self, now: SystemTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    #[inline]
+    pub fn with_resolver(mut self, resolver: impl PlaceholderResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    #[inline]
+    pub fn with_time_format(mut self, format: String) -> Self {
+        self.time_format = format;
+        self
+    }
+
+    #[inline]
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    /// Set the value of the placeholder `name`, replacing its previous value.
+    pub fn with_value(mut self, name: String, value: String) -> Self {
+        match self.values.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.values.push((name, value)),
+        }
+        self
+    }
+
+    /// The value of the placeholder `name`, or `None` if it has none.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        if let Some((_, value)) = self.values.iter().find(|(n, _)| n == name) {
+            return Some(value.clone());
+        }
+        match name {
+            "today" => Some(self.format_now(&self.date_format)),
+            "now" => Some(self.format_now(&self.time_format)),
+            _ => self.resolver.as_ref()?.resolve(name),
+        }
+    }
+
+    fn format_now(&self, format: &str) -> String {
+        let now = self.now.unwrap_or_else(SystemTime::now);
+        DateTime::new(now, self.utc_offset).format(format)
+    }
+}
+
+/// The name of the placeholder `s` starts with, like `today` for `{{today}}`, with the length of
+/// the placeholder.
+pub(crate) fn placeholder_at(s: &str) -> Option<(&str, usize)> {
+    let rest = s.strip_prefix("{{")?;
+    let end = rest.find("}}")?;
+    let name = &rest[..end];
+    let is_name = !name.trim().is_empty() && !name.contains(['{', '}', '\n']);
+    is_name.then_some((name.trim(), end + 4))
+}
+
+/// A date and time of the proleptic Gregorian calendar.
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+impl DateTime {
+    fn new(time: SystemTime, utc_offset: i32) -> Self {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        } + utc_offset as i64 * 60;
+        let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+        // from the days since 1970-01-01, in eras of 400 years starting on March 1st
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: (secs / 3600) as u32,
+            minute: (secs % 3600 / 60) as u32,
+            second: (secs % 60) as u32,
+        }
+    }
+
+    fn format(&self, format: &str) -> String {
+        let mut s = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                s.push(c);
+                continue;
+            }
+            let month = MONTHS[self.month as usize - 1];
+            match chars.next() {
+                Some('Y') => s.push_str(&format!("{:04}", self.year)),
+                Some('m') => s.push_str(&format!("{:02}", self.month)),
+                Some('d') => s.push_str(&format!("{:02}", self.day)),
+                Some('e') => s.push_str(&self.day.to_string()),
+                Some('H') => s.push_str(&format!("{:02}", self.hour)),
+                Some('M') => s.push_str(&format!("{:02}", self.minute)),
+                Some('S') => s.push_str(&format!("{:02}", self.second)),
+                Some('B') => s.push_str(month),
+                Some('b') => s.push_str(&month[..3]),
+                Some('%') => s.push('%'),
+                Some(other) => {
+                    s.push('%');
+                    s.push(other);
+                }
+                None => s.push('%'),
+            }
+        }
+        s
+    }
+}
@@ -0,0 +1,190 @@
+//! Several decks presented in a row, like the modules of a workshop day.
+//!
+//! A [`Playlist`] goes through the steps of its decks as if they were one deck: going past the
+//! last page of a deck goes to the first page of the next one, and going back from the first
+//! page goes to the last page of the previous one. All decks are shown with the same theme, if
+//! the playlist has one, see [`Playlist::with_theme`].
+//!
+//! ```
+//! use opmark::{deck::Deck, playlist::Playlist};
+//!
+//! let mut playlist = Playlist::new()
+//!     .with_deck(Deck::new("# Module 1\n---t\nGoals".to_owned()))
+//!     .with_deck(Deck::new("# Module 2".to_owned()));
+//! assert_eq!(playlist.progress(), (1, 2));
+//! assert!(playlist.next_step());
+//! assert!(playlist.next_step());
+//! assert_eq!((playlist.current(), playlist.progress()), (1, (2, 2)));
+//! assert!(!playlist.next_step());
+//! assert!(playlist.prev_step());
+//! assert_eq!(playlist.current(), 0);
+//! assert_eq!(playlist.current_deck().unwrap().current_page().unwrap().step, 1);
+//! ```
+
+use crate::{
+    deck::Deck, include::FsResolver, parser::Parser, render::html::HtmlTheme, ParserOptions,
+};
+use std::{fs, io, path::Path};
+
+/// Decks presented in order, with their navigation joined.
+#[derive(Clone, Debug, Default)]
+pub struct Playlist {
+    decks: Vec<Deck>,
+    current: usize,
+    theme: Option<HtmlTheme>,
+}
+
+impl Playlist {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Append `deck` to the playlist.
+    #[inline]
+    pub fn with_deck(mut self, deck: Deck) -> Self {
+        self.decks.push(deck);
+        self
+    }
+
+    /// Show all decks with `theme`, over the theme frontends show decks with otherwise.
+    #[inline]
+    pub fn with_theme(mut self, theme: HtmlTheme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Read the decks in the files at `paths`, in order. Each deck includes files relative to
+    /// its own directory.
+    pub fn read<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> io::Result<Self> {
+        let mut playlist = Self::new();
+        for path in paths {
+            let path = path.as_ref();
+            let source = fs::read_to_string(path)?;
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let options = ParserOptions::new().with_resolver(FsResolver::new(dir));
+            playlist = playlist.with_deck(Deck::from_parser(Parser::with_options(source, options)));
+        }
+        Ok(playlist)
+    }
+
+    /// All decks of the playlist.
+    #[inline]
+    pub fn decks(&self) -> &[Deck] {
+        &self.decks
+    }
+
+    /// The index of the current deck.
+    #[inline]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The current deck. `None` if the playlist has no decks.
+    #[inline]
+    pub fn current_deck(&self) -> Option<&Deck> {
+        self.decks.get(self.current)
+    }
+
+    /// The theme all decks are shown with, if set.
+    #[inline]
+    pub fn theme(&self) -> Option<&HtmlTheme> {
+        self.theme.as_ref()
+    }
+
+    /// Go to the first page of the deck at `index`. Returns `false` if there is no such deck.
+    pub fn goto_deck(&mut self, index: usize) -> bool {
+        let Some(deck) = self.decks.get_mut(index) else {
+            return false;
+        };
+        let first = deck.visible_pages().first().copied().unwrap_or(0);
+        deck.goto_page(first);
+        self.current = index;
+        true
+    }
+
+    /// Go to the next page, in the next deck after the last page of a deck. Returns `false` if
+    /// there is no next page to show.
+    pub fn next_page(&mut self) -> bool {
+        let moved = self
+            .decks
+            .get_mut(self.current)
+            .is_some_and(Deck::next_page);
+        moved || self.next_deck()
+    }
+
+    /// Go to the previous page, in the previous deck before the first page of a deck. Returns
+    /// `false` if there is no previous page to show.
+    pub fn prev_page(&mut self) -> bool {
+        let moved = self
+            .decks
+            .get_mut(self.current)
+            .is_some_and(Deck::prev_page);
+        moved || self.prev_deck()
+    }
+
+    /// Reveal the next transition, or go to the next page, in the next deck after the last page
+    /// of a deck, see [`Deck::next_step`]. Returns `false` if the playlist did not move.
+    pub fn next_step(&mut self) -> bool {
+        let moved = self
+            .decks
+            .get_mut(self.current)
+            .is_some_and(Deck::next_step);
+        moved || self.next_deck()
+    }
+
+    /// Hide the last transition, or go to the previous page, in the previous deck before the
+    /// first page of a deck, see [`Deck::prev_step`]. Returns `false` if the playlist did not
+    /// move.
+    pub fn prev_step(&mut self) -> bool {
+        let moved = self
+            .decks
+            .get_mut(self.current)
+            .is_some_and(Deck::prev_step);
+        moved || self.prev_deck()
+    }
+
+    /// The number of the visible pages up to the current page, and the number of all visible
+    /// pages, in all decks, see [`Deck::progress`].
+    pub fn progress(&self) -> (usize, usize) {
+        let mut progress = (0, 0);
+        for (idx, deck) in self.decks.iter().enumerate() {
+            let (current, total) = deck.progress();
+            progress.1 += total;
+            if idx < self.current {
+                progress.0 += total;
+            } else if idx == self.current {
+                progress.0 += current;
+            }
+        }
+        progress
+    }
+
+    /// Go to the first page of the next deck with pages.
+    fn next_deck(&mut self) -> bool {
+        match (self.current + 1..self.decks.len()).find(|&idx| !self.decks[idx].pages().is_empty())
+        {
+            Some(idx) => self.goto_deck(idx),
+            None => false,
+        }
+    }
+
+    /// Go to the last page of the previous deck with pages.
+    fn prev_deck(&mut self) -> bool {
+        let Some(idx) = (0..self.current)
+            .rev()
+            .find(|&idx| !self.decks[idx].pages().is_empty())
+        else {
+            return false;
+        };
+        let deck = &mut self.decks[idx];
+        let last = deck
+            .visible_pages()
+            .last()
+            .copied()
+            .unwrap_or(deck.pages().len() - 1);
+        deck.goto_page(last);
+        self.current = idx;
+        true
+    }
+}
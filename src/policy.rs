@@ -0,0 +1,335 @@
+//! Rules decks of an organization have to follow, like a corporate template, checked as code.
+//!
+//! A policy is written in TOML, all rules are optional:
+//! ```toml
+//! # the first page starts with a heading
+//! require_title_slide = true
+//! # at most 6 list items in a page
+//! max_bullets = 6
+//! # words which must not appear, in any case
+//! banned_words = ["synergy", "leverage"]
+//! # text which every page contains
+//! required_footer = "Confidential"
+//! # schemes of links which must not appear, see `link::LinkTarget`
+//! disallowed_link_schemes = ["file", "tel"]
+//! ```
+//!
+//! ```
+//! use opmark::{deck::Deck, policy::{Policy, Violation}};
+//!
+//! let policy: Policy = r#"
+//! max_bullets = 2
+//! banned_words = ["synergy"]
+//! disallowed_link_schemes = ["file"]
+//! "#
+//! .parse()
+//! .unwrap();
+//! let deck = Deck::new("# Plan\n- one\n- two\n- Synergy\n[notes](file:///notes.txt)".to_owned());
+//! let violations: Vec<Violation> = policy
+//!     .validate(&deck)
+//!     .into_iter()
+//!     .map(|diagnostic| diagnostic.violation)
+//!     .collect();
+//! assert_eq!(
+//!     violations,
+//!     [
+//!         Violation::TooManyBullets { count: 3, max: 2 },
+//!         Violation::BannedWord("synergy".to_owned()),
+//!         Violation::DisallowedLinkScheme {
+//!             scheme: "file".to_owned(),
+//!             url: "file:///notes.txt".to_owned(),
+//!         },
+//!     ]
+//! );
+//! ```
+
+use crate::{
+    deck::Deck,
+    link::LinkTarget,
+    mark::{Listing, Mark},
+    render::page_marks,
+    text::plain_text,
+};
+use std::{error, fmt, fs, io, path::Path, str::FromStr};
+use toml_edit::Document;
+
+/// The rules of a policy.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Policy {
+    /// The first page of a deck starts with a heading.
+    pub require_title_slide: bool,
+    /// The maximum number of list items in a page.
+    pub max_bullets: Option<usize>,
+    /// Words which must not appear in a deck, compared regardless of case.
+    pub banned_words: Vec<String>,
+    /// Text which every page of a deck contains.
+    pub required_footer: Option<String>,
+    /// The schemes of links which must not appear in a deck, like `file`, compared regardless
+    /// of case.
+    pub disallowed_link_schemes: Vec<String>,
+}
+
+impl Policy {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_title_slide(mut self) -> Self {
+        self.require_title_slide = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_max_bullets(mut self, max_bullets: usize) -> Self {
+        self.max_bullets = Some(max_bullets);
+        self
+    }
+
+    #[inline]
+    pub fn with_banned_word(mut self, word: String) -> Self {
+        self.banned_words.push(word);
+        self
+    }
+
+    #[inline]
+    pub fn with_footer(mut self, footer: String) -> Self {
+        self.required_footer = Some(footer);
+        self
+    }
+
+    #[inline]
+    pub fn with_disallowed_link_scheme(mut self, scheme: String) -> Self {
+        self.disallowed_link_schemes.push(scheme);
+        self
+    }
+
+    /// Read the policy in the TOML file at `path`.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Check `deck` against the rules, returning a diagnostic for each violation, in the order of
+    /// the pages.
+    pub fn validate(&self, deck: &Deck) -> Vec<Diagnostic> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("validate", pages = deck.pages().len()).entered();
+
+        let mut diagnostics = vec![];
+        let first_page = deck.pages().first().map(page_marks);
+        let starts_with_heading = first_page.as_deref().is_some_and(|marks| {
+            marks
+                .iter()
+                .find(|mark| !matches!(mark, Mark::NewLine))
+                .is_some_and(
+                    |mark| matches!(mark, Mark::Text(_, style) if style.heading.to_int() > 0),
+                )
+        });
+        if self.require_title_slide && !starts_with_heading {
+            diagnostics.push(Diagnostic {
+                page: 0,
+                violation: Violation::MissingTitleSlide,
+            });
+        }
+
+        let banned_words: Vec<String> = self
+            .banned_words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect();
+        for (page, marks) in deck.pages().iter().map(page_marks).enumerate() {
+            let mut violations = vec![];
+            if let Some(max) = self.max_bullets {
+                let count = marks
+                    .iter()
+                    .filter(|mark| {
+                        matches!(mark, Mark::Text(_, style) if !matches!(style.listing, Listing::None))
+                    })
+                    .count();
+                if count > max {
+                    violations.push(Violation::TooManyBullets { count, max });
+                }
+            }
+            let text = plain_text(&marks);
+            let lowercase = text.to_lowercase();
+            let words: Vec<&str> = lowercase
+                .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '\'')
+                .collect();
+            for word in &banned_words {
+                if words.contains(&word.as_str())
+                    || (word.contains(' ') && lowercase.contains(word))
+                {
+                    violations.push(Violation::BannedWord(word.clone()));
+                }
+            }
+            if let Some(footer) = &self.required_footer {
+                if !text.contains(footer.as_str()) {
+                    violations.push(Violation::MissingFooter(footer.clone()));
+                }
+            }
+            for url in links(&marks) {
+                let target = LinkTarget::parse(url);
+                let Some(scheme) = target.scheme() else {
+                    continue;
+                };
+                if self
+                    .disallowed_link_schemes
+                    .iter()
+                    .any(|disallowed| disallowed.eq_ignore_ascii_case(scheme))
+                {
+                    violations.push(Violation::DisallowedLinkScheme {
+                        scheme: scheme.to_owned(),
+                        url: url.to_owned(),
+                    });
+                }
+            }
+            diagnostics.extend(
+                violations
+                    .into_iter()
+                    .map(|violation| Diagnostic { page, violation }),
+            );
+        }
+        diagnostics
+    }
+}
+
+impl FromStr for Policy {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let document: Document<String> = s
+            .parse()
+            .map_err(|err: toml_edit::TomlError| PolicyError::Toml(err.to_string()))?;
+        let mut policy = Self::new();
+        for (key, item) in document.as_table().iter() {
+            let invalid = |expected| PolicyError::InvalidValue {
+                key: key.to_owned(),
+                expected,
+            };
+            match key {
+                "require_title_slide" => {
+                    policy.require_title_slide =
+                        item.as_bool().ok_or_else(|| invalid("a boolean"))?
+                }
+                "max_bullets" => {
+                    let max = item
+                        .as_integer()
+                        .and_then(|max| usize::try_from(max).ok())
+                        .ok_or_else(|| invalid("a non-negative integer"))?;
+                    policy.max_bullets = Some(max);
+                }
+                "banned_words" => {
+                    policy.banned_words = item
+                        .as_array()
+                        .and_then(|words| {
+                            words
+                                .iter()
+                                .map(|word| word.as_str().map(str::to_owned))
+                                .collect()
+                        })
+                        .ok_or_else(|| invalid("an array of strings"))?
+                }
+                "required_footer" => {
+                    let footer = item.as_str().ok_or_else(|| invalid("a string"))?;
+                    policy.required_footer = Some(footer.to_owned());
+                }
+                "disallowed_link_schemes" => {
+                    policy.disallowed_link_schemes = item
+                        .as_array()
+                        .and_then(|schemes| {
+                            schemes
+                                .iter()
+                                .map(|scheme| scheme.as_str().map(str::to_owned))
+                                .collect()
+                        })
+                        .ok_or_else(|| invalid("an array of strings"))?
+                }
+                key => return Err(PolicyError::UnknownRule(key.to_owned())),
+            }
+        }
+        Ok(policy)
+    }
+}
+
+/// The URLs `marks` link to, in order.
+fn links(marks: &[Mark]) -> impl Iterator<Item = &str> {
+    marks.iter().filter_map(|mark| match mark {
+        Mark::Text(_, style) if !style.hyperlink.is_empty() => Some(style.hyperlink.as_str()),
+        Mark::Image(_, _, style) if !style.hyperlink.is_empty() => Some(style.hyperlink.as_str()),
+        Mark::Embed(url, _) => Some(url.as_str()),
+        _ => None,
+    })
+}
+
+/// A violation of a rule of a policy, see [`Policy::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// The index of the page violating the rule.
+    pub page: usize,
+    pub violation: Violation,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "page {}: {}", self.page + 1, self.violation)
+    }
+}
+
+/// A rule of a policy which is not followed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// The first page does not start with a heading.
+    MissingTitleSlide,
+    /// A page has `count` list items, more than `max`.
+    TooManyBullets { count: usize, max: usize },
+    /// A page contains a banned word, in lowercase.
+    BannedWord(String),
+    /// A page does not contain the required footer.
+    MissingFooter(String),
+    /// A page links to `url`, whose scheme is disallowed.
+    DisallowedLinkScheme { scheme: String, url: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::MissingTitleSlide => f.write_str("the deck does not start with a title"),
+            Violation::TooManyBullets { count, max } => {
+                write!(f, "{} list items, more than {}", count, max)
+            }
+            Violation::BannedWord(word) => write!(f, "banned word \"{}\"", word),
+            Violation::MissingFooter(footer) => write!(f, "missing footer \"{}\"", footer),
+            Violation::DisallowedLinkScheme { scheme, url } => {
+                write!(f, "link to {} with the disallowed scheme {}", url, scheme)
+            }
+        }
+    }
+}
+
+/// An error reading a policy.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolicyError {
+    /// The policy is not valid TOML.
+    Toml(String),
+    /// The policy has a rule which does not exist.
+    UnknownRule(String),
+    /// The value of the rule `key` is not of the `expected` kind.
+    InvalidValue { key: String, expected: &'static str },
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Toml(err) => write!(f, "invalid policy: {}", err),
+            PolicyError::UnknownRule(key) => write!(f, "unknown rule {}", key),
+            PolicyError::InvalidValue { key, expected } => {
+                write!(f, "the value of {} must be {}", key, expected)
+            }
+        }
+    }
+}
+
+impl error::Error for PolicyError {}
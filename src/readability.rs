@@ -0,0 +1,575 @@
+//! Accessibility checks of decks shown with a theme, so unreadable slides are caught while
+//! writing them rather than in the room.
+//!
+//! [`check`] reports the elements whose colors in an [`HtmlTheme`] contrast less than the
+//! [WCAG](https://www.w3.org/TR/WCAG21/#contrast-minimum) asks, see [`contrast_ratio`], and the
+//! pages so dense that their text would shrink to fit below a readable size. With
+//! [`ReadabilityOptions::with_color_vision`], it also reports the colors shown side by side which
+//! can not be told apart with a common color vision deficiency, see [`Deficiency`].
+//!
+//! ```
+//! use opmark::{
+//!     deck::Deck,
+//!     readability::{check, Issue, ReadabilityOptions, ThemeElement},
+//!     render::html::HtmlTheme,
+//! };
+//!
+//! let theme = HtmlTheme::new()
+//!     .with_foreground("#999".to_owned())
+//!     .with_accent("#1d4ed8".to_owned());
+//! let dense = "- a long point which takes most of a line on its own\n".repeat(24);
+//! let deck = Deck::new(format!("# Intro\nHello\n---\n{}", dense));
+//! let issues = check(&deck, &theme, &ReadabilityOptions::new());
+//! assert!(matches!(
+//!     issues[0],
+//!     Issue::LowContrast { element: ThemeElement::Text, .. }
+//! ));
+//! let issues: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+//! assert_eq!(
+//!     issues,
+//!     [
+//!         "text: contrast of #999 on #ffffff is 2.85, less than 4.5",
+//!         "code: contrast of #999 on #f6f8fa is 2.68, less than 4.5",
+//!         "page 2: text shrinks to 16.3pt to fit, smaller than 18pt",
+//!     ]
+//! );
+//! ```
+
+use crate::{
+    deck::{Deck, Page},
+    mark::{Listing, Mark},
+    render::{
+        html::{ExportProfile, HtmlTheme},
+        page_marks,
+    },
+    text::grapheme_len,
+};
+use std::fmt;
+
+/// Options for the readability checks. The defaults follow the WCAG level AA, and slides where
+/// body text is set at 20 points, like in PowerPoint.
+#[derive(Clone, Debug)]
+pub struct ReadabilityOptions {
+    /// The lowest contrast ratio of text. 4.5 by default.
+    pub min_contrast: f32,
+    /// The lowest contrast ratio of headings, which are large text. 3 by default.
+    pub min_large_contrast: f32,
+    /// The size of body text on pages which are not crowded, in points. 20 by default.
+    pub font_size: f32,
+    /// The smallest readable size of body text, in points. 18 by default.
+    pub min_font_size: f32,
+    /// How many lines of body text fit on a page at `font_size`. 16 by default.
+    pub page_lines: usize,
+    /// How many characters of body text fit on a line at `font_size`. 80 by default.
+    pub line_chars: usize,
+    /// Check that the colors shown side by side stay apart with color vision deficiencies.
+    pub color_vision: bool,
+    /// The smallest difference between colors shown side by side, as a CIE76 ΔE. 10 by default.
+    pub min_color_difference: f32,
+}
+
+impl Default for ReadabilityOptions {
+    fn default() -> Self {
+        Self {
+            min_contrast: 4.5,
+            min_large_contrast: 3.0,
+            font_size: 20.0,
+            min_font_size: 18.0,
+            page_lines: 16,
+            line_chars: 80,
+            color_vision: false,
+            min_color_difference: 10.0,
+        }
+    }
+}
+
+impl ReadabilityOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_color_vision(mut self) -> Self {
+        self.color_vision = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    #[inline]
+    pub fn with_line_chars(mut self, chars: usize) -> Self {
+        self.line_chars = chars;
+        self
+    }
+
+    #[inline]
+    pub fn with_min_contrast(mut self, ratio: f32) -> Self {
+        self.min_contrast = ratio;
+        self
+    }
+
+    #[inline]
+    pub fn with_min_color_difference(mut self, difference: f32) -> Self {
+        self.min_color_difference = difference;
+        self
+    }
+
+    #[inline]
+    pub fn with_min_font_size(mut self, size: f32) -> Self {
+        self.min_font_size = size;
+        self
+    }
+
+    #[inline]
+    pub fn with_min_large_contrast(mut self, ratio: f32) -> Self {
+        self.min_large_contrast = ratio;
+        self
+    }
+
+    #[inline]
+    pub fn with_page_lines(mut self, lines: usize) -> Self {
+        self.page_lines = lines;
+        self
+    }
+
+    /// Check what `profile` requires: the contrasts of the WCAG level AAA for
+    /// [`ExportProfile::HighContrast`], body text of at least
+    /// [`ExportProfile::LARGE_PRINT_SIZE`] for [`ExportProfile::LargePrint`].
+    pub fn with_profile(mut self, profile: ExportProfile) -> Self {
+        match profile {
+            ExportProfile::HighContrast => {
+                self.min_contrast = self.min_contrast.max(7.0);
+                self.min_large_contrast = self.min_large_contrast.max(4.5);
+            }
+            ExportProfile::LargePrint => {
+                let size = ExportProfile::LARGE_PRINT_SIZE;
+                // the text is set larger, so fewer lines and characters fit
+                let scale = self.font_size / size.max(self.font_size);
+                self.page_lines = (self.page_lines as f32 * scale) as usize;
+                self.line_chars = (self.line_chars as f32 * scale) as usize;
+                self.font_size = self.font_size.max(size);
+                self.min_font_size = self.min_font_size.max(size);
+            }
+        }
+        self
+    }
+}
+
+/// An element of a page with its own colors in a theme.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ThemeElement {
+    /// Body text, in the foreground color.
+    Text,
+    /// Headings, in the heading color.
+    Heading,
+    /// Links and quotes, in the accent color.
+    Link,
+    /// Code, in the foreground color on the code background.
+    Code,
+}
+
+impl ThemeElement {
+    pub const ALL: [ThemeElement; 4] = [
+        ThemeElement::Text,
+        ThemeElement::Heading,
+        ThemeElement::Link,
+        ThemeElement::Code,
+    ];
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThemeElement::Text => "text",
+            ThemeElement::Heading => "heading",
+            ThemeElement::Link => "link",
+            ThemeElement::Code => "code",
+        }
+    }
+
+    /// The foreground and background colors of the element in `theme`.
+    pub fn colors<'a>(&self, theme: &'a HtmlTheme) -> (&'a str, &'a str) {
+        match self {
+            ThemeElement::Text => (&theme.foreground, &theme.background),
+            ThemeElement::Heading => (&theme.heading, &theme.background),
+            ThemeElement::Link => (&theme.accent, &theme.background),
+            ThemeElement::Code => (&theme.foreground, &theme.code_background),
+        }
+    }
+}
+
+/// A color vision deficiency, simulated at full severity with the matrices of
+/// [Machado et al. (2009)](https://doi.org/10.1109/TVCG.2009.113).
+///
+/// ```
+/// use opmark::{
+///     deck::Deck,
+///     readability::{check, Deficiency, ReadabilityOptions},
+///     render::html::HtmlTheme,
+/// };
+///
+/// // red and green turn to yellows without green cones
+/// assert_eq!(Deficiency::Deuteranopia.simulate("#ff0000").as_deref(), Some("#a39000"));
+/// assert_eq!(Deficiency::Deuteranopia.simulate("#00ff00").as_deref(), Some("#efd63a"));
+///
+/// let theme = HtmlTheme::new()
+///     .with_foreground("#5c7a00".to_owned())
+///     .with_accent("#b23c17".to_owned());
+/// let deck = Deck::new("Read [the docs](https://example.com)".to_owned());
+/// let options = ReadabilityOptions::new().with_color_vision();
+/// let issues: Vec<String> = check(&deck, &theme, &options)
+///     .iter()
+///     .map(|issue| issue.to_string())
+///     .collect();
+/// assert_eq!(
+///     issues,
+///     ["text (#5c7a00) and link (#b23c17) look alike with deuteranopia, ΔE 1.0"]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Deficiency {
+    /// No red cones.
+    Protanopia,
+    /// No green cones, the most common deficiency.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia,
+}
+
+impl Deficiency {
+    pub const ALL: [Deficiency; 3] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+    ];
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// The CSS color `color` as seen with the deficiency, as `#rrggbb`. `None` if the color can
+    /// not be read, see [`contrast_ratio`].
+    pub fn simulate(&self, color: &str) -> Option<String> {
+        let [r, g, b] = self
+            .simulate_linear(parse_color(color)?.map(linear))
+            .map(|c| {
+                let c = if c <= 0.003_130_8 {
+                    c * 12.92
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                };
+                (c * 255.0).round() as u8
+            });
+        Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+    }
+
+    /// The linear RGB color `rgb` as seen with the deficiency.
+    fn simulate_linear(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let matrix = match self {
+            Deficiency::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+        };
+        matrix.map(|row| {
+            let c: f32 = row.iter().zip(rgb).map(|(m, c)| m * c).sum();
+            c.clamp(0.0, 1.0)
+        })
+    }
+}
+
+/// A readability issue of a deck, see [`check`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    /// The colors of an element in the theme contrast less than `min`.
+    LowContrast {
+        element: ThemeElement,
+        foreground: String,
+        background: String,
+        ratio: f32,
+        min: f32,
+    },
+    /// The text of the page `page` has to shrink to `size` points to fit, less than `min`.
+    TinyText { page: usize, size: f32, min: f32 },
+    /// The colors of two elements shown side by side in the theme differ by `difference`, less
+    /// than the minimum, when seen with `deficiency`. The colors are the ones of the theme.
+    Indistinguishable {
+        elements: [ThemeElement; 2],
+        colors: [String; 2],
+        deficiency: Deficiency,
+        difference: f32,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::LowContrast {
+                element,
+                foreground,
+                background,
+                ratio,
+                min,
+            } => write!(
+                f,
+                "{}: contrast of {} on {} is {:.2}, less than {}",
+                element.name(),
+                foreground,
+                background,
+                ratio,
+                min
+            ),
+            Issue::TinyText { page, size, min } => write!(
+                f,
+                "page {}: text shrinks to {:.1}pt to fit, smaller than {}pt",
+                page + 1,
+                size,
+                min
+            ),
+            Issue::Indistinguishable {
+                elements,
+                colors,
+                deficiency,
+                difference,
+            } => write!(
+                f,
+                "{} ({}) and {} ({}) look alike with {}, ΔE {:.1}",
+                elements[0].name(),
+                colors[0],
+                elements[1].name(),
+                colors[1],
+                deficiency.name(),
+                difference
+            ),
+        }
+    }
+}
+
+/// The contrast ratio of two CSS colors, from 1 to 21, as defined by the WCAG. `None` if a color
+/// is not written as `#rgb`, `#rrggbb`, their forms with alpha, or `rgb()`. Alpha is ignored.
+///
+/// ```
+/// use opmark::readability::contrast_ratio;
+///
+/// let ratio = contrast_ratio("#000", "#ffffff").unwrap();
+/// assert_eq!(format!("{:.1}", ratio), "21.0");
+/// assert_eq!(contrast_ratio("rgb(255, 255, 255)", "white"), None);
+/// ```
+pub fn contrast_ratio(foreground: &str, background: &str) -> Option<f32> {
+    let a = luminance(parse_color(foreground)?);
+    let b = luminance(parse_color(background)?);
+    Some((a.max(b) + 0.05) / (a.min(b) + 0.05))
+}
+
+/// The readability issues of `deck` shown with `theme`: the elements of the theme with a low
+/// contrast, the colors which look alike with color vision deficiencies if checked, then the
+/// pages too dense to read, in order.
+pub fn check(deck: &Deck, theme: &HtmlTheme, options: &ReadabilityOptions) -> Vec<Issue> {
+    let mut issues = vec![];
+    for element in ThemeElement::ALL {
+        let (foreground, background) = element.colors(theme);
+        let min = match element {
+            ThemeElement::Heading => options.min_large_contrast,
+            _ => options.min_contrast,
+        };
+        match contrast_ratio(foreground, background) {
+            Some(ratio) if ratio < min => issues.push(Issue::LowContrast {
+                element,
+                foreground: foreground.to_owned(),
+                background: background.to_owned(),
+                ratio,
+                min,
+            }),
+            _ => {}
+        }
+    }
+    if options.color_vision {
+        issues.extend(color_vision_issues(theme, options.min_color_difference));
+    }
+    for (idx, page) in deck.pages().iter().enumerate() {
+        if page.style.hidden {
+            continue;
+        }
+        let lines = page_lines(page, options.line_chars.max(1));
+        if lines <= options.page_lines as f32 {
+            continue;
+        }
+        // text shrinks in both directions, so the lines it takes shrink with the square
+        let size = options.font_size * (options.page_lines as f32 / lines).sqrt();
+        if size < options.min_font_size {
+            issues.push(Issue::TinyText {
+                page: idx,
+                size,
+                min: options.min_font_size,
+            });
+        }
+    }
+    issues
+}
+
+/// The colors of the elements shown side by side in `theme` which differ by at least
+/// `min_difference`, but less when seen with a deficiency.
+fn color_vision_issues(theme: &HtmlTheme, min_difference: f32) -> Vec<Issue> {
+    // links are told apart from the text and the headings around them by their color alone
+    let pairs = [
+        [ThemeElement::Text, ThemeElement::Link],
+        [ThemeElement::Heading, ThemeElement::Link],
+    ];
+    let mut issues = vec![];
+    for elements in pairs {
+        let colors = elements.map(|element| element.colors(theme).0);
+        let (Some(a), Some(b)) = (parse_color(colors[0]), parse_color(colors[1])) else {
+            continue;
+        };
+        let (a, b) = (a.map(linear), b.map(linear));
+        if delta_e(a, b) < min_difference {
+            continue;
+        }
+        for deficiency in Deficiency::ALL {
+            let difference = delta_e(deficiency.simulate_linear(a), deficiency.simulate_linear(b));
+            if difference < min_difference {
+                issues.push(Issue::Indistinguishable {
+                    elements,
+                    colors: colors.map(str::to_owned),
+                    deficiency,
+                    difference,
+                });
+            }
+        }
+    }
+    issues
+}
+
+/// The lines of body text `page` takes at full size, with headings counting for the larger
+/// lines they take.
+fn page_lines(page: &Page, line_chars: usize) -> f32 {
+    let wrapped = |text: &str| grapheme_len(text).div_ceil(line_chars).max(1) as f32;
+    let mut lines = 0.0;
+    let mut paragraph = String::new();
+    for mark in page_marks(page) {
+        match mark {
+            Mark::Text(text, style)
+                if style.heading.to_int() == 0
+                    && !style.quote
+                    && matches!(style.listing, Listing::None) =>
+            {
+                paragraph.push_str(&text);
+                continue;
+            }
+            Mark::Text(text, style) if style.heading.to_int() > 0 => {
+                lines += wrapped(&text) * 1.5;
+            }
+            Mark::Text(text, _) => lines += wrapped(&text),
+            Mark::CodeBlock(code, _) | Mark::CodeWalkthrough(code, ..) => {
+                lines += code.lines().count() as f32;
+            }
+            _ => {}
+        }
+        if !paragraph.trim().is_empty() {
+            lines += wrapped(&paragraph);
+        }
+        paragraph.clear();
+    }
+    if !paragraph.trim().is_empty() {
+        lines += wrapped(&paragraph);
+    }
+    lines
+}
+
+/// The red, green and blue channels of a CSS color.
+pub(crate) fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let color = color.trim();
+    if let Some(hex) = color.strip_prefix('#') {
+        if !hex.is_ascii() {
+            return None;
+        }
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 | 4 => {
+                let mut rgb = [0; 3];
+                for (idx, c) in rgb.iter_mut().enumerate() {
+                    *c = channel(&hex[idx..idx + 1])? * 17;
+                }
+                Some(rgb)
+            }
+            6 | 8 => Some([
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            ]),
+            _ => None,
+        };
+    }
+    let args = color
+        .strip_prefix("rgba(")
+        .or_else(|| color.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let channels: Vec<u8> = args
+        .split([',', ' ', '/'])
+        .filter(|arg| !arg.is_empty())
+        .take(3)
+        .map(|arg| arg.parse().ok())
+        .collect::<Option<_>>()?;
+    channels.try_into().ok()
+}
+
+/// A channel of an sRGB color, in linear light from 0 to 1.
+fn linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.03928 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The relative luminance of a color, as defined by the WCAG.
+fn luminance(rgb: [u8; 3]) -> f32 {
+    let [r, g, b] = rgb.map(linear);
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// The CIE76 difference of two linear RGB colors, their distance in the CIELAB space.
+fn delta_e(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let (a, b) = (lab(a), lab(b));
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// The CIELAB coordinates of a linear RGB color, under the D65 illuminant.
+fn lab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let f = |t: f32| {
+        if t > 0.008_856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let x = f((0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.950_47);
+    let y = f(0.2126 * r + 0.7152 * g + 0.0722 * b);
+    let z = f((0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.088_83);
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
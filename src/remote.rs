@@ -0,0 +1,319 @@
+//! Load decks over HTTP, to present straight from the URL of a document, like a gist.
+//!
+//! [`Deck::from_url`](crate::deck::Deck::from_url), with the `remote` feature, loads a deck
+//! blocking the thread, and
+//! [`Deck::from_url_async`](crate::deck::Deck::from_url_async), with the `remote-async` feature,
+//! loads it asynchronously, in a Tokio runtime. Both resolve the relative sources of the images,
+//! galleries and narrations of the deck, and the files included by its code blocks, against the
+//! URL of the document.
+//!
+//! Documents and included files can be cached in a directory, see [`CachePolicy`]. A cached
+//! copy is kept with its ETag, so the server sends the file again only when it changed.
+//!
+//! ```no_run
+//! use opmark::{deck::Deck, remote::CachePolicy};
+//!
+//! let deck = Deck::from_url(
+//!     "https://example.com/talks/opmark.opmark",
+//!     &CachePolicy::Revalidate("cache".into()),
+//! )
+//! .unwrap();
+//! // `![logo](img/logo.png)` shows `https://example.com/talks/img/logo.png`
+//! ```
+
+use crate::{include::IncludeResolver, link::LinkTarget};
+#[cfg(feature = "remote-async")]
+use crate::{Parser, ParserOptions};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "remote-async")]
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use std::{
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Whether and how downloaded files are cached.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CachePolicy {
+    /// Files are downloaded every time, and not kept.
+    #[default]
+    NoCache,
+    /// Files are kept in the directory, and downloaded again only when the server has another
+    /// version, as told by their ETag.
+    Revalidate(PathBuf),
+    /// Files are kept in the directory, and taken from it without asking the server, like to
+    /// present offline. Files not in the directory are downloaded.
+    PreferCache(PathBuf),
+}
+
+impl CachePolicy {
+    /// The cache directory, `None` if files are not cached.
+    pub fn dir(&self) -> Option<&Path> {
+        match self {
+            CachePolicy::NoCache => None,
+            CachePolicy::Revalidate(dir) | CachePolicy::PreferCache(dir) => Some(dir),
+        }
+    }
+}
+
+/// An error loading a deck over HTTP.
+#[derive(Debug)]
+pub enum RemoteError {
+    /// The request could not be made, for the given reason.
+    Request(String),
+    /// The server answered with the error status.
+    Status(u16),
+    /// The document is not UTF-8 text.
+    NotText,
+    /// The cache could not be written.
+    Cache(io::Error),
+}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteError::Request(reason) => write!(f, "request failed: {}", reason),
+            RemoteError::Status(status) => write!(f, "server answered with status {}", status),
+            RemoteError::NotText => write!(f, "the document is not UTF-8 text"),
+            RemoteError::Cache(err) => write!(f, "cache not written: {}", err),
+        }
+    }
+}
+
+impl error::Error for RemoteError {}
+
+/// Resolve the link `url`, as written in the document at `base`, into an absolute URL.
+///
+/// Links with a scheme are kept, and so are links to a place in the document, like `#intro`.
+///
+/// ```
+/// use opmark::remote::join_url;
+///
+/// let base = "https://example.com/talks/deck.opmark?raw=1";
+/// assert_eq!(join_url(base, "img/logo.png"), "https://example.com/talks/img/logo.png");
+/// assert_eq!(join_url(base, "../logo.png"), "https://example.com/logo.png");
+/// assert_eq!(join_url(base, "/logo.png"), "https://example.com/logo.png");
+/// assert_eq!(join_url(base, "//cdn.example.com/a.png"), "https://cdn.example.com/a.png");
+/// assert_eq!(join_url(base, "data:image/png;base64,AA=="), "data:image/png;base64,AA==");
+/// assert_eq!(join_url(base, "#intro"), "#intro");
+/// ```
+pub fn join_url(base: &str, url: &str) -> String {
+    if !matches!(LinkTarget::parse(url), LinkTarget::Relative(_)) || url.starts_with('#') {
+        return url.to_owned();
+    }
+    let Some((scheme, rest)) = base.split_once("://") else {
+        return url.to_owned();
+    };
+    if let Some(network_path) = url.strip_prefix("//") {
+        return format!("{}://{}", scheme, network_path);
+    }
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let mut segments: Vec<&str> = match url.strip_prefix('/') {
+        Some(_) => vec![],
+        // the segments of the directory of the document
+        None => path.split('/').skip(1).collect(),
+    };
+    segments.pop();
+    for segment in url.trim_start_matches('/').split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("{}://{}/{}", scheme, host, segments.join("/"))
+}
+
+/// Reads the files included by a document downloaded from a URL, relative to the URL, blocking
+/// the thread.
+#[cfg(feature = "remote")]
+#[derive(Clone, Debug)]
+pub struct UrlResolver {
+    base: String,
+    policy: CachePolicy,
+}
+
+#[cfg(feature = "remote")]
+impl UrlResolver {
+    /// Create a resolver for the document at `base`.
+    #[inline]
+    pub fn new(base: String, policy: CachePolicy) -> Self {
+        Self { base, policy }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl IncludeResolver for UrlResolver {
+    fn resolve(&self, path: &str) -> Option<String> {
+        String::from_utf8(self.resolve_bytes(path)?).ok()
+    }
+
+    fn resolve_bytes(&self, path: &str) -> Option<Vec<u8>> {
+        fetch(&join_url(&self.base, path), &self.policy).ok()
+    }
+}
+
+/// Download the file at `url`, blocking the thread.
+#[cfg(feature = "remote")]
+pub(crate) fn fetch(url: &str, policy: &CachePolicy) -> Result<Vec<u8>, RemoteError> {
+    let cached = Cached::read(url, policy);
+    if let (CachePolicy::PreferCache(_), Some(cached)) = (policy, &cached) {
+        return Ok(cached.body.clone());
+    }
+    let mut request = ureq::get(url);
+    if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref()) {
+        request = request.set("If-None-Match", etag);
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => return Err(RemoteError::Status(status)),
+        Err(err) => return Err(RemoteError::Request(err.to_string())),
+    };
+    if response.status() == 304 {
+        if let Some(cached) = cached {
+            return Ok(cached.body);
+        }
+    }
+    let etag = response.header("ETag").map(str::to_owned);
+    let mut body = vec![];
+    io::Read::read_to_end(&mut response.into_reader(), &mut body)
+        .map_err(|err| RemoteError::Request(err.to_string()))?;
+    Cached { etag, body }.write(url, policy)
+}
+
+/// Download the file at `url` with `client`.
+#[cfg(feature = "remote-async")]
+pub(crate) async fn fetch_async(
+    client: &reqwest::Client,
+    url: &str,
+    policy: &CachePolicy,
+) -> Result<Vec<u8>, RemoteError> {
+    let cached = Cached::read(url, policy);
+    if let (CachePolicy::PreferCache(_), Some(cached)) = (policy, &cached) {
+        return Ok(cached.body.clone());
+    }
+    let mut request = client.get(url);
+    if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref()) {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|err| RemoteError::Request(err.to_string()))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(cached) = cached {
+            return Ok(cached.body);
+        }
+    }
+    if status.is_client_error() || status.is_server_error() {
+        return Err(RemoteError::Status(status.as_u16()));
+    }
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_owned);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|err| RemoteError::Request(err.to_string()))?;
+    Cached {
+        etag,
+        body: body.to_vec(),
+    }
+    .write(url, policy)
+}
+
+/// The paths of the files included by the document `source`.
+#[cfg(feature = "remote-async")]
+pub(crate) fn included_paths(source: &str) -> Vec<String> {
+    let recorder = Recorder::default();
+    let options = ParserOptions::new().with_resolver(recorder.clone());
+    Parser::with_options(source.to_owned(), options).for_each(drop);
+    let paths = recorder.0.lock().map(|paths| paths.clone());
+    paths.unwrap_or_default()
+}
+
+/// Records the paths of the files included by a document, resolving none.
+#[cfg(feature = "remote-async")]
+#[derive(Clone, Debug, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+#[cfg(feature = "remote-async")]
+impl IncludeResolver for Recorder {
+    fn resolve(&self, path: &str) -> Option<String> {
+        if let Ok(mut paths) = self.0.lock() {
+            paths.push(path.to_owned());
+        }
+        None
+    }
+}
+
+/// Reads included files downloaded beforehand, by path.
+#[cfg(feature = "remote-async")]
+#[derive(Debug, Default)]
+pub(crate) struct Prefetched(pub HashMap<String, Vec<u8>>);
+
+#[cfg(feature = "remote-async")]
+impl IncludeResolver for Prefetched {
+    fn resolve(&self, path: &str) -> Option<String> {
+        String::from_utf8(self.resolve_bytes(path)?).ok()
+    }
+
+    fn resolve_bytes(&self, path: &str) -> Option<Vec<u8>> {
+        self.0.get(path).cloned()
+    }
+}
+
+/// A downloaded file kept in the cache.
+struct Cached {
+    etag: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Cached {
+    /// The copy of the file at `url`, if it is cached.
+    fn read(url: &str, policy: &CachePolicy) -> Option<Self> {
+        let path = cache_path(policy.dir()?, url);
+        let body = fs::read(&path).ok()?;
+        let etag = fs::read_to_string(path.with_extension("etag")).ok();
+        Some(Self { etag, body })
+    }
+
+    /// Keep the file at `url` in the cache, and return its content.
+    fn write(self, url: &str, policy: &CachePolicy) -> Result<Vec<u8>, RemoteError> {
+        let Some(dir) = policy.dir() else {
+            return Ok(self.body);
+        };
+        let path = cache_path(dir, url);
+        let write = || -> io::Result<()> {
+            fs::create_dir_all(dir)?;
+            fs::write(&path, &self.body)?;
+            match &self.etag {
+                Some(etag) => fs::write(path.with_extension("etag"), etag),
+                None => match fs::remove_file(path.with_extension("etag")) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                    _ => Ok(()),
+                },
+            }
+        };
+        write().map_err(RemoteError::Cache)?;
+        Ok(self.body)
+    }
+}
+
+/// The path of the copy of the file at `url` in the cache directory `dir`.
+fn cache_path(dir: &Path, url: &str) -> PathBuf {
+    let hash: String = Sha256::digest(url)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    dir.join(hash).with_extension("body")
+}
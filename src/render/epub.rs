@@ -0,0 +1,410 @@
+//! Export decks to e-books (EPUB 3), turning talks into articles to share.
+//!
+//! The deck is [`linearize`]d, and each chapter becomes a section of the book, listed in its
+//! table of contents. Images are embedded when their files can be read with the resolver of the
+//! options, and replaced by their title otherwise. Links to labels, like cross-references, point
+//! to the chapter of the label, and links to pages, like the ones of agenda pages, to the chapter
+//! of the page.
+//!
+//! ```
+//! use opmark::{deck::Deck, render::epub::{to_epub, EpubOptions}};
+//!
+//! let deck = Deck::new("# Intro\nHello\n---\n# Details\n- one".to_owned());
+//! let options = EpubOptions::new().with_modified("2024-01-01T00:00:00Z".to_owned());
+//! let epub = to_epub(&deck, &options).unwrap();
+//! assert_eq!(&epub[30..58], b"mimetypeapplication/epub+zip");
+//! ```
+
+use super::{escape_xml, html::to_html_with_links, linearize, Chapter};
+use crate::{
+    deck::Deck,
+    include::IncludeResolver,
+    link::{AllowLinks, Guarded, LinkHooks, LinkPolicy},
+    mark::{Directive, Mark},
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io::{self, Cursor, Seek, Write},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+const NS_XHTML: &str = "http://www.w3.org/1999/xhtml";
+const NS_OPS: &str = "http://www.idpf.org/2007/ops";
+
+/// The media types of the images which can be embedded, by extension.
+const IMAGE_TYPES: [(&str, &str); 6] = [
+    ("gif", "image/gif"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("webp", "image/webp"),
+];
+
+/// Options for the exported book.
+#[derive(Clone, Debug)]
+pub struct EpubOptions {
+    /// The title of the book. If `None`, the title of the first chapter is used.
+    pub title: Option<String>,
+    /// The language of the book, as a BCP 47 tag. `en` by default.
+    pub language: String,
+    /// The unique identifier of the book. If `None`, an identifier is derived from the deck.
+    pub identifier: Option<String>,
+    /// The time of the last modification, like `2024-01-01T00:00:00Z`. If `None`, the time of
+    /// the export is used.
+    pub modified: Option<String>,
+    /// Read the images to embed.
+    pub resolver: Option<Arc<dyn IncludeResolver>>,
+    /// Which links are written, see [`LinkPolicy`]. All links are allowed by default.
+    pub link_policy: Option<Arc<dyn LinkPolicy>>,
+}
+
+impl Default for EpubOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            language: "en".to_owned(),
+            identifier: None,
+            modified: None,
+            resolver: None,
+            link_policy: None,
+        }
+    }
+}
+
+impl EpubOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_identifier(mut self, identifier: String) -> Self {
+        self.identifier = Some(identifier);
+        self
+    }
+
+    #[inline]
+    pub fn with_language(mut self, language: String) -> Self {
+        self.language = language;
+        self
+    }
+
+    #[inline]
+    pub fn with_link_policy(mut self, link_policy: impl LinkPolicy + 'static) -> Self {
+        self.link_policy = Some(Arc::new(link_policy));
+        self
+    }
+
+    #[inline]
+    pub fn with_modified(mut self, modified: String) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    #[inline]
+    pub fn with_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    #[inline]
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = Some(title);
+        self
+    }
+}
+
+/// Export `deck` into the bytes of an `.epub` file.
+pub fn to_epub(deck: &Deck, options: &EpubOptions) -> io::Result<Vec<u8>> {
+    let mut cursor = Cursor::new(vec![]);
+    write_epub(deck, options, &mut cursor)?;
+    Ok(cursor.into_inner())
+}
+
+/// Export `deck` as an `.epub` file into `writer`.
+pub fn write_epub<W: Write + Seek>(
+    deck: &Deck,
+    options: &EpubOptions,
+    writer: W,
+) -> io::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("write_epub", pages = deck.pages().len()).entered();
+
+    let mut chapters = linearize(deck);
+    if chapters.is_empty() {
+        chapters.push(Chapter {
+            title: String::new(),
+            marks: vec![],
+            pages: vec![],
+        });
+    }
+    let mut images = Images::new(options.resolver.as_deref());
+    let anchors = Anchors::new(&chapters);
+    let links = Guarded {
+        hooks: &anchors,
+        policy: options.link_policy.as_deref().unwrap_or(&AllowLinks),
+    };
+    for chapter in &mut chapters {
+        chapter.marks = chapter
+            .marks
+            .drain(..)
+            .filter_map(|mark| images.embed(mark))
+            .collect();
+    }
+    let title = options
+        .title
+        .clone()
+        .or_else(|| Some(chapters[0].title.clone()).filter(|title| !title.is_empty()))
+        .unwrap_or_else(|| "OpMark".to_owned());
+    let identifier = options.identifier.clone().unwrap_or_else(|| {
+        let mut hasher = DefaultHasher::new();
+        for page in deck.pages() {
+            page.id().hash(&mut hasher);
+        }
+        format!("urn:opmark:{:016x}", hasher.finish())
+    });
+    let modified = options.modified.clone().unwrap_or_else(now);
+
+    let mut zip = ZipWriter::new(writer);
+    // the mimetype comes first and uncompressed, so it can be sniffed
+    zip.start_file(
+        "mimetype",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(b"application/epub+zip")?;
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut file = |name: &str, content: &[u8]| -> io::Result<()> {
+        zip.start_file(name, deflated)?;
+        zip.write_all(content)
+    };
+
+    file(
+        "META-INF/container.xml",
+        format!(
+            r#"{}<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container"><rootfiles><rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/></rootfiles></container>"#,
+            XML_DECLARATION
+        )
+        .as_bytes(),
+    )?;
+
+    let mut manifest = String::from(
+        r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>"#,
+    );
+    let mut spine = String::new();
+    let mut toc = String::new();
+    for (idx, chapter) in chapters.iter().enumerate() {
+        let name = format!("chapter{}", idx + 1);
+        let chapter_title = if chapter.title.is_empty() {
+            format!("Chapter {}", idx + 1)
+        } else {
+            chapter.title.clone()
+        };
+        manifest.push_str(&format!(
+            r#"<item id="{0}" href="{0}.xhtml" media-type="application/xhtml+xml"/>"#,
+            name
+        ));
+        spine.push_str(&format!(r#"<itemref idref="{}"/>"#, name));
+        toc.push_str(&format!(
+            r#"<li><a href="{}.xhtml">{}</a></li>"#,
+            name,
+            escape_xml(&chapter_title)
+        ));
+        file(
+            &format!("EPUB/{}.xhtml", name),
+            xhtml(
+                &options.language,
+                &chapter_title,
+                &to_html_with_links(&chapter.marks, &links),
+            )
+            .as_bytes(),
+        )?;
+    }
+    for (idx, (href, media_type, bytes)) in images.files.iter().enumerate() {
+        manifest.push_str(&format!(
+            r#"<item id="image{}" href="{}" media-type="{}"/>"#,
+            idx + 1,
+            href,
+            media_type
+        ));
+        file(&format!("EPUB/{}", href), bytes)?;
+    }
+    file(
+        "EPUB/nav.xhtml",
+        xhtml(
+            &options.language,
+            &title,
+            &format!(
+                r#"<nav epub:type="toc"><h1>{}</h1><ol>{}</ol></nav>"#,
+                escape_xml(&title),
+                toc
+            ),
+        )
+        .as_bytes(),
+    )?;
+    file(
+        "EPUB/package.opf",
+        format!(
+            r#"{}<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id" xml:lang="{}"><metadata xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:identifier id="id">{}</dc:identifier><dc:title>{}</dc:title><dc:language>{}</dc:language><meta property="dcterms:modified">{}</meta></metadata><manifest>{}</manifest><spine>{}</spine></package>"#,
+            XML_DECLARATION,
+            escape_xml(&options.language),
+            escape_xml(&identifier),
+            escape_xml(&title),
+            escape_xml(&options.language),
+            escape_xml(&modified),
+            manifest,
+            spine
+        )
+        .as_bytes(),
+    )?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// The chapters of the anchors in the book, so links to them work from other chapters.
+#[derive(Debug)]
+struct Anchors {
+    /// The index of the chapter of each label.
+    chapters: HashMap<String, usize>,
+    /// The index of the chapter of each page, by the number of the page from 1.
+    pages: HashMap<usize, usize>,
+}
+
+impl Anchors {
+    fn new(chapters: &[Chapter]) -> Self {
+        let mut anchors = HashMap::new();
+        let mut pages = HashMap::new();
+        for (idx, chapter) in chapters.iter().enumerate() {
+            pages.extend(chapter.pages.iter().map(|page| (page + 1, idx)));
+            for mark in &chapter.marks {
+                if let Mark::Directive(Directive::Label(label)) = mark {
+                    anchors.entry(label.clone()).or_insert(idx);
+                }
+            }
+        }
+        Self {
+            chapters: anchors,
+            pages,
+        }
+    }
+}
+
+impl LinkHooks for Anchors {
+    fn relative(&self, url: &str) -> Option<String> {
+        let Some(anchor) = url.strip_prefix('#') else {
+            return Some(url.to_owned());
+        };
+        // pages have no anchor in their chapter
+        let page = anchor
+            .strip_prefix("page-")
+            .and_then(|number| number.parse().ok())
+            .and_then(|number: usize| self.pages.get(&number));
+        let href = match (self.chapters.get(anchor), page) {
+            (Some(idx), _) => format!("chapter{}.xhtml{}", idx + 1, url),
+            (None, Some(idx)) => format!("chapter{}.xhtml", idx + 1),
+            (None, None) => url.to_owned(),
+        };
+        Some(href)
+    }
+}
+
+/// The images embedded in the book.
+struct Images<'a> {
+    resolver: Option<&'a dyn IncludeResolver>,
+    /// The path in the book of each embedded source.
+    hrefs: HashMap<String, Option<String>>,
+    /// The path, the media type and the bytes of each embedded image.
+    files: Vec<(String, &'static str, Vec<u8>)>,
+}
+
+impl<'a> Images<'a> {
+    fn new(resolver: Option<&'a dyn IncludeResolver>) -> Self {
+        Self {
+            resolver,
+            hrefs: HashMap::new(),
+            files: vec![],
+        }
+    }
+
+    /// `mark` with its images embedded, or replaced by their title if they can not be read.
+    fn embed(&mut self, mark: Mark) -> Option<Mark> {
+        match mark {
+            Mark::Image(src, title, style) => match self.href(&src) {
+                Some(href) => Some(Mark::Image(href, title, style)),
+                None if title.is_empty() => None,
+                None => Some(Mark::Text(title, Default::default())),
+            },
+            Mark::Gallery(items, style) => {
+                let items = items
+                    .into_iter()
+                    .filter_map(|(src, title)| Some((self.href(&src)?, title)))
+                    .collect();
+                Some(Mark::Gallery(items, style))
+            }
+            mark => Some(mark),
+        }
+    }
+
+    /// The path in the book of the image at `src`.
+    fn href(&mut self, src: &str) -> Option<String> {
+        if let Some(href) = self.hrefs.get(src) {
+            return href.clone();
+        }
+        let extension = src.rsplit_once('.')?.1.to_ascii_lowercase();
+        let href = IMAGE_TYPES
+            .iter()
+            .find(|(ext, _)| *ext == extension)
+            .and_then(|(_, media_type)| {
+                let bytes = self.resolver?.resolve_bytes(src)?;
+                let href = format!("images/image{}.{}", self.files.len() + 1, extension);
+                self.files.push((href.clone(), media_type, bytes));
+                Some(href)
+            });
+        self.hrefs.insert(src.to_owned(), href.clone());
+        href
+    }
+}
+
+/// An XHTML document with the given `body`.
+fn xhtml(language: &str, title: &str, body: &str) -> String {
+    format!(
+        r#"{}<!DOCTYPE html><html xmlns="{}" xmlns:epub="{}" xml:lang="{}"><head><title>{}</title></head><body>{}</body></html>"#,
+        XML_DECLARATION,
+        NS_XHTML,
+        NS_OPS,
+        escape_xml(language),
+        escape_xml(title),
+        body
+    )
+}
+
+/// The current time in UTC, like `2024-01-01T00:00:00Z`.
+fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    // the civil date of the days since 1970-01-01
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
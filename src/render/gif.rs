@@ -0,0 +1,177 @@
+//! Export single pages to animated GIFs cycling through their steps, to share an animated page
+//! where slides can not be presented, like chats and documents.
+//!
+//! Each step of the page is drawn by a [`Rasterizer`] and shown for `step_duration`. The images
+//! of all steps must have the same size.
+//!
+//! ```
+//! use opmark::{
+//!     deck::{Deck, Page},
+//!     render::{gif::{render_page, GifOptions}, Rasterizer},
+//! };
+//! use std::{io, time::Duration};
+//!
+//! // draws each step as a pixel getting brighter
+//! struct Pixel;
+//!
+//! impl Rasterizer for Pixel {
+//!     fn rasterize(&self, page: &Page) -> io::Result<Vec<u8>> {
+//!         let mut png = vec![];
+//!         let mut encoder = png::Encoder::new(&mut png, 1, 1);
+//!         encoder.set_color(png::ColorType::Grayscale);
+//!         let mut writer = encoder.write_header()?;
+//!         writer.write_image_data(&[page.step as u8 * 100])?;
+//!         writer.finish()?;
+//!         Ok(png)
+//!     }
+//! }
+//!
+//! let deck = Deck::new("First\n---t\nSecond\n---t\nThird".to_owned());
+//! let options = GifOptions::new().with_step_duration(Duration::from_millis(1500));
+//! let bytes = render_page(&deck.pages()[0], &Pixel, &options).unwrap();
+//!
+//! let mut decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+//! let mut delays = vec![];
+//! while let Some(frame) = decoder.read_next_frame().unwrap() {
+//!     delays.push(frame.delay);
+//! }
+//! assert_eq!(delays, [150, 150, 150]);
+//! ```
+
+use super::Rasterizer;
+use crate::deck::Page;
+use std::{io, time::Duration};
+
+/// Options for the exported GIF.
+#[derive(Clone, Debug)]
+pub struct GifOptions {
+    /// How long each step of the page is shown. 2 seconds by default.
+    pub step_duration: Duration,
+    /// Whether the steps are cycled through forever, rather than played once. `true` by default.
+    pub repeat: bool,
+    /// The speed of the quantization of the colors of the images, from 1, the slowest and best,
+    /// to 30. 10 by default.
+    pub speed: i32,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            step_duration: Duration::from_secs(2),
+            repeat: true,
+            speed: 10,
+        }
+    }
+}
+
+impl GifOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_repeat(mut self, repeat: bool) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    #[inline]
+    pub fn with_speed(mut self, speed: i32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    #[inline]
+    pub fn with_step_duration(mut self, step_duration: Duration) -> Self {
+        self.step_duration = step_duration;
+        self
+    }
+}
+
+/// Render the steps of `page` drawn by `rasterizer` into an animated GIF.
+///
+/// The step `page` is at is ignored: every step is shown, from the first one.
+pub fn render_page(
+    page: &Page,
+    rasterizer: &dyn Rasterizer,
+    options: &GifOptions,
+) -> io::Result<Vec<u8>> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("render_gif", steps = page.max_step + 1).entered();
+
+    let mut images = vec![];
+    for step in 0..=page.max_step {
+        let mut page = page.clone();
+        page.step = step;
+        images.push(decode_png(&rasterizer.rasterize(&page)?)?);
+    }
+    // a page has at least one step
+    let (width, height) = (images[0].0, images[0].1);
+    if let Some(step) = images
+        .iter()
+        .position(|(w, h, _)| (*w, *h) != (width, height))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "the image of step {} is {}x{}, not {}x{} like the first step",
+                step, images[step].0, images[step].1, width, height
+            ),
+        ));
+    }
+
+    // the delay of frames is in hundredths of a second
+    let delay = (options.step_duration.as_millis() / 10).min(u16::MAX as u128) as u16;
+    let mut bytes = vec![];
+    {
+        let mut encoder =
+            ::gif::Encoder::new(&mut bytes, width, height, &[]).map_err(io::Error::other)?;
+        if options.repeat {
+            encoder
+                .set_repeat(::gif::Repeat::Infinite)
+                .map_err(io::Error::other)?;
+        }
+        for (_, _, mut rgba) in images {
+            let mut frame =
+                ::gif::Frame::from_rgba_speed(width, height, &mut rgba, options.speed.clamp(1, 30));
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// The width, the height and the RGBA pixels of the PNG image `png`.
+fn decode_png(png: &[u8]) -> io::Result<(u16, u16, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(io::Error::other)?;
+    pixels.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels,
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|c| [c[0], c[1], c[2], 0xff])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|c| [c[0], c[0], c[0], c[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&c| [c, c, c, 0xff]).collect(),
+        // expanded into RGB by the transformations
+        png::ColorType::Indexed => unreachable!(),
+    };
+    let dimension = |value: u32| {
+        u16::try_from(value).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the image is too large for a GIF",
+            )
+        })
+    };
+    Ok((dimension(info.width)?, dimension(info.height)?, rgba))
+}
@@ -0,0 +1,964 @@
+//! Render marks into HTML.
+//!
+//! The HTML is also well-formed XML, so it can be embedded in XHTML documents.
+//!
+//! ```
+//! use opmark::{mark::Mark, render::html::to_html, Parser};
+//!
+//! let marks: Vec<Mark> = Parser::new("# Title\n*Bold*\n- one\n  - two".to_owned()).collect();
+//! assert_eq!(
+//!     to_html(&marks),
+//!     "<section id=\"page-1\" class=\"page\"><h1>Title</h1><p><strong>Bold</strong></p>\
+//!      <ul><li>one<ul><li>two</li></ul></li></ul></section>"
+//! );
+//! ```
+//!
+//! [`to_html_document`] renders a standalone page instead, styled by an [`HtmlTheme`]. The theme
+//! is emitted as CSS custom properties, like `--opmark-accent`, which the stylesheet of the page
+//! uses, so extra CSS can both override the properties and add rules:
+//!
+//! ```
+//! use opmark::{mark::Mark, render::html::{to_html_document, HtmlOptions}, Parser};
+//!
+//! let marks: Vec<Mark> = Parser::new("# Title".to_owned()).collect();
+//! let options = HtmlOptions::new()
+//!     .with_extra_css(":root { --opmark-accent: #e6007e; }".to_owned())
+//!     .with_head_html("<link rel=\"icon\" href=\"logo.png\" />".to_owned());
+//! let html = to_html_document(&marks, &options);
+//! assert!(html.contains("<title>Title</title>"));
+//! assert!(html.contains("--opmark-accent: #3b82f6;"));
+//! assert!(html.contains(":root { --opmark-accent: #e6007e; }"));
+//! ```
+//!
+//! A theme can pair a dark variant with its colors, used when the reader prefers a dark color
+//! scheme, and derive one from its own colors with [`HtmlTheme::derive_dark`]:
+//!
+//! ```
+//! use opmark::render::html::HtmlTheme;
+//!
+//! let theme = HtmlTheme::new();
+//! let dark = theme.derive_dark();
+//! assert_eq!((dark.background.as_str(), dark.foreground.as_str()), ("#000000", "#d7dbe0"));
+//! let css = theme.with_dark(dark).to_css();
+//! assert!(css.contains("@media (prefers-color-scheme: dark) {\n  :root {\n    --opmark-background: #000000;"));
+//! ```
+//!
+//! Deliverables with accessibility requirements can be exported with an [`ExportProfile`], see
+//! [`HtmlOptions::with_profile`].
+
+use super::escape_xml;
+use crate::{
+    font::{FontRegistry, FontRole},
+    link::{AllowLinks, Guarded, KeepLinks, LinkHooks, LinkPolicy},
+    mark::{
+        Directive, Listing, Mark, MathMode, SeparatorDir, SeparatorLine, SeparatorStyle,
+        SeparatorThickness, StyleText,
+    },
+    parser::format_time_of_day,
+    readability::parse_color,
+};
+use std::sync::Arc;
+
+/// The stylesheet of the marks, using the custom properties of [`HtmlTheme`].
+pub const STYLE: &str = "body { background: var(--opmark-background); color: var(--opmark-foreground); font-family: var(--opmark-font-family); }
+a { color: var(--opmark-accent); }
+h1, h2, h3 { color: var(--opmark-heading); font-family: var(--opmark-heading-font-family); }
+blockquote { border-left: 0.25em solid var(--opmark-accent); margin-left: 0; padding-left: 1em; }
+pre, code { background: var(--opmark-code-background); font-family: var(--opmark-code-font-family); }
+pre { overflow-x: auto; padding: 1em; }
+hr { border: none; border-top: 1px solid var(--opmark-border); }
+hr.dotted { border-top-style: dotted; }
+hr.dashed { border-top-style: dashed; }
+hr.thick { border-top-width: 3px; }
+hr.thin { border-top-width: 0.5px; }
+.gallery { display: flex; flex-wrap: wrap; gap: 1em; }
+.gallery > img, p > img { max-width: 100%; }
+.holdslide { text-align: center; }
+.holdslide .countdown { font-size: 3em; font-variant-numeric: tabular-nums; }
+";
+
+/// The script of [`to_html_document`] counting down to the start of hold pages, see
+/// [`Mark::HoldSlide`]. The start is a time of day in the local time of the viewer.
+pub const COUNTDOWN: &str = "function opmarkCountdown() {
+  const now = new Date();
+  for (const slide of document.querySelectorAll('.holdslide')) {
+    const [h, m, s] = slide.dataset.start.split(':').map(Number);
+    const start = new Date(now);
+    start.setHours(h, m, s || 0, 0);
+    const left = Math.max(0, Math.ceil((start - now) / 1000));
+    const pad = (n) => String(n).padStart(2, '0');
+    slide.querySelector('.countdown').textContent = left > 0
+      ? (left >= 3600 ? Math.floor(left / 3600) + ':' : '') + pad(Math.floor(left / 60) % 60) + ':' + pad(left % 60)
+      : 'Starting now';
+  }
+}
+opmarkCountdown();
+setInterval(opmarkCountdown, 1000);
+";
+
+/// The colors and fonts of rendered HTML, emitted as CSS custom properties.
+#[derive(Clone, Debug)]
+pub struct HtmlTheme {
+    /// `--opmark-background`.
+    pub background: String,
+    /// `--opmark-foreground`, the color of the text.
+    pub foreground: String,
+    /// `--opmark-heading`, the color of headings.
+    pub heading: String,
+    /// `--opmark-accent`, the color of links and quotes.
+    pub accent: String,
+    /// `--opmark-border`, the color of separators and borders.
+    pub border: String,
+    /// `--opmark-code-background`.
+    pub code_background: String,
+    /// `--opmark-font-family`.
+    pub font_family: String,
+    /// `--opmark-heading-font-family`.
+    pub heading_font_family: String,
+    /// `--opmark-code-font-family`.
+    pub code_font_family: String,
+    /// `@font-face` rules of the fonts used by the theme, see [`HtmlTheme::with_fonts`].
+    pub font_faces: String,
+    /// The size of body text, in points. If `None`, the size set by the reader.
+    pub font_size: Option<f32>,
+    /// The watermark shown over every page.
+    pub watermark: Option<Watermark>,
+    /// The colors used when the reader prefers a dark color scheme. Its fonts are not used.
+    pub dark: Option<Box<HtmlTheme>>,
+}
+
+impl Default for HtmlTheme {
+    fn default() -> Self {
+        Self {
+            background: "#ffffff".to_owned(),
+            foreground: "#1f2328".to_owned(),
+            heading: "#1f2328".to_owned(),
+            accent: "#3b82f6".to_owned(),
+            border: "#d0d7de".to_owned(),
+            code_background: "#f6f8fa".to_owned(),
+            font_family: "system-ui, sans-serif".to_owned(),
+            heading_font_family: "system-ui, sans-serif".to_owned(),
+            code_font_family: "ui-monospace, monospace".to_owned(),
+            font_faces: String::new(),
+            font_size: None,
+            watermark: None,
+            dark: None,
+        }
+    }
+}
+
+impl HtmlTheme {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_accent(mut self, accent: String) -> Self {
+        self.accent = accent;
+        self
+    }
+
+    #[inline]
+    pub fn with_background(mut self, background: String) -> Self {
+        self.background = background;
+        self
+    }
+
+    #[inline]
+    pub fn with_border(mut self, border: String) -> Self {
+        self.border = border;
+        self
+    }
+
+    #[inline]
+    pub fn with_code_background(mut self, code_background: String) -> Self {
+        self.code_background = code_background;
+        self
+    }
+
+    #[inline]
+    pub fn with_code_font_family(mut self, code_font_family: String) -> Self {
+        self.code_font_family = code_font_family;
+        self
+    }
+
+    #[inline]
+    pub fn with_dark(mut self, dark: HtmlTheme) -> Self {
+        self.dark = Some(Box::new(dark));
+        self
+    }
+
+    #[inline]
+    pub fn with_font_family(mut self, font_family: String) -> Self {
+        self.font_family = font_family;
+        self
+    }
+
+    #[inline]
+    pub fn with_font_size(mut self, size: f32) -> Self {
+        self.font_size = Some(size);
+        self
+    }
+
+    /// Use the fonts of `fonts`: the font families of the roles with a chain, and the
+    /// `@font-face` rules of the loaded fonts.
+    pub fn with_fonts(mut self, fonts: &FontRegistry) -> Self {
+        for role in FontRole::ALL {
+            if fonts.families(role).is_empty() {
+                continue;
+            }
+            let family = fonts.css_font_family(role);
+            match role {
+                FontRole::Body => self.font_family = family,
+                FontRole::Heading => self.heading_font_family = family,
+                FontRole::Code => self.code_font_family = family,
+            }
+        }
+        self.font_faces = fonts.to_css();
+        self
+    }
+
+    #[inline]
+    pub fn with_foreground(mut self, foreground: String) -> Self {
+        self.foreground = foreground;
+        self
+    }
+
+    #[inline]
+    pub fn with_heading(mut self, heading: String) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    #[inline]
+    pub fn with_heading_font_family(mut self, heading_font_family: String) -> Self {
+        self.heading_font_family = heading_font_family;
+        self
+    }
+
+    #[inline]
+    pub fn with_watermark(mut self, watermark: Watermark) -> Self {
+        self.watermark = Some(watermark);
+        self
+    }
+
+    /// A dark variant of the theme, with the same fonts: the lightness of the backgrounds and
+    /// the borders is reversed, and the one of the dark text colors too, so the contrasts are
+    /// kept. Colors which can not be read are kept as they are.
+    pub fn derive_dark(&self) -> HtmlTheme {
+        HtmlTheme {
+            background: dark_color(&self.background, false),
+            foreground: dark_color(&self.foreground, true),
+            heading: dark_color(&self.heading, true),
+            accent: dark_color(&self.accent, true),
+            border: dark_color(&self.border, false),
+            code_background: dark_color(&self.code_background, false),
+            dark: None,
+            ..self.clone()
+        }
+    }
+
+    /// The theme with its colors replaced by black, white and saturated colors, keeping whether
+    /// its background is dark. A dark variant is replaced too.
+    pub fn to_high_contrast(&self) -> HtmlTheme {
+        let is_dark = parse_color(&self.background)
+            .map(|[r, g, b]| (r as u32 + g as u32 + b as u32) < 384)
+            .unwrap_or(false);
+        let (background, foreground, accent) = if is_dark {
+            ("#000000", "#ffffff", "#ffff00")
+        } else {
+            ("#ffffff", "#000000", "#0000ee")
+        };
+        HtmlTheme {
+            background: background.to_owned(),
+            foreground: foreground.to_owned(),
+            heading: foreground.to_owned(),
+            accent: accent.to_owned(),
+            border: foreground.to_owned(),
+            code_background: background.to_owned(),
+            dark: self
+                .dark
+                .as_ref()
+                .map(|dark| Box::new(dark.to_high_contrast())),
+            ..self.clone()
+        }
+    }
+
+    /// The `@font-face` rules of the theme, then its custom properties as a `:root` rule, the
+    /// colors of its dark variant in a `prefers-color-scheme` media query, and its watermark.
+    pub fn to_css(&self) -> String {
+        let mut css = self.font_faces.clone();
+        css.push_str(":root {\n");
+        for (name, value) in self.colors().into_iter().chain([
+            ("font-family", &self.font_family),
+            ("heading-font-family", &self.heading_font_family),
+            ("code-font-family", &self.code_font_family),
+        ]) {
+            css.push_str(&format!("  --opmark-{}: {};\n", name, value));
+        }
+        if let Some(size) = self.font_size {
+            css.push_str(&format!("  font-size: {}pt;\n", size));
+        }
+        css.push_str("}\n");
+        if let Some(dark) = &self.dark {
+            css.push_str("@media (prefers-color-scheme: dark) {\n  :root {\n");
+            for (name, value) in dark.colors() {
+                css.push_str(&format!("    --opmark-{}: {};\n", name, value));
+            }
+            css.push_str("  }\n}\n");
+        }
+        if let Some(watermark) = &self.watermark {
+            css.push_str(&watermark.to_css());
+        }
+        css
+    }
+
+    fn colors(&self) -> [(&'static str, &String); 6] {
+        [
+            ("background", &self.background),
+            ("foreground", &self.foreground),
+            ("heading", &self.heading),
+            ("accent", &self.accent),
+            ("border", &self.border),
+            ("code-background", &self.code_background),
+        ]
+    }
+}
+
+/// What a watermark shows.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatermarkContent {
+    /// A text, like `DRAFT`, in the color of the text.
+    Text(String),
+    /// The image at a URL, like a logo.
+    Image(String),
+}
+
+/// A watermark shown over every page, like for draft or confidential decks, see
+/// [`HtmlTheme::with_watermark`].
+///
+/// ```
+/// use opmark::render::html::{HtmlTheme, Watermark, WatermarkContent};
+///
+/// let watermark = Watermark::new(WatermarkContent::Text("CONFIDENTIAL".to_owned()))
+///     .with_opacity(0.2)
+///     .with_angle(-45.0);
+/// let css = HtmlTheme::new().with_watermark(watermark).to_css();
+/// assert!(css.contains("section.page::after { content: \"CONFIDENTIAL\";"));
+/// assert!(css.contains("opacity: 0.2; transform: rotate(-45deg);"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watermark {
+    pub content: WatermarkContent,
+    /// The opacity of the watermark, from 0 to 1. 0.15 by default.
+    pub opacity: f32,
+    /// The angle of the watermark, in degrees clockwise. -30 by default.
+    pub angle: f32,
+}
+
+impl Watermark {
+    #[inline]
+    pub fn new(content: WatermarkContent) -> Self {
+        Self {
+            content,
+            opacity: 0.15,
+            angle: -30.0,
+        }
+    }
+
+    #[inline]
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    #[inline]
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// The rules showing the watermark over the pages, centered and not catching the pointer.
+    fn to_css(&self) -> String {
+        let content = match &self.content {
+            WatermarkContent::Text(text) => format!(
+                "content: {}; font-size: 6em; font-weight: bold; color: var(--opmark-foreground);",
+                css_string(text)
+            ),
+            WatermarkContent::Image(src) => format!(
+                "content: \"\"; background: url({}) center / 50% no-repeat;",
+                css_string(src)
+            ),
+        };
+        format!(
+            "section.page {{ position: relative; }}\n\
+             section.page::after {{ {} position: absolute; inset: 0; display: flex; \
+             align-items: center; justify-content: center; opacity: {}; \
+             transform: rotate({}deg); pointer-events: none; }}\n",
+            content, self.opacity, self.angle
+        )
+    }
+}
+
+/// `s` as a quoted CSS string.
+fn css_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\A ");
+    format!("\"{}\"", escaped)
+}
+
+/// A preset for deliverables with accessibility requirements, transforming the theme of an
+/// export, see [`HtmlOptions::with_profile`].
+///
+/// ```
+/// use opmark::render::html::{ExportProfile, HtmlOptions, HtmlTheme};
+///
+/// let theme = HtmlTheme::new().with_accent("#e6007e".to_owned());
+/// let options = HtmlOptions::new()
+///     .with_theme(theme)
+///     .with_profile(ExportProfile::HighContrast)
+///     .with_profile(ExportProfile::LargePrint);
+/// assert_eq!(options.theme.accent, "#0000ee");
+/// assert!(options.to_css().contains("  font-size: 24pt;\n}"));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ExportProfile {
+    /// Black and white colors with saturated links, see [`HtmlTheme::to_high_contrast`].
+    HighContrast,
+    /// Body text of at least [`ExportProfile::LARGE_PRINT_SIZE`].
+    LargePrint,
+}
+
+impl ExportProfile {
+    /// The smallest size of body text in large print, in points.
+    pub const LARGE_PRINT_SIZE: f32 = 24.0;
+
+    /// `theme` transformed for the profile.
+    pub fn apply(&self, theme: HtmlTheme) -> HtmlTheme {
+        match self {
+            ExportProfile::HighContrast => theme.to_high_contrast(),
+            ExportProfile::LargePrint => {
+                let size = theme.font_size.map_or(Self::LARGE_PRINT_SIZE, |size| {
+                    size.max(Self::LARGE_PRINT_SIZE)
+                });
+                theme.with_font_size(size)
+            }
+        }
+    }
+}
+
+/// Options for standalone HTML pages.
+#[derive(Clone, Debug, Default)]
+pub struct HtmlOptions {
+    /// The title of the page. If `None`, the text of the first heading is used.
+    pub title: Option<String>,
+    pub theme: HtmlTheme,
+    /// CSS added after the stylesheet, to override the theme or add rules.
+    pub extra_css: String,
+    /// HTML added at the end of the `head` element as it is, like links to fonts or scripts.
+    pub head_html: String,
+    /// How links are written, see [`LinkHooks`]. Links are kept as they are by default.
+    pub link_hooks: Option<Arc<dyn LinkHooks>>,
+    /// Which links are written, after the hooks, see [`LinkPolicy`]. All links are allowed by
+    /// default.
+    pub link_policy: Option<Arc<dyn LinkPolicy>>,
+}
+
+impl HtmlOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_extra_css(mut self, extra_css: String) -> Self {
+        self.extra_css = extra_css;
+        self
+    }
+
+    #[inline]
+    pub fn with_head_html(mut self, head_html: String) -> Self {
+        self.head_html = head_html;
+        self
+    }
+
+    #[inline]
+    pub fn with_link_hooks(mut self, link_hooks: impl LinkHooks + 'static) -> Self {
+        self.link_hooks = Some(Arc::new(link_hooks));
+        self
+    }
+
+    #[inline]
+    pub fn with_link_policy(mut self, link_policy: impl LinkPolicy + 'static) -> Self {
+        self.link_policy = Some(Arc::new(link_policy));
+        self
+    }
+
+    /// Transform the theme with `profile`. Profiles add up, and apply to the theme set so far.
+    #[inline]
+    pub fn with_profile(mut self, profile: ExportProfile) -> Self {
+        self.theme = profile.apply(self.theme);
+        self
+    }
+
+    #[inline]
+    pub fn with_theme(mut self, theme: HtmlTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    #[inline]
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    /// The hooks links are written with, decided by the policy.
+    pub fn links(&self) -> Guarded<'_> {
+        Guarded {
+            hooks: self.link_hooks.as_deref().unwrap_or(&KeepLinks),
+            policy: self.link_policy.as_deref().unwrap_or(&AllowLinks),
+        }
+    }
+
+    /// The whole stylesheet: the theme, [`STYLE`] and the extra CSS.
+    pub fn to_css(&self) -> String {
+        let mut css = self.theme.to_css();
+        css.push_str(STYLE);
+        if !self.extra_css.is_empty() {
+            css.push_str(&self.extra_css);
+            if !self.extra_css.ends_with('\n') {
+                css.push('\n');
+            }
+        }
+        css
+    }
+}
+
+/// Render `marks` into HTML.
+///
+/// `marks` may either be flat or nested, like for
+/// [`to_source`](crate::serializer::to_source). Pages become `section` elements with the class
+/// `page` and the id `page-` followed by their number from 1, which agenda pages link to, and
+/// the content of all transitions is shown. A label gives its id to the element
+/// before it, so cross-references link to it, see [`crossref`](crate::crossref).
+///
+/// ```
+/// use opmark::{mark::Mark, render::html::to_html, Parser, ParserOptions};
+///
+/// let source = "See @ref(fig:arch).\n![Architecture](arch.png)\n{#fig:arch}";
+/// let options = ParserOptions::new().with_cross_references();
+/// let marks: Vec<Mark> = Parser::with_options(source.to_owned(), options).collect();
+/// let html = to_html(&marks);
+/// assert!(html.contains("<a href=\"#fig:arch\">Figure 1</a>"));
+/// assert!(html.contains("<p id=\"fig:arch\"><img src=\"arch.png\""));
+/// ```
+pub fn to_html(marks: &[Mark]) -> String {
+    to_html_with_links(marks, &KeepLinks)
+}
+
+/// Render `marks` into HTML like [`to_html`], writing links with `links`.
+pub fn to_html_with_links(marks: &[Mark], links: &dyn LinkHooks) -> String {
+    let mut writer = Writer {
+        html: String::new(),
+        is_paragraph: false,
+        lists: vec![],
+        is_page: false,
+        pages: 0,
+        block: None,
+        links,
+    };
+    writer.marks(marks);
+    writer.finish()
+}
+
+/// Render `marks` into a standalone HTML page, see [`to_html`]. Pages with hold slides get the
+/// [`COUNTDOWN`] script.
+pub fn to_html_document(marks: &[Mark], options: &HtmlOptions) -> String {
+    let title = options.title.clone().or_else(|| first_heading(marks));
+    let mut body = to_html_with_links(marks, &options.links());
+    if body.contains("<div class=\"holdslide\"") {
+        body.push_str(&format!("<script>\n{}</script>", COUNTDOWN));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\" />\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\
+         <title>{}</title><style>\n{}</style>{}</head><body>{}</body></html>\n",
+        escape_xml(title.as_deref().unwrap_or("OpMark")),
+        style_content(&options.to_css()),
+        options.head_html,
+        body
+    )
+}
+
+/// The text of the first heading in `marks`, within pages and transitions too.
+fn first_heading(marks: &[Mark]) -> Option<String> {
+    marks.iter().find_map(|mark| match mark {
+        Mark::Page(marks, _) | Mark::Transition(_, marks, _) => first_heading(marks),
+        Mark::Text(text, style) if style.heading.to_int() > 0 => Some(text.clone()),
+        _ => None,
+    })
+}
+
+/// The color `color` for a dark theme, with its lightness reversed, or only raised if it is a
+/// color of text.
+fn dark_color(color: &str, is_text: bool) -> String {
+    let Some(rgb) = parse_color(color) else {
+        return color.to_owned();
+    };
+    let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let lightness = (max + min) / 2.0;
+    let dark = if is_text {
+        lightness.max(1.0 - lightness)
+    } else {
+        1.0 - lightness
+    };
+    // the channels keep their distance to the lightness in proportion, so the hue and the
+    // saturation are kept
+    let scale = if lightness == 0.0 || lightness == 1.0 {
+        0.0
+    } else {
+        dark.min(1.0 - dark) / lightness.min(1.0 - lightness)
+    };
+    let [r, g, b] = [r, g, b].map(|c| (((c - lightness) * scale + dark) * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// `css` escaped for the content of a `style` element, which ends at the first `</`.
+fn style_content(css: &str) -> String {
+    css.replace("</", "<\\/")
+}
+
+struct Writer<'a> {
+    html: String,
+    /// Whether a paragraph is open.
+    is_paragraph: bool,
+    /// The indent levels of the open lists, and whether they are ordered.
+    lists: Vec<(u8, bool)>,
+    /// Whether a page section is open, for flat marks.
+    is_page: bool,
+    /// The number of pages written, which number the ids of their sections, like `page-1`.
+    pages: usize,
+    /// Where the last block element starts, which a label after it identifies.
+    block: Option<usize>,
+    links: &'a dyn LinkHooks,
+}
+
+impl Writer<'_> {
+    fn marks(&mut self, marks: &[Mark]) {
+        for mark in marks {
+            self.mark(mark);
+        }
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        if let Mark::Text(text, style) = mark {
+            match &style.listing {
+                Listing::Ordered(_, indent) => return self.list_item(indent.to_int(), true, text),
+                Listing::Unordered(indent) => return self.list_item(indent.to_int(), false, text),
+                Listing::None => {}
+            }
+            self.finish_list();
+            let heading = style.heading.to_int();
+            if heading > 0 {
+                self.finish_paragraph();
+                self.block = Some(self.html.len());
+                // HTML has no heading deeper than `h6`
+                self.html.push_str(&format!(
+                    "<h{0}{1}>{2}</h{0}>",
+                    heading.min(6),
+                    class_attr(&style.classes),
+                    escape_xml(text)
+                ));
+            } else if style.quote {
+                self.finish_paragraph();
+                self.block = Some(self.html.len());
+                self.html.push_str(&format!(
+                    "<blockquote{}><p>{}</p></blockquote>",
+                    class_attr(&style.classes),
+                    escape_xml(text)
+                ));
+            } else {
+                if !self.is_paragraph {
+                    self.block = Some(self.html.len());
+                    self.html.push_str("<p>");
+                    self.is_paragraph = true;
+                }
+                let href = self.href(&style.hyperlink);
+                self.html.push_str(&run(text, style, href));
+            }
+            return;
+        }
+        if let Mark::Math(math, MathMode::Inline) = mark {
+            // within the open list item, or a paragraph
+            if self.lists.is_empty() && !self.is_paragraph {
+                self.block = Some(self.html.len());
+                self.html.push_str("<p>");
+                self.is_paragraph = true;
+            }
+            self.html.push_str(&format!(
+                "<span class=\"math inline\">\\({}\\)</span>",
+                escape_xml(math)
+            ));
+            return;
+        }
+
+        self.finish_paragraph();
+        self.finish_list();
+        if let Mark::Directive(Directive::Label(label)) = mark {
+            return self.label(label);
+        }
+        if !matches!(
+            mark,
+            Mark::Directive(_)
+                | Mark::NewLine
+                | Mark::Page(..)
+                | Mark::Transition(..)
+                | Mark::TransitionEnd
+        ) {
+            self.block = Some(self.html.len());
+        }
+        match mark {
+            Mark::CodeBlock(code, language) | Mark::CodeWalkthrough(code, language, _) => {
+                let class = match language {
+                    Some(language) => format!(" class=\"language-{}\"", escape_xml(language)),
+                    None => String::new(),
+                };
+                self.html.push_str(&format!(
+                    "<pre><code{}>{}</code></pre>",
+                    class,
+                    escape_xml(code)
+                ));
+            }
+            Mark::Embed(url, _) => {
+                let link = match self.links.href(url) {
+                    Some(href) => {
+                        format!("<a href=\"{}\">{}</a>", escape_xml(&href), escape_xml(url))
+                    }
+                    None => escape_xml(url),
+                };
+                self.html
+                    .push_str(&format!("<p class=\"embed\">{}</p>", link));
+            }
+            Mark::Gallery(items, style) => {
+                let mut classes = vec!["gallery".to_owned()];
+                classes.extend(style.classes.iter().cloned());
+                self.html
+                    .push_str(&format!("<div{}>", class_attr(&classes)));
+                for (src, title) in items {
+                    self.html.push_str(&image(src, title, &[]));
+                }
+                self.html.push_str("</div>");
+            }
+            Mark::Image(src, title, style) => {
+                let image = image(src, title, &style.classes);
+                match self.href(&style.hyperlink) {
+                    Some(href) => self.html.push_str(&format!(
+                        "<p><a href=\"{}\">{}</a></p>",
+                        escape_xml(&href),
+                        image
+                    )),
+                    None => self.html.push_str(&format!("<p>{}</p>", image)),
+                }
+            }
+            Mark::Math(math, _) => self.html.push_str(&format!(
+                "<div class=\"math display\">\\[{}\\]</div>",
+                escape_xml(math)
+            )),
+            Mark::Page(marks, style) => {
+                self.finish_page();
+                self.block = None;
+                let mut classes = vec!["page".to_owned()];
+                classes.extend(style.classes.iter().cloned());
+                self.pages += 1;
+                self.html.push_str(&format!(
+                    "<section id=\"page-{}\"{}>",
+                    self.pages,
+                    class_attr(&classes)
+                ));
+                self.is_page = true;
+                self.marks(marks);
+                if !marks.is_empty() {
+                    self.finish_page();
+                }
+            }
+            Mark::Separator(dir, style) => self.html.push_str(&separator(dir, style)),
+            Mark::HoldSlide(start, message) => {
+                let start = format_time_of_day(*start);
+                self.html.push_str(&format!(
+                    "<div class=\"holdslide\" data-start=\"{0}\">{1}\
+                     <p class=\"countdown\"><time>{0}</time></p></div>",
+                    start,
+                    match message.is_empty() {
+                        true => String::new(),
+                        false => format!("<p class=\"message\">{}</p>", escape_xml(message)),
+                    }
+                ));
+            }
+            Mark::Space(lines) => self.html.push_str(&format!(
+                "<div class=\"space\" style=\"height: {}lh\"></div>",
+                lines
+            )),
+            Mark::Transition(_, marks, _) => self.marks(marks),
+            // only paged media break, like print and e-book readers, so slides on screen do not
+            Mark::Directive(Directive::PageBreak) => self.html.push_str(
+                "<div class=\"pagebreak\" style=\"break-after: page; page-break-after: always\"></div>",
+            ),
+            Mark::Directive(_) | Mark::NewLine | Mark::Text(..) | Mark::TransitionEnd => {}
+        }
+    }
+
+    /// Identify the last block element with `label`, which cross-references link to, see
+    /// [`crossref`](crate::crossref).
+    fn label(&mut self, label: &str) {
+        let id = escape_xml(label);
+        let name_end = self
+            .block
+            .take()
+            .and_then(|start| Some(start + self.html[start..].find([' ', '/', '>'])?));
+        match name_end {
+            Some(idx) => self.html.insert_str(idx, &format!(" id=\"{}\"", id)),
+            None => self.html.push_str(&format!("<a id=\"{}\"></a>", id)),
+        }
+    }
+
+    /// The URL to link to for the hyperlink `url`, `None` if there is no link.
+    fn href(&self, url: &str) -> Option<String> {
+        if url.is_empty() {
+            return None;
+        }
+        self.links.href(url)
+    }
+
+    fn list_item(&mut self, indent: u8, is_ordered: bool, text: &str) {
+        self.finish_paragraph();
+        self.block = None;
+        // close the deeper lists, and the list of the other kind at the same level
+        while let Some(&(list_indent, list_is_ordered)) = self.lists.last() {
+            if list_indent > indent || (list_indent == indent && list_is_ordered != is_ordered) {
+                self.close_list();
+            } else {
+                break;
+            }
+        }
+        match self.lists.last() {
+            Some(&(list_indent, _)) if list_indent == indent => self.html.push_str("</li><li>"),
+            _ => {
+                self.html
+                    .push_str(if is_ordered { "<ol><li>" } else { "<ul><li>" });
+                self.lists.push((indent, is_ordered));
+            }
+        }
+        self.html.push_str(&escape_xml(text));
+    }
+
+    fn close_list(&mut self) {
+        if let Some((_, is_ordered)) = self.lists.pop() {
+            self.html.push_str(if is_ordered {
+                "</li></ol>"
+            } else {
+                "</li></ul>"
+            });
+        }
+    }
+
+    fn finish_list(&mut self) {
+        while !self.lists.is_empty() {
+            self.close_list();
+        }
+    }
+
+    fn finish_paragraph(&mut self) {
+        if self.is_paragraph {
+            self.html.push_str("</p>");
+            self.is_paragraph = false;
+        }
+    }
+
+    fn finish_page(&mut self) {
+        self.finish_paragraph();
+        self.finish_list();
+        if self.is_page {
+            self.html.push_str("</section>");
+            self.is_page = false;
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.finish_page();
+        self.html
+    }
+}
+
+/// The `<hr />` of a separator.
+fn separator(dir: &SeparatorDir, style: &SeparatorStyle) -> String {
+    let mut classes = vec![];
+    if let SeparatorDir::Vertical = dir {
+        classes.push("vertical".to_owned());
+    }
+    match style.line {
+        SeparatorLine::Solid => {}
+        SeparatorLine::Dotted => classes.push("dotted".to_owned()),
+        SeparatorLine::Dashed => classes.push("dashed".to_owned()),
+    }
+    match style.thickness {
+        SeparatorThickness::Thin => classes.push("thin".to_owned()),
+        SeparatorThickness::Normal => {}
+        SeparatorThickness::Thick => classes.push("thick".to_owned()),
+    }
+    let length = match (style.length, dir) {
+        (Some(length), SeparatorDir::Horizontal) => format!(" style=\"width: {}%\"", length),
+        (Some(length), SeparatorDir::Vertical) => format!(" style=\"height: {}%\"", length),
+        (None, _) => String::new(),
+    };
+    format!("<hr{}{} />", class_attr(&classes), length)
+}
+
+/// Text in `style` within a paragraph, linking to `href`.
+fn run(text: &str, style: &StyleText, href: Option<String>) -> String {
+    let mut html = escape_xml(text);
+    for (is_set, tag) in [
+        (style.code, "code"),
+        (style.small, "small"),
+        (style.underline, "u"),
+        (style.strikethrough, "s"),
+        (style.italics, "em"),
+        (style.bold, "strong"),
+    ] {
+        if is_set {
+            html = format!("<{0}>{1}</{0}>", tag, html);
+        }
+    }
+    if let Some(href) = href {
+        let title = match &style.link_title {
+            Some(title) => format!(" title=\"{}\"", escape_xml(title)),
+            None => String::new(),
+        };
+        html = format!("<a href=\"{}\"{}>{}</a>", escape_xml(&href), title, html);
+    }
+    if !style.classes.is_empty() {
+        html = format!("<span{}>{}</span>", class_attr(&style.classes), html);
+    }
+    html
+}
+
+fn image(src: &str, title: &str, classes: &[String]) -> String {
+    format!(
+        "<img src=\"{}\" alt=\"{}\"{} />",
+        escape_xml(src),
+        escape_xml(title),
+        class_attr(classes)
+    )
+}
+
+/// The `class` attribute, with a leading space, or nothing without classes.
+pub(super) fn class_attr(classes: &[String]) -> String {
+    if classes.is_empty() {
+        return String::new();
+    }
+    format!(" class=\"{}\"", escape_xml(&classes.join(" ")))
+}
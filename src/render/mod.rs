@@ -0,0 +1,200 @@
+//! Renderers exporting decks into other formats.
+//!
+//! Decks are either rendered as slides, or [`linearize`]d into chapters to be read as a
+//! document. Some renderers need extra dependencies and are enabled by Cargo features:
+//! - `epub`: [`epub`], e-books.
+//! - `gif`: [`gif`], animated GIFs of single pages.
+//! - `pptx`: [`pptx`], PowerPoint slides.
+//! - `video`: [`video`], videos of decks with their narration, encoded by FFmpeg, and [`srt`],
+//!   their subtitles.
+
+#[cfg(feature = "epub")]
+pub mod epub;
+#[cfg(feature = "gif")]
+pub mod gif;
+pub mod html;
+#[cfg(feature = "pptx")]
+pub mod pptx;
+pub mod site;
+#[cfg(feature = "video")]
+pub mod srt;
+#[cfg(feature = "video")]
+pub mod video;
+
+use crate::{
+    deck::{Deck, Page},
+    mark::Mark,
+};
+
+/// Draws pages into images, for the renderers exporting pages as pictures.
+#[cfg(any(feature = "gif", feature = "video"))]
+pub trait Rasterizer {
+    /// The PNG image of `page`, at its current step.
+    fn rasterize(&self, page: &Page) -> std::io::Result<Vec<u8>>;
+}
+
+/// A chapter of a deck read as a document, see [`linearize`].
+#[derive(Clone, Debug)]
+pub struct Chapter {
+    /// The text of the heading starting the chapter, empty if there is none.
+    pub title: String,
+    /// The content of the pages of the chapter, without page and transition marks.
+    pub marks: Vec<Mark>,
+    /// The indices of the pages of the chapter in the deck.
+    pub pages: Vec<usize>,
+}
+
+/// Flatten `deck` into chapters, to be read as a document rather than presented.
+///
+/// The content of all transitions of a page is kept. A new chapter starts at each page
+/// starting with a heading of the highest level any page starts with, the other pages are
+/// appended to the chapter before them.
+///
+/// ```
+/// use opmark::{deck::Deck, render::linearize};
+///
+/// let deck = Deck::new("# One\n---\nmore\n---\n# Two\n---t\nlast".to_owned());
+/// let titles: Vec<String> = linearize(&deck).into_iter().map(|c| c.title).collect();
+/// assert_eq!(titles, ["One", "Two"]);
+/// ```
+pub fn linearize(deck: &Deck) -> Vec<Chapter> {
+    let pages: Vec<(usize, Vec<Mark>)> = deck
+        .pages()
+        .iter()
+        .map(page_marks)
+        .enumerate()
+        .filter(|(_, marks)| !marks.is_empty())
+        .collect();
+    let heading = |marks: &[Mark]| match marks.first() {
+        Some(Mark::Text(text, style)) if style.heading.to_int() > 0 => {
+            Some((style.heading.to_int(), text.clone()))
+        }
+        _ => None,
+    };
+    let level = pages
+        .iter()
+        .filter_map(|(_, marks)| heading(marks))
+        .map(|(level, _)| level)
+        .min();
+
+    let mut chapters: Vec<Chapter> = vec![];
+    for (idx, marks) in pages {
+        let title = heading(&marks)
+            .filter(|(heading_level, _)| Some(*heading_level) == level)
+            .map(|(_, text)| text);
+        match (title, chapters.last_mut()) {
+            (None, Some(chapter)) => {
+                // end the paragraph of the page before
+                chapter.marks.push(Mark::NewLine);
+                chapter.marks.extend(marks);
+                chapter.pages.push(idx);
+            }
+            (title, _) => chapters.push(Chapter {
+                title: title.unwrap_or_default(),
+                marks,
+                pages: vec![idx],
+            }),
+        }
+    }
+    chapters
+}
+
+/// The content of all transitions of `page`, without transition marks.
+pub(crate) fn page_marks(page: &Page) -> Vec<Mark> {
+    page.transitions
+        .iter()
+        .flat_map(|transition| match transition {
+            Mark::Transition(_, marks, _) => marks.as_slice(),
+            _ => &[],
+        })
+        .filter(|mark| !matches!(mark, Mark::TransitionEnd))
+        .cloned()
+        .collect()
+}
+
+/// Escape `s` for the text or an attribute value of XML.
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // chars not allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replace the character and entity references of `s`, the ones of XML and the common named
+/// ones of HTML.
+pub(crate) fn unescape_xml(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+            unescaped.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            "ndash" => Some('\u{2013}'),
+            "mdash" => Some('\u{2014}'),
+            "lsquo" => Some('\u{2018}'),
+            "rsquo" => Some('\u{2019}'),
+            "ldquo" => Some('\u{201c}'),
+            "rdquo" => Some('\u{201d}'),
+            "hellip" => Some('\u{2026}'),
+            "copy" => Some('\u{a9}'),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// The end of the tag at the start of `s`, skipping `>` in quoted attribute values. `None` if
+/// the tag is not closed, or if `<` is not followed by a tag name, like in `a < b`.
+pub(crate) fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, c) in s.char_indices().skip(1) {
+        match (c, quote) {
+            (c, None) if idx == 1 && !(c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) => {
+                return None
+            }
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('>', None) => return Some(idx),
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,706 @@
+//! Export decks to PowerPoint slides (Office Open XML, `.pptx`).
+//!
+//! Each page becomes a slide, with the first heading of the page in a title text box and the
+//! rest of the text in a body text box below it. Lists keep their bullets or numbers and their
+//! indent, code is set in a monospace font, and images are laid out in rows of up to four below
+//! the text, linked to their source rather than embedded. Links to pages, like the ones of agenda
+//! pages, jump to their slide, and speaker notes become the notes of the slide. All transitions of
+//! a page are shown at once.
+//!
+//! ```
+//! use opmark::{deck::Deck, render::pptx::to_pptx};
+//!
+//! let deck = Deck::new("# Title\n- first\n- second\n---\n![chart](chart.png)".to_owned());
+//! let pptx = to_pptx(&deck).unwrap();
+//! assert!(pptx.starts_with(b"PK"));
+//! ```
+
+use super::escape_xml;
+use crate::{
+    deck::{Deck, Page},
+    mark::{Listing, Mark, MathMode, StyleText},
+    parser::format_time_of_day,
+};
+use std::io::{self, Cursor, Seek, Write};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+const NS_A: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+const NS_P: &str = "http://schemas.openxmlformats.org/presentationml/2006/main";
+const NS_R: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const NS_RELS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+const NS_TYPES: &str = "http://schemas.openxmlformats.org/package/2006/content-types";
+const CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument";
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+
+/// The size of the slides, 16:9 in EMU (914400 per inch).
+const SLIDE_WIDTH: u64 = 12_192_000;
+const SLIDE_HEIGHT: u64 = 6_858_000;
+const MARGIN: u64 = 457_200;
+const TITLE_HEIGHT: u64 = 1_005_840;
+const GAP: u64 = 182_880;
+/// The most images side by side on a slide, more go on the next rows.
+const MAX_IMAGE_COLUMNS: u64 = 4;
+
+/// Font sizes, in hundredths of a point.
+const TITLE_SIZE: u32 = 4000;
+const HEADING_SIZE: u32 = 2800;
+const BODY_SIZE: u32 = 2000;
+const CODE_SIZE: u32 = 1600;
+const SMALL_SIZE: u32 = 1400;
+
+const MONOSPACE_FONT: &str = "Courier New";
+
+/// Export `deck` into the bytes of a `.pptx` file.
+pub fn to_pptx(deck: &Deck) -> io::Result<Vec<u8>> {
+    let mut cursor = Cursor::new(vec![]);
+    write_pptx(deck, &mut cursor)?;
+    Ok(cursor.into_inner())
+}
+
+/// Export `deck` as a `.pptx` file into `writer`.
+pub fn write_pptx<W: Write + Seek>(deck: &Deck, writer: W) -> io::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("write_pptx", pages = deck.pages().len()).entered();
+
+    let pages = deck.pages().len();
+    let mut slides: Vec<Slide> = deck
+        .pages()
+        .iter()
+        .map(|page| Slide::new(page, pages))
+        .collect();
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut file = |name: &str, content: &str| -> io::Result<()> {
+        zip.start_file(name, options)?;
+        zip.write_all(XML_DECLARATION.as_bytes())?;
+        zip.write_all(content.as_bytes())
+    };
+
+    let has_notes = slides.iter().any(|slide| !slide.notes.is_empty());
+    file("[Content_Types].xml", &content_types(&slides))?;
+    file(
+        "_rels/.rels",
+        &relationships(&[Relationship::internal(
+            "officeDocument",
+            "ppt/presentation.xml",
+        )]),
+    )?;
+    file(
+        "ppt/presentation.xml",
+        &presentation(slides.len(), has_notes),
+    )?;
+    let mut presentation_rels = vec![Relationship::internal(
+        "slideMaster",
+        "slideMasters/slideMaster1.xml",
+    )];
+    for idx in 1..=slides.len() {
+        presentation_rels.push(Relationship::internal(
+            "slide",
+            &format!("slides/slide{}.xml", idx),
+        ));
+    }
+    presentation_rels.push(Relationship::internal("theme", "theme/theme1.xml"));
+    if has_notes {
+        presentation_rels.push(Relationship::internal(
+            "notesMaster",
+            "notesMasters/notesMaster1.xml",
+        ));
+    }
+    file(
+        "ppt/_rels/presentation.xml.rels",
+        &relationships(&presentation_rels),
+    )?;
+    file("ppt/slideMasters/slideMaster1.xml", &slide_master())?;
+    file(
+        "ppt/slideMasters/_rels/slideMaster1.xml.rels",
+        &relationships(&[
+            Relationship::internal("slideLayout", "../slideLayouts/slideLayout1.xml"),
+            Relationship::internal("theme", "../theme/theme1.xml"),
+        ]),
+    )?;
+    file("ppt/slideLayouts/slideLayout1.xml", &slide_layout())?;
+    file(
+        "ppt/slideLayouts/_rels/slideLayout1.xml.rels",
+        &relationships(&[Relationship::internal(
+            "slideMaster",
+            "../slideMasters/slideMaster1.xml",
+        )]),
+    )?;
+    file("ppt/theme/theme1.xml", &theme())?;
+    if has_notes {
+        file("ppt/notesMasters/notesMaster1.xml", &notes_master())?;
+        file(
+            "ppt/notesMasters/_rels/notesMaster1.xml.rels",
+            &relationships(&[Relationship::internal("theme", "../theme/theme2.xml")]),
+        )?;
+        // each master has its own theme
+        file("ppt/theme/theme2.xml", &theme())?;
+    }
+    for (idx, slide) in slides.iter_mut().enumerate() {
+        if !slide.notes.is_empty() {
+            file(
+                &format!("ppt/notesSlides/notesSlide{}.xml", idx + 1),
+                &slide.notes_xml(),
+            )?;
+            file(
+                &format!("ppt/notesSlides/_rels/notesSlide{}.xml.rels", idx + 1),
+                &relationships(&[
+                    Relationship::internal("notesMaster", "../notesMasters/notesMaster1.xml"),
+                    Relationship::internal("slide", &format!("../slides/slide{}.xml", idx + 1)),
+                ]),
+            )?;
+            slide.relate(Relationship::internal(
+                "notesSlide",
+                &format!("../notesSlides/notesSlide{}.xml", idx + 1),
+            ));
+        }
+        file(&format!("ppt/slides/slide{}.xml", idx + 1), &slide.xml())?;
+        file(
+            &format!("ppt/slides/_rels/slide{}.xml.rels", idx + 1),
+            &relationships(&slide.relationships),
+        )?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// A relationship between the parts of the package.
+struct Relationship {
+    kind: &'static str,
+    target: String,
+    is_external: bool,
+}
+
+impl Relationship {
+    fn internal(kind: &'static str, target: &str) -> Self {
+        Self {
+            kind,
+            target: target.to_owned(),
+            is_external: false,
+        }
+    }
+
+    fn external(kind: &'static str, target: &str) -> Self {
+        Self {
+            kind,
+            target: target.to_owned(),
+            is_external: true,
+        }
+    }
+}
+
+/// The content of a slide.
+struct Slide {
+    title: Option<String>,
+    /// The `a:p` elements of the body.
+    paragraphs: Vec<String>,
+    /// The `a:r` elements of the current paragraph.
+    runs: Vec<String>,
+    /// The images, with the id of their relationship and their title.
+    images: Vec<(String, String)>,
+    /// The relationships of the slide, the first is the layout.
+    relationships: Vec<Relationship>,
+    /// The number of slides of the presentation, which links to pages may jump to.
+    slide_count: usize,
+    /// The speaker notes of the page, a paragraph each.
+    notes: Vec<String>,
+}
+
+impl Slide {
+    fn new(page: &Page, slide_count: usize) -> Self {
+        let mut slide = Self {
+            title: None,
+            paragraphs: vec![],
+            runs: vec![],
+            images: vec![],
+            slide_count,
+            notes: page.notes().into_iter().map(str::to_owned).collect(),
+            relationships: vec![Relationship::internal(
+                "slideLayout",
+                "../slideLayouts/slideLayout1.xml",
+            )],
+        };
+        for transition in &page.transitions {
+            if let Mark::Transition(_, marks, _) = transition {
+                for mark in marks {
+                    slide.mark(mark);
+                }
+            }
+        }
+        slide.finish_paragraph();
+        slide
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        match mark {
+            Mark::CodeBlock(code, _) | Mark::CodeWalkthrough(code, _, _) => {
+                self.finish_paragraph();
+                let style = StyleText::new().with_code();
+                for line in code.lines() {
+                    let run = self.run(line, &style, CODE_SIZE);
+                    self.paragraphs.push(paragraph("", &[run]));
+                }
+            }
+            Mark::Embed(url, _) => {
+                self.finish_paragraph();
+                let run = self.run(
+                    url,
+                    &StyleText::new().with_hyperlink(url.clone()),
+                    BODY_SIZE,
+                );
+                self.paragraphs.push(paragraph("", &[run]));
+            }
+            Mark::Gallery(items, _) => {
+                for (src, title) in items {
+                    self.image(src, title);
+                }
+            }
+            Mark::Image(src, title, _) => self.image(src, title),
+            // slides have no TeX, so math is shown as its source
+            Mark::Math(math, MathMode::Inline) => {
+                let run = self.run(math, &StyleText::new().with_code(), BODY_SIZE);
+                self.runs.push(run);
+            }
+            Mark::Math(math, MathMode::Display) => {
+                self.finish_paragraph();
+                let style = StyleText::new().with_code();
+                for line in math.lines() {
+                    let run = self.run(line, &style, BODY_SIZE);
+                    self.paragraphs
+                        .push(paragraph(r#"<a:pPr algn="ctr"/>"#, &[run]));
+                }
+            }
+            Mark::NewLine => self.finish_paragraph(),
+            Mark::Text(text, style) if style.heading.to_int() > 0 && self.title.is_none() => {
+                self.title = Some(text.clone());
+            }
+            Mark::Text(text, style) if style.heading.to_int() > 0 => {
+                self.finish_paragraph();
+                let run = self.run(text, &style.clone().with_bold(), HEADING_SIZE);
+                self.paragraphs.push(paragraph("", &[run]));
+            }
+            Mark::Text(text, style) => {
+                let properties = match &style.listing {
+                    Listing::None if !style.quote => {
+                        let run = self.run(text, style, BODY_SIZE);
+                        self.runs.push(run);
+                        return;
+                    }
+                    Listing::None => r#"<a:pPr marL="457200"/>"#.to_owned(),
+                    Listing::Ordered(_, indent) => {
+                        list_properties(indent.to_int(), r#"<a:buAutoNum type="arabicPeriod"/>"#)
+                    }
+                    Listing::Unordered(indent) => list_properties(
+                        indent.to_int(),
+                        r#"<a:buFont typeface="Arial"/><a:buChar char="&#8226;"/>"#,
+                    ),
+                };
+                self.finish_paragraph();
+                let style = if style.quote {
+                    style.clone().with_italics()
+                } else {
+                    style.clone()
+                };
+                let run = self.run(text, &style, BODY_SIZE);
+                self.paragraphs.push(paragraph(&properties, &[run]));
+            }
+            Mark::HoldSlide(start, message) => {
+                self.finish_paragraph();
+                let properties = r#"<a:pPr algn="ctr"/>"#;
+                if !message.is_empty() {
+                    let run = self.run(message, &StyleText::new().with_bold(), BODY_SIZE);
+                    self.paragraphs.push(paragraph(properties, &[run]));
+                }
+                // slides are static, so the start is shown instead of the time left
+                let start = format!("Starting at {}", format_time_of_day(*start));
+                let run = self.run(&start, &StyleText::new(), BODY_SIZE);
+                self.paragraphs.push(paragraph(properties, &[run]));
+            }
+            Mark::Space(lines) => {
+                self.finish_paragraph();
+                // an empty paragraph as high as the lines
+                let spacing = (lines * 100_000.0).round() as u64;
+                let properties = format!(
+                    r#"<a:pPr><a:lnSpc><a:spcPct val="{}"/></a:lnSpc></a:pPr>"#,
+                    spacing
+                );
+                self.paragraphs.push(paragraph(&properties, &[]));
+            }
+            Mark::Directive(_)
+            | Mark::Page(..)
+            | Mark::Separator(..)
+            | Mark::Transition(..)
+            | Mark::TransitionEnd => {}
+        }
+    }
+
+    fn image(&mut self, src: &str, title: &str) {
+        let id = self.relate(Relationship::external("image", src));
+        self.images.push((id, title.to_owned()));
+    }
+
+    /// Add a relationship and return its id.
+    fn relate(&mut self, relationship: Relationship) -> String {
+        self.relationships.push(relationship);
+        format!("rId{}", self.relationships.len())
+    }
+
+    /// An `a:r` element with `text` in `style`.
+    fn run(&mut self, text: &str, style: &StyleText, size: u32) -> String {
+        let size = if style.small { SMALL_SIZE } else { size };
+        let mut attrs = format!(r#" lang="en-US" sz="{}""#, size);
+        for (is_set, attr) in [
+            (style.bold, r#" b="1""#),
+            (style.italics, r#" i="1""#),
+            (style.underline, r#" u="sng""#),
+            (style.strikethrough, r#" strike="sngStrike""#),
+        ] {
+            if is_set {
+                attrs.push_str(attr);
+            }
+        }
+        let mut children = String::new();
+        if style.code {
+            children.push_str(&format!(r#"<a:latin typeface="{}"/>"#, MONOSPACE_FONT));
+        }
+        // links to pages, like `#page-2`, jump to their slide, links to other anchors are dropped
+        let link = match style.hyperlink.strip_prefix('#') {
+            _ if style.hyperlink.is_empty() => None,
+            Some(anchor) => anchor
+                .strip_prefix("page-")
+                .and_then(|number| number.parse::<usize>().ok())
+                .filter(|number| (1..=self.slide_count).contains(number))
+                .map(|number| {
+                    let slide = format!("slide{}.xml", number);
+                    let id = self.relate(Relationship::internal("slide", &slide));
+                    format!(r#"r:id="{}" action="ppaction://hlinksldjump""#, id)
+                }),
+            None => {
+                let id = self.relate(Relationship::external("hyperlink", &style.hyperlink));
+                Some(format!(r#"r:id="{}""#, id))
+            }
+        };
+        if let Some(link) = link {
+            let tooltip = match &style.link_title {
+                Some(title) => format!(r#" tooltip="{}""#, escape_xml(title)),
+                None => String::new(),
+            };
+            children.push_str(&format!(r#"<a:hlinkClick {}{}/>"#, link, tooltip));
+        }
+        format!(
+            "<a:r><a:rPr{} dirty=\"0\">{}</a:rPr><a:t>{}</a:t></a:r>",
+            attrs,
+            children,
+            escape_xml(text)
+        )
+    }
+
+    fn finish_paragraph(&mut self) {
+        if !self.runs.is_empty() {
+            let runs = std::mem::take(&mut self.runs);
+            self.paragraphs.push(paragraph("", &runs));
+        }
+    }
+
+    fn xml(&self) -> String {
+        let mut shapes = String::new();
+        let mut id = 1;
+        let width = SLIDE_WIDTH - 2 * MARGIN;
+        let mut top = MARGIN;
+        if let Some(title) = &self.title {
+            id += 1;
+            let run = format!(
+                r#"<a:r><a:rPr lang="en-US" sz="{}" b="1" dirty="0"/><a:t>{}</a:t></a:r>"#,
+                TITLE_SIZE,
+                escape_xml(title)
+            );
+            shapes.push_str(&text_box(
+                id,
+                "Title",
+                (MARGIN, top, width, TITLE_HEIGHT),
+                &paragraph("", &[run]),
+            ));
+            top += TITLE_HEIGHT + GAP;
+        }
+        let mut bottom = SLIDE_HEIGHT - MARGIN;
+        if !self.images.is_empty() {
+            // the images take the lower half of the space, or all of it without text
+            let images_top = if self.paragraphs.is_empty() {
+                top
+            } else {
+                top + (bottom - top) / 2
+            };
+            // in a grid, filled row by row
+            let count = self.images.len() as u64;
+            let columns = count.min(MAX_IMAGE_COLUMNS);
+            let rows = count.div_ceil(columns);
+            let image_width = (width - GAP * (columns - 1)) / columns;
+            let image_height = (bottom - images_top).saturating_sub(GAP * (rows - 1)) / rows;
+            for (idx, (rel_id, title)) in self.images.iter().enumerate() {
+                id += 1;
+                let (row, column) = (idx as u64 / columns, idx as u64 % columns);
+                let left = MARGIN + column * (image_width + GAP);
+                let image_top = images_top + row * (image_height + GAP);
+                shapes.push_str(&picture(
+                    id,
+                    rel_id,
+                    title,
+                    (left, image_top, image_width, image_height),
+                ));
+            }
+            bottom = images_top.saturating_sub(GAP);
+        }
+        if !self.paragraphs.is_empty() {
+            id += 1;
+            shapes.push_str(&text_box(
+                id,
+                "Body",
+                (MARGIN, top, width, bottom.saturating_sub(top)),
+                &self.paragraphs.concat(),
+            ));
+        }
+        format!(
+            r#"<p:sld xmlns:a="{}" xmlns:r="{}" xmlns:p="{}"><p:cSld><p:spTree>{}{}</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sld>"#,
+            NS_A, NS_R, NS_P, GROUP_PROPERTIES, shapes
+        )
+    }
+
+    /// The notes slide, with the speaker notes in its body placeholder.
+    fn notes_xml(&self) -> String {
+        let paragraphs: String = self
+            .notes
+            .iter()
+            .map(|note| {
+                let run = format!(
+                    r#"<a:r><a:rPr lang="en-US" dirty="0"/><a:t>{}</a:t></a:r>"#,
+                    escape_xml(note)
+                );
+                paragraph("", &[run])
+            })
+            .collect();
+        format!(
+            r#"<p:notes xmlns:a="{}" xmlns:r="{}" xmlns:p="{}"><p:cSld><p:spTree>{}{}</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:notes>"#,
+            NS_A,
+            NS_R,
+            NS_P,
+            GROUP_PROPERTIES,
+            notes_body(&paragraphs)
+        )
+    }
+}
+
+/// The required properties of the shape tree of a slide.
+const GROUP_PROPERTIES: &str =
+    r#"<p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr/>"#;
+
+/// The body placeholder of notes with the `a:p` elements `paragraphs`.
+fn notes_body(paragraphs: &str) -> String {
+    format!(
+        r#"<p:sp><p:nvSpPr><p:cNvPr id="2" name="Notes"/><p:cNvSpPr><a:spLocks noGrp="1"/></p:cNvSpPr><p:nvPr><p:ph type="body" idx="1"/></p:nvPr></p:nvSpPr><p:spPr/><p:txBody><a:bodyPr/><a:lstStyle/>{}</p:txBody></p:sp>"#,
+        paragraphs
+    )
+}
+
+/// An `a:p` element with the properties `properties` and the runs `runs`.
+fn paragraph(properties: &str, runs: &[String]) -> String {
+    format!("<a:p>{}{}</a:p>", properties, runs.concat())
+}
+
+/// The properties of a paragraph in a list at the indent level `indent`, with the bullet
+/// `bullet`.
+fn list_properties(indent: u8, bullet: &str) -> String {
+    format!(
+        r#"<a:pPr marL="{}" lvl="{}" indent="-342900">{}</a:pPr>"#,
+        342_900 + indent as u64 * 457_200,
+        // the deepest level of PowerPoint
+        indent.min(8),
+        bullet
+    )
+}
+
+/// A `p:sp` text box at `(x, y, width, height)`.
+fn text_box(id: usize, name: &str, frame: (u64, u64, u64, u64), paragraphs: &str) -> String {
+    format!(
+        r#"<p:sp><p:nvSpPr><p:cNvPr id="{}" name="{}"/><p:cNvSpPr txBox="1"/><p:nvPr/></p:nvSpPr><p:spPr>{}<a:prstGeom prst="rect"><a:avLst/></a:prstGeom></p:spPr><p:txBody><a:bodyPr wrap="square"><a:normAutofit/></a:bodyPr><a:lstStyle/>{}</p:txBody></p:sp>"#,
+        id,
+        name,
+        transform(frame),
+        paragraphs
+    )
+}
+
+/// A `p:pic` picture linked with the relationship `rel_id` at `(x, y, width, height)`.
+fn picture(id: usize, rel_id: &str, title: &str, frame: (u64, u64, u64, u64)) -> String {
+    format!(
+        r#"<p:pic><p:nvPicPr><p:cNvPr id="{}" name="Picture {}" descr="{}"/><p:cNvPicPr><a:picLocks noChangeAspect="1"/></p:cNvPicPr><p:nvPr/></p:nvPicPr><p:blipFill><a:blip r:link="{}"/><a:stretch><a:fillRect/></a:stretch></p:blipFill><p:spPr>{}<a:prstGeom prst="rect"><a:avLst/></a:prstGeom></p:spPr></p:pic>"#,
+        id,
+        id,
+        escape_xml(title),
+        rel_id,
+        transform(frame)
+    )
+}
+
+fn transform((x, y, width, height): (u64, u64, u64, u64)) -> String {
+    format!(
+        r#"<a:xfrm><a:off x="{}" y="{}"/><a:ext cx="{}" cy="{}"/></a:xfrm>"#,
+        x, y, width, height
+    )
+}
+
+fn content_types(slides: &[Slide]) -> String {
+    let mut types = format!(
+        r#"<Types xmlns="{}"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/>"#,
+        NS_TYPES
+    );
+    let mut part = |name: &str, kind: &str| {
+        types.push_str(&format!(
+            r#"<Override PartName="/ppt/{}" ContentType="{}.{}+xml"/>"#,
+            name, CONTENT_TYPE, kind
+        ));
+    };
+    part("presentation.xml", "presentationml.presentation.main");
+    part(
+        "slideMasters/slideMaster1.xml",
+        "presentationml.slideMaster",
+    );
+    part(
+        "slideLayouts/slideLayout1.xml",
+        "presentationml.slideLayout",
+    );
+    part("theme/theme1.xml", "theme");
+    if slides.iter().any(|slide| !slide.notes.is_empty()) {
+        part(
+            "notesMasters/notesMaster1.xml",
+            "presentationml.notesMaster",
+        );
+        part("theme/theme2.xml", "theme");
+    }
+    for (idx, slide) in slides.iter().enumerate() {
+        part(
+            &format!("slides/slide{}.xml", idx + 1),
+            "presentationml.slide",
+        );
+        if !slide.notes.is_empty() {
+            part(
+                &format!("notesSlides/notesSlide{}.xml", idx + 1),
+                "presentationml.notesSlide",
+            );
+        }
+    }
+    types.push_str("</Types>");
+    types
+}
+
+fn relationships(relationships: &[Relationship]) -> String {
+    let mut xml = format!(r#"<Relationships xmlns="{}">"#, NS_RELS);
+    for (idx, relationship) in relationships.iter().enumerate() {
+        xml.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="{}/{}" Target="{}"{}/>"#,
+            idx + 1,
+            NS_R,
+            relationship.kind,
+            escape_xml(&relationship.target),
+            if relationship.is_external {
+                r#" TargetMode="External""#
+            } else {
+                ""
+            }
+        ));
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+/// The presentation part, relating the master as `rId1`, the slides from `rId2`, then the theme
+/// and, with `has_notes`, the notes master.
+fn presentation(slides: usize, has_notes: bool) -> String {
+    let slide_ids: String = (0..slides)
+        .map(|idx| format!(r#"<p:sldId id="{}" r:id="rId{}"/>"#, 256 + idx, idx + 2))
+        .collect();
+    let slide_ids = if slide_ids.is_empty() {
+        slide_ids
+    } else {
+        format!("<p:sldIdLst>{}</p:sldIdLst>", slide_ids)
+    };
+    let notes_master_id = if has_notes {
+        format!(
+            r#"<p:notesMasterIdLst><p:notesMasterId r:id="rId{}"/></p:notesMasterIdLst>"#,
+            slides + 3
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        r#"<p:presentation xmlns:a="{}" xmlns:r="{}" xmlns:p="{}"><p:sldMasterIdLst><p:sldMasterId id="2147483648" r:id="rId1"/></p:sldMasterIdLst>{}{}<p:sldSz cx="{}" cy="{}"/><p:notesSz cx="{}" cy="{}"/></p:presentation>"#,
+        NS_A,
+        NS_R,
+        NS_P,
+        notes_master_id,
+        slide_ids,
+        SLIDE_WIDTH,
+        SLIDE_HEIGHT,
+        SLIDE_HEIGHT,
+        SLIDE_WIDTH
+    )
+}
+
+fn slide_master() -> String {
+    format!(
+        r#"<p:sldMaster xmlns:a="{}" xmlns:r="{}" xmlns:p="{}"><p:cSld><p:spTree>{}</p:spTree></p:cSld><p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/><p:sldLayoutIdLst><p:sldLayoutId id="2147483649" r:id="rId1"/></p:sldLayoutIdLst></p:sldMaster>"#,
+        NS_A, NS_R, NS_P, GROUP_PROPERTIES
+    )
+}
+
+fn notes_master() -> String {
+    format!(
+        r#"<p:notesMaster xmlns:a="{}" xmlns:r="{}" xmlns:p="{}"><p:cSld><p:spTree>{}{}</p:spTree></p:cSld><p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/></p:notesMaster>"#,
+        NS_A,
+        NS_R,
+        NS_P,
+        GROUP_PROPERTIES,
+        notes_body("<a:p/>")
+    )
+}
+
+fn slide_layout() -> String {
+    format!(
+        r#"<p:sldLayout xmlns:a="{}" xmlns:r="{}" xmlns:p="{}" type="blank" preserve="1"><p:cSld name="Blank"><p:spTree>{}</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sldLayout>"#,
+        NS_A, NS_R, NS_P, GROUP_PROPERTIES
+    )
+}
+
+fn theme() -> String {
+    let colors = [
+        ("dk1", "000000"),
+        ("lt1", "FFFFFF"),
+        ("dk2", "44546A"),
+        ("lt2", "E7E6E6"),
+        ("accent1", "4472C4"),
+        ("accent2", "ED7D31"),
+        ("accent3", "A5A5A5"),
+        ("accent4", "FFC000"),
+        ("accent5", "5B9BD5"),
+        ("accent6", "70AD47"),
+        ("hlink", "0563C1"),
+        ("folHlink", "954F72"),
+    ]
+    .iter()
+    .map(|(name, rgb)| format!(r#"<a:{0}><a:srgbClr val="{1}"/></a:{0}>"#, name, rgb))
+    .collect::<String>();
+    let font = r#"<a:latin typeface="Calibri"/><a:ea typeface=""/><a:cs typeface=""/>"#;
+    let fill = r#"<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>"#;
+    let line = format!(r#"<a:ln w="6350">{}</a:ln>"#, fill);
+    let effect = "<a:effectStyle><a:effectLst/></a:effectStyle>";
+    format!(
+        r#"<a:theme xmlns:a="{}" name="OpMark"><a:themeElements><a:clrScheme name="OpMark">{}</a:clrScheme><a:fontScheme name="OpMark"><a:majorFont>{}</a:majorFont><a:minorFont>{}</a:minorFont></a:fontScheme><a:fmtScheme name="OpMark"><a:fillStyleLst>{}</a:fillStyleLst><a:lnStyleLst>{}</a:lnStyleLst><a:effectStyleLst>{}</a:effectStyleLst><a:bgFillStyleLst>{}</a:bgFillStyleLst></a:fmtScheme></a:themeElements></a:theme>"#,
+        NS_A,
+        colors,
+        font,
+        font,
+        fill.repeat(3),
+        line.repeat(3),
+        effect.repeat(3),
+        fill.repeat(3)
+    )
+}
@@ -0,0 +1,425 @@
+//! Export several decks as a static website, to publish a whole course.
+//!
+//! The site has:
+//! - `index.html`, listing the decks with a thumbnail of each, and a search box.
+//! - `decks/<name>.html` for each deck, with all of its pages one below the other and the pages
+//!   of its appendix after an `hr.appendix` divider, linked to the decks before and after it.
+//! - `search-index.json`, with a document for each page of every deck. The documents have the
+//!   `id`, `title` and `body` fields, so they can be indexed with
+//!   [lunr](https://lunrjs.com/) as they are; `search.js` searches them without it.
+//! - `style.css`, with the theme and the extra CSS of the [`HtmlOptions`] of the options.
+//! - `assets/`, with the images the decks link to with relative paths, when the resolver of the
+//!   options can read them.
+//!
+//! ```
+//! use opmark::{deck::Deck, render::site::{to_site, SiteOptions}};
+//!
+//! let intro = Deck::new("# Intro\nHello".to_owned());
+//! let details = Deck::new("# Details\n- one".to_owned());
+//! let files = to_site(&[("intro", &intro), ("details", &details)], &SiteOptions::new());
+//! let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+//! assert_eq!(
+//!     paths,
+//!     [
+//!         "decks/intro.html",
+//!         "decks/details.html",
+//!         "index.html",
+//!         "search-index.json",
+//!         "search.js",
+//!         "style.css",
+//!     ]
+//! );
+//! ```
+
+use super::{
+    escape_xml,
+    html::{self, to_html_with_links, HtmlOptions},
+    page_marks,
+};
+use crate::{
+    deck::{heading, Deck},
+    include::IncludeResolver,
+    mark::Mark,
+    text::plain_text,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Component, Path},
+    sync::Arc,
+};
+
+const STYLE: &str =
+    "body { font-family: sans-serif; margin: 0 auto; max-width: 60em; padding: 1em; }
+nav { display: flex; gap: 1em; margin-bottom: 1em; }
+.page { border: 1px solid var(--opmark-border); margin-bottom: 1em; padding: 1em; }
+.decks { display: grid; gap: 1em; grid-template-columns: repeat(auto-fill, minmax(16em, 1fr)); }
+.deck { color: inherit; text-decoration: none; }
+.thumbnail { aspect-ratio: 16 / 9; border: 1px solid var(--opmark-border); overflow: hidden; }
+.thumbnail > img { height: 100%; object-fit: cover; width: 100%; }
+.thumbnail > .preview { transform: scale(0.5); transform-origin: top left; width: 200%; }
+";
+
+const SEARCH: &str = "const input = document.getElementById('search');
+const results = document.getElementById('results');
+fetch('search-index.json')
+  .then((response) => response.json())
+  .then((documents) => {
+    input.addEventListener('input', () => {
+      const terms = input.value.toLowerCase().split(/\\s+/).filter((term) => term);
+      results.replaceChildren(
+        ...documents
+          .filter((doc) => terms.length > 0 && terms.every((term) =>
+            (doc.title + ' ' + doc.body).toLowerCase().includes(term)))
+          .map((doc) => {
+            const item = document.createElement('li');
+            const link = document.createElement('a');
+            link.href = doc.id;
+            link.textContent = doc.title;
+            item.append(link);
+            return item;
+          }),
+      );
+    });
+  });
+";
+
+/// Options for the exported site.
+#[derive(Clone, Debug)]
+pub struct SiteOptions {
+    /// The title of the index. `Decks` by default.
+    pub title: String,
+    /// The theme, the extra CSS and the extra head HTML of the pages. The title is not used.
+    pub html: HtmlOptions,
+    /// Read the images to copy into the site.
+    pub resolver: Option<Arc<dyn IncludeResolver>>,
+}
+
+impl Default for SiteOptions {
+    fn default() -> Self {
+        Self {
+            title: "Decks".to_owned(),
+            html: Default::default(),
+            resolver: None,
+        }
+    }
+}
+
+impl SiteOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_html(mut self, html: HtmlOptions) -> Self {
+        self.html = html;
+        self
+    }
+
+    #[inline]
+    pub fn with_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    #[inline]
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
+}
+
+/// A file of the exported site.
+#[derive(Clone, Debug)]
+pub struct SiteFile {
+    /// The path of the file, relative to the root of the site and separated by `/`.
+    pub path: String,
+    pub content: Vec<u8>,
+}
+
+/// Export `decks`, given with the names of their pages, into the files of a static site.
+///
+/// The names are reduced to ASCII letters, digits, `-` and `_`, and made unique.
+pub fn to_site(decks: &[(&str, &Deck)], options: &SiteOptions) -> Vec<SiteFile> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("to_site", decks = decks.len()).entered();
+
+    let mut names = HashSet::new();
+    let decks: Vec<(String, String, Vec<Vec<Mark>>, &Deck)> = decks
+        .iter()
+        .map(|(name, deck)| {
+            let name = unique_name(name, &mut names);
+            let pages: Vec<Vec<Mark>> = deck.pages().iter().map(page_marks).collect();
+            let title = pages
+                .iter()
+                .find_map(|marks| heading(marks))
+                .map_or_else(|| name.clone(), str::to_owned);
+            (name, title, pages, *deck)
+        })
+        .collect();
+
+    let mut assets = Assets::new(options.resolver.as_deref());
+    let mut files = vec![];
+    let mut search_index = vec![];
+    let mut cards = String::new();
+    for (idx, (name, title, pages, deck)) in decks.iter().enumerate() {
+        let mut sections = String::new();
+        let appendix = deck.appendix();
+        for (page_idx, (marks, page)) in pages.iter().zip(deck.pages()).enumerate() {
+            // backup pages are grouped after a divider
+            if appendix == Some(page_idx) {
+                sections.push_str("<hr class=\"appendix\" />");
+            }
+            let mut classes = vec!["page".to_owned()];
+            classes.extend(page.style.classes.iter().cloned());
+            let marks = assets.copy(marks, "../");
+            sections.push_str(&format!(
+                "<section id=\"page-{}\"{}>{}</section>",
+                page_idx + 1,
+                html::class_attr(&classes),
+                to_html_with_links(&marks, &options.html.links())
+            ));
+            search_index.push(format!(
+                "{{\"id\":{},\"title\":{},\"body\":{}}}",
+                escape_json(&format!("decks/{}.html#page-{}", name, page_idx + 1)),
+                escape_json(&page.title().unwrap_or_else(|| title.clone())),
+                escape_json(&plain_text(&marks))
+            ));
+        }
+
+        let mut nav = String::from("<a href=\"../index.html\">Index</a>");
+        if let Some((prev, prev_title, ..)) = idx.checked_sub(1).map(|idx| &decks[idx]) {
+            nav.push_str(&format!(
+                "<a rel=\"prev\" href=\"{}.html\">{}</a>",
+                prev,
+                escape_xml(prev_title)
+            ));
+        }
+        if let Some((next, next_title, ..)) = decks.get(idx + 1) {
+            nav.push_str(&format!(
+                "<a rel=\"next\" href=\"{}.html\">{}</a>",
+                next,
+                escape_xml(next_title)
+            ));
+        }
+        if sections.contains("<div class=\"holdslide\"") {
+            sections.push_str(&format!("<script>\n{}</script>", html::COUNTDOWN));
+        }
+        files.push(SiteFile {
+            path: format!("decks/{}.html", name),
+            content: document(
+                title,
+                "../",
+                &options.html.head_html,
+                &format!("<nav>{}</nav><main>{}</main>", nav, sections),
+            )
+            .into_bytes(),
+        });
+
+        let first = pages.first().map(Vec::as_slice).unwrap_or_default();
+        // the first image of the deck, or a preview of its first page
+        let image = pages.iter().flatten().find_map(|mark| match mark {
+            Mark::Image(src, title, _) => Some((assets.src(src, ""), title.clone()))
+                .filter(|(src, _)| src.starts_with("assets/")),
+            _ => None,
+        });
+        let thumbnail = match image {
+            Some((src, alt)) => format!(
+                "<img src=\"{}\" alt=\"{}\" />",
+                escape_xml(&src),
+                escape_xml(&alt)
+            ),
+            None => format!(
+                "<div class=\"preview\">{}</div>",
+                to_html_with_links(&assets.copy(first, ""), &options.html.links())
+            ),
+        };
+        cards.push_str(&format!(
+            "<li><a class=\"deck\" href=\"decks/{}.html\"><div class=\"thumbnail\">{}</div>{}</a></li>",
+            name,
+            thumbnail,
+            escape_xml(title)
+        ));
+    }
+
+    files.push(SiteFile {
+        path: "index.html".to_owned(),
+        content: document(
+            &options.title,
+            "",
+            &options.html.head_html,
+            &format!(
+                "<h1>{}</h1><input id=\"search\" type=\"search\" placeholder=\"Search\" />\
+                 <ul id=\"results\"></ul><ul class=\"decks\">{}</ul>\
+                 <script src=\"search.js\"></script>",
+                escape_xml(&options.title),
+                cards
+            ),
+        )
+        .into_bytes(),
+    });
+    files.push(SiteFile {
+        path: "search-index.json".to_owned(),
+        content: format!("[{}]", search_index.join(",")).into_bytes(),
+    });
+    files.push(SiteFile {
+        path: "search.js".to_owned(),
+        content: SEARCH.as_bytes().to_vec(),
+    });
+    files.push(SiteFile {
+        path: "style.css".to_owned(),
+        content: [
+            options.html.theme.to_css().as_str(),
+            html::STYLE,
+            STYLE,
+            &options.html.extra_css,
+        ]
+        .concat()
+        .into_bytes(),
+    });
+    files.extend(assets.files);
+    files
+}
+
+/// Export `decks` as a static site into the directory `dir`, see [`to_site`].
+pub fn write_site(
+    decks: &[(&str, &Deck)],
+    options: &SiteOptions,
+    dir: impl AsRef<Path>,
+) -> io::Result<()> {
+    let dir = dir.as_ref();
+    for file in to_site(decks, options) {
+        let path = dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, file.content)?;
+    }
+    Ok(())
+}
+
+/// The images copied into the site.
+struct Assets<'a> {
+    resolver: Option<&'a dyn IncludeResolver>,
+    /// Whether each source could be copied.
+    copied: HashMap<String, bool>,
+    files: Vec<SiteFile>,
+}
+
+impl<'a> Assets<'a> {
+    fn new(resolver: Option<&'a dyn IncludeResolver>) -> Self {
+        Self {
+            resolver,
+            copied: HashMap::new(),
+            files: vec![],
+        }
+    }
+
+    /// `marks` with the images linking to copied assets, from a page `prefix` away from the
+    /// root of the site.
+    fn copy(&mut self, marks: &[Mark], prefix: &str) -> Vec<Mark> {
+        marks
+            .iter()
+            .map(|mark| match mark {
+                Mark::Image(src, title, style) => {
+                    Mark::Image(self.src(src, prefix), title.clone(), style.clone())
+                }
+                Mark::Gallery(items, style) => Mark::Gallery(
+                    items
+                        .iter()
+                        .map(|(src, title)| (self.src(src, prefix), title.clone()))
+                        .collect(),
+                    style.clone(),
+                ),
+                mark => mark.clone(),
+            })
+            .collect()
+    }
+
+    /// The link to the image at `src`, copied into the site if possible.
+    fn src(&mut self, src: &str, prefix: &str) -> String {
+        let is_copied = match self.copied.get(src) {
+            Some(is_copied) => *is_copied,
+            None => {
+                let is_relative = !src.contains(':')
+                    && Path::new(src)
+                        .components()
+                        .all(|component| matches!(component, Component::Normal(_)));
+                let content = is_relative
+                    .then(|| self.resolver?.resolve_bytes(src))
+                    .flatten();
+                let is_copied = content.is_some();
+                if let Some(content) = content {
+                    self.files.push(SiteFile {
+                        path: format!("assets/{}", src),
+                        content,
+                    });
+                }
+                self.copied.insert(src.to_owned(), is_copied);
+                is_copied
+            }
+        };
+        if is_copied {
+            format!("{}assets/{}", prefix, src)
+        } else {
+            src.to_owned()
+        }
+    }
+}
+
+/// An HTML document using the stylesheet of the site, from a page `prefix` away from the root.
+fn document(title: &str, prefix: &str, head_html: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\" />\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\
+         <title>{}</title><link rel=\"stylesheet\" href=\"{}style.css\" />{}</head>\
+         <body>{}</body></html>\n",
+        escape_xml(title),
+        prefix,
+        head_html,
+        body
+    )
+}
+
+/// Names of pages only made of ASCII letters, digits, `-` and `_`, unique among `names`.
+fn unique_name(name: &str, names: &mut HashSet<String>) -> String {
+    let base: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect();
+    let base = if base.is_empty() {
+        "deck".to_owned()
+    } else {
+        base
+    };
+    let mut name = base.clone();
+    let mut count = 1;
+    while !names.insert(name.clone()) {
+        count += 1;
+        name = format!("{}-{}", base, count);
+    }
+    name
+}
+
+/// `s` as a JSON string.
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
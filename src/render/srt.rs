@@ -0,0 +1,189 @@
+//! Export the text of decks as subtitles, timed like their videos, for accessibility.
+//!
+//! Each step of a page shows, as a subtitle, the text its transition adds, or its speaker notes
+//! with [`SubtitleOptions::with_notes`], for the time the step is shown in the [`Timeline`] of
+//! the video. Steps adding no text keep the subtitle of the step before them on the page.
+//! Subtitles are written as SubRip ([`to_srt`]) or WebVTT ([`to_vtt`]).
+//!
+//! ```
+//! use opmark::{
+//!     deck::Deck,
+//!     render::{
+//!         srt::{subtitles, to_srt, SubtitleOptions},
+//!         video::{timeline, VideoOptions},
+//!     },
+//! };
+//! use std::time::Duration;
+//!
+//! let deck = Deck::new("# Title\n---\nFirst *point*\n---t\nSecond point".to_owned());
+//! let options = VideoOptions::new().with_step_duration(Duration::from_millis(2500));
+//! let subtitles = subtitles(&deck, &timeline(&deck, &options), &SubtitleOptions::new());
+//! assert_eq!(
+//!     to_srt(&subtitles),
+//!     "1\n00:00:00,000 --> 00:00:02,500\nTitle\n\n\
+//!      2\n00:00:02,500 --> 00:00:05,000\nFirst point\n\n\
+//!      3\n00:00:05,000 --> 00:00:07,500\nSecond point\n"
+//! );
+//! ```
+
+use super::video::Timeline;
+use crate::{
+    deck::{Deck, Page},
+    mark::{Directive, Mark},
+    parser::LINE_ENDING,
+    text::plain_text,
+};
+use std::time::Duration;
+
+/// Options for the exported subtitles.
+#[derive(Clone, Debug, Default)]
+pub struct SubtitleOptions {
+    /// Show the speaker notes of a step instead of its text, when it has some, like the script
+    /// of its narration.
+    pub notes: bool,
+}
+
+impl SubtitleOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_notes(mut self) -> Self {
+        self.notes = true;
+        self
+    }
+}
+
+/// A subtitle, shown from `start` to `end` since the start of the video.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subtitle {
+    /// The index of the page of the subtitle.
+    pub page: usize,
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+/// The subtitles of the steps of `deck`, timed by `timeline`.
+pub fn subtitles(deck: &Deck, timeline: &Timeline, options: &SubtitleOptions) -> Vec<Subtitle> {
+    let mut subtitles: Vec<Subtitle> = vec![];
+    for frame in &timeline.frames {
+        let Some(page) = deck.pages().get(frame.page) else {
+            continue;
+        };
+        let end = frame.start + frame.duration;
+        let text = step_text(page, frame.step, options);
+        if !text.is_empty() {
+            subtitles.push(Subtitle {
+                page: frame.page,
+                start: frame.start,
+                end,
+                text,
+            });
+            continue;
+        }
+        if let Some(last) = subtitles
+            .last_mut()
+            .filter(|last| last.page == frame.page && last.end == frame.start)
+        {
+            last.end = end;
+        }
+    }
+    subtitles
+}
+
+/// Write `subtitles` in the SubRip format, as an `.srt` file.
+pub fn to_srt(subtitles: &[Subtitle]) -> String {
+    let cues: Vec<String> = subtitles
+        .iter()
+        .enumerate()
+        .map(|(idx, subtitle)| {
+            format!(
+                "{}{}{} --> {}{}{}{}",
+                idx + 1,
+                LINE_ENDING,
+                timestamp(subtitle.start, ','),
+                timestamp(subtitle.end, ','),
+                LINE_ENDING,
+                subtitle.text,
+                LINE_ENDING
+            )
+        })
+        .collect();
+    cues.join(LINE_ENDING)
+}
+
+/// Write `subtitles` in the WebVTT format, as a `.vtt` file.
+///
+/// ```
+/// use opmark::render::srt::{to_vtt, Subtitle};
+/// use std::time::Duration;
+///
+/// let subtitle = Subtitle {
+///     page: 0,
+///     start: Duration::from_secs(61),
+///     end: Duration::from_millis(63_250),
+///     text: "Questions?".to_owned(),
+/// };
+/// assert_eq!(
+///     to_vtt(&[subtitle]),
+///     "WEBVTT\n\n00:01:01.000 --> 00:01:03.250\nQuestions?\n"
+/// );
+/// ```
+pub fn to_vtt(subtitles: &[Subtitle]) -> String {
+    let mut vtt = format!("WEBVTT{}", LINE_ENDING);
+    for subtitle in subtitles {
+        vtt.push_str(&format!(
+            "{}{} --> {}{}{}{}",
+            LINE_ENDING,
+            timestamp(subtitle.start, '.'),
+            timestamp(subtitle.end, '.'),
+            LINE_ENDING,
+            subtitle.text,
+            LINE_ENDING
+        ));
+    }
+    vtt
+}
+
+/// The text of the step `step` of `page`, on a line.
+fn step_text(page: &Page, step: usize, options: &SubtitleOptions) -> String {
+    let marks: Vec<Mark> = page
+        .transitions
+        .iter()
+        .filter_map(|transition| match transition {
+            Mark::Transition(order, marks, _) if *order == step => Some(marks),
+            _ => None,
+        })
+        .flatten()
+        .cloned()
+        .collect();
+    if options.notes {
+        let notes: Vec<&str> = marks
+            .iter()
+            .filter_map(|mark| match mark {
+                Mark::Directive(Directive::Note(note)) => Some(note.as_str()),
+                _ => None,
+            })
+            .collect();
+        if !notes.is_empty() {
+            return notes.join(" ");
+        }
+    }
+    plain_text(&marks)
+}
+
+/// `duration` as `hh:mm:ss,mmm`, with `separator` before the milliseconds.
+fn timestamp(duration: Duration, separator: char) -> String {
+    let millis = duration.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
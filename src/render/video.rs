@@ -0,0 +1,260 @@
+//! Export decks to videos, playing every step of every page along with its narration.
+//!
+//! The pages are drawn by a [`Rasterizer`] into an image sequence, listed with their timings in
+//! a file for the concat demuxer of [FFmpeg](https://ffmpeg.org/), which [`encode_mp4`] runs to
+//! produce an MP4 file.
+//!
+//! Each step of a page lasts `step_duration`, unless the page is narrated: then its steps share
+//! the duration of the narration, when it is longer. The narration of a page starts when the
+//! page appears.
+//!
+//! ```
+//! use opmark::{deck::Deck, render::video::{timeline, VideoOptions}};
+//! use std::time::Duration;
+//!
+//! let deck = Deck::new("# Title\n---\nFirst\n---t\nSecond".to_owned());
+//! let options = VideoOptions::new().with_step_duration(Duration::from_secs(2));
+//! let timeline = timeline(&deck, &options);
+//! let frames: Vec<(usize, usize)> = timeline.frames.iter().map(|f| (f.page, f.step)).collect();
+//! assert_eq!(frames, [(0, 0), (1, 0), (1, 1)]);
+//! assert_eq!(timeline.duration, Duration::from_secs(6));
+//! ```
+
+pub use super::Rasterizer;
+use crate::{audio, deck::Deck, include::IncludeResolver};
+use std::{fs, io, path::Path, process::Command, sync::Arc, time::Duration};
+
+/// Options for the exported video.
+#[derive(Clone, Debug)]
+pub struct VideoOptions {
+    /// How long each step of a page is shown, unless its narration is longer. 3 seconds by
+    /// default.
+    pub step_duration: Duration,
+    /// The frames per second of the video. 30 by default.
+    pub fps: u32,
+    /// Read the narration of pages, to time them and mix them into the video.
+    pub resolver: Option<Arc<dyn IncludeResolver>>,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self {
+            step_duration: Duration::from_secs(3),
+            fps: 30,
+            resolver: None,
+        }
+    }
+}
+
+impl VideoOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    #[inline]
+    pub fn with_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    #[inline]
+    pub fn with_step_duration(mut self, step_duration: Duration) -> Self {
+        self.step_duration = step_duration;
+        self
+    }
+}
+
+/// A step of a page, shown in the video.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The index of the page.
+    pub page: usize,
+    /// The step of the page.
+    pub step: usize,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// The narration of a page, played in the video.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+    /// The source of the audio.
+    pub src: String,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// When the steps of the pages are shown and their narration played.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timeline {
+    pub frames: Vec<Frame>,
+    pub clips: Vec<Clip>,
+    /// The duration of the video.
+    pub duration: Duration,
+}
+
+/// Time the steps and the narration of the pages of `deck`.
+///
+/// Narration which can not be read or probed with the resolver of the options is left out.
+pub fn timeline(deck: &Deck, options: &VideoOptions) -> Timeline {
+    let mut timeline = Timeline::default();
+    for (idx, page) in deck.pages().iter().enumerate() {
+        let clip = page.narration().and_then(|src| {
+            let bytes = options.resolver.as_ref()?.resolve_bytes(src)?;
+            Some(Clip {
+                src: src.to_owned(),
+                start: timeline.duration,
+                duration: audio::duration(&bytes)?,
+            })
+        });
+        let steps = page.max_step as u32 + 1;
+        let step_duration = match &clip {
+            Some(clip) => options.step_duration.max(clip.duration / steps),
+            None => options.step_duration,
+        };
+        for step in 0..=page.max_step {
+            timeline.frames.push(Frame {
+                page: idx,
+                step,
+                start: timeline.duration,
+                duration: step_duration,
+            });
+            timeline.duration += step_duration;
+        }
+        timeline.clips.extend(clip);
+    }
+    timeline
+}
+
+/// Draw the frames of `deck` with `rasterizer` into the directory `dir`, with the narration and
+/// the list of frames for FFmpeg, and return their timeline.
+///
+/// The frames are written as `frame-00001.png` and so on, the narration as `narration-1.ogg` and
+/// so on, keeping the extension of their sources, and the list of frames as `frames.txt`.
+pub fn write_frames(
+    deck: &Deck,
+    rasterizer: &dyn Rasterizer,
+    options: &VideoOptions,
+    dir: impl AsRef<Path>,
+) -> io::Result<Timeline> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("write_frames", pages = deck.pages().len()).entered();
+
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let timeline = timeline(deck, options);
+
+    let mut list = String::from("ffconcat version 1.0\n");
+    for (idx, frame) in timeline.frames.iter().enumerate() {
+        let mut page = deck.pages()[frame.page].clone();
+        page.step = frame.step;
+        let name = format!("frame-{:05}.png", idx + 1);
+        fs::write(dir.join(&name), rasterizer.rasterize(&page)?)?;
+        list.push_str(&format!(
+            "file '{}'\nduration {}\n",
+            name,
+            frame.duration.as_secs_f64()
+        ));
+    }
+    // the duration of the last file is only applied when it is listed again
+    if let Some(last) = timeline.frames.len().checked_sub(1) {
+        list.push_str(&format!("file 'frame-{:05}.png'\n", last + 1));
+    }
+    fs::write(dir.join("frames.txt"), list)?;
+
+    if let Some(resolver) = &options.resolver {
+        for (idx, clip) in timeline.clips.iter().enumerate() {
+            let bytes = resolver
+                .resolve_bytes(&clip.src)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, clip.src.clone()))?;
+            fs::write(dir.join(narration_file(idx, &clip.src)), bytes)?;
+        }
+    }
+    Ok(timeline)
+}
+
+/// The arguments of FFmpeg encoding the frames and narration written by [`write_frames`] into
+/// the MP4 file `output`, run from their directory.
+pub fn ffmpeg_args(timeline: &Timeline, fps: u32, output: &str) -> Vec<String> {
+    let mut args: Vec<String> = ["-y", "-f", "concat", "-safe", "0", "-i", "frames.txt"]
+        .map(str::to_owned)
+        .into();
+    for (idx, clip) in timeline.clips.iter().enumerate() {
+        args.push("-i".to_owned());
+        args.push(narration_file(idx, &clip.src));
+    }
+    if !timeline.clips.is_empty() {
+        let mut filter = String::new();
+        for (idx, clip) in timeline.clips.iter().enumerate() {
+            filter.push_str(&format!(
+                "[{}:a]adelay={}:all=1[a{}];",
+                idx + 1,
+                clip.start.as_millis(),
+                idx
+            ));
+        }
+        for idx in 0..timeline.clips.len() {
+            filter.push_str(&format!("[a{}]", idx));
+        }
+        filter.push_str(&format!(
+            "amix=inputs={}:normalize=0[audio]",
+            timeline.clips.len()
+        ));
+        args.extend(["-filter_complex".to_owned(), filter]);
+        args.extend(["-map", "0:v", "-map", "[audio]"].map(str::to_owned));
+    }
+    args.extend([
+        "-c:v".to_owned(),
+        "libx264".to_owned(),
+        "-pix_fmt".to_owned(),
+        "yuv420p".to_owned(),
+        "-r".to_owned(),
+        fps.to_string(),
+        "-t".to_owned(),
+        timeline.duration.as_secs_f64().to_string(),
+        output.to_owned(),
+    ]);
+    args
+}
+
+/// Export `deck` to the MP4 file `output`, drawing its frames with `rasterizer` into the
+/// directory `dir` and encoding them with the `ffmpeg` executable.
+pub fn encode_mp4(
+    deck: &Deck,
+    rasterizer: &dyn Rasterizer,
+    options: &VideoOptions,
+    dir: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+) -> io::Result<()> {
+    let dir = dir.as_ref();
+    let timeline = write_frames(deck, rasterizer, options, dir)?;
+    let output = std::path::absolute(output)?;
+    let status = Command::new("ffmpeg")
+        .args(ffmpeg_args(
+            &timeline,
+            options.fps,
+            &output.to_string_lossy(),
+        ))
+        .current_dir(dir)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("ffmpeg failed: {}", status)));
+    }
+    Ok(())
+}
+
+/// The name of the file of the narration `idx` with the source `src`.
+fn narration_file(idx: usize, src: &str) -> String {
+    let extension = Path::new(src)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("audio");
+    format!("narration-{}.{}", idx + 1, extension)
+}
@@ -0,0 +1,273 @@
+//! Review comments on the marks of a deck, to review decks together.
+//!
+//! Comments are written inline, on a line of their own before the mark they comment on, like
+//! `@comment(Ana): Shorten this title`, see [`Directive::Comment`]. A comment is on the nearest
+//! mark of its page: the next mark showing content, or else the previous one, or else the page.
+//!
+//! Comments can also be kept out of the document, saved alongside their deck in a text file with
+//! a line per comment, with the id of the mark it is on, see [`MarkId`], and whether it is
+//! resolved:
+//! ```text
+//! comment 5f1d7c3b9a2e4d60 open (Ana): Shorten this title
+//! comment 0b8e1f4a7c2d9e35 resolved (Ben): Typo in the second item
+//! ```
+//!
+//! ```
+//! use opmark::{deck::Deck, review::Review};
+//!
+//! let source = "@comment(Ana): Shorten this title\n# A title which goes on\nText";
+//! let deck = Deck::new(source.to_owned());
+//! let mut review = Review::from_deck(&deck);
+//! let title = deck.mark_ids()[3].1;
+//! assert_eq!(review.comments()[0].target, title);
+//!
+//! let text = deck.mark_ids()[4].1;
+//! let idx = review.add(text, "Ben".to_owned(), "More details".to_owned());
+//! assert!(review.resolve(idx));
+//! assert_eq!(review.unresolved().count(), 1);
+//!
+//! let saved = review.to_string();
+//! let loaded: Review = saved.parse().unwrap();
+//! assert_eq!(loaded, review);
+//! assert_eq!(loaded.on(text).next().unwrap().author, "Ben");
+//! ```
+
+use crate::{
+    deck::Deck,
+    id::MarkId,
+    mark::{Directive, Mark},
+    parser::LINE_ENDING,
+};
+use std::{
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// A review comment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    /// The id of the mark the comment is on, or of its page.
+    pub target: MarkId,
+    pub author: String,
+    /// The text of the comment, on a single line.
+    pub text: String,
+    /// Whether the comment has been addressed.
+    pub resolved: bool,
+}
+
+/// The review comments of a deck.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Review {
+    comments: Vec<Comment>,
+}
+
+impl Review {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The comments written in `deck`, open, in the order they are written.
+    pub fn from_deck(deck: &Deck) -> Self {
+        let ids = deck.mark_ids();
+        let mark_at = |path: &[usize]| -> Option<&Mark> {
+            match path {
+                [page, transition, idx] => {
+                    match deck.pages().get(*page)?.transitions.get(*transition)? {
+                        Mark::Transition(_, marks, _) => marks.get(*idx),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        };
+        // the marks showing content, with their page
+        let contents: Vec<(usize, MarkId)> = ids
+            .iter()
+            .filter(|(path, _)| {
+                mark_at(path).is_some_and(|mark| {
+                    !matches!(
+                        mark,
+                        Mark::Directive(_) | Mark::NewLine | Mark::TransitionEnd
+                    )
+                })
+            })
+            .map(|(path, id)| (path[0], *id))
+            .collect();
+
+        let mut review = Self::new();
+        for (pos, (path, _)) in ids.iter().enumerate() {
+            let Some(Mark::Directive(Directive::Comment(author, text))) = mark_at(path) else {
+                continue;
+            };
+            let page = path[0];
+            let next = ids[pos..]
+                .iter()
+                .map(|(_, id)| id)
+                .find(|id| contents.contains(&(page, **id)));
+            let prev = ids[..pos]
+                .iter()
+                .rev()
+                .map(|(_, id)| id)
+                .find(|id| contents.contains(&(page, **id)));
+            let target = next
+                .or(prev)
+                .copied()
+                .unwrap_or_else(|| deck.pages()[page].id());
+            review.add(target, author.clone(), text.clone());
+        }
+        review
+    }
+
+    /// The path of the comments saved alongside the deck at `path`, like `talk.opmark.review`
+    /// for `talk.opmark`.
+    pub fn sidecar_path(path: impl AsRef<Path>) -> PathBuf {
+        let mut path = path.as_ref().as_os_str().to_owned();
+        path.push(".review");
+        path.into()
+    }
+
+    /// Read comments saved at `path`. No comments if the file does not exist.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(s) => s
+                .parse()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Save the comments at `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Add an open comment on the mark or page of id `target`, and return its index.
+    ///
+    /// Line breaks in `text` are replaced by spaces.
+    pub fn add(&mut self, target: MarkId, author: String, text: String) -> usize {
+        self.comments.push(Comment {
+            target,
+            author,
+            text: text.lines().collect::<Vec<&str>>().join(" "),
+            resolved: false,
+        });
+        self.comments.len() - 1
+    }
+
+    /// Add the comments of `other` after these ones, like the comments saved alongside a deck
+    /// after the comments written in it.
+    pub fn extend(&mut self, other: Review) {
+        self.comments.extend(other.comments);
+    }
+
+    /// All comments, in the order they were added.
+    #[inline]
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// The comments on the mark or page of id `target`.
+    pub fn on(&self, target: MarkId) -> impl Iterator<Item = &Comment> {
+        self.comments
+            .iter()
+            .filter(move |comment| comment.target == target)
+    }
+
+    /// The comments which are not resolved.
+    pub fn unresolved(&self) -> impl Iterator<Item = &Comment> {
+        self.comments.iter().filter(|comment| !comment.resolved)
+    }
+
+    /// Resolve the comment at `idx`. Returns `false` if there is no such comment.
+    pub fn resolve(&mut self, idx: usize) -> bool {
+        match self.comments.get_mut(idx) {
+            Some(comment) => {
+                comment.resolved = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the comments on marks and pages which are not in `deck` anymore.
+    pub fn retain_deck(&mut self, deck: &Deck) {
+        let ids: Vec<MarkId> = deck.mark_ids().into_iter().map(|(_, id)| id).collect();
+        self.comments
+            .retain(|comment| ids.contains(&comment.target));
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty()
+    }
+}
+
+impl fmt::Display for Review {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for comment in &self.comments {
+            write!(
+                f,
+                "comment {} {} ({}): {}{}",
+                comment.target,
+                if comment.resolved { "resolved" } else { "open" },
+                comment.author,
+                comment.text,
+                LINE_ENDING
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Review {
+    type Err = ReviewError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut review = Self::new();
+        for (idx, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let comment = (|| {
+                let rest = line.strip_prefix("comment ")?;
+                let (id, rest) = rest.split_once(' ')?;
+                let target = MarkId(u64::from_str_radix(id, 16).ok()?);
+                let (state, rest) = rest.split_once(' ')?;
+                let resolved = match state {
+                    "open" => false,
+                    "resolved" => true,
+                    _ => return None,
+                };
+                let (author, text) = rest.strip_prefix('(')?.split_once("): ")?;
+                Some(Comment {
+                    target,
+                    author: author.to_owned(),
+                    text: text.to_owned(),
+                    resolved,
+                })
+            })();
+            review
+                .comments
+                .push(comment.ok_or(ReviewError { line: idx + 1 })?);
+        }
+        Ok(review)
+    }
+}
+
+/// An invalid line in saved comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReviewError {
+    /// The number of the line, starting from 1.
+    pub line: usize,
+}
+
+impl fmt::Display for ReviewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid comment at line {}", self.line)
+    }
+}
+
+impl error::Error for ReviewError {}
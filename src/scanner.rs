@@ -0,0 +1,177 @@
+//! A cursor over OpMark text, the scanning primitives the parser is built on.
+//!
+//! Syntax extensions and tools can use the cursor to scan OpMark text the same way the parser
+//! does, keeping track of where each element is in the text.
+//!
+//! ```
+//! use opmark::scanner::Cursor;
+//!
+//! let mut cursor = Cursor::new("## Title\nbody");
+//! let start = cursor.pos();
+//! let hashes = cursor.eat_while(|c| c == '#');
+//! assert_eq!(hashes, "##");
+//! assert!(cursor.eat(" "));
+//! assert_eq!(cursor.eat_line(), "Title");
+//! assert_eq!(cursor.span_from(start).slice(cursor.source()), "## Title\n");
+//! assert_eq!(cursor.line_col(), (1, 0));
+//! ```
+
+use crate::parser::LINE_ENDING;
+
+/// A range of bytes in the text.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    #[inline]
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// The text of the span in `s`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span is out of the bounds of `s`, or not on char boundaries.
+    #[inline]
+    pub fn slice<'a>(&self, s: &'a str) -> &'a str {
+        &s[self.start..self.end]
+    }
+}
+
+/// A position in a text, which only moves forward.
+#[derive(Clone, Debug)]
+pub struct Cursor<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    #[inline]
+    pub fn new(s: &'a str) -> Self {
+        Self { s, pos: 0 }
+    }
+
+    /// The whole text.
+    #[inline]
+    pub fn source(&self) -> &'a str {
+        self.s
+    }
+
+    /// The byte offset of the cursor.
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// The text after the cursor.
+    #[inline]
+    pub fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    #[inline]
+    pub fn is_eof(&self) -> bool {
+        self.pos >= self.s.len()
+    }
+
+    /// The rest of the current line after the cursor, without the line ending.
+    pub fn line(&self) -> &'a str {
+        let rest = self.rest();
+        &rest[..rest.find(LINE_ENDING).unwrap_or(rest.len())]
+    }
+
+    /// Whether the cursor is at a line ending.
+    #[inline]
+    pub fn is_line_end(&self) -> bool {
+        self.starts_with(LINE_ENDING)
+    }
+
+    /// The char after the cursor.
+    #[inline]
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// The `n`-th char after the cursor, starting from 0.
+    #[inline]
+    pub fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    #[inline]
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.rest().starts_with(prefix)
+    }
+
+    /// Move past the next char and return it.
+    pub fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Move past `prefix` if the text after the cursor starts with it.
+    pub fn eat(&mut self, prefix: &str) -> bool {
+        if self.starts_with(prefix) {
+            self.pos += prefix.len();
+            return true;
+        }
+        false
+    }
+
+    /// Move past the chars matching `predicate` and return them.
+    pub fn eat_while(&mut self, mut predicate: impl FnMut(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    /// Move to the next occurrence of `pattern` and return the text before it. Returns `None`
+    /// without moving if there is none.
+    pub fn eat_until(&mut self, pattern: &str) -> Option<&'a str> {
+        let rest = self.rest();
+        let end = rest.find(pattern)?;
+        self.pos += end;
+        Some(&rest[..end])
+    }
+
+    /// Move past the rest of the current line and its line ending, and return the line without
+    /// the line ending.
+    pub fn eat_line(&mut self) -> &'a str {
+        let line = self.line();
+        self.pos += line.len();
+        self.eat(LINE_ENDING);
+        line
+    }
+
+    /// The span from `start` to the cursor.
+    #[inline]
+    pub fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.pos)
+    }
+
+    /// The line and the column of the cursor, both starting from 0. The column counts chars.
+    pub fn line_col(&self) -> (usize, usize) {
+        let before = &self.s[..self.pos];
+        let line = before.matches(LINE_ENDING).count();
+        let line_start = before
+            .rfind(LINE_ENDING)
+            .map_or(0, |idx| idx + LINE_ENDING.len());
+        (line, before[line_start..].chars().count())
+    }
+}
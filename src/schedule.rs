@@ -0,0 +1,90 @@
+//! Pages shown at times of day, like the hold page of a conference room before a talk, or the
+//! pages of an agenda board.
+//!
+//! A page is scheduled with its `at` option, like `---<at=09:30>`, or `at=09:30:15` with
+//! seconds, in the local time of the venue. A [`Scheduler`], ticked by the application with the
+//! time of day, goes to each scheduled page when its time comes. Between two scheduled times,
+//! the deck can be navigated as usual.
+//!
+//! ```
+//! use opmark::{deck::Deck, schedule::Scheduler};
+//! use std::time::Duration;
+//!
+//! let mut deck = Deck::new(
+//!     "# Welcome\n---<at=09:25>\n# Starting soon\n---<at=09:30>\n# Keynote\n---\nIntro".to_owned(),
+//! );
+//! let mut scheduler = Scheduler::new();
+//! let time = |h: u64, m: u64| Duration::from_secs(h * 3600 + m * 60);
+//!
+//! assert!(!scheduler.tick(&mut deck, time(9, 0)));
+//! assert_eq!(scheduler.next_due(&deck, time(9, 0)), Some(time(9, 25)));
+//! assert!(scheduler.tick(&mut deck, time(9, 26)));
+//! assert_eq!(deck.current(), 1);
+//! // the page stays where the presenter goes until the next scheduled time
+//! deck.next_page();
+//! assert!(!scheduler.tick(&mut deck, time(9, 28)));
+//! assert!(scheduler.tick(&mut deck, time(9, 30)));
+//! assert_eq!(deck.current(), 2);
+//! ```
+
+use crate::deck::Deck;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Goes to the scheduled pages of a deck when their time comes.
+#[derive(Clone, Debug, Default)]
+pub struct Scheduler {
+    /// The index of the last scheduled page gone to.
+    last: Option<usize>,
+}
+
+impl Scheduler {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Go to the scheduled page of `deck` whose time is the latest up to `now`, the time of day,
+    /// if the scheduler has not gone to it yet. Returns `false` if the deck did not move.
+    ///
+    /// The time of day only goes forward between ticks, so pages scheduled before the first
+    /// tick are shown at once, like when a deck is opened late.
+    pub fn tick(&mut self, deck: &mut Deck, now: Duration) -> bool {
+        let due = scheduled_pages(deck)
+            .filter(|&(_, at)| at <= now)
+            .max_by_key(|&(idx, at)| (at, idx))
+            .map(|(idx, _)| idx);
+        match due {
+            Some(idx) if self.last != Some(idx) => {
+                self.last = Some(idx);
+                deck.goto_page(idx)
+            }
+            _ => false,
+        }
+    }
+
+    /// The next time of day after `now` a page of `deck` is scheduled at, to sleep until it.
+    pub fn next_due(&self, deck: &Deck, now: Duration) -> Option<Duration> {
+        scheduled_pages(deck)
+            .map(|(_, at)| at)
+            .filter(|&at| at > now)
+            .min()
+    }
+}
+
+/// The time of day of `time`, in the time zone `utc_offset` minutes ahead of UTC, to tick a
+/// [`Scheduler`] with.
+pub fn time_of_day(time: SystemTime, utc_offset: i32) -> Duration {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    } + utc_offset as i64 * 60;
+    Duration::from_secs(secs.rem_euclid(86400) as u64)
+}
+
+/// The indexes of the scheduled pages of `deck`, with their times.
+fn scheduled_pages(deck: &Deck) -> impl Iterator<Item = (usize, Duration)> + '_ {
+    deck.pages()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, page)| page.style.at.map(|at| (idx, at)))
+}
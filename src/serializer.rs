@@ -0,0 +1,725 @@
+//! Serializer turning marks back into OpMark text.
+
+use crate::{
+    front_matter::FrontMatter,
+    grammar::{Markers, Rule, RuleId, SPECIAL_CHARS},
+    mark::{
+        AlignHorizontal, CodeStep, Directive, Listing, Mark, MathMode, SeparatorDir, SeparatorLine,
+        SeparatorStyle, SeparatorThickness, StyleGallery, StyleImage, StylePage, StyleText,
+        StyleTransition, TransitionEffect, TransitionTarget,
+    },
+    parser::{
+        format_duration, format_time_of_day, parse_directive, parse_link_definition, LINE_ENDING,
+    },
+    Parser,
+};
+
+/// Serialize `marks` into the text content of an OpMark document.
+///
+/// `marks` may either be the flat marks yielded by [`Parser`](crate::Parser) or the nested pages
+/// returned by [`Parser::into_pages`](crate::Parser::into_pages). Parsing the result gives back
+/// the same marks, except that adjacent text elements with the same style may be merged, and
+/// the style of code and hyperlink text elements is dropped, as are empty code text elements.
+/// Code blocks included from files are written with the included code, see
+/// [`include`](crate::include).
+///
+/// ```
+/// use opmark::{mark::Mark, serializer::to_source, Parser};
+///
+/// let marks: Vec<Mark> = Parser::new("## Title\n\nSome *bold* text".to_owned()).collect();
+/// assert_eq!(to_source(&marks), "## Title\n\nSome *bold* text");
+/// ```
+pub fn to_source(marks: &[Mark]) -> String {
+    to_source_with_markers(marks, &Markers::new())
+}
+
+/// Serialize `marks` like [`to_source`], writing styled text with `markers`, to be parsed with
+/// the same markers, see [`ParserOptions::with_markers`](crate::ParserOptions::with_markers).
+///
+/// ```
+/// use opmark::{grammar::Markers, mark::Mark, serializer::to_source_with_markers, Parser};
+///
+/// let marks: Vec<Mark> = Parser::new("Some *bold* and /italic/ text".to_owned()).collect();
+/// assert_eq!(
+///     to_source_with_markers(&marks, &Markers::markdown()),
+///     "Some **bold** and _italic_ text"
+/// );
+/// ```
+pub fn to_source_with_markers(marks: &[Mark], markers: &Markers) -> String {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("to_source", marks = marks.len()).entered();
+
+    let mut writer = Writer {
+        markers: markers.clone(),
+        ..Default::default()
+    };
+    writer.marks(marks);
+    writer.finish()
+}
+
+/// Rewrite the OpMark document `source` in its canonical form, the way [`to_source`] writes its
+/// marks, keeping its front matter.
+///
+/// The canonical form of a document is stable: formatting it again gives the same bytes, so
+/// tools rewriting documents, like formatters run on save, make no changes to unchanged
+/// documents and small diffs to edited ones. Options are written in a fixed order, and markers,
+/// escapes and line breaks always the same way. The dialect declaration is dropped, since the
+/// document is written in the current dialect, and reference links are written inline.
+///
+/// ```
+/// use opmark::serializer::format;
+///
+/// let source =
+///     "+++\ntitle = Talk\n+++\n![logo](logo.png)<center|w50>\n---<justify|class=dark>\n__x__";
+/// let formatted = format(source);
+/// assert_eq!(
+///     formatted,
+///     "+++\ntitle = \"Talk\"\n+++\n![logo](logo.png)<w50|center>\n---<class=dark|justify>\n_x_"
+/// );
+/// assert_eq!(format(&formatted), formatted);
+/// ```
+pub fn format(source: &str) -> String {
+    format_with_options(source, &FormatOptions::new())
+}
+
+/// Rewrite the OpMark document `source` in its canonical form like [`format()`], with the given
+/// options.
+///
+/// ```
+/// use opmark::serializer::{format_with_options, FormatOptions};
+///
+/// let source = "\u{feff}# Title\r\n__bold__ text";
+/// let options = FormatOptions::new().with_preserve_line_endings();
+/// assert_eq!(format_with_options(source, &options), "\u{feff}# Title\r\n_bold_ text");
+/// assert_eq!(format_with_options(source, &FormatOptions::new()), "# Title\n_bold_ text");
+/// ```
+pub fn format_with_options(source: &str, options: &FormatOptions) -> String {
+    let parser = Parser::new(source.to_owned());
+    let line_ending = parser.line_ending();
+    let bom = parser.has_bom();
+    let mut s = front_matter(parser.front_matter());
+    let marks: Vec<Mark> = parser.collect();
+    s.push_str(&to_source(&marks));
+    if !options.preserve_line_endings {
+        return s;
+    }
+    if let Some(line_ending) = line_ending.filter(|line_ending| line_ending.as_str() != LINE_ENDING)
+    {
+        s = s.replace(LINE_ENDING, line_ending.as_str());
+    }
+    if bom {
+        s.insert(0, '\u{feff}');
+    }
+    s
+}
+
+/// The options of [`format_with_options`].
+#[derive(Clone, Debug, Default)]
+pub struct FormatOptions {
+    /// Write the line endings of the source, and its byte order mark if it has one, instead of
+    /// the line endings of the platform, see [`Parser::line_ending`](crate::Parser::line_ending).
+    /// The source is expected to end all its lines the same way.
+    pub preserve_line_endings: bool,
+}
+
+impl FormatOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_preserve_line_endings(mut self) -> Self {
+        self.preserve_line_endings = true;
+        self
+    }
+}
+
+/// Write `front_matter` between `+++` lines, with quoted values. Empty if there is none.
+pub(crate) fn front_matter(front_matter: &FrontMatter) -> String {
+    if front_matter.is_empty() {
+        return String::new();
+    }
+    let mut s = format!("+++{}", LINE_ENDING);
+    for (key, value) in front_matter.fields() {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        s.push_str(&format!("{} = \"{}\"{}", key, value, LINE_ENDING));
+    }
+    s.push_str("+++");
+    s.push_str(LINE_ENDING);
+    s
+}
+
+/// Escape `text` so it is parsed as plain text at any position of a line.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let trimmed = text.trim_start_matches([' ', '\t']);
+    let indent = text.len() - trimmed.len();
+    escaped.push_str(&text[..indent]);
+    if is_block_start(trimmed) {
+        escaped.push('\\');
+    }
+    for c in trimmed.chars() {
+        // an escaped line break is text too
+        if SPECIAL_CHARS.contains(&c) || c == '\n' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Whether `s` would be parsed as a block element at the start of a line.
+fn is_block_start(s: &str) -> bool {
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.starts_with('#')
+        || s.starts_with("> ")
+        || s.starts_with("- ")
+        || s.starts_with("---")
+        || s.starts_with("t---")
+        || s.starts_with("![")
+        || s.starts_with("!!!")
+        || s.starts_with("@space(")
+        || s.starts_with("@holdslide(")
+        || s[digits_end..].starts_with(". ")
+        || parse_directive(s).is_some()
+        || parse_link_definition(s).is_some()
+}
+
+/// Where the writer is in the current line.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum LineState {
+    /// At the start of a line.
+    #[default]
+    Start,
+    /// Within a line of inline elements.
+    Inline,
+    /// After a block element which takes the whole line.
+    Closed,
+}
+
+#[derive(Debug, Default)]
+struct Writer {
+    s: String,
+    line_state: LineState,
+    /// Where the current line of inline elements starts in `s`.
+    line_start: usize,
+    style_text: StyleText,
+    /// The order of the next transition without an explicit order.
+    transition_order: usize,
+    /// Whether the next transition may be the synthetic one of its page.
+    is_transition_synthetic: bool,
+    has_page: bool,
+    markers: Markers,
+}
+
+impl Writer {
+    fn finish(mut self) -> String {
+        self.close_toggles();
+        self.escape_line_start();
+        self.s
+    }
+
+    fn marks(&mut self, marks: &[Mark]) {
+        for mark in marks {
+            self.mark(mark);
+        }
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        let is_transition_synthetic = self.is_transition_synthetic;
+        self.is_transition_synthetic = false;
+        match mark {
+            Mark::CodeBlock(code, language) => {
+                self.start_block();
+                self.s.push_str("```");
+                self.s.push_str(language.as_deref().unwrap_or_default());
+                self.s.push_str(LINE_ENDING);
+                self.s.push_str(code);
+                self.s.push_str(LINE_ENDING);
+                self.s.push_str("```");
+                self.line_state = LineState::Closed;
+            }
+            Mark::CodeWalkthrough(code, language, steps) => {
+                self.start_block();
+                self.s.push_str("```");
+                if let Some(language) = language {
+                    self.s.push_str(language);
+                    self.s.push(' ');
+                }
+                self.s.push_str(&format!("steps=[{}]", code_steps(steps)));
+                self.s.push_str(LINE_ENDING);
+                self.s.push_str(code);
+                self.s.push_str(LINE_ENDING);
+                self.s.push_str("```");
+                self.line_state = LineState::Closed;
+                self.transition_order += steps.len().saturating_sub(1);
+            }
+            Mark::Directive(Directive::Label(label)) => {
+                self.start_block();
+                self.s.push_str(&format!("{{#{}}}", label));
+                self.line_state = LineState::Closed;
+            }
+            Mark::Directive(directive) => {
+                self.start_block();
+                self.s.push('@');
+                self.s.push_str(directive.name());
+                if !directive.argument().is_empty() {
+                    self.s.push_str(&format!("({})", directive.argument()));
+                }
+                if let Directive::Comment(_, text) = directive {
+                    self.s.push_str(&format!(": {}", text));
+                }
+                self.line_state = LineState::Closed;
+            }
+            Mark::Embed(url, _) => {
+                self.start_block();
+                self.s.push_str(&format!("<{}>", url));
+                self.line_state = LineState::Closed;
+            }
+            Mark::Gallery(items, style) => {
+                self.start_block();
+                self.s.push_str("!!!");
+                self.options(&gallery_options(style));
+                self.s.push_str(LINE_ENDING);
+                for (src, title) in items {
+                    self.s.push_str(&format!("![{}]({})", title, src));
+                    self.s.push_str(LINE_ENDING);
+                }
+                self.s.push_str("!!!");
+                self.line_state = LineState::Closed;
+            }
+            Mark::Image(src, title, style) => {
+                self.start_block();
+                self.s.push_str(&format!("![{}]({})", title, src));
+                self.options(&image_options(style));
+                self.line_state = LineState::Closed;
+            }
+            Mark::Math(math, MathMode::Inline) => {
+                if self.line_state == LineState::Closed {
+                    self.s.push_str(LINE_ENDING);
+                    self.line_state = LineState::Start;
+                }
+                if self.line_state == LineState::Start {
+                    self.line_start = self.s.len();
+                }
+                self.s.push_str(&format!("\\({}\\)", math));
+                self.line_state = LineState::Inline;
+            }
+            Mark::Math(math, MathMode::Display) => {
+                self.start_block();
+                self.s.push_str("$$");
+                // math of several lines goes between `$$` lines
+                if math.contains(LINE_ENDING) {
+                    self.s.push_str(LINE_ENDING);
+                    self.s.push_str(math);
+                    self.s.push_str(LINE_ENDING);
+                } else {
+                    self.s.push_str(math);
+                }
+                self.s.push_str("$$");
+                self.line_state = LineState::Closed;
+            }
+            Mark::NewLine => {
+                self.close_toggles();
+                self.escape_line_start();
+                self.s.push_str(LINE_ENDING);
+                self.s.push_str(LINE_ENDING);
+                self.line_state = LineState::Start;
+            }
+            Mark::Page(marks, style) => {
+                let is_first = !self.has_page && self.s.is_empty();
+                self.has_page = true;
+                let options = page_options(style);
+                if !is_first || !options.is_empty() {
+                    self.start_block();
+                    self.s.push_str("---");
+                    self.options(&options);
+                    self.s.push_str(LINE_ENDING);
+                }
+                self.transition_order = 0;
+                self.is_transition_synthetic = true;
+                self.marks(marks);
+            }
+            Mark::Separator(dir, style) => {
+                self.start_block();
+                self.s.push_str(match dir {
+                    SeparatorDir::Horizontal => "----",
+                    SeparatorDir::Vertical => "----v",
+                });
+                self.options(&separator_options(style));
+                self.s.push_str(LINE_ENDING);
+            }
+            Mark::HoldSlide(start, message) => {
+                self.start_block();
+                self.s
+                    .push_str(&format!("@holdslide(start={}", format_time_of_day(*start)));
+                if !message.is_empty() {
+                    let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+                    self.s.push_str(&format!(", message=\"{}\"", message));
+                }
+                self.s.push(')');
+                self.line_state = LineState::Closed;
+            }
+            Mark::Space(lines) => {
+                self.start_block();
+                self.s.push_str(&format!("@space({})", lines));
+                self.line_state = LineState::Closed;
+            }
+            Mark::Text(text, style) => self.text(text, style),
+            Mark::Transition(order, marks, style) => {
+                let options = transition_options(style);
+                let is_synthetic = (is_transition_synthetic || !self.has_page)
+                    && *order == 0
+                    && self.transition_order == 0
+                    && options.is_empty();
+                if !is_synthetic {
+                    self.start_block();
+                    self.s.push_str("---t");
+                    if *order != self.transition_order {
+                        self.s.push_str(&order.to_string());
+                    }
+                    self.options(&options);
+                    self.line_state = LineState::Closed;
+                }
+                self.has_page = true;
+                self.transition_order = order + 1;
+                self.marks(marks);
+            }
+            Mark::TransitionEnd => {
+                self.start_block();
+                self.s.push_str("t---");
+                self.s.push_str(LINE_ENDING);
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str, style: &StyleText) {
+        let heading = style.heading.to_int() as usize;
+        if heading > 0 {
+            self.start_block();
+            self.s.push_str(&"#".repeat(heading));
+            self.s.push(' ');
+            self.s.push_str(text);
+            self.line_state = LineState::Closed;
+            return;
+        }
+        match &style.listing {
+            Listing::Ordered(number, indent) => {
+                self.start_block();
+                self.s.push_str(&"  ".repeat(indent.to_int() as usize));
+                self.s.push_str(&format!("{}. {}", number, text));
+                self.line_state = LineState::Closed;
+                return;
+            }
+            Listing::Unordered(indent) => {
+                self.start_block();
+                self.s.push_str(&"  ".repeat(indent.to_int() as usize));
+                self.s.push_str("- ");
+                self.s.push_str(text);
+                self.line_state = LineState::Closed;
+                return;
+            }
+            Listing::None => {}
+        }
+        if style.quote {
+            self.start_block();
+            self.s.push_str("> ");
+            self.s.push_str(text);
+            self.line_state = LineState::Closed;
+            return;
+        }
+
+        // runs of backticks would be read as longer code spans, or as a code block fence
+        if style.code && text.is_empty() {
+            return;
+        }
+        if self.line_state == LineState::Closed {
+            self.s.push_str(LINE_ENDING);
+            self.line_state = LineState::Start;
+        }
+        if self.line_state == LineState::Start {
+            self.line_start = self.s.len();
+        }
+        if style.code {
+            self.s.push_str(&format!("`{}`", text));
+        } else if !style.hyperlink.is_empty() {
+            match &style.link_title {
+                Some(title) => self
+                    .s
+                    .push_str(&format!("[{}]({} \"{}\")", text, style.hyperlink, title)),
+                None if text == style.hyperlink => self.s.push_str(&format!("<{}>", text)),
+                None => self.s.push_str(&format!("[{}]({})", text, style.hyperlink)),
+            }
+        } else {
+            // classes last until the end of the line
+            if style.classes.is_empty() && !self.style_text.classes.is_empty() {
+                self.start_block();
+                self.line_start = self.s.len();
+            }
+            if !style.classes.is_empty() && style.classes != self.style_text.classes {
+                let classes: Vec<String> = style
+                    .classes
+                    .iter()
+                    .map(|class| format!(".{}", class))
+                    .collect();
+                self.s.push_str(&format!("{{{}}}", classes.join(" ")));
+                self.style_text.classes = style.classes.clone();
+            }
+            let next = text
+                .chars()
+                .next()
+                .map(|c| if self.is_escaped(c) { '\\' } else { c });
+            self.toggles(style, next);
+            for c in text.chars() {
+                if self.is_escaped(c) {
+                    self.s.push('\\');
+                }
+                self.s.push(c);
+            }
+        }
+        self.line_state = LineState::Inline;
+    }
+
+    /// Whether `c` is escaped in plain text.
+    fn is_escaped(&self, c: char) -> bool {
+        SPECIAL_CHARS.contains(&c) || c == '\n' || self.markers.is_marker_start(c)
+    }
+
+    /// Emit the toggle marks needed to switch from the current style to `style`, before the
+    /// source char `next`.
+    ///
+    /// Toggles are turned off in the reverse order they are turned on, so they nest. Markers
+    /// which would be text within a word are doubled.
+    fn toggles(&mut self, style: &StyleText, next: Option<char>) {
+        let current = &mut self.style_text;
+        let mut toggles = [
+            (RuleId::Bold, &mut current.bold, style.bold),
+            (RuleId::Italics, &mut current.italics, style.italics),
+            (RuleId::Small, &mut current.small, style.small),
+            (
+                RuleId::Strikethrough,
+                &mut current.strikethrough,
+                style.strikethrough,
+            ),
+            (RuleId::Underline, &mut current.underline, style.underline),
+        ];
+        let mut switched = vec![];
+        for (id, from, to) in toggles.iter_mut().rev() {
+            if **from && !*to {
+                switched.push(*id);
+                **from = false;
+            }
+        }
+        for (id, from, to) in toggles.iter_mut() {
+            if !**from && *to {
+                switched.push(*id);
+                **from = true;
+            }
+        }
+
+        let markers = &self.markers;
+        let marker = |id| markers.get(id).unwrap_or_default();
+        for (idx, &id) in switched.iter().enumerate() {
+            let next = switched
+                .get(idx + 1)
+                .map_or(next, |&id| marker(id).chars().next());
+            let is_intraword =
+                self.s.ends_with(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric);
+            match markers.doubled(id).filter(|_| is_intraword) {
+                Some(doubled) => self.s.push_str(&doubled),
+                None => self.s.push_str(marker(id)),
+            }
+        }
+    }
+
+    /// Close the toggle marks which are still open in the current line.
+    fn close_toggles(&mut self) {
+        let open = std::mem::take(&mut self.style_text);
+        let markers = &self.markers;
+        for (marker, is_open) in [
+            (&markers.underline, open.underline),
+            (&markers.strikethrough, open.strikethrough),
+            (&markers.small, open.small),
+            (&markers.italics, open.italics),
+            (&markers.bold, open.bold),
+        ] {
+            if is_open {
+                self.s.push_str(marker);
+            }
+        }
+    }
+
+    /// Move to the start of a new line, unless already there.
+    fn start_block(&mut self) {
+        self.close_toggles();
+        self.escape_line_start();
+        if self.line_state != LineState::Start {
+            self.s.push_str(LINE_ENDING);
+            self.line_state = LineState::Start;
+        }
+    }
+
+    /// Escape the current line of inline elements if it would be parsed as a block element.
+    ///
+    /// This is checked on the whole line, since a block mark may be made up of several text
+    /// elements, like `1` and `. text`.
+    fn escape_line_start(&mut self) {
+        if self.line_state != LineState::Inline {
+            return;
+        }
+        let line = &self.s[self.line_start..];
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        // a declaration is only read on the first line of the document
+        let is_declaration =
+            self.line_start == 0 && line.starts_with(Rule::get(RuleId::Declaration).markers[0]);
+        // and a byte order mark is dropped from its start
+        let is_bom = self.line_start == 0 && line.starts_with('\u{feff}');
+        if is_declaration || is_bom || is_block_start(trimmed) {
+            let indent = line.len() - trimmed.len();
+            self.s.insert(self.line_start + indent, '\\');
+        }
+    }
+
+    fn options(&mut self, options: &[String]) {
+        if !options.is_empty() {
+            self.s.push_str(&format!("<{}>", options.join("|")));
+        }
+    }
+}
+
+fn code_steps(steps: &[CodeStep]) -> String {
+    steps
+        .iter()
+        .map(|step| match *step {
+            CodeStep::All => "all".to_owned(),
+            CodeStep::Lines(first, last) if first == last => first.to_string(),
+            CodeStep::Lines(first, last) => format!("{}-{}", first, last),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn gallery_options(style: &StyleGallery) -> Vec<String> {
+    let mut options = vec![];
+    if let Some(columns) = style.columns {
+        options.push(format!("columns={}", columns));
+    }
+    if let Some(rows) = style.rows {
+        options.push(format!("rows={}", rows));
+    }
+    if !style.classes.is_empty() {
+        options.push(format!("class={}", style.classes.join(" ")));
+    }
+    options
+}
+
+fn image_options(style: &StyleImage) -> Vec<String> {
+    let mut options = vec![];
+    if let Some(width) = style.width {
+        options.push(format!("w{}", width));
+    }
+    if let Some(height) = style.height {
+        options.push(format!("h{}", height));
+    }
+    match style.align_h {
+        AlignHorizontal::Auto => {}
+        AlignHorizontal::Left => options.push("left".to_owned()),
+        AlignHorizontal::Right => options.push("right".to_owned()),
+        AlignHorizontal::Center => options.push("center".to_owned()),
+    }
+    if !style.classes.is_empty() {
+        options.push(format!("class={}", style.classes.join(" ")));
+    }
+    if style.autoplay {
+        options.push("autoplay".to_owned());
+    }
+    if style.looping {
+        options.push("loop".to_owned());
+    }
+    if let Some(fps) = style.fps {
+        options.push(format!("fps={}", fps));
+    }
+    if let Some(frame) = style.pause_at {
+        options.push(format!("pause-at={}", frame));
+    }
+    if !style.hyperlink.is_empty() {
+        options.push(style.hyperlink.clone());
+    }
+    options
+}
+
+fn separator_options(style: &SeparatorStyle) -> Vec<String> {
+    let mut options = vec![];
+    match style.line {
+        SeparatorLine::Solid => {}
+        SeparatorLine::Dotted => options.push("dotted".to_owned()),
+        SeparatorLine::Dashed => options.push("dashed".to_owned()),
+    }
+    if let Some(length) = style.length {
+        options.push(format!("{}%", length));
+    }
+    match style.thickness {
+        SeparatorThickness::Thin => options.push("thin".to_owned()),
+        SeparatorThickness::Normal => {}
+        SeparatorThickness::Thick => options.push("thick".to_owned()),
+    }
+    options
+}
+
+fn page_options(style: &StylePage) -> Vec<String> {
+    let mut options = vec![];
+    if !style.classes.is_empty() {
+        options.push(format!("class={}", style.classes.join(" ")));
+    }
+    if style.reset_on_enter {
+        options.push("reset-on-enter".to_owned());
+    }
+    if let Some(src) = &style.narration {
+        options.push(format!("narration={}", src));
+    }
+    if style.justify {
+        options.push("justify".to_owned());
+    }
+    if style.hyphenate {
+        options.push("hyphenate".to_owned());
+    }
+    if style.hidden {
+        options.push("hidden".to_owned());
+    }
+    if let Some(duration) = style.duration {
+        options.push(format!("duration={}", format_duration(duration)));
+    }
+    if !style.audiences.is_empty() {
+        options.push(format!("audience={}", style.audiences.join(",")));
+    }
+    if let Some(at) = style.at {
+        options.push(format!("at={}", format_time_of_day(at)));
+    }
+    if style.no_split {
+        options.push("no-split".to_owned());
+    }
+    options
+}
+
+fn transition_options(style: &StyleTransition) -> Vec<String> {
+    let mut options = vec![];
+    let effect = match style.effect {
+        TransitionEffect::Appear => "appear",
+        TransitionEffect::Disappear => "disappear",
+        TransitionEffect::Dim => "dim",
+        TransitionEffect::Replace => "replace",
+    };
+    match (&style.effect, &style.target) {
+        // a replacement of the transition itself is the same as appearing
+        (TransitionEffect::Appear | TransitionEffect::Replace, TransitionTarget::This) => {}
+        (TransitionEffect::Replace, TransitionTarget::Prev) => options.push(effect.to_owned()),
+        (_, TransitionTarget::This) => options.push(effect.to_owned()),
+        (_, TransitionTarget::Prev) => options.push(format!("{}:prev", effect)),
+        (_, TransitionTarget::Order(order)) => options.push(format!("{}:{}", effect, order)),
+    }
+    if style.looping {
+        options.push("loop".to_owned());
+    }
+    options
+}
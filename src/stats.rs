@@ -0,0 +1,207 @@
+//! Statistics of decks, like the main topics of their pages and the time planned to present
+//! them.
+//!
+//! [`keywords`] ranks the words of a deck by TF-IDF, taking each page as a document: words
+//! frequent in a few pages rank higher than words spread over all pages. Code, common English
+//! words, numbers and words of less than 3 letters are ignored.
+//!
+//! ```
+//! use opmark::{deck::Deck, stats::{keywords, page_keywords}};
+//!
+//! let deck = Deck::new(
+//!     "# Parsing\nThe parser reads marks, the parser is fast\n---\n# Rendering\nRendering marks"
+//!         .to_owned(),
+//! );
+//! let words: Vec<String> = keywords(&deck, 3).into_iter().map(|k| k.word).collect();
+//! assert_eq!(words, ["rendering", "marks", "parser"]);
+//! assert_eq!(page_keywords(&deck, 0, 1)[0].word, "parser");
+//! ```
+
+use crate::{deck::Deck, mark::Mark, render::page_marks, text::plain_text};
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
+
+/// Words too common to be keywords.
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "all", "also", "and", "any", "are", "because", "been", "before", "but",
+    "can", "could", "did", "does", "each", "for", "from", "had", "has", "have", "her", "him",
+    "his", "how", "into", "its", "just", "more", "most", "not", "now", "our", "out", "over", "she",
+    "should", "some", "such", "than", "that", "the", "their", "them", "then", "there", "these",
+    "they", "this", "those", "too", "use", "very", "was", "way", "were", "what", "when", "where",
+    "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+/// A word ranking among the main topics of a deck or page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyword {
+    /// The word, in lowercase.
+    pub word: String,
+    /// The TF-IDF score of the word, higher for more relevant words.
+    pub score: f32,
+    /// The indexes of the pages the word appears in.
+    pub pages: Vec<usize>,
+}
+
+/// The `top_n` keywords of `deck`, the most relevant first.
+///
+/// The score of a word is the sum of its scores on each page.
+pub fn keywords(deck: &Deck, top_n: usize) -> Vec<Keyword> {
+    let index = Index::new(deck);
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    for page in 0..index.pages.len() {
+        for (word, score) in index.scores(page) {
+            *scores.entry(word).or_default() += score;
+        }
+    }
+    index.top(scores, top_n)
+}
+
+/// The `top_n` keywords of the page at `page` of `deck`, among all pages, the most relevant
+/// first. Empty if there is no such page.
+pub fn page_keywords(deck: &Deck, page: usize, top_n: usize) -> Vec<Keyword> {
+    let index = Index::new(deck);
+    if page >= index.pages.len() {
+        return vec![];
+    }
+    let scores = index.scores(page).collect();
+    index.top(scores, top_n)
+}
+
+/// The words of the pages of a deck.
+struct Index {
+    /// The number of times each word appears in each page, with the number of words of the
+    /// page.
+    pages: Vec<(HashMap<String, usize>, usize)>,
+    /// The pages each word appears in.
+    word_pages: HashMap<String, BTreeSet<usize>>,
+}
+
+impl Index {
+    fn new(deck: &Deck) -> Self {
+        let mut pages = vec![];
+        let mut word_pages: HashMap<String, BTreeSet<usize>> = HashMap::new();
+        for (idx, page) in deck.pages().iter().enumerate() {
+            let marks: Vec<Mark> = page_marks(page)
+                .into_iter()
+                .filter(|mark| !matches!(mark, Mark::CodeBlock(..) | Mark::CodeWalkthrough(..)))
+                .filter(|mark| !matches!(mark, Mark::Text(_, style) if style.code))
+                .collect();
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            let mut total = 0;
+            for word in words(&plain_text(&marks)) {
+                total += 1;
+                word_pages.entry(word.clone()).or_default().insert(idx);
+                *counts.entry(word).or_default() += 1;
+            }
+            pages.push((counts, total));
+        }
+        Self { pages, word_pages }
+    }
+
+    /// The TF-IDF scores of the words of the page at `page`.
+    fn scores(&self, page: usize) -> impl Iterator<Item = (&str, f32)> {
+        let (counts, total) = &self.pages[page];
+        let len = self.pages.len() as f32;
+        counts.iter().map(move |(word, count)| {
+            let tf = *count as f32 / *total as f32;
+            // smoothed, so words of all pages still count in decks of a single page
+            let df = self.word_pages[word].len() as f32;
+            let idf = ((1.0 + len) / (1.0 + df)).ln() + 1.0;
+            (word.as_str(), tf * idf)
+        })
+    }
+
+    /// The `top_n` words with the highest scores, ties broken alphabetically.
+    fn top(&self, scores: HashMap<&str, f32>, top_n: usize) -> Vec<Keyword> {
+        let mut scores: Vec<(&str, f32)> = scores.into_iter().collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        scores
+            .into_iter()
+            .take(top_n)
+            .map(|(word, score)| Keyword {
+                word: word.to_owned(),
+                score,
+                pages: self.word_pages[word].iter().copied().collect(),
+            })
+            .collect()
+    }
+}
+
+/// The words of `text` which may be keywords, in lowercase.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '-' && c != '\'')
+        .map(|word| word.trim_matches(['-', '\'']).to_lowercase())
+        .filter(|word| {
+            word.chars().count() >= 3
+                && !word.chars().all(|c| c.is_numeric())
+                && !STOP_WORDS.contains(&word.as_str())
+        })
+}
+
+/// Totals of a deck, to plan a talk.
+///
+/// Pages are planned to take the time set by their `duration` option, see
+/// [`StylePage`](crate::mark::StylePage). The parser warns when the shown pages are planned to
+/// take longer than the talk, see [`Warning::OverBudget`](crate::lint::Warning::OverBudget).
+///
+/// ```
+/// use opmark::{deck::Deck, lint::Warning, stats::DeckStats, Parser};
+/// use std::time::Duration;
+///
+/// let source = "+++\nduration = 5m\n+++\n# Talk\n---<duration=1m>\nIntro\n---<duration=3m30s>\n";
+/// let stats = DeckStats::new(&Deck::new(source.to_owned()));
+/// assert_eq!(stats.budget, Duration::from_secs(270));
+/// assert_eq!(stats.unbudgeted_pages, 1);
+/// assert_eq!(stats.overtime(), None);
+///
+/// let parser = Parser::new(source.replace("5m", "4m"));
+/// assert_eq!(
+///     parser.warnings(),
+///     [Warning::OverBudget {
+///         budget: Duration::from_secs(270),
+///         duration: Duration::from_secs(240)
+///     }]
+/// );
+/// assert_eq!(
+///     parser.warnings()[0].to_string(),
+///     "pages are planned to take 4m30s, more than the 4m of the talk"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeckStats {
+    /// The number of pages, hidden pages included.
+    pub pages: usize,
+    /// The sum of the durations planned for the shown pages.
+    pub budget: Duration,
+    /// The number of shown pages without a planned duration.
+    pub unbudgeted_pages: usize,
+    /// The length of the talk, set by the `duration` of the front matter.
+    pub duration: Option<Duration>,
+}
+
+impl DeckStats {
+    pub fn new(deck: &Deck) -> Self {
+        let mut stats = Self {
+            pages: deck.pages().len(),
+            duration: deck.front_matter().duration(),
+            ..Default::default()
+        };
+        for page in deck.pages().iter().filter(|page| !page.style.hidden) {
+            match page.style.duration {
+                Some(duration) => stats.budget += duration,
+                None => stats.unbudgeted_pages += 1,
+            }
+        }
+        stats
+    }
+
+    /// How much longer than the talk the pages are planned to take. `None` if they fit, or if
+    /// the length of the talk is not set.
+    pub fn overtime(&self) -> Option<Duration> {
+        self.budget
+            .checked_sub(self.duration?)
+            .filter(|overtime| !overtime.is_zero())
+    }
+}
@@ -0,0 +1,250 @@
+//! Keep the devices of the audience on the page of the presenter.
+//!
+//! A [`Broadcaster`], observing the deck of the presenter, publishes its position as a
+//! [`SyncMessage`] after each move, through a [`SyncSink`] the application provides, like a
+//! WebSocket or a pub/sub channel. A [`Follower`] applies the messages it receives to the deck of
+//! a device of the audience.
+//!
+//! Each message carries the whole position, so followers missing messages catch up with the next
+//! one. Messages are numbered, so a follower ignores the ones arriving late. A follower
+//! reconnecting, or joining late, syncs by applying [`Broadcaster::latest`], which the
+//! application sends to each new connection. A presenter restarting starts a new session, numbered
+//! after the previous one, which followers switch to, ignoring the messages of older sessions.
+//!
+//! ```
+//! use opmark::{
+//!     deck::Deck,
+//!     sync::{Broadcaster, Follower, SyncSink},
+//! };
+//! use std::sync::{Arc, Mutex};
+//!
+//! #[derive(Clone, Debug, Default)]
+//! struct Channel(Arc<Mutex<Vec<String>>>);
+//!
+//! impl SyncSink for Channel {
+//!     fn send(&self, message: &str) {
+//!         self.0.lock().unwrap().push(message.to_owned());
+//!     }
+//! }
+//!
+//! let source = "# One\n---t\nMore\n---\n# Two";
+//! let channel = Channel::default();
+//! let broadcaster = Broadcaster::new(channel.clone()).with_session(7);
+//! let mut presenter = Deck::new(source.to_owned()).with_observer(broadcaster.clone());
+//! presenter.next_step();
+//! presenter.next_step();
+//!
+//! let messages = channel.0.lock().unwrap().clone();
+//! assert_eq!(messages[1], "opmark-sync session=7 seq=2 page=1 step=0");
+//!
+//! let mut audience = Deck::new(source.to_owned());
+//! let mut follower = Follower::new();
+//! assert_eq!(follower.receive(&mut audience, &messages[1]), Ok(true));
+//! // the first message arrives late
+//! assert_eq!(follower.receive(&mut audience, &messages[0]), Ok(false));
+//! assert_eq!(audience.current(), 1);
+//!
+//! // a device joining late
+//! let mut late = Deck::new(source.to_owned());
+//! let latest = broadcaster.latest().unwrap().to_string();
+//! assert_eq!(Follower::new().receive(&mut late, &latest), Ok(true));
+//! assert_eq!(late.current(), 1);
+//!
+//! // a message of the previous session of the presenter arrives late
+//! let stale = "opmark-sync session=6 seq=9 page=0 step=0";
+//! assert_eq!(follower.receive(&mut audience, stale), Ok(false));
+//! assert_eq!(audience.current(), 1);
+//! ```
+
+use crate::deck::{Deck, DeckEvent, DeckObserver};
+use std::{
+    error, fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Publishes the messages of a [`Broadcaster`] to the followers.
+pub trait SyncSink: fmt::Debug + Send + Sync {
+    /// Send `message` to all followers.
+    fn send(&self, message: &str);
+}
+
+/// The position of the deck of the presenter, see [`sync`](self).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SyncMessage {
+    /// The session of the presenter, new each time a presenter starts broadcasting.
+    pub session: u64,
+    /// The number of the message in the session, from 1.
+    pub seq: u64,
+    /// The index of the current page.
+    pub page: usize,
+    /// The current step of the page.
+    pub step: usize,
+}
+
+impl fmt::Display for SyncMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "opmark-sync session={} seq={} page={} step={}",
+            self.session, self.seq, self.page, self.step
+        )
+    }
+}
+
+impl FromStr for SyncMessage {
+    type Err = SyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || SyncError::Malformed(s.to_owned());
+        let mut fields = s.trim().split(' ');
+        if fields.next() != Some("opmark-sync") {
+            return Err(malformed());
+        }
+        let mut field = |key: &str| -> Result<u64, SyncError> {
+            fields
+                .next()
+                .and_then(|field| field.strip_prefix(key)?.strip_prefix('='))
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(malformed)
+        };
+        let message = SyncMessage {
+            session: field("session")?,
+            seq: field("seq")?,
+            page: field("page")? as usize,
+            step: field("step")? as usize,
+        };
+        match fields.next() {
+            Some(_) => Err(malformed()),
+            None => Ok(message),
+        }
+    }
+}
+
+/// An error applying a message to the deck of a follower.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncError {
+    /// The message is not a [`SyncMessage`].
+    Malformed(String),
+    /// The message shows a page the deck does not have, like when the presenter shows another
+    /// version of the deck.
+    NoSuchPage(usize),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Malformed(message) => write!(f, "malformed sync message `{}`", message),
+            SyncError::NoSuchPage(page) => write!(f, "the deck has no page {}", page + 1),
+        }
+    }
+}
+
+impl error::Error for SyncError {}
+
+/// Publishes the position of the deck it observes, see [`Deck::with_observer`]. Clones share
+/// the same session, so the application keeps one to send [`Broadcaster::latest`] to new
+/// followers.
+#[derive(Clone, Debug)]
+pub struct Broadcaster {
+    sink: Arc<dyn SyncSink>,
+    latest: Arc<Mutex<SyncMessage>>,
+}
+
+impl Broadcaster {
+    /// A broadcaster sending its messages to `sink`, starting a session numbered after the
+    /// current time.
+    pub fn new(sink: impl SyncSink + 'static) -> Self {
+        let session = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        Self {
+            sink: Arc::new(sink),
+            latest: Arc::new(Mutex::new(SyncMessage {
+                session,
+                seq: 0,
+                page: 0,
+                step: 0,
+            })),
+        }
+    }
+
+    /// Number the session `session` instead, higher than the sessions broadcast before, since
+    /// followers ignore the messages of older sessions.
+    #[inline]
+    pub fn with_session(self, session: u64) -> Self {
+        self.lock().session = session;
+        self
+    }
+
+    /// The last message sent, `None` if the deck has not moved yet.
+    pub fn latest(&self) -> Option<SyncMessage> {
+        let latest = *self.lock();
+        (latest.seq > 0).then_some(latest)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SyncMessage> {
+        // a message is always left whole, so it is still valid after a panic
+        self.latest
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl DeckObserver for Broadcaster {
+    fn on_event(&self, event: &DeckEvent) {
+        let mut latest = self.lock();
+        let (page, step) = match *event {
+            DeckEvent::PageEntered { page, step }
+            | DeckEvent::StepRevealed { page, step }
+            | DeckEvent::StepHidden { page, step } => (page, step),
+            DeckEvent::DeckFinished => return,
+        };
+        *latest = SyncMessage {
+            seq: latest.seq + 1,
+            page,
+            step,
+            ..*latest
+        };
+        self.sink.send(&latest.to_string());
+    }
+}
+
+/// Applies the messages of a [`Broadcaster`] to the deck of a follower.
+#[derive(Clone, Debug, Default)]
+pub struct Follower {
+    /// The session and the number of the last message applied.
+    last: Option<(u64, u64)>,
+}
+
+impl Follower {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Parse `message` and apply it to `deck`. Returns `false` if the message was ignored, being
+    /// older than the last message applied in its session, or of an older session. A message of
+    /// a newer session is always applied.
+    pub fn receive(&mut self, deck: &mut Deck, message: &str) -> Result<bool, SyncError> {
+        let message: SyncMessage = message.parse()?;
+        self.apply(deck, &message)
+    }
+
+    /// Apply `message` to `deck`, see [`Follower::receive`].
+    pub fn apply(&mut self, deck: &mut Deck, message: &SyncMessage) -> Result<bool, SyncError> {
+        // sessions are numbered from the clock, so a lower one is from a previous presenter
+        if self
+            .last
+            .is_some_and(|last| last >= (message.session, message.seq))
+        {
+            return Ok(false);
+        }
+        if !deck.goto_step(message.page, message.step) {
+            return Err(SyncError::NoSuchPage(message.page));
+        }
+        self.last = Some((message.session, message.seq));
+        Ok(true)
+    }
+}
@@ -0,0 +1,170 @@
+//! The prose of a deck as timed words, to drive a scrolling teleprompter or highlight words as
+//! they are spoken.
+//!
+//! The prose of a page is its speaker notes if it has any, see [`Page::notes`], or else its
+//! text. Code and images are not read.
+//!
+//! ```
+//! use opmark::{deck::Deck, teleprompter::{teleprompter, TeleprompterOptions}};
+//! use std::time::Duration;
+//!
+//! let deck = Deck::new("# Welcome\n---\nSlides\n@note(Thanks for coming.)".to_owned());
+//! let options = TeleprompterOptions::new().with_rehearsal(vec![Duration::from_secs(2)]);
+//! let words = teleprompter(&deck, &options);
+//! let texts: Vec<&str> = words.iter().map(|word| word.text.as_str()).collect();
+//! assert_eq!(texts, ["Welcome", "Thanks", "for", "coming."]);
+//! // the first page lasts as long as in the rehearsal
+//! assert_eq!(words[1].start, Duration::from_secs(2));
+//! ```
+
+use crate::{
+    deck::{Deck, Page},
+    mark::{Directive, Mark},
+    text::{grapheme_len, plain_text},
+};
+use std::time::Duration;
+
+/// The options of [`teleprompter`].
+#[derive(Clone, Debug)]
+pub struct TeleprompterOptions {
+    /// The pace of speech, for pages without rehearsal timings. Defaults to 130.
+    pub words_per_minute: f32,
+    /// How long each page took in a rehearsal, by index. Pages after the end of the list are
+    /// timed from `words_per_minute`.
+    pub rehearsal: Vec<Duration>,
+}
+
+impl Default for TeleprompterOptions {
+    fn default() -> Self {
+        Self {
+            words_per_minute: 130.0,
+            rehearsal: vec![],
+        }
+    }
+}
+
+impl TeleprompterOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn with_words_per_minute(mut self, words_per_minute: f32) -> Self {
+        self.words_per_minute = words_per_minute;
+        self
+    }
+
+    #[inline]
+    pub fn with_rehearsal(mut self, rehearsal: Vec<Duration>) -> Self {
+        self.rehearsal = rehearsal;
+        self
+    }
+}
+
+/// A word of the prose of a deck.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Word {
+    /// The word, with the punctuation around it.
+    pub text: String,
+    /// The index of the page of the word.
+    pub page: usize,
+    /// The order of the transition the word is in.
+    pub step: usize,
+    /// When the word starts to be spoken, since the start of the deck.
+    pub start: Duration,
+    /// How long the word takes to be spoken, including the pause after it.
+    pub duration: Duration,
+}
+
+/// The words of the prose of `deck`, timed one after another.
+///
+/// Each word is weighted by its length and the pause its punctuation calls for, and the weights
+/// are spread over the time of its page: the rehearsal timing of the page if there is one, or
+/// else the time to speak its words at `words_per_minute`.
+pub fn teleprompter(deck: &Deck, options: &TeleprompterOptions) -> Vec<Word> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("teleprompter", pages = deck.pages().len()).entered();
+
+    let mut words = vec![];
+    let mut start = Duration::ZERO;
+    for (idx, page) in deck.pages().iter().enumerate() {
+        let page_words: Vec<(usize, String)> = prose(page)
+            .into_iter()
+            .flat_map(|(step, text)| {
+                text.split_whitespace()
+                    .map(|word| (step, word.to_owned()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let weights: Vec<f32> = page_words.iter().map(|(_, word)| weight(word)).collect();
+        let total_weight: f32 = weights.iter().sum();
+        let page_duration = match options.rehearsal.get(idx) {
+            Some(duration) => *duration,
+            None => Duration::from_secs_f32(
+                page_words.len() as f32 * 60.0 / options.words_per_minute.max(1.0),
+            ),
+        };
+        let page_start = start;
+        let mut elapsed = 0.0;
+        for ((step, text), weight) in page_words.into_iter().zip(weights) {
+            let word_start = page_start + page_duration.mul_f32(elapsed / total_weight);
+            elapsed += weight;
+            let word_end = page_start + page_duration.mul_f32(elapsed / total_weight);
+            words.push(Word {
+                text,
+                page: idx,
+                step,
+                start: word_start,
+                duration: word_end - word_start,
+            });
+        }
+        start = page_start + page_duration;
+    }
+    words
+}
+
+/// The prose of `page` with the order of the transition of each part: its speaker notes, or else
+/// its text.
+fn prose(page: &Page) -> Vec<(usize, String)> {
+    let notes: Vec<(usize, String)> = page
+        .directives()
+        .filter_map(|(order, directive)| match directive {
+            Directive::Note(note) => Some((order, note.clone())),
+            _ => None,
+        })
+        .collect();
+    if !notes.is_empty() {
+        return notes;
+    }
+    page.transitions
+        .iter()
+        .filter_map(|transition| match transition {
+            Mark::Transition(order, marks, _) => Some((*order, marks)),
+            _ => None,
+        })
+        .map(|(order, marks)| {
+            let prose: Vec<Mark> = marks
+                .iter()
+                .filter(|mark| match mark {
+                    Mark::Text(_, style) => !style.code,
+                    Mark::NewLine | Mark::Separator(..) => true,
+                    _ => false,
+                })
+                .cloned()
+                .collect();
+            (order, plain_text(&prose))
+        })
+        .collect()
+}
+
+/// The relative time to speak `word`: longer words take longer, and punctuation calls for a
+/// pause.
+fn weight(word: &str) -> f32 {
+    let pause = match word.trim_end_matches(['"', '\'', ')', ']']).chars().last() {
+        Some('.' | '!' | '?') => 1.0,
+        Some(',' | ';' | ':') => 0.5,
+        _ => 0.0,
+    };
+    1.0 + grapheme_len(word) as f32 / 10.0 + pause
+}
@@ -0,0 +1,364 @@
+//! Helpers to write regression tests against OpMark documents.
+//!
+//! Enabled by the `test-util` feature. Fixture decks are parsed into marks, which are written as
+//! a readable tree and compared against a snapshot file:
+//!
+//! ```no_run
+//! use opmark::test_util::{assert_snapshot, mark_tree, parse_fixture};
+//!
+//! let marks = parse_fixture("tests/fixtures/lists.opmark");
+//! assert_snapshot("tests/snapshots/lists.snap", &mark_tree(&marks));
+//! ```
+//!
+//! Missing snapshots are created, and outdated ones are overwritten when the
+//! `OPMARK_UPDATE_SNAPSHOTS` environment variable is set.
+
+use crate::{
+    mark::{
+        AlignHorizontal, Directive, Listing, Mark, SeparatorDir, SeparatorLine, SeparatorStyle,
+        SeparatorThickness, StyleGallery, StyleImage, StylePage, StyleText, StyleTransition,
+        TransitionEffect, TransitionTarget,
+    },
+    Parser,
+};
+use std::{env, fs, path::Path};
+
+/// The environment variable which makes [`assert_snapshot`] overwrite outdated snapshots.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "OPMARK_UPDATE_SNAPSHOTS";
+
+/// Read the OpMark document at `path` and parse it.
+///
+/// # Panics
+///
+/// Panics if the file can not be read.
+pub fn parse_fixture(path: impl AsRef<Path>) -> Vec<Mark> {
+    let path = path.as_ref();
+    let s = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read fixture {}: {}", path.display(), err));
+    Parser::new(s).collect()
+}
+
+/// Write `marks` as a tree, one mark per line, with the children of pages and transitions
+/// indented below them. Only the style fields which are set are written.
+///
+/// ```
+/// use opmark::{mark::Mark, test_util::mark_tree, Parser};
+///
+/// let marks: Vec<Mark> = Parser::new("## Title\n*bold*".to_owned()).collect();
+/// assert_eq!(
+///     mark_tree(&marks),
+///     "Page\nTransition 0\nText \"Title\" heading=2\nText \"bold\" bold\n"
+/// );
+/// ```
+pub fn mark_tree(marks: &[Mark]) -> String {
+    let mut tree = String::new();
+    write_marks(&mut tree, marks, 0);
+    tree
+}
+
+/// Compare `actual` with the content of the snapshot file at `path`.
+///
+/// If the snapshot does not exist yet, or [`UPDATE_SNAPSHOTS_VAR`] is set, the snapshot is written
+/// instead.
+///
+/// # Panics
+///
+/// Panics if `actual` differs from the snapshot, or the snapshot can not be read or written.
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if path.exists() && env::var_os(UPDATE_SNAPSHOTS_VAR).is_none() {
+        let expected = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("failed to read snapshot {}: {}", path.display(), err));
+        if expected != actual {
+            panic!(
+                "snapshot {} does not match, set {} to update it\n--- expected\n{}\n+++ actual\n{}",
+                path.display(),
+                UPDATE_SNAPSHOTS_VAR,
+                expected,
+                actual
+            );
+        }
+        return;
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|err| {
+            panic!("failed to create snapshot dir {}: {}", dir.display(), err)
+        });
+    }
+    fs::write(path, actual)
+        .unwrap_or_else(|err| panic!("failed to write snapshot {}: {}", path.display(), err));
+}
+
+fn write_marks(tree: &mut String, marks: &[Mark], depth: usize) {
+    for mark in marks {
+        tree.push_str(&"  ".repeat(depth));
+        let children = match mark {
+            Mark::CodeBlock(code, language) => {
+                tree.push_str(&format!("CodeBlock {:?}", code));
+                if let Some(language) = language {
+                    tree.push_str(&format!(" language={}", language));
+                }
+                None
+            }
+            Mark::CodeWalkthrough(code, language, steps) => {
+                tree.push_str(&format!("CodeWalkthrough {:?}", code));
+                if let Some(language) = language {
+                    tree.push_str(&format!(" language={}", language));
+                }
+                tree.push_str(&format!(" steps={:?}", steps));
+                None
+            }
+            Mark::Directive(directive) => {
+                tree.push_str(&format!(
+                    "Directive {} {:?}",
+                    directive.name(),
+                    directive.argument()
+                ));
+                if let Directive::Comment(_, text) = directive {
+                    tree.push_str(&format!(" text={:?}", text));
+                }
+                None
+            }
+            Mark::Embed(url, provider) => {
+                tree.push_str(&format!("Embed {:?} {:?}", url, provider));
+                None
+            }
+            Mark::Gallery(items, style) => {
+                tree.push_str("Gallery");
+                write_attrs(tree, &gallery_attrs(style));
+                for (src, title) in items {
+                    tree.push('\n');
+                    tree.push_str(&"  ".repeat(depth + 1));
+                    tree.push_str(&format!("Item {:?} src={:?}", title, src));
+                }
+                None
+            }
+            Mark::Image(src, title, style) => {
+                tree.push_str(&format!("Image {:?} src={:?}", title, src));
+                write_attrs(tree, &image_attrs(style));
+                None
+            }
+            Mark::Math(math, mode) => {
+                tree.push_str(&format!("Math {:?} {:?}", mode, math));
+                None
+            }
+            Mark::NewLine => {
+                tree.push_str("NewLine");
+                None
+            }
+            Mark::Page(marks, style) => {
+                tree.push_str("Page");
+                write_attrs(tree, &page_attrs(style));
+                Some(marks)
+            }
+            Mark::Separator(dir, style) => {
+                tree.push_str(match dir {
+                    SeparatorDir::Horizontal => "Separator horizontal",
+                    SeparatorDir::Vertical => "Separator vertical",
+                });
+                write_attrs(tree, &separator_attrs(style));
+                None
+            }
+            Mark::Space(lines) => {
+                tree.push_str(&format!("Space {}", lines));
+                None
+            }
+            Mark::HoldSlide(start, message) => {
+                tree.push_str(&format!("HoldSlide {:?} {:?}", start, message));
+                None
+            }
+            Mark::Text(text, style) => {
+                tree.push_str(&format!("Text {:?}", text));
+                write_attrs(tree, &text_attrs(style));
+                None
+            }
+            Mark::Transition(order, marks, style) => {
+                tree.push_str(&format!("Transition {}", order));
+                write_attrs(tree, &transition_attrs(style));
+                Some(marks)
+            }
+            Mark::TransitionEnd => {
+                tree.push_str("TransitionEnd");
+                None
+            }
+        };
+        tree.push('\n');
+        if let Some(children) = children {
+            write_marks(tree, children, depth + 1);
+        }
+    }
+}
+
+fn write_attrs(tree: &mut String, attrs: &[String]) {
+    for attr in attrs {
+        tree.push(' ');
+        tree.push_str(attr);
+    }
+}
+
+fn gallery_attrs(style: &StyleGallery) -> Vec<String> {
+    let mut attrs = vec![];
+    if !style.classes.is_empty() {
+        attrs.push(format!("classes={:?}", style.classes));
+    }
+    if let Some(columns) = style.columns {
+        attrs.push(format!("columns={}", columns));
+    }
+    if let Some(rows) = style.rows {
+        attrs.push(format!("rows={}", rows));
+    }
+    attrs
+}
+
+fn image_attrs(style: &StyleImage) -> Vec<String> {
+    let mut attrs = vec![];
+    match style.align_h {
+        AlignHorizontal::Auto => {}
+        AlignHorizontal::Left => attrs.push("left".to_owned()),
+        AlignHorizontal::Right => attrs.push("right".to_owned()),
+        AlignHorizontal::Center => attrs.push("center".to_owned()),
+    }
+    if !style.classes.is_empty() {
+        attrs.push(format!("classes={:?}", style.classes));
+    }
+    if !style.hyperlink.is_empty() {
+        attrs.push(format!("hyperlink={:?}", style.hyperlink));
+    }
+    if style.autoplay {
+        attrs.push("autoplay".to_owned());
+    }
+    if style.looping {
+        attrs.push("loop".to_owned());
+    }
+    if let Some(fps) = style.fps {
+        attrs.push(format!("fps={}", fps));
+    }
+    if let Some(frame) = style.pause_at {
+        attrs.push(format!("pause-at={}", frame));
+    }
+    if let Some(width) = style.width {
+        attrs.push(format!("width={}", width));
+    }
+    if let Some(height) = style.height {
+        attrs.push(format!("height={}", height));
+    }
+    attrs
+}
+
+fn page_attrs(style: &StylePage) -> Vec<String> {
+    let mut attrs = vec![];
+    if !style.classes.is_empty() {
+        attrs.push(format!("classes={:?}", style.classes));
+    }
+    if style.reset_on_enter {
+        attrs.push("reset-on-enter".to_owned());
+    }
+    if let Some(src) = &style.narration {
+        attrs.push(format!("narration={:?}", src));
+    }
+    if style.justify {
+        attrs.push("justify".to_owned());
+    }
+    if style.hyphenate {
+        attrs.push("hyphenate".to_owned());
+    }
+    if style.hidden {
+        attrs.push("hidden".to_owned());
+    }
+    if let Some(duration) = style.duration {
+        attrs.push(format!("duration={:?}", duration));
+    }
+    if !style.audiences.is_empty() {
+        attrs.push(format!("audiences={:?}", style.audiences));
+    }
+    if let Some(at) = style.at {
+        attrs.push(format!("at={:?}", at));
+    }
+    if style.no_split {
+        attrs.push("no-split".to_owned());
+    }
+    attrs
+}
+
+fn separator_attrs(style: &SeparatorStyle) -> Vec<String> {
+    let mut attrs = vec![];
+    match style.line {
+        SeparatorLine::Solid => {}
+        SeparatorLine::Dotted => attrs.push("dotted".to_owned()),
+        SeparatorLine::Dashed => attrs.push("dashed".to_owned()),
+    }
+    if let Some(length) = style.length {
+        attrs.push(format!("length={}%", length));
+    }
+    match style.thickness {
+        SeparatorThickness::Thin => attrs.push("thin".to_owned()),
+        SeparatorThickness::Normal => {}
+        SeparatorThickness::Thick => attrs.push("thick".to_owned()),
+    }
+    attrs
+}
+
+fn text_attrs(style: &StyleText) -> Vec<String> {
+    let mut attrs = vec![];
+    if style.bold {
+        attrs.push("bold".to_owned());
+    }
+    if !style.classes.is_empty() {
+        attrs.push(format!("classes={:?}", style.classes));
+    }
+    if style.code {
+        attrs.push("code".to_owned());
+    }
+    if style.heading.to_int() > 0 {
+        attrs.push(format!("heading={}", style.heading.to_int()));
+    }
+    if !style.hyperlink.is_empty() {
+        attrs.push(format!("hyperlink={:?}", style.hyperlink));
+    }
+    if style.italics {
+        attrs.push("italics".to_owned());
+    }
+    if let Some(title) = &style.link_title {
+        attrs.push(format!("link_title={:?}", title));
+    }
+    match &style.listing {
+        Listing::None => {}
+        Listing::Ordered(number, indent) => {
+            attrs.push(format!("ordered={} indent={}", number, indent.to_int()))
+        }
+        Listing::Unordered(indent) => attrs.push(format!("unordered indent={}", indent.to_int())),
+    }
+    if style.quote {
+        attrs.push("quote".to_owned());
+    }
+    if style.small {
+        attrs.push("small".to_owned());
+    }
+    if style.strikethrough {
+        attrs.push("strikethrough".to_owned());
+    }
+    if style.underline {
+        attrs.push("underline".to_owned());
+    }
+    attrs
+}
+
+fn transition_attrs(style: &StyleTransition) -> Vec<String> {
+    let mut attrs = vec![];
+    let effect = match style.effect {
+        TransitionEffect::Appear => "appear",
+        TransitionEffect::Disappear => "disappear",
+        TransitionEffect::Dim => "dim",
+        TransitionEffect::Replace => "replace",
+    };
+    match style.target {
+        TransitionTarget::This if style.effect == TransitionEffect::Appear => {}
+        TransitionTarget::This => attrs.push(effect.to_owned()),
+        TransitionTarget::Prev => attrs.push(format!("{}:prev", effect)),
+        TransitionTarget::Order(order) => attrs.push(format!("{}:{}", effect, order)),
+    }
+    if style.looping {
+        attrs.push("loop".to_owned());
+    }
+    attrs
+}
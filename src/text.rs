@@ -0,0 +1,216 @@
+//! Helpers for working with the text of marks.
+
+use crate::{
+    deck::{Deck, Page},
+    mark::{Listing, Mark, MathMode, StyleText},
+    parser::{format_time_of_day, LINE_ENDING},
+    render::page_marks,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The string appended to text which has been truncated.
+pub const ELLIPSIS: &str = "…";
+
+/// Count the grapheme clusters in `s`.
+#[inline]
+pub fn grapheme_len(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// Shorten `s` to at most `max_graphemes` grapheme clusters.
+///
+/// If `s` has to be shortened, the result ends with [`ELLIPSIS`], which counts as one grapheme.
+/// Emoji sequences and combining characters are never split.
+///
+/// ```
+/// use opmark::text::truncate;
+///
+/// assert_eq!(truncate("Hello OpMark", 6), "Hello…");
+/// assert_eq!(truncate("👩‍👩‍👧 family", 2), "👩‍👩‍👧…");
+/// ```
+pub fn truncate(s: &str, max_graphemes: usize) -> String {
+    if max_graphemes == 0 {
+        return String::new();
+    }
+    let mut starts = s.grapheme_indices(true).map(|(idx, _)| idx);
+    match starts.nth(max_graphemes - 1) {
+        Some(end) if starts.next().is_some() => format!("{}{}", s[..end].trim_end(), ELLIPSIS),
+        _ => s.to_owned(),
+    }
+}
+
+/// The text of `marks`, with blocks separated by spaces, to be searched or summarized.
+///
+/// ```
+/// use opmark::{text::plain_text, Parser};
+///
+/// let marks: Vec<_> = Parser::new("# Title\nSome *bold* text".to_owned()).collect();
+/// assert_eq!(plain_text(&marks), "Title Some bold text");
+/// ```
+pub fn plain_text(marks: &[Mark]) -> String {
+    let mut text = String::new();
+    for mark in marks {
+        let (s, is_block) = match mark {
+            Mark::CodeBlock(code, _) | Mark::CodeWalkthrough(code, ..) => (code.as_str(), true),
+            Mark::Image(_, title, _) | Mark::HoldSlide(_, title) => (title.as_str(), true),
+            Mark::Math(math, mode) => (math.as_str(), *mode == MathMode::Display),
+            Mark::Text(s, style) => (
+                s.as_str(),
+                style.heading.to_int() > 0
+                    || style.quote
+                    || !matches!(style.listing, Listing::None),
+            ),
+            Mark::NewLine | Mark::Separator(..) | Mark::Space(_) => ("", true),
+            _ => ("", false),
+        };
+        if is_block && !text.is_empty() && !text.ends_with(' ') {
+            text.push(' ');
+        }
+        text.push_str(s);
+        if is_block && !s.is_empty() {
+            text.push(' ');
+        }
+    }
+    text.trim_end().to_owned()
+}
+
+/// Shorten a sequence of marks to at most `max_graphemes` grapheme clusters of text.
+///
+/// Only `Mark::Text` elements count towards the limit. The text element where the limit is
+/// reached is shortened with [`truncate`], and all marks after it are dropped.
+pub fn truncate_marks(marks: &[Mark], max_graphemes: usize) -> Vec<Mark> {
+    let total = marks
+        .iter()
+        .map(|mark| match mark {
+            Mark::Text(text, _) => grapheme_len(text),
+            _ => 0,
+        })
+        .sum::<usize>();
+    if total <= max_graphemes {
+        return marks.to_vec();
+    }
+
+    let mut remaining = max_graphemes;
+    let mut truncated = vec![];
+    for mark in marks {
+        match mark {
+            Mark::Text(text, style) => {
+                let len = grapheme_len(text);
+                if len < remaining {
+                    remaining -= len;
+                    truncated.push(mark.clone());
+                } else {
+                    let text = truncate(&format!("{}{}", text, ELLIPSIS), remaining);
+                    if !text.is_empty() {
+                        truncated.push(Mark::Text(text, style.clone()));
+                    }
+                    break;
+                }
+            }
+            _ => truncated.push(mark.clone()),
+        }
+    }
+    truncated
+}
+
+/// The text of the pages of `deck` for screen readers, see [`Deck::to_accessible_text`].
+pub(crate) fn accessible_text(deck: &Deck) -> String {
+    let pages: Vec<&Page> = deck
+        .pages()
+        .iter()
+        .filter(|page| !page.style.hidden)
+        .collect();
+    let mut lines = vec![];
+    for (idx, page) in pages.iter().enumerate() {
+        if idx > 0 {
+            lines.push(String::new());
+        }
+        lines.push(format!("Page {} of {}", idx + 1, pages.len()));
+        let mut paragraph = String::new();
+        for mark in page_marks(page) {
+            if let Mark::Text(text, style) = &mark {
+                if style.heading.to_int() == 0
+                    && !style.quote
+                    && matches!(style.listing, Listing::None)
+                {
+                    paragraph.push_str(text);
+                    if !style.hyperlink.is_empty() && style.hyperlink != *text {
+                        paragraph.push_str(&format!(" (link to {})", style.hyperlink));
+                    }
+                    continue;
+                }
+            }
+            // math is read as its TeX source
+            if let Mark::Math(math, MathMode::Inline) = &mark {
+                paragraph.push_str(math);
+                continue;
+            }
+            if !paragraph.trim().is_empty() {
+                lines.push(paragraph.trim().to_owned());
+            }
+            paragraph.clear();
+            match mark {
+                Mark::Text(text, style) => lines.push(accessible_block(&text, &style)),
+                Mark::CodeBlock(code, language) | Mark::CodeWalkthrough(code, language, _) => {
+                    lines.push(match language {
+                        Some(language) => format!("Code block in {}:", language),
+                        None => "Code block:".to_owned(),
+                    });
+                    lines.extend(code.lines().map(str::to_owned));
+                    lines.push("End of code block.".to_owned());
+                }
+                Mark::Embed(url, _) => lines.push(format!("Embedded content: {}", url)),
+                Mark::Math(math, _) => lines.push(format!("Math: {}", math.trim())),
+                Mark::HoldSlide(start, message) => {
+                    if !message.trim().is_empty() {
+                        lines.push(message.trim().to_owned());
+                    }
+                    lines.push(format!("Starts at {}.", format_time_of_day(start)));
+                }
+                Mark::Gallery(items, _) => {
+                    let titles: Vec<&str> = items
+                        .iter()
+                        .map(|(_, title)| match title.trim() {
+                            "" => "no description",
+                            title => title,
+                        })
+                        .collect();
+                    lines.push(format!(
+                        "Gallery of {} images: {}.",
+                        items.len(),
+                        titles.join("; ")
+                    ));
+                }
+                Mark::Image(_, title, _) if title.trim().is_empty() => {
+                    lines.push("Image without description.".to_owned())
+                }
+                Mark::Image(_, title, _) => lines.push(format!("Image: {}", title.trim())),
+                _ => {}
+            }
+        }
+        if !paragraph.trim().is_empty() {
+            lines.push(paragraph.trim().to_owned());
+        }
+    }
+    lines.join(LINE_ENDING)
+}
+
+/// The line of a heading, a quote or a list item, announcing what it is.
+fn accessible_block(text: &str, style: &StyleText) -> String {
+    let heading = style.heading.to_int();
+    if heading > 0 {
+        return format!("Heading level {}: {}", heading, text);
+    }
+    if style.quote {
+        return format!("Quote: {}", text);
+    }
+    let (item, indent) = match &style.listing {
+        Listing::Ordered(number, indent) => (format!("Item {}", number), indent),
+        Listing::Unordered(indent) => ("Bullet".to_owned(), indent),
+        Listing::None => return text.to_owned(),
+    };
+    match indent.to_int() {
+        0 => format!("{}: {}", item, text),
+        level => format!("{}, level {}: {}", item, level + 1, text),
+    }
+}
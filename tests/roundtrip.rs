@@ -0,0 +1,592 @@
+//! Round-trips generated marks through the serializer and the parser.
+//!
+//! The generated marks are the ones the parser could have yielded, so parsing their serialized
+//! text must give them back, up to the differences documented on `to_source`.
+
+use opmark::{
+    grammar::Markers,
+    mark::{
+        AlignHorizontal, CodeStep, ConflictMarker, Directive, Heading, IndentLevel, Listing, Mark,
+        MathMode, SeparatorDir, SeparatorLine, SeparatorStyle, SeparatorThickness, StyleGallery,
+        StyleImage, StylePage, StyleText, StyleTransition, TransitionEffect, TransitionTarget,
+    },
+    serializer::{format, format_with_options, to_source, to_source_with_markers, FormatOptions},
+    Parser, ParserOptions,
+};
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestRunner},
+};
+use std::time::Duration;
+
+/// An element of a generated document.
+#[derive(Clone, Debug)]
+enum Item {
+    Directive(Directive),
+    CodeBlock(Vec<String>, Option<String>),
+    CodeWalkthrough(Vec<String>, Option<String>, Vec<CodeStep>),
+    /// The lines of the math.
+    DisplayMath(Vec<String>),
+    Gallery(Vec<(String, String)>, StyleGallery),
+    Heading(u8, String),
+    /// The start in seconds since midnight, and the message.
+    HoldSlide(u64, String),
+    Image(String, String, StyleImage),
+    /// Inline elements, with the classes applied from the given element to the end of the line.
+    Inline(Vec<Inline>, Option<(usize, Vec<String>)>),
+    NewLine,
+    Ordered(u8, String),
+    Page(StylePage),
+    Quote(String),
+    Separator(SeparatorDir, SeparatorStyle),
+    Space(u8),
+    Transition(Option<usize>, StyleTransition),
+    TransitionEnd,
+    Unordered(u8, String),
+}
+
+#[derive(Clone, Debug)]
+enum Inline {
+    Code(String),
+    Hyperlink(String, String, Option<String>),
+    Math(String),
+    Plain(String, [bool; 5]),
+}
+
+fn raw_text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[ -~]{1,12}",
+        "[^\r\n]{1,6}",
+        // text which looks like the start of a block element
+        " {0,2}(#{1,3} |> |- |[0-9]{1,2}\\. |---|t---|!)[ -~]{0,6}",
+        " {0,2}@(bookmark|note|narration)\\([a-z ]{0,6}\\)",
+    ]
+}
+
+fn classes() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec("[a-z][a-z0-9-]{0,5}", 1..3)
+}
+
+fn inline() -> impl Strategy<Value = Inline> {
+    prop_oneof![
+        3 => (raw_text(), prop::array::uniform5(prop::bool::weighted(0.2)))
+            .prop_map(|(text, toggles)| Inline::Plain(text, toggles)),
+        1 => "[^`\r\n]{1,8}".prop_map(Inline::Code),
+        1 => (
+            "[a-z ]{1,8}",
+            "https?://[a-z./]{1,10}",
+            prop::option::of("[a-z][a-z ]{0,7}"),
+        )
+            .prop_map(|(title, url, link_title)| Inline::Hyperlink(title, url, link_title)),
+        1 => "[a-z0-9^_{}+=\\\\][a-z0-9^_{}+= ]{0,9}".prop_map(Inline::Math),
+    ]
+}
+
+fn style_gallery() -> impl Strategy<Value = StyleGallery> {
+    (
+        prop::option::of(classes()),
+        prop::option::of(0..10usize),
+        prop::option::of(0..10usize),
+    )
+        .prop_map(|(classes, columns, rows)| {
+            let mut style = StyleGallery::new();
+            for class in classes.unwrap_or_default() {
+                style = style.with_class(class);
+            }
+            if let Some(columns) = columns {
+                style = style.with_columns(columns);
+            }
+            if let Some(rows) = rows {
+                style = style.with_rows(rows);
+            }
+            style
+        })
+}
+
+fn style_separator() -> impl Strategy<Value = SeparatorStyle> {
+    (
+        prop_oneof![
+            Just(SeparatorLine::Solid),
+            Just(SeparatorLine::Dotted),
+            Just(SeparatorLine::Dashed),
+        ],
+        prop::option::of(1..=100u8),
+        prop_oneof![
+            Just(SeparatorThickness::Thin),
+            Just(SeparatorThickness::Normal),
+            Just(SeparatorThickness::Thick),
+        ],
+    )
+        .prop_map(|(line, length, thickness)| {
+            let style = SeparatorStyle::new()
+                .with_line(line)
+                .with_thickness(thickness);
+            match length {
+                Some(length) => style.with_length(length as f32),
+                None => style,
+            }
+        })
+}
+
+fn style_image() -> impl Strategy<Value = StyleImage> {
+    (
+        0..4u8,
+        prop::option::of(classes()),
+        prop::option::of("https://[a-z]{1,8}"),
+        prop::option::of(1..2000u16),
+        prop::option::of(1..2000u16),
+        (
+            any::<bool>(),
+            any::<bool>(),
+            prop::option::of(1..60u8),
+            prop::option::of(0..100usize),
+        ),
+    )
+        .prop_map(|(align, classes, hyperlink, width, height, animation)| {
+            let mut style = StyleImage::new().with_align_h(match align {
+                0 => AlignHorizontal::Auto,
+                1 => AlignHorizontal::Left,
+                2 => AlignHorizontal::Right,
+                _ => AlignHorizontal::Center,
+            });
+            for class in classes.unwrap_or_default() {
+                style = style.with_class(class);
+            }
+            if let Some(hyperlink) = hyperlink {
+                style = style.with_hyperlink(hyperlink);
+            }
+            if let Some(width) = width {
+                style = style.with_width(width as f32);
+            }
+            if let Some(height) = height {
+                style = style.with_height(height as f32);
+            }
+            let (autoplay, looping, fps, pause_at) = animation;
+            if autoplay {
+                style = style.with_autoplay();
+            }
+            if looping {
+                style = style.with_loop();
+            }
+            if let Some(fps) = fps {
+                style = style.with_fps(fps as f32);
+            }
+            if let Some(frame) = pause_at {
+                style = style.with_pause_at(frame);
+            }
+            style
+        })
+}
+
+fn style_page() -> impl Strategy<Value = StylePage> {
+    (
+        prop::option::of(classes()),
+        any::<bool>(),
+        prop::option::of("[a-z]{1,6}/[a-z0-9]{1,6}\\.ogg"),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        prop::option::of(0..10_000u64),
+        prop::collection::vec("[a-z][a-z-]{0,6}", 0..3),
+        prop::option::of(0..86_400u64),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(
+                classes,
+                reset_on_enter,
+                narration,
+                justify,
+                hyphenate,
+                hidden,
+                duration,
+                audiences,
+                at,
+                no_split,
+            )| {
+                let mut style = StylePage::new();
+                for class in classes.unwrap_or_default() {
+                    style = style.with_class(class);
+                }
+                if reset_on_enter {
+                    style = style.with_reset_on_enter();
+                }
+                if let Some(src) = narration {
+                    style = style.with_narration(src);
+                }
+                if justify {
+                    style = style.with_justify();
+                }
+                if hyphenate {
+                    style = style.with_hyphenate();
+                }
+                if hidden {
+                    style = style.with_hidden();
+                }
+                if let Some(secs) = duration {
+                    style = style.with_duration(Duration::from_secs(secs));
+                }
+                for audience in audiences {
+                    style = style.with_audience(audience);
+                }
+                if let Some(secs) = at {
+                    style = style.with_at(Duration::from_secs(secs));
+                }
+                if no_split {
+                    style = style.with_no_split();
+                }
+                style
+            },
+        )
+}
+
+fn style_transition() -> impl Strategy<Value = StyleTransition> {
+    let effect = prop_oneof![
+        Just(TransitionEffect::Appear),
+        Just(TransitionEffect::Disappear),
+        Just(TransitionEffect::Dim),
+        Just(TransitionEffect::Replace),
+    ];
+    let target = prop_oneof![
+        Just(TransitionTarget::This),
+        Just(TransitionTarget::Prev),
+        (0..8usize).prop_map(TransitionTarget::Order),
+    ];
+    (effect, target, any::<bool>()).prop_map(|(effect, target, looping)| {
+        // a replacement of the transition itself is parsed as a replacement of the previous one
+        let target = match (&effect, target) {
+            (TransitionEffect::Replace, TransitionTarget::This) => TransitionTarget::Prev,
+            (_, target) => target,
+        };
+        let style = StyleTransition::new().with_effect(effect, target);
+        if looping {
+            style.with_loop()
+        } else {
+            style
+        }
+    })
+}
+
+fn code_step() -> impl Strategy<Value = CodeStep> {
+    prop_oneof![
+        Just(CodeStep::All),
+        (1..10usize, 0..3usize).prop_map(|(first, len)| CodeStep::Lines(first, first + len)),
+    ]
+}
+
+fn item() -> impl Strategy<Value = Item> {
+    prop_oneof![
+        1 => prop_oneof![
+            "[a-z][a-z -]{0,8}[a-z]".prop_map(Directive::Bookmark),
+            "[a-z]{1,6}/[a-z0-9]{1,6}\\.ogg".prop_map(Directive::Narration),
+            "#[a-z][a-z -]{0,8}[a-z]".prop_map(Directive::See),
+            Just(Directive::Appendix),
+            Just(Directive::PageBreak),
+            prop_oneof![
+                Just(ConflictMarker::Ours),
+                Just(ConflictMarker::Theirs),
+                Just(ConflictMarker::End),
+            ]
+            .prop_map(Directive::Conflict),
+            ("[a-z][a-z ]{0,6}[a-z]", "[!-~][^\r\n]{0,10}[!-~]")
+                .prop_map(|(author, text)| Directive::Comment(author, text)),
+            "[!-'*-~][^()\r\n]{0,10}[!-'*-~]".prop_map(Directive::Note),
+            "(fig|tbl|code):[a-z][a-z0-9-]{0,8}".prop_map(Directive::Label),
+            "[a-z]{1,8}(, [a-z]{1,8})?".prop_map(Directive::Audience),
+        ]
+        .prop_map(Item::Directive),
+        1 => (
+            prop::collection::vec("[^`\r\n][^\r\n]{0,10}|", 0..3),
+            prop::option::of("[a-z]{1,6}"),
+        )
+            .prop_map(|(code, language)| Item::CodeBlock(code, language)),
+        1 => (
+            prop::collection::vec("[^`\r\n][^\r\n]{0,10}|", 0..3),
+            prop::option::of("[a-z]{1,6}"),
+            prop::collection::vec(code_step(), 1..4),
+        )
+            .prop_map(|(code, language, steps)| Item::CodeWalkthrough(code, language, steps)),
+        1 => prop::collection::vec("[a-z0-9^_{}+=\\\\][a-z0-9^_{}+=\\\\ ]{0,9}", 1..3)
+            .prop_map(Item::DisplayMath),
+        1 => (
+            prop::collection::vec(("[a-z]{1,8}\\.png", "[a-z ]{0,8}"), 0..4),
+            style_gallery(),
+        )
+            .prop_map(|(items, style)| Item::Gallery(items, style)),
+        1 => (1..=8u8, raw_text()).prop_map(|(level, text)| Item::Heading(level, text)),
+        1 => (0..86400u64, "[a-z \",\\\\()=]{0,12}")
+            .prop_map(|(start, message)| Item::HoldSlide(start, message)),
+        1 => ("[a-z]{1,8}\\.png", "[a-z ]{0,8}", style_image())
+            .prop_map(|(src, title, style)| Item::Image(src, title, style)),
+        6 => (
+            prop::collection::vec(inline(), 1..5),
+            prop::option::of((0..5usize, classes())),
+        )
+            .prop_map(|(inlines, classes)| Item::Inline(inlines, classes)),
+        1 => Just(Item::NewLine),
+        1 => (0..=8u8, raw_text()).prop_map(|(indent, text)| Item::Ordered(indent, text)),
+        1 => style_page().prop_map(Item::Page),
+        1 => raw_text().prop_map(Item::Quote),
+        1 => (
+            prop_oneof![
+                Just(SeparatorDir::Horizontal),
+                Just(SeparatorDir::Vertical),
+            ],
+            style_separator(),
+        )
+            .prop_map(|(dir, style)| Item::Separator(dir, style)),
+        1 => (0..8u8).prop_map(Item::Space),
+        1 => (prop::option::of(0..8usize), style_transition())
+            .prop_map(|(order, style)| Item::Transition(order, style)),
+        1 => Just(Item::TransitionEnd),
+        1 => (0..=8u8, raw_text()).prop_map(|(indent, text)| Item::Unordered(indent, text)),
+    ]
+}
+
+/// Build the flat marks the parser yields for `items`.
+fn marks(items: &[Item]) -> Vec<Mark> {
+    let mut marks = vec![
+        Mark::Page(vec![], StylePage::new()),
+        Mark::Transition(0, vec![], StyleTransition::new()),
+    ];
+    let mut transition_order = 1;
+    for item in items {
+        match item {
+            Item::Directive(directive) => marks.push(Mark::Directive(directive.clone())),
+            Item::CodeBlock(code, language) => {
+                marks.push(Mark::CodeBlock(code.join("\n"), language.clone()));
+            }
+            Item::CodeWalkthrough(code, language, steps) => {
+                marks.push(Mark::CodeWalkthrough(
+                    code.join("\n"),
+                    language.clone(),
+                    steps.clone(),
+                ));
+                transition_order += steps.len() - 1;
+            }
+            Item::Gallery(items, style) => {
+                marks.push(Mark::Gallery(items.clone(), style.clone()));
+            }
+            Item::Heading(level, text) => marks.push(Mark::Text(
+                text.clone(),
+                StyleText::new().with_heading(Heading::from(*level)),
+            )),
+            Item::Image(src, title, style) => {
+                marks.push(Mark::Image(src.clone(), title.clone(), style.clone()));
+            }
+            Item::Inline(inlines, classes) => {
+                for (idx, inline) in inlines.iter().enumerate() {
+                    let (text, mut style) = match inline {
+                        Inline::Code(text) => (text, StyleText::new().with_code()),
+                        Inline::Hyperlink(title, url, link_title) => {
+                            let style = StyleText::new().with_hyperlink(url.clone());
+                            match link_title {
+                                Some(link_title) => {
+                                    (title, style.with_link_title(link_title.clone()))
+                                }
+                                None => (title, style),
+                            }
+                        }
+                        Inline::Math(math) => {
+                            marks.push(Mark::Math(math.clone(), MathMode::Inline));
+                            continue;
+                        }
+                        Inline::Plain(text, toggles) => (
+                            text,
+                            StyleText {
+                                bold: toggles[0],
+                                italics: toggles[1],
+                                small: toggles[2],
+                                strikethrough: toggles[3],
+                                underline: toggles[4],
+                                ..StyleText::new()
+                            },
+                        ),
+                    };
+                    if let (Inline::Plain(..), Some((from, classes))) = (inline, classes) {
+                        if idx >= *from {
+                            style.classes = classes.clone();
+                        }
+                    }
+                    marks.push(Mark::Text(text.clone(), style));
+                }
+            }
+            Item::DisplayMath(lines) => {
+                marks.push(Mark::Math(lines.join("\n"), MathMode::Display));
+            }
+            Item::HoldSlide(start, message) => marks.push(Mark::HoldSlide(
+                Duration::from_secs(*start),
+                message.clone(),
+            )),
+            Item::NewLine => marks.push(Mark::NewLine),
+            Item::Ordered(indent, text) => marks.push(Mark::Text(
+                text.clone(),
+                StyleText::new().with_listing(Listing::Ordered(0, IndentLevel::from(*indent))),
+            )),
+            Item::Page(style) => {
+                marks.push(Mark::Page(vec![], style.clone()));
+                marks.push(Mark::Transition(0, vec![], StyleTransition::new()));
+                transition_order = 1;
+            }
+            Item::Quote(text) => {
+                marks.push(Mark::Text(text.clone(), StyleText::new().with_quote()));
+            }
+            Item::Separator(dir, style) => marks.push(Mark::Separator(dir.clone(), style.clone())),
+            Item::Space(lines) => marks.push(Mark::Space(f32::from(*lines) / 2.0)),
+            Item::Transition(order, style) => {
+                let order = order.unwrap_or(transition_order);
+                marks.push(Mark::Transition(order, vec![], style.clone()));
+                transition_order = order + 1;
+            }
+            Item::TransitionEnd => marks.push(Mark::TransitionEnd),
+            Item::Unordered(indent, text) => marks.push(Mark::Text(
+                text.clone(),
+                StyleText::new().with_listing(Listing::Unordered(IndentLevel::from(*indent))),
+            )),
+        }
+    }
+    marks
+}
+
+/// Merge adjacent text elements with the same style, and drop the numbers of ordered lists
+/// which the parser computes itself.
+fn normalize(marks: Vec<Mark>) -> Vec<String> {
+    let mut normalized: Vec<Mark> = vec![];
+    for mark in marks {
+        let mark = match mark {
+            Mark::Text(text, mut style) => {
+                if let Listing::Ordered(_, indent) = style.listing {
+                    style.listing = Listing::Ordered(0, indent);
+                }
+                Mark::Text(text, style)
+            }
+            mark => mark,
+        };
+        if let (Some(Mark::Text(prev, prev_style)), Mark::Text(text, style)) =
+            (normalized.last_mut(), &mark)
+        {
+            let is_block = prev_style.heading.to_int() > 0
+                || !matches!(prev_style.listing, Listing::None)
+                || prev_style.quote;
+            if !is_block && prev_style == style {
+                prev.push_str(text);
+                continue;
+            }
+        }
+        normalized.push(mark);
+    }
+    normalized
+        .iter()
+        .map(|mark| format!("{:?}", mark))
+        .collect()
+}
+
+fn assert_roundtrip(marks: Vec<Mark>) {
+    let source = to_source(&marks);
+    let parsed: Vec<Mark> = Parser::new(source.clone()).collect();
+    assert_eq!(normalize(parsed), normalize(marks), "source: {:?}", source);
+}
+
+fn assert_roundtrip_with_markers(marks: Vec<Mark>, markers: &Markers) {
+    let source = to_source_with_markers(&marks, markers);
+    let options = ParserOptions::new().with_markers(markers.clone());
+    let parsed: Vec<Mark> = Parser::with_options(source.clone(), options).collect();
+    assert_eq!(normalize(parsed), normalize(marks), "source: {:?}", source);
+}
+
+fn runner() -> TestRunner {
+    TestRunner::new_with_rng(
+        Config {
+            cases: 512,
+            failure_persistence: None,
+            ..Config::default()
+        },
+        TestRunner::deterministic().new_rng(),
+    )
+}
+
+#[test]
+fn roundtrip_generated() {
+    runner()
+        .run(&prop::collection::vec(item(), 0..12), |items| {
+            assert_roundtrip(marks(&items));
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn roundtrip_generated_with_markdown_markers() {
+    let markers = Markers::markdown();
+    runner()
+        .run(&prop::collection::vec(item(), 0..12), |items| {
+            assert_roundtrip_with_markers(marks(&items), &markers);
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn roundtrip_block_marks_split_across_text() {
+    let plain = |text: &str| Inline::Plain(text.to_owned(), [false; 5]);
+    for inlines in [
+        vec![plain("1"), plain(". one")],
+        vec![plain("  "), plain("- two")],
+        vec![
+            plain("!"),
+            Inline::Hyperlink("three".to_owned(), "four".to_owned(), None),
+        ],
+    ] {
+        assert_roundtrip(marks(&[Item::Inline(inlines, None)]));
+    }
+}
+
+#[test]
+fn serialized_source_is_stable() {
+    runner()
+        .run(&prop::collection::vec(item(), 0..12), |items| {
+            let source = format(&to_source(&marks(&items)));
+            assert_eq!(format(&source), source);
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn formatted_source_is_stable() {
+    runner()
+        .run(
+            &prop_oneof![
+                "[-*_~/`#>!@\\[\\]()<>|{}.=+ a1t\\\\\n]{0,40}",
+                // a declaration is only read on the first line
+                "\n?\\\\?%opmark 0\\.[0-9][-*#>! a1t\\\\\n]{0,20}",
+                // empty code spans, like {.a}````
+                "(\\{\\.a\\})?`{2,6}[` a1\n]{0,8}",
+                // byte order marks, only the first of which is dropped
+                "(\\[r\\]: http://e\\.com\n)?\u{feff}{1,3}[\u{feff} a1\n]{0,8}",
+            ],
+            |source| {
+                let formatted = format(&source);
+                assert_eq!(format(&formatted), formatted, "source: {:?}", source);
+                Ok(())
+            },
+        )
+        .unwrap();
+}
+
+#[test]
+fn windows_line_endings_are_normalized() {
+    let options = FormatOptions::new().with_preserve_line_endings();
+    runner()
+        .run(&prop::collection::vec(item(), 0..12), |items| {
+            let source = to_source(&marks(&items));
+            let windows = source.replace('\n', "\r\n");
+            assert_eq!(format(&windows), format(&source));
+            assert_eq!(
+                format_with_options(&windows, &options),
+                format(&source).replace('\n', "\r\n")
+            );
+            Ok(())
+        })
+        .unwrap();
+}
@@ -0,0 +1,9 @@
+//! Snapshots of the marks of fixture decks.
+
+use opmark::test_util::{assert_snapshot, mark_tree, parse_fixture};
+
+#[test]
+fn snapshot_features() {
+    let marks = parse_fixture("tests/fixtures/features.opmark");
+    assert_snapshot("tests/snapshots/features.snap", &mark_tree(&marks));
+}